@@ -0,0 +1,713 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use spec::{borrowed_structs, FieldSpec, FieldType, MessageKind, MessageSpec, StructSpec, Versions};
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "do", "final", "macro", "override",
+    "priv", "yield",
+];
+
+/// Generate the module of a request or response message.
+pub fn generate(spec: &MessageSpec, file_name: &str) -> String {
+    let structs = spec.structs();
+    let generator = Generator {
+        spec,
+        is_request: spec.kind == MessageKind::Request,
+        borrowed: borrowed_structs(&structs),
+        flexible: spec.flexible_versions.condition(&spec.valid_versions),
+    };
+
+    let mut out = Output::default();
+
+    out.line(&format!("/// The `{}` message, generated from `{}`.", spec.name, file_name));
+    out.line("#[allow(unused_imports, unused_variables)]");
+    out.open(&format!("pub mod {} {{", snake_case(&spec.name)));
+    out.line("use std::borrow::Cow;");
+    out.blank();
+    out.line("use bytes::{BufMut, ByteOrder, Bytes, BytesMut};");
+    out.blank();
+    out.line("use nom::{IResult, be_f64, be_i16, be_i32, be_i64, be_i8, be_u16};");
+    out.blank();
+    out.line("use errors::Result;");
+    out.line("use protocol::{parse_tagged_fields, tagged_fields_size, ApiKey, ApiVersion, Encodable, Record,");
+    out.line("               RequestHeader, ResponseHeader, TaggedField, WriteExt};");
+    out.blank();
+    out.line("use super::*;");
+
+    for s in &structs {
+        let is_message = s.name == spec.name;
+
+        out.blank();
+        generator.struct_definition(&mut out, s, is_message);
+
+        if is_message {
+            out.blank();
+            generator.message_impl(&mut out, s);
+        } else {
+            out.blank();
+            generator.default_impl(&mut out, s);
+        }
+
+        out.blank();
+
+        if generator.is_request {
+            generator.size_impl(&mut out, s, is_message);
+            out.blank();
+            generator.encode_impl(&mut out, s, is_message);
+        } else {
+            generator.parse_fn(&mut out, s, is_message);
+        }
+    }
+
+    out.close("}");
+    out.blank();
+    out.line(&format!("pub use self::{}::{};", snake_case(&spec.name), spec.name));
+    out.blank();
+
+    out.buf
+}
+
+struct Generator<'a> {
+    spec: &'a MessageSpec,
+    is_request: bool,
+    borrowed: HashMap<String, bool>,
+    /// The condition of the flexible versions.
+    flexible: String,
+}
+
+impl<'a> Generator<'a> {
+    fn is_flexible(&self) -> bool {
+        self.flexible != "false"
+    }
+
+    fn api_key(&self) -> i16 {
+        self.spec
+            .api_key
+            .unwrap_or_else(|| panic!("message `{}` without API key", self.spec.name))
+    }
+
+    fn generics(&self, name: &str) -> &'static str {
+        if self.is_request && (name == self.spec.name || self.borrowed.get(name).cloned().unwrap_or_default()) {
+            "<'a>"
+        } else {
+            ""
+        }
+    }
+
+    fn rust_type(&self, ty: &FieldType) -> String {
+        match *ty {
+            FieldType::Bool => "bool".to_owned(),
+            FieldType::Int8 => "i8".to_owned(),
+            FieldType::Int16 => "i16".to_owned(),
+            FieldType::Int32 => "i32".to_owned(),
+            FieldType::Int64 => "i64".to_owned(),
+            FieldType::Uint16 => "u16".to_owned(),
+            FieldType::Float64 => "f64".to_owned(),
+            FieldType::String if self.is_request => "Cow<'a, str>".to_owned(),
+            FieldType::String => "String".to_owned(),
+            FieldType::Bytes | FieldType::Records => "Bytes".to_owned(),
+            FieldType::Array(ref ty) => format!("Vec<{}>", self.rust_type(ty)),
+            FieldType::Struct(ref name) => format!("{}{}", name, self.generics(name)),
+        }
+    }
+
+    /// The tagged and nullable fields are optional, and the response fields are optional
+    /// if they are not present in all the valid versions.
+    fn is_optional(&self, field: &FieldSpec) -> bool {
+        field.is_tagged() || field.is_nullable() || (!self.is_request && !field.versions.contains(&self.versions()))
+    }
+
+    fn versions(&self) -> Versions {
+        self.spec.valid_versions
+    }
+
+    fn condition(&self, field: &FieldSpec) -> String {
+        field.versions.condition(&self.versions())
+    }
+
+    fn struct_definition(&self, out: &mut Output, s: &StructSpec, is_message: bool) {
+        out.line("#[derive(Clone, Debug, PartialEq)]");
+        out.open(&format!("pub struct {}{} {{", s.name, self.generics(&s.name)));
+
+        if is_message {
+            out.line(if self.is_request {
+                "pub header: RequestHeader<'a>,"
+            } else {
+                "pub header: ResponseHeader,"
+            });
+        }
+
+        for field in &s.fields {
+            if !field.about.is_empty() {
+                for line in field.about.lines() {
+                    out.line(&format!("/// {}", line.trim()));
+                }
+            }
+            if !field.versions.contains(&self.versions()) || field.is_tagged() {
+                out.line(&format!("///\n/// versions: {}", field.versions));
+            }
+
+            let ty = self.rust_type(&field.ty);
+
+            if self.is_optional(field) {
+                out.line(&format!("pub {}: Option<{}>,", ident(&field.name), ty));
+            } else {
+                out.line(&format!("pub {}: {},", ident(&field.name), ty));
+            }
+        }
+
+        if self.is_flexible() {
+            out.line("/// The tagged fields which are unknown to this version of the client.");
+            out.line("pub unknown_tagged_fields: Vec<TaggedField>,");
+        }
+
+        out.close("}");
+    }
+
+    fn message_impl(&self, out: &mut Output, s: &StructSpec) {
+        let generics = self.generics(&s.name);
+
+        out.open(&format!("impl{} {}{} {{", generics, s.name, generics));
+        out.line(&format!("pub const API_KEY: ApiKey = {};", self.api_key()));
+        out.line(&format!(
+            "pub const LOWEST_VERSION: ApiVersion = {};",
+            self.versions().lowest()
+        ));
+        out.line(&format!(
+            "pub const HIGHEST_VERSION: ApiVersion = {};",
+            self.versions().highest()
+        ));
+
+        if !self.is_request {
+            out.blank();
+            out.open("pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {");
+            out.line(&format!("{}(buf, api_version)", parse_fn_name(&s.name)));
+            out.close("}");
+        }
+
+        out.close("}");
+    }
+
+    fn default_impl(&self, out: &mut Output, s: &StructSpec) {
+        let generics = self.generics(&s.name);
+
+        out.open(&format!("impl{} Default for {}{} {{", generics, s.name, generics));
+        out.open("fn default() -> Self {");
+        out.open(&format!("{} {{", s.name));
+
+        for field in &s.fields {
+            out.line(&format!("{}: {},", ident(&field.name), self.default_value(field)));
+        }
+
+        if self.is_flexible() {
+            out.line("unknown_tagged_fields: Vec::new(),");
+        }
+
+        out.close("}");
+        out.close("}");
+        out.close("}");
+    }
+
+    fn default_value(&self, field: &FieldSpec) -> String {
+        if self.is_optional(field) {
+            return "None".to_owned();
+        }
+
+        let value = match field.default {
+            Some(Value::String(ref s)) if !s.is_empty() => s.clone(),
+            Some(Value::Number(ref n)) => n.to_string(),
+            Some(Value::Bool(b)) => b.to_string(),
+            _ => return "Default::default()".to_owned(),
+        };
+
+        match field.ty {
+            FieldType::Float64 if !value.contains('.') => format!("{}.0", value),
+            FieldType::String => format!("{:?}.into()", value),
+            ref ty if ty.fixed_size().is_some() => value,
+            _ => "Default::default()".to_owned(),
+        }
+    }
+
+    fn size_impl(&self, out: &mut Output, s: &StructSpec, is_message: bool) {
+        let generics = self.generics(&s.name);
+
+        out.open(&format!("impl{} Record for {}{} {{", generics, s.name, generics));
+        out.open("fn size(&self, api_version: ApiVersion) -> usize {");
+
+        if is_message {
+            out.line("let mut size = self.header.size(api_version);");
+        } else {
+            out.line("let mut size = 0;");
+        }
+        out.blank();
+
+        let mut tagged = vec![];
+
+        for field in &s.fields {
+            let condition = self.condition(field);
+
+            if condition == "false" {
+                continue;
+            }
+
+            if field.is_tagged() {
+                let size = format!(
+                    "self.{}.as_ref().map(|value| ({}, {}))",
+                    ident(&field.name),
+                    field.tag.unwrap(),
+                    self.size_expr(&field.ty, false, "*value")
+                );
+
+                tagged.push(if condition == "true" {
+                    size
+                } else {
+                    format!("if {} {{ {} }} else {{ None }}", condition, size)
+                });
+            } else {
+                let size = format!(
+                    "size += {};",
+                    self.size_expr(&field.ty, field.is_nullable(), &format!("self.{}", ident(&field.name)))
+                );
+
+                guarded(out, &condition, &[size]);
+            }
+        }
+
+        if self.is_flexible() {
+            let size = if tagged.is_empty() {
+                "size += tagged_fields_size(&self.unknown_tagged_fields);".to_owned()
+            } else {
+                format!(
+                    "size += tagged_section_size(&self.unknown_tagged_fields, &[{}]);",
+                    tagged.join(", ")
+                )
+            };
+
+            guarded(out, &self.flexible, &[size]);
+        }
+
+        out.blank();
+        out.line("size");
+        out.close("}");
+        out.close("}");
+    }
+
+    fn encode_impl(&self, out: &mut Output, s: &StructSpec, is_message: bool) {
+        let generics = self.generics(&s.name);
+
+        if is_message {
+            out.open(&format!("impl{} Encodable for {}{} {{", generics, s.name, generics));
+            out.open("fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {");
+            out.line("let api_version = self.header.api_version;");
+            out.blank();
+            out.line("self.header.encode::<T>(dst)?;");
+        } else {
+            out.open(&format!("impl{} {}{} {{", generics, s.name, generics));
+            out.open(
+                "pub fn encode<T: ByteOrder>(&self, dst: &mut BytesMut, api_version: ApiVersion) -> Result<()> {",
+            );
+        }
+
+        let mut tagged = vec![];
+
+        for field in &s.fields {
+            let condition = self.condition(field);
+
+            if condition == "false" {
+                continue;
+            }
+
+            if field.is_tagged() {
+                tagged.push((field, condition));
+            } else {
+                let encode = self.encode_stmt(&field.ty, field.is_nullable(), &format!("self.{}", ident(&field.name)));
+
+                guarded(out, &condition, &[encode]);
+            }
+        }
+
+        if self.is_flexible() {
+            if tagged.is_empty() {
+                guarded(
+                    out,
+                    &self.flexible,
+                    &["dst.put_tagged_fields(&self.unknown_tagged_fields)?;".to_owned()],
+                );
+            } else {
+                let mut stmts = vec!["let mut tagged_fields = self.unknown_tagged_fields.clone();".to_owned()];
+
+                for (field, condition) in tagged {
+                    let encode = format!(
+                        "if let Some(ref value) = self.{} {{ tagged_fields.push({}); }}",
+                        ident(&field.name),
+                        format!(
+                            "encode_tagged_field({}, {}, |dst| {{ {} Ok(()) }})?",
+                            field.tag.unwrap(),
+                            self.size_expr(&field.ty, false, "*value"),
+                            self.encode_stmt(&field.ty, false, "*value")
+                        )
+                    );
+
+                    stmts.push(if condition == "true" {
+                        encode
+                    } else {
+                        format!("if {} {{ {} }}", condition, encode)
+                    });
+                }
+
+                stmts.push("tagged_fields.sort_by_key(|field| field.tag);".to_owned());
+                stmts.push("dst.put_tagged_fields(&tagged_fields)?;".to_owned());
+
+                guarded(out, &self.flexible, &stmts);
+            }
+        }
+
+        out.blank();
+        out.line("Ok(())");
+        out.close("}");
+        out.close("}");
+    }
+
+    /// The expression of the encoded size of the value, which is `Option<T>` if it is nullable.
+    fn size_expr(&self, ty: &FieldType, nullable: bool, value: &str) -> String {
+        if let Some(size) = ty.fixed_size() {
+            return size.to_string();
+        }
+
+        match *ty {
+            FieldType::String | FieldType::Bytes | FieldType::Records => {
+                let f = if *ty == FieldType::String {
+                    "string_size"
+                } else {
+                    "bytes_size"
+                };
+
+                if nullable {
+                    format!("{}({}.as_ref(), {})", f, value, self.flexible)
+                } else {
+                    format!("{}(Some({}), {})", f, by_ref(value), self.flexible)
+                }
+            }
+            FieldType::Array(ref elem) => {
+                let (len, items) = if nullable {
+                    (
+                        format!("{}.as_ref().map(|items| items.len())", value),
+                        format!("{}.iter().flat_map(|items| items.iter())", value),
+                    )
+                } else {
+                    (
+                        format!("Some({}.len())", by_value(value)),
+                        format!("{}.iter()", by_value(value)),
+                    )
+                };
+
+                let items_size = match elem.fixed_size() {
+                    Some(size) => format!("{}.count() * {}", items, size),
+                    None => format!(
+                        "{}.map(|item| {}).sum::<usize>()",
+                        items,
+                        self.size_expr(elem, false, "*item")
+                    ),
+                };
+
+                format!("array_size({}, {}) + {}", len, self.flexible, items_size)
+            }
+            FieldType::Struct(ref name) => {
+                if nullable {
+                    panic!("nullable structure `{}` is not supported", name)
+                }
+
+                format!("{}.size(api_version)", by_value(value))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// The statement to encode the value, which is `Option<T>` if it is nullable.
+    fn encode_stmt(&self, ty: &FieldType, nullable: bool, value: &str) -> String {
+        match *ty {
+            FieldType::Bool => format!("dst.put_i8({} as i8);", value),
+            FieldType::Int8 => format!("dst.put_i8({});", value),
+            FieldType::Int16 => format!("dst.put_i16::<T>({});", value),
+            FieldType::Uint16 => format!("dst.put_u16::<T>({});", value),
+            FieldType::Int32 => format!("dst.put_i32::<T>({});", value),
+            FieldType::Int64 => format!("dst.put_i64::<T>({});", value),
+            FieldType::Float64 => format!("dst.put_f64::<T>({});", value),
+            FieldType::String | FieldType::Bytes | FieldType::Records => {
+                let f = if *ty == FieldType::String {
+                    "put_string_field"
+                } else {
+                    "put_bytes_field"
+                };
+
+                if nullable {
+                    format!("{}::<T, _>(dst, {}.as_ref(), {})?;", f, value, self.flexible)
+                } else {
+                    format!("{}::<T, _>(dst, Some({}), {})?;", f, by_ref(value), self.flexible)
+                }
+            }
+            FieldType::Array(ref elem) => {
+                let items = if nullable {
+                    format!("{}.as_ref().map(|items| &items[..])", value)
+                } else {
+                    format!("Some(&{}[..])", by_value(value))
+                };
+
+                format!(
+                    "put_array_field::<T, _, _>(dst, {}, {}, |dst, item| {{ {} Ok(()) }})?;",
+                    items,
+                    self.flexible,
+                    self.encode_stmt(elem, false, "*item")
+                )
+            }
+            FieldType::Struct(ref name) => {
+                if nullable {
+                    panic!("nullable structure `{}` is not supported", name)
+                }
+
+                format!("{}.encode::<T>(dst, api_version)?;", by_value(value))
+            }
+        }
+    }
+
+    fn parse_fn(&self, out: &mut Output, s: &StructSpec, is_message: bool) {
+        out.line(&format!(
+            "named_args!({}(api_version: ApiVersion)<{}>,",
+            parse_fn_name(&s.name),
+            s.name
+        ));
+        out.indent += 1;
+        out.line("do_parse!(");
+
+        let mut parsers = vec![];
+        let mut values = vec![];
+
+        if is_message {
+            parsers.push(format!(
+                "header: call!(parse_versioned_response_header, {}, api_version)",
+                self.api_key()
+            ));
+            values.push("header".to_owned());
+        }
+
+        for field in s.fields.iter().filter(|field| !field.is_tagged()) {
+            let name = ident(&field.name);
+            let condition = self.condition(field);
+            let parser = submac(&self.parser(&field.ty, field.is_nullable()));
+
+            if condition == "false" {
+                values.push(format!("{}: None", name));
+                continue;
+            }
+
+            parsers.push(if condition == "true" {
+                format!("{}: {}", name, parser)
+            } else if field.is_nullable() {
+                format!("{}: map!(cond!({}, {}), flatten)", name, condition, parser)
+            } else {
+                format!("{}: cond!({}, {})", name, condition, parser)
+            });
+            values.push(name);
+        }
+
+        if self.is_flexible() {
+            let mut tags = vec![];
+
+            parsers.push(format!("tagged_fields: cond!({}, parse_tagged_fields)", self.flexible));
+
+            for field in s.fields.iter().filter(|field| field.is_tagged()) {
+                let name = ident(&field.name);
+                let tag = field.tag.unwrap();
+
+                parsers.push(format!(
+                    "{}: expr_res!(parse_tagged_field(tagged_fields.as_ref(), {}, {}))",
+                    name,
+                    tag,
+                    closure(&self.parser(&field.ty, false))
+                ));
+                values.push(name);
+                tags.push(tag.to_string());
+            }
+
+            values.push(format!(
+                "unknown_tagged_fields: unknown_tagged_fields(tagged_fields, &[{}])",
+                tags.join(", ")
+            ));
+        }
+
+        for (i, parser) in parsers.iter().enumerate() {
+            if i == 0 {
+                out.line(&format!("    {}", parser));
+            } else {
+                out.line(&format!(" >> {}", parser));
+            }
+        }
+
+        out.line(&format!("{}({} {{", if parsers.is_empty() { "    " } else { " >> " }, s.name));
+        for value in values {
+            out.line(&format!("        {},", value));
+        }
+        out.line("    })");
+        out.line(")");
+        out.indent -= 1;
+        out.line(");");
+    }
+
+    /// The parser function and its arguments, the parser returns `Option<T>` if it is nullable.
+    fn parser(&self, ty: &FieldType, nullable: bool) -> (String, Vec<String>) {
+        let f = |name: &str| (name.to_owned(), vec![]);
+
+        match *ty {
+            FieldType::Bool => f("parse_bool"),
+            FieldType::Int8 => f("be_i8"),
+            FieldType::Int16 => f("be_i16"),
+            FieldType::Int32 => f("be_i32"),
+            FieldType::Int64 => f("be_i64"),
+            FieldType::Uint16 => f("be_u16"),
+            FieldType::Float64 => f("be_f64"),
+            FieldType::String if nullable => ("parse_opt_string_field".to_owned(), vec![self.flexible.clone()]),
+            FieldType::String => ("parse_string_field".to_owned(), vec![self.flexible.clone()]),
+            FieldType::Bytes | FieldType::Records if nullable => {
+                ("parse_opt_bytes_field".to_owned(), vec![self.flexible.clone()])
+            }
+            FieldType::Bytes | FieldType::Records => ("parse_bytes_field".to_owned(), vec![self.flexible.clone()]),
+            FieldType::Array(ref elem) => (
+                if nullable {
+                    "parse_opt_array_field".to_owned()
+                } else {
+                    "parse_array_field".to_owned()
+                },
+                vec![self.flexible.clone(), closure(&self.parser(elem, false))],
+            ),
+            FieldType::Struct(ref name) => {
+                if nullable {
+                    panic!("nullable structure `{}` is not supported", name)
+                }
+
+                (parse_fn_name(name), vec!["api_version".to_owned()])
+            }
+        }
+    }
+}
+
+/// Write the statements, which are guarded by the condition unless it is always true.
+fn guarded(out: &mut Output, condition: &str, stmts: &[String]) {
+    if condition == "true" {
+        for stmt in stmts {
+            out.line(stmt);
+        }
+    } else {
+        out.open(&format!("if {} {{", condition));
+        for stmt in stmts {
+            out.line(stmt);
+        }
+        out.close("}");
+    }
+}
+
+/// The parser as a nom sub-macro.
+fn submac(&(ref f, ref args): &(String, Vec<String>)) -> String {
+    if args.is_empty() {
+        f.clone()
+    } else {
+        format!("call!({}, {})", f, args.join(", "))
+    }
+}
+
+/// The parser as a function or closure.
+fn closure(&(ref f, ref args): &(String, Vec<String>)) -> String {
+    if args.is_empty() {
+        f.clone()
+    } else {
+        format!("|i| {}(i, {})", f, args.join(", "))
+    }
+}
+
+/// Borrow the value, `*item` is borrowed as `item`.
+fn by_ref(value: &str) -> String {
+    if value.starts_with('*') {
+        value[1..].to_owned()
+    } else {
+        format!("&{}", value)
+    }
+}
+
+/// Call the method of the value, `*item` is called as `item`.
+fn by_value(value: &str) -> String {
+    if value.starts_with('*') {
+        value[1..].to_owned()
+    } else {
+        value.to_owned()
+    }
+}
+
+fn parse_fn_name(name: &str) -> String {
+    format!("parse_{}", snake_case(name))
+}
+
+fn ident(name: &str) -> String {
+    let name = snake_case(name);
+
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+/// Convert the `PascalCase` name to `snake_case`, e.g. `ThrottleTimeMs` to `throttle_time_ms`.
+pub fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut s = String::with_capacity(name.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev_lower = i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_numeric());
+            let next_lower =
+                i > 0 && chars[i - 1].is_uppercase() && chars.get(i + 1).map_or(false, |c| c.is_lowercase());
+
+            if prev_lower || next_lower {
+                s.push('_');
+            }
+
+            s.extend(c.to_lowercase());
+        } else {
+            s.push(c);
+        }
+    }
+
+    s
+}
+
+#[derive(Default)]
+struct Output {
+    buf: String,
+    indent: usize,
+}
+
+impl Output {
+    fn line(&mut self, line: &str) {
+        for line in line.lines() {
+            let _ = writeln!(self.buf, "{:width$}{}", "", line, width = self.indent * 4);
+        }
+    }
+
+    fn blank(&mut self) {
+        self.buf.push('\n');
+    }
+
+    fn open(&mut self, line: &str) {
+        self.line(line);
+        self.indent += 1;
+    }
+
+    fn close(&mut self, line: &str) {
+        self.indent -= 1;
+        self.line(line);
+    }
+}
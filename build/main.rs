@@ -0,0 +1,53 @@
+//! Generate the protocol messages from the Kafka's JSON message schemas in the `schema` directory.
+//!
+//! The generated code is included by the `protocol::messages` module.
+
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+mod codegen;
+mod spec;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use spec::{strip_comments, MessageKind, MessageSpec};
+
+fn main() {
+    let schema_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("schema");
+    let out_file = Path::new(&env::var("OUT_DIR").unwrap()).join("messages.rs");
+
+    println!("cargo:rerun-if-changed={}", schema_dir.display());
+
+    let mut files = fs::read_dir(&schema_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect::<Vec<_>>();
+
+    files.sort();
+
+    let mut code = String::new();
+
+    for path in files {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let s = fs::read_to_string(&path).unwrap();
+        let spec: MessageSpec = serde_json::from_str(&strip_comments(&s))
+            .unwrap_or_else(|err| panic!("fail to parse message schema {}, {}", path.display(), err));
+
+        match spec.kind {
+            MessageKind::Request | MessageKind::Response => {
+                let file_name = path.file_name().unwrap().to_string_lossy();
+
+                code.push_str(&codegen::generate(&spec, &file_name));
+            }
+            _ => {}
+        }
+    }
+
+    fs::write(&out_file, code).unwrap();
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::i16;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde_json::Value;
+
+/// The message specification, which is defined in the Kafka's JSON message schemas.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSpec {
+    pub api_key: Option<i16>,
+    #[serde(rename = "type")]
+    pub kind: MessageKind,
+    pub name: String,
+    pub valid_versions: Versions,
+    #[serde(default)]
+    pub flexible_versions: Versions,
+    #[serde(default)]
+    pub fields: Vec<FieldSpec>,
+    #[serde(default)]
+    pub common_structs: Vec<StructSpec>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    Request,
+    Response,
+    Header,
+    Data,
+}
+
+/// The common structure, which may be referenced by the fields of the message.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructSpec {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<FieldSpec>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: FieldType,
+    pub versions: Versions,
+    #[serde(default)]
+    pub nullable_versions: Versions,
+    pub tag: Option<u32>,
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub about: String,
+    #[serde(default)]
+    pub fields: Vec<FieldSpec>,
+}
+
+impl FieldSpec {
+    pub fn is_nullable(&self) -> bool {
+        !self.nullable_versions.is_none()
+    }
+
+    pub fn is_tagged(&self) -> bool {
+        self.tag.is_some()
+    }
+}
+
+impl MessageSpec {
+    /// Collect the structures of the message, the nested structures are defined inline by the array fields
+    /// or referenced the common structures.
+    pub fn structs(&self) -> Vec<StructSpec> {
+        let mut structs = vec![StructSpec {
+            name: self.name.clone(),
+            fields: self.fields.clone(),
+        }];
+
+        collect_structs(&self.fields, &mut structs);
+
+        for common in &self.common_structs {
+            structs.push(common.clone());
+            collect_structs(&common.fields, &mut structs);
+        }
+
+        structs
+    }
+}
+
+fn collect_structs(fields: &[FieldSpec], structs: &mut Vec<StructSpec>) {
+    for field in fields {
+        if !field.fields.is_empty() {
+            match field.ty.struct_name() {
+                Some(name) => structs.push(StructSpec {
+                    name: name.to_owned(),
+                    fields: field.fields.clone(),
+                }),
+                None => panic!("field `{}` with nested fields must be a structure", field.name),
+            }
+
+            collect_structs(&field.fields, structs);
+        }
+    }
+}
+
+/// Whether the structures borrow the strings with a lifetime, which are used in the requests.
+pub fn borrowed_structs(structs: &[StructSpec]) -> HashMap<String, bool> {
+    let mut borrowed = HashMap::new();
+
+    loop {
+        let mut changed = false;
+
+        for s in structs {
+            let b = s.fields.iter().any(|field| field.ty.is_borrowed(&borrowed));
+
+            if b && !borrowed.get(&s.name).cloned().unwrap_or_default() {
+                borrowed.insert(s.name.clone(), true);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return borrowed;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldType {
+    Bool,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint16,
+    Float64,
+    String,
+    Bytes,
+    Records,
+    Array(Box<FieldType>),
+    Struct(String),
+}
+
+impl FieldType {
+    /// The name of the structure, or the element structure of the array.
+    pub fn struct_name(&self) -> Option<&str> {
+        match *self {
+            FieldType::Struct(ref name) => Some(name),
+            FieldType::Array(ref ty) => ty.struct_name(),
+            _ => None,
+        }
+    }
+
+    /// The size of the fixed-length types.
+    pub fn fixed_size(&self) -> Option<usize> {
+        match *self {
+            FieldType::Bool | FieldType::Int8 => Some(1),
+            FieldType::Int16 | FieldType::Uint16 => Some(2),
+            FieldType::Int32 => Some(4),
+            FieldType::Int64 | FieldType::Float64 => Some(8),
+            _ => None,
+        }
+    }
+
+    fn is_borrowed(&self, borrowed: &HashMap<String, bool>) -> bool {
+        match *self {
+            FieldType::String => true,
+            FieldType::Array(ref ty) => ty.is_borrowed(borrowed),
+            FieldType::Struct(ref name) => borrowed.get(name).cloned().unwrap_or_default(),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bool" => FieldType::Bool,
+            "int8" => FieldType::Int8,
+            "int16" => FieldType::Int16,
+            "int32" => FieldType::Int32,
+            "int64" => FieldType::Int64,
+            "uint16" => FieldType::Uint16,
+            "float64" => FieldType::Float64,
+            "string" => FieldType::String,
+            "bytes" => FieldType::Bytes,
+            "records" => FieldType::Records,
+            _ if s.starts_with("[]") => FieldType::Array(Box::new(s[2..].parse()?)),
+            _ if s.chars().next().map_or(false, |c| c.is_uppercase()) => FieldType::Struct(s.to_owned()),
+            _ => return Err(format!("unsupported field type `{}`", s)),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// The range of versions, e.g. `0+`, `1-3` or `none`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Versions {
+    None,
+    Range(i16, i16),
+}
+
+impl Default for Versions {
+    fn default() -> Self {
+        Versions::None
+    }
+}
+
+impl Versions {
+    pub fn is_none(&self) -> bool {
+        *self == Versions::None
+    }
+
+    pub fn lowest(&self) -> i16 {
+        match *self {
+            Versions::Range(lowest, _) => lowest,
+            Versions::None => panic!("empty versions"),
+        }
+    }
+
+    pub fn highest(&self) -> i16 {
+        match *self {
+            Versions::Range(_, highest) => highest,
+            Versions::None => panic!("empty versions"),
+        }
+    }
+
+    pub fn intersect(&self, other: &Versions) -> Versions {
+        match (*self, *other) {
+            (Versions::Range(a, b), Versions::Range(c, d)) if a.max(c) <= b.min(d) => {
+                Versions::Range(a.max(c), b.min(d))
+            }
+            _ => Versions::None,
+        }
+    }
+
+    pub fn contains(&self, other: &Versions) -> bool {
+        match (*self, *other) {
+            (_, Versions::None) => true,
+            (Versions::Range(a, b), Versions::Range(c, d)) => a <= c && d <= b,
+            _ => false,
+        }
+    }
+
+    /// The condition to check whether the `api_version` is in the range,
+    /// with the assumption that it is one of the valid versions of the message.
+    pub fn condition(&self, valid_versions: &Versions) -> String {
+        match self.intersect(valid_versions) {
+            Versions::None => "false".to_owned(),
+            Versions::Range(lowest, highest) => {
+                let mut conditions = vec![];
+
+                if lowest > valid_versions.lowest() {
+                    conditions.push(format!("api_version >= {}", lowest));
+                }
+                if highest < valid_versions.highest() {
+                    conditions.push(format!("api_version <= {}", highest));
+                }
+
+                if conditions.is_empty() {
+                    "true".to_owned()
+                } else {
+                    conditions.join(" && ")
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Versions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Versions::None => write!(f, "none"),
+            Versions::Range(lowest, i16::MAX) => write!(f, "{}+", lowest),
+            Versions::Range(lowest, highest) if lowest == highest => write!(f, "{}", lowest),
+            Versions::Range(lowest, highest) => write!(f, "{}-{}", lowest, highest),
+        }
+    }
+}
+
+impl FromStr for Versions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |v: &str| v.trim().parse::<i16>().map_err(|err| format!("invalid version `{}`, {}", s, err));
+
+        let s = s.trim();
+
+        if s == "none" {
+            Ok(Versions::None)
+        } else if s.ends_with('+') {
+            Ok(Versions::Range(parse(&s[..s.len() - 1])?, i16::MAX))
+        } else if let Some(idx) = s.find('-') {
+            Ok(Versions::Range(parse(&s[..idx])?, parse(&s[idx + 1..])?))
+        } else {
+            let v = parse(s)?;
+
+            Ok(Versions::Range(v, v))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Versions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Strip the comment lines, which are not allowed in the JSON.
+pub fn strip_comments(s: &str) -> String {
+    s.lines()
+        .filter(|line| !line.trim().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
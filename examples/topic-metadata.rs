@@ -18,7 +18,8 @@ use getopts::Options;
 
 use futures::Future;
 use tokio_core::reactor::Core;
-use tokio_kafka::{Client, Cluster, FetchOffset, KafkaClient, KafkaVersion, ListedOffset, Metadata, TopicPartition};
+use tokio_kafka::{Client, Cluster, FetchOffset, IsolationLevel, KafkaClient, KafkaVersion, ListedOffset, Metadata,
+                  TopicPartition};
 
 const DEFAULT_BROKER: &'static str = "127.0.0.1:9092";
 
@@ -150,6 +151,7 @@ fn main() {
 
         client
             .list_offsets(
+                IsolationLevel::default(),
                 topics
                     .iter()
                     .flat_map(|tp| vec![(tp.clone(), FetchOffset::Earliest), (tp.clone(), FetchOffset::Latest)])
@@ -0,0 +1,432 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::Future;
+
+use admin::offset::{committed_offsets, ensure_group_inactive, partitions_to_reset, plan_reset_offsets};
+use admin::{ConsumerGroupDescription, ConsumerLag, DescribeConsumerGroups, PartitionLag, ResetOffsets,
+            ResetOffsetsOptions, ResetOffsetsTo};
+use client::{AlterConfigs, Client, CreateAcls, CreatePartitions, CreateTopics, DeleteAcls, DeleteGroups, DeleteRecords,
+             DeleteTopics, DescribeAcls, DescribeConfigs, DescribeLogDirs, Generation, KafkaClient, ListGroups,
+             ToStaticBoxFuture};
+use consumer::RetrieveOffsets;
+use errors::ErrorKind::KafkaError;
+use errors::Result;
+use network::{ConfigResource, NewPartitions, NewTopic, OffsetAndMetadata, TopicPartition};
+use protocol::{AclBinding, AclBindingFilter, FetchOffset, IsolationLevel, KafkaCode, Offset};
+
+/// The administrative client for Kafka, which supports managing and inspecting
+/// topics, records, configurations, ACLs and consumer groups.
+///
+/// The admin requests require the `api.version.request` option to discover the API versions supported by brokers.
+#[derive(Clone)]
+pub struct AdminClient<'a> {
+    client: KafkaClient<'a>,
+}
+
+impl<'a> Deref for AdminClient<'a> {
+    type Target = KafkaClient<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.client
+    }
+}
+
+impl<'a> From<KafkaClient<'a>> for AdminClient<'a> {
+    fn from(client: KafkaClient<'a>) -> Self {
+        AdminClient { client }
+    }
+}
+
+impl<'a> AdminClient<'a>
+where
+    Self: 'static,
+{
+    pub fn new(client: KafkaClient<'a>) -> Self {
+        AdminClient { client }
+    }
+
+    /// Create a batch of new topics.
+    ///
+    /// The future will eventually contain the result of each topic.
+    pub fn create_topics<I>(&self, topics: I, options: CreateTopicsOptions) -> CreateTopics
+    where
+        I: IntoIterator<Item = NewTopic<'a>>,
+    {
+        let timeout = options.timeout.unwrap_or_else(|| self.client.request_timeout());
+
+        self.client.create_topics(topics.into_iter().collect(), timeout, options.validate_only)
+    }
+
+    /// Delete a batch of topics.
+    ///
+    /// The future will eventually contain the error code of each topic.
+    pub fn delete_topics<I, S>(&self, topic_names: I, options: DeleteTopicsOptions) -> DeleteTopics
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        let timeout = options.timeout.unwrap_or_else(|| self.client.request_timeout());
+
+        self.client.delete_topics(topic_names.into_iter().map(|s| s.into()).collect(), timeout)
+    }
+
+    /// Increase the number of partitions of the topics.
+    ///
+    /// The future will eventually contain the result of each topic.
+    pub fn create_partitions<I, S>(&self, topics: I, options: CreatePartitionsOptions) -> CreatePartitions
+    where
+        I: IntoIterator<Item = (S, NewPartitions)>,
+        S: Into<Cow<'a, str>>,
+    {
+        let timeout = options.timeout.unwrap_or_else(|| self.client.request_timeout());
+
+        self.client.create_partitions(
+            topics
+                .into_iter()
+                .map(|(topic_name, partitions)| (topic_name.into(), partitions))
+                .collect(),
+            timeout,
+            options.validate_only,
+        )
+    }
+
+    /// Delete the records of the partitions before the given offsets.
+    ///
+    /// The future will eventually contain the new low watermark of each partition.
+    pub fn delete_records<I>(&self, offsets: I, options: DeleteRecordsOptions) -> DeleteRecords
+    where
+        I: 'static + IntoIterator<Item = (TopicPartition<'a>, Offset)>,
+    {
+        let timeout = options.timeout.unwrap_or_else(|| self.client.request_timeout());
+
+        self.client.delete_records(offsets, timeout)
+    }
+
+    /// Describe the configurations of the topics or brokers.
+    ///
+    /// The future will eventually contain the configuration entries of each resource.
+    pub fn describe_configs<I>(&self, resources: I) -> DescribeConfigs
+    where
+        I: IntoIterator<Item = ConfigResource<'a>>,
+    {
+        self.client.describe_configs(resources.into_iter().collect())
+    }
+
+    /// Update the configurations of the topics or brokers.
+    ///
+    /// The given configurations replace all the existing dynamic configurations of each resource,
+    /// the configurations that are not specified will be reverted to the default value.
+    pub fn alter_configs<I>(&self, configs: I, options: AlterConfigsOptions) -> AlterConfigs
+    where
+        I: IntoIterator<Item = (ConfigResource<'a>, HashMap<String, String>)>,
+    {
+        self.client.alter_configs(configs.into_iter().collect(), options.validate_only)
+    }
+
+    /// Describe the ACL bindings which match the filter.
+    pub fn describe_acls(&self, filter: AclBindingFilter<'a>) -> DescribeAcls {
+        self.client.describe_acls(filter)
+    }
+
+    /// Create the ACL bindings.
+    ///
+    /// The future will eventually contain the result of each ACL binding, in the given order.
+    pub fn create_acls<I>(&self, acls: I) -> CreateAcls
+    where
+        I: IntoIterator<Item = AclBinding<'a>>,
+    {
+        self.client.create_acls(acls.into_iter().collect())
+    }
+
+    /// Delete the ACL bindings which match the filters.
+    ///
+    /// The future will eventually contain the ACL bindings which matched each filter, in the given order.
+    pub fn delete_acls<I>(&self, filters: I) -> DeleteAcls
+    where
+        I: IntoIterator<Item = AclBindingFilter<'a>>,
+    {
+        self.client.delete_acls(filters.into_iter().collect())
+    }
+
+    /// List the groups managed by all the brokers of the Kafka cluster.
+    pub fn list_groups(&self) -> ListGroups {
+        self.client.list_groups()
+    }
+
+    /// Describe the groups and their members.
+    ///
+    /// The future will eventually contain the description of each group,
+    /// with the decoded assignments of the members if it is a consumer group.
+    pub fn describe_groups<I, S>(&self, group_ids: I) -> DescribeConsumerGroups
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        self.client
+            .describe_groups(group_ids.into_iter().map(|s| s.into()).collect())
+            .and_then(|groups| {
+                groups
+                    .into_iter()
+                    .map(|(group_id, group)| ConsumerGroupDescription::new(group).map(|group| (group_id, group)))
+                    .collect::<Result<HashMap<_, _>>>()
+            })
+            .static_boxed()
+    }
+
+    /// Delete a batch of groups, which must be empty.
+    ///
+    /// The future will eventually contain the error code of each group.
+    pub fn delete_groups<I, S>(&self, group_ids: I) -> DeleteGroups
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>,
+    {
+        self.client.delete_groups(group_ids.into_iter().map(|s| s.into()).collect())
+    }
+
+    /// Describe the log directories of all the brokers with the replicas of the partitions.
+    ///
+    /// The future will eventually contain the log directories of each broker,
+    /// with all the replicas on the broker if the partitions are not specified.
+    pub fn describe_log_dirs<I>(&self, partitions: Option<I>) -> DescribeLogDirs
+    where
+        I: IntoIterator<Item = TopicPartition<'a>>,
+    {
+        self.client.describe_log_dirs(partitions.map(|partitions| partitions.into_iter().collect()))
+    }
+
+    /// Reset the committed offsets of an inactive consumer group for the partitions.
+    ///
+    /// The partitions of the `Offsets` target are reset instead of the given partitions,
+    /// so the offsets loaded from a file could be applied as a whole.
+    ///
+    /// The future will eventually contain the previous and new offsets of each partition,
+    /// the new offsets are only calculated without being committed in the dry run mode.
+    pub fn reset_offsets<S, I>(
+        &self,
+        group_id: S,
+        partitions: I,
+        target: ResetOffsetsTo<'a>,
+        options: ResetOffsetsOptions,
+    ) -> ResetOffsets<'a>
+    where
+        S: Into<Cow<'a, str>>,
+        I: IntoIterator<Item = TopicPartition<'a>>,
+    {
+        let group_id = group_id.into();
+        let partitions = partitions_to_reset(&target, partitions.into_iter().collect());
+        let client = self.client.clone();
+
+        let generation = standalone_generation(&group_id);
+
+        self.client
+            .describe_groups(vec![group_id.clone()])
+            .and_then(move |groups| ensure_group_inactive(&group_id, groups).map(|_| group_id))
+            .and_then(move |group_id| {
+                client.group_coordinator(group_id).and_then(move |coordinator| {
+                    let coordinator = coordinator.as_ref();
+
+                    let (earliest, latest, by_time) = {
+                        let list_offsets = |fetch_offset: FetchOffset| {
+                            RetrieveOffsets::<Offset>::new(client.list_offsets(
+                                IsolationLevel::ReadUncommitted,
+                                partitions
+                                    .iter()
+                                    .map(|tp| (tp.clone(), fetch_offset))
+                                    .collect::<Vec<_>>(),
+                            ))
+                        };
+
+                        let by_time = match target {
+                            ResetOffsetsTo::Datetime(timestamp) => {
+                                Either::A(list_offsets(FetchOffset::ByTime(timestamp)))
+                            }
+                            _ => Either::B(future::ok(HashMap::new())),
+                        };
+
+                        (list_offsets(FetchOffset::Earliest), list_offsets(FetchOffset::Latest), by_time)
+                    };
+
+                    let committed = client
+                        .offset_fetch(coordinator, generation.clone(), partitions.clone())
+                        .and_then(committed_offsets);
+
+                    committed
+                        .join4(earliest, latest, by_time)
+                        .and_then(move |(committed, earliest, latest, by_time)| {
+                            plan_reset_offsets(&target, partitions, &committed, &earliest, &latest, &by_time)
+                        })
+                        .and_then(move |offsets| {
+                            if options.dry_run {
+                                return Either::A(future::ok(offsets));
+                            }
+
+                            let commit_offsets = offsets
+                                .iter()
+                                .map(|(tp, reset)| (tp.clone(), OffsetAndMetadata::new(reset.offset)))
+                                .collect::<Vec<_>>();
+
+                            Either::B(
+                                client
+                                    .offset_commit(Some(coordinator), Some(generation), None, commit_offsets)
+                                    .and_then(move |topics| {
+                                        for partitions in topics.values() {
+                                            for partition in partitions {
+                                                if partition.error_code != KafkaCode::None {
+                                                    bail!(KafkaError(partition.error_code))
+                                                }
+                                            }
+                                        }
+
+                                        Ok(offsets)
+                                    }),
+                            )
+                        })
+                })
+            })
+            .static_boxed()
+    }
+
+    /// Calculate the lag of the consumer group for each partition with committed offset.
+    ///
+    /// The lag of a partition is the distance between its committed offset and log end offset.
+    pub fn consumer_lag<S>(&self, group_id: S) -> ConsumerLag<'a>
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        let group_id = group_id.into();
+        let client = self.client.clone();
+
+        self.client
+            .group_coordinator(group_id.clone())
+            .and_then(move |coordinator| {
+                client
+                    .group_offsets(coordinator.as_ref(), group_id)
+                    .and_then(committed_offsets)
+                    .and_then(move |committed| {
+                        let partitions = committed
+                            .keys()
+                            .map(|tp| (tp.clone(), FetchOffset::Latest))
+                            .collect::<Vec<_>>();
+
+                        let latest = client.list_offsets(IsolationLevel::ReadUncommitted, partitions);
+
+                        RetrieveOffsets::<Offset>::new(latest).map(move |latest| {
+                            committed
+                                .into_iter()
+                                .flat_map(|(tp, committed_offset)| {
+                                    let lag = latest
+                                        .get(&tp)
+                                        .map(|&log_end_offset| PartitionLag::new(committed_offset, log_end_offset));
+
+                                    lag.map(|lag| (tp, lag))
+                                })
+                                .collect()
+                        })
+                    })
+            })
+            .static_boxed()
+    }
+}
+
+/// The generation to commit or fetch the offsets without joining the group, like a standalone consumer.
+fn standalone_generation(group_id: &str) -> Generation {
+    Generation {
+        group_id: group_id.to_owned(),
+        generation_id: -1,
+        member_id: String::new(),
+        protocol: String::new(),
+    }
+}
+
+/// Options for `AdminClient::create_topics`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CreateTopicsOptions {
+    /// The time to wait for the topics to be created on the controller,
+    /// or the `request.timeout.ms` of the client if not set.
+    pub timeout: Option<Duration>,
+    /// Validate the request without creating the topics.
+    pub validate_only: bool,
+}
+
+impl CreateTopicsOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_validate_only(mut self, validate_only: bool) -> Self {
+        self.validate_only = validate_only;
+        self
+    }
+}
+
+/// Options for `AdminClient::delete_topics`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteTopicsOptions {
+    /// The time to wait for the topics to be deleted on the controller,
+    /// or the `request.timeout.ms` of the client if not set.
+    pub timeout: Option<Duration>,
+}
+
+impl DeleteTopicsOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Options for `AdminClient::create_partitions`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CreatePartitionsOptions {
+    /// The time to wait for the partitions to be created on the controller,
+    /// or the `request.timeout.ms` of the client if not set.
+    pub timeout: Option<Duration>,
+    /// Validate the request without creating the partitions.
+    pub validate_only: bool,
+}
+
+impl CreatePartitionsOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_validate_only(mut self, validate_only: bool) -> Self {
+        self.validate_only = validate_only;
+        self
+    }
+}
+
+/// Options for `AdminClient::delete_records`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteRecordsOptions {
+    /// The time to wait for the records to be deleted on the leaders,
+    /// or the `request.timeout.ms` of the client if not set.
+    pub timeout: Option<Duration>,
+}
+
+impl DeleteRecordsOptions {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Options for `AdminClient::alter_configs`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlterConfigsOptions {
+    /// Validate the request without altering the configurations.
+    pub validate_only: bool,
+}
+
+impl AlterConfigsOptions {
+    pub fn with_validate_only(mut self, validate_only: bool) -> Self {
+        self.validate_only = validate_only;
+        self
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use client::{DescribedGroup, DescribedGroupMember, StaticBoxFuture};
+use consumer::{Assignment, CONSUMER_PROTOCOL};
+use errors::{Result, ResultExt};
+use protocol::{KafkaCode, Schema};
+
+/// The future of describing consumer groups.
+pub type DescribeConsumerGroups = StaticBoxFuture<HashMap<String, ConsumerGroupDescription>>;
+
+/// The description of the consumer group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsumerGroupDescription {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The current state of the group
+    pub state: String,
+    /// The group protocol type, `consumer` for the consumer groups
+    pub protocol_type: String,
+    /// The assignment strategy of the group
+    pub protocol: String,
+    /// The members of the group
+    pub members: Vec<MemberDescription>,
+}
+
+impl ConsumerGroupDescription {
+    /// Decode the assignments of the members if the group is a consumer group.
+    pub fn new(group: DescribedGroup) -> Result<Self> {
+        let is_consumer_group = group.protocol_type == CONSUMER_PROTOCOL;
+
+        let members = group
+            .members
+            .into_iter()
+            .map(|member| MemberDescription::new(member, is_consumer_group))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ConsumerGroupDescription {
+            error_code: group.error_code,
+            state: group.state,
+            protocol_type: group.protocol_type,
+            protocol: group.protocol,
+            members,
+        })
+    }
+}
+
+/// The member of the consumer group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberDescription {
+    /// The member id assigned by the coordinator
+    pub member_id: String,
+    /// The client id of the member
+    pub client_id: String,
+    /// The client host of the member
+    pub client_host: String,
+    /// The partitions assigned to the member,
+    /// or `None` if the group is not a consumer group or is rebalancing.
+    pub assignment: Option<Assignment<'static>>,
+}
+
+impl MemberDescription {
+    fn new(member: DescribedGroupMember, is_consumer_group: bool) -> Result<Self> {
+        let assignment = if is_consumer_group && !member.member_assignment.is_empty() {
+            Some(Schema::deserialize(&member.member_assignment[..]).chain_err(|| "fail to deserialize assignment")?)
+        } else {
+            None
+        };
+
+        Ok(MemberDescription {
+            member_id: member.member_id,
+            client_id: member.client_id,
+            client_host: member.client_host,
+            assignment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    fn described_group(protocol_type: &str, member_assignment: Bytes) -> DescribedGroup {
+        DescribedGroup {
+            error_code: KafkaCode::None,
+            state: "Stable".to_owned(),
+            protocol_type: protocol_type.to_owned(),
+            protocol: "range".to_owned(),
+            members: vec![
+                DescribedGroupMember {
+                    member_id: "member".to_owned(),
+                    client_id: "client".to_owned(),
+                    client_host: "/127.0.0.1".to_owned(),
+                    member_metadata: Bytes::new(),
+                    member_assignment,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_decode_consumer_group_assignment() {
+        let assignment = Assignment {
+            partitions: vec![topic_partition!("topic", 0), topic_partition!("topic", 1)],
+            user_data: None,
+        };
+        let data = Schema::serialize(&assignment).unwrap();
+
+        let group = ConsumerGroupDescription::new(described_group(CONSUMER_PROTOCOL, Bytes::from(data))).unwrap();
+
+        assert_eq!(group.members.len(), 1);
+        assert_eq!(group.members[0].member_id, "member");
+        assert_eq!(group.members[0].assignment, Some(assignment));
+    }
+
+    #[test]
+    fn test_decode_without_assignment() {
+        let group = ConsumerGroupDescription::new(described_group(CONSUMER_PROTOCOL, Bytes::new())).unwrap();
+
+        assert_eq!(group.members[0].assignment, None);
+
+        let group =
+            ConsumerGroupDescription::new(described_group("connect", Bytes::from(&b"assignment"[..]))).unwrap();
+
+        assert_eq!(group.members[0].assignment, None);
+    }
+}
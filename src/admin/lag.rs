@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures::{future, Future, Stream};
+use prometheus::GaugeVec;
+
+use admin::AdminClient;
+use client::{StaticBoxFuture, ToStaticBoxFuture, NAMESPACE_KAFKA};
+use errors::{Error, ErrorKind, Result};
+use network::TopicPartition;
+use protocol::Offset;
+
+pub const SUBSYSTEM_CONSUMER_GROUP: &str = "consumer_group";
+
+/// The future of calculating the lag of a consumer group.
+pub type ConsumerLag<'a> = StaticBoxFuture<HashMap<TopicPartition<'a>, PartitionLag>>;
+
+/// The lag of the consumer group on the partition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PartitionLag {
+    /// The offset committed by the consumer group
+    pub committed_offset: Offset,
+    /// The offset of the next record will be appended to the partition
+    pub log_end_offset: Offset,
+    /// The number of records have not been consumed
+    pub lag: i64,
+}
+
+impl PartitionLag {
+    pub fn new(committed_offset: Offset, log_end_offset: Offset) -> Self {
+        PartitionLag {
+            committed_offset,
+            log_end_offset,
+            lag: if log_end_offset > committed_offset {
+                log_end_offset - committed_offset
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// A collector that exports the lag of consumer groups as Prometheus gauges.
+///
+/// The gauges are registered in the metrics registry of the client,
+/// which must be created with the `metrics` option.
+#[derive(Clone)]
+pub struct LagExporter<'a> {
+    admin: AdminClient<'a>,
+    committed_offset: GaugeVec,
+    log_end_offset: GaugeVec,
+    lag: GaugeVec,
+    /// The partitions of each consumer group which have been exported.
+    exported: Rc<RefCell<HashMap<String, HashSet<TopicPartition<'a>>>>>,
+}
+
+impl<'a> LagExporter<'a>
+where
+    Self: 'static,
+{
+    pub fn new(admin: AdminClient<'a>) -> Result<Self> {
+        let metrics = admin
+            .metrics()
+            .ok_or_else(|| ErrorKind::ConfigError("metrics of the client is disabled"))?;
+
+        let committed_offset = GaugeVec::new(
+            opts!("committed_offset", "committed offset of the consumer group")
+                .namespace(NAMESPACE_KAFKA.to_owned())
+                .subsystem(SUBSYSTEM_CONSUMER_GROUP.to_owned()),
+            &["group", "topic", "partition"],
+        )?;
+
+        let log_end_offset = GaugeVec::new(
+            opts!("log_end_offset", "log end offset of the partition")
+                .namespace(NAMESPACE_KAFKA.to_owned())
+                .subsystem(SUBSYSTEM_CONSUMER_GROUP.to_owned()),
+            &["group", "topic", "partition"],
+        )?;
+
+        let lag = GaugeVec::new(
+            opts!("lag", "lag of the consumer group")
+                .namespace(NAMESPACE_KAFKA.to_owned())
+                .subsystem(SUBSYSTEM_CONSUMER_GROUP.to_owned()),
+            &["group", "topic", "partition"],
+        )?;
+
+        metrics.register(Box::new(committed_offset.clone()))?;
+        metrics.register(Box::new(log_end_offset.clone()))?;
+        metrics.register(Box::new(lag.clone()))?;
+
+        Ok(LagExporter {
+            admin,
+            committed_offset,
+            log_end_offset,
+            lag,
+            exported: Rc::new(RefCell::new(HashMap::new())),
+        })
+    }
+
+    /// Update the gauges with the current lag of the consumer group.
+    pub fn collect(&self, group_id: String) -> StaticBoxFuture {
+        let exporter = self.clone();
+
+        self.admin
+            .consumer_lag(group_id.clone())
+            .map(move |lags| exporter.update(&group_id, &lags))
+            .static_boxed()
+    }
+
+    /// Set the gauges of the partitions with the lag of the consumer group,
+    /// and remove the gauges of the partitions which are no longer committed by the group.
+    fn update(&self, group_id: &str, lags: &HashMap<TopicPartition<'a>, PartitionLag>) {
+        for (tp, lag) in lags {
+            let partition_id = tp.partition_id.to_string();
+            let labels: [&str; 3] = [group_id, &tp.topic_name, &partition_id];
+
+            self.committed_offset
+                .with_label_values(&labels)
+                .set(lag.committed_offset as f64);
+            self.log_end_offset
+                .with_label_values(&labels)
+                .set(lag.log_end_offset as f64);
+            self.lag.with_label_values(&labels).set(lag.lag as f64);
+        }
+
+        let exported = self.exported
+            .borrow_mut()
+            .insert(group_id.to_owned(), lags.keys().cloned().collect())
+            .unwrap_or_default();
+
+        for tp in exported.iter().filter(|tp| !lags.contains_key(tp)) {
+            debug!("remove the stale lag of the `{}` group on {}", group_id, tp);
+
+            let partition_id = tp.partition_id.to_string();
+            let labels: [&str; 3] = [group_id, &tp.topic_name, &partition_id];
+
+            for gauge in &[&self.committed_offset, &self.log_end_offset, &self.lag] {
+                if let Err(err) = gauge.remove_label_values(&labels) {
+                    warn!("fail to remove the lag of the `{}` group on {}, {}", group_id, tp, err);
+                }
+            }
+        }
+    }
+
+    /// Poll the lag of the consumer groups on an interval, the future should be spawned on the event loop.
+    ///
+    /// The failure of polling a group is logged and retried on the next interval.
+    pub fn run<I, S>(self, group_ids: I, interval: Duration) -> StaticBoxFuture
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let group_ids = group_ids.into_iter().map(|s| s.into()).collect::<Vec<String>>();
+
+        self.admin
+            .timer()
+            .interval(interval)
+            .from_err()
+            .for_each(move |_| {
+                let collects = group_ids
+                    .iter()
+                    .map(|group_id| {
+                        let group_id = group_id.clone();
+
+                        self.collect(group_id.clone()).then(move |result| {
+                            if let Err(err) = result {
+                                warn!("fail to collect lag of the `{}` group, {}", group_id, err);
+                            }
+
+                            Ok::<(), Error>(())
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                future::join_all(collects).map(|_| ())
+            })
+            .static_boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::core::Collector;
+    use tokio_core::reactor::Core;
+
+    use client::{ClientConfig, KafkaClient};
+
+    use super::*;
+
+    fn exported_partitions(gauge: &GaugeVec) -> usize {
+        gauge
+            .collect()
+            .iter()
+            .map(|family| family.get_metric().len())
+            .sum()
+    }
+
+    #[test]
+    fn test_partition_lag() {
+        assert_eq!(PartitionLag::new(100, 150).lag, 50);
+        assert_eq!(PartitionLag::new(150, 150).lag, 0);
+        assert_eq!(PartitionLag::new(200, 150).lag, 0);
+    }
+
+    #[test]
+    fn test_remove_stale_lag() {
+        let core = Core::new().unwrap();
+        let config = ClientConfig {
+            metrics: true,
+            ..ClientConfig::with_bootstrap_servers(vec!["127.0.0.1:9092".to_owned()])
+        };
+        let exporter = LagExporter::new(AdminClient::new(KafkaClient::new(config, core.handle()))).unwrap();
+
+        let mut lags = HashMap::new();
+
+        lags.insert(topic_partition!("topic", 0), PartitionLag::new(100, 150));
+        lags.insert(topic_partition!("topic", 1), PartitionLag::new(100, 100));
+
+        exporter.update("group", &lags);
+        exporter.update("other", &lags);
+
+        assert_eq!(exported_partitions(&exporter.lag), 4);
+
+        lags.remove(&topic_partition!("topic", 1));
+
+        exporter.update("group", &lags);
+
+        assert_eq!(exported_partitions(&exporter.committed_offset), 3);
+        assert_eq!(exported_partitions(&exporter.log_end_offset), 3);
+        assert_eq!(exported_partitions(&exporter.lag), 3);
+
+        // the group has been deleted
+        exporter.update("other", &HashMap::new());
+
+        assert_eq!(exported_partitions(&exporter.lag), 1);
+    }
+}
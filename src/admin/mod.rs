@@ -0,0 +1,10 @@
+mod admin;
+mod group;
+mod lag;
+mod offset;
+
+pub use self::admin::{AdminClient, AlterConfigsOptions, CreatePartitionsOptions, CreateTopicsOptions,
+                      DeleteRecordsOptions, DeleteTopicsOptions};
+pub use self::group::{ConsumerGroupDescription, DescribeConsumerGroups, MemberDescription};
+pub use self::lag::{ConsumerLag, LagExporter, PartitionLag, SUBSYSTEM_CONSUMER_GROUP};
+pub use self::offset::{ResetOffset, ResetOffsets, ResetOffsetsOptions, ResetOffsetsTo};
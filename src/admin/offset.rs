@@ -0,0 +1,267 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use client::{DescribedGroup, FetchedOffset, StaticBoxFuture};
+use errors::{ErrorKind, Result};
+use network::TopicPartition;
+use protocol::{KafkaCode, Offset, PartitionId, Timestamp};
+
+/// The state of a group without any active member.
+const GROUP_STATE_EMPTY: &str = "Empty";
+/// The state of a group without any member or committed offset.
+const GROUP_STATE_DEAD: &str = "Dead";
+
+/// The future of resetting the committed offsets of a consumer group.
+pub type ResetOffsets<'a> = StaticBoxFuture<HashMap<TopicPartition<'a>, ResetOffset>>;
+
+/// The result of resetting the committed offset of the partition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResetOffset {
+    /// The committed offset before resetting, or `None` if the group has not committed any offset.
+    pub previous_offset: Option<Offset>,
+    /// The new offset
+    pub offset: Offset,
+}
+
+/// The target of resetting the committed offsets of a consumer group.
+///
+/// The new offsets are always clamped to the range of available offsets of each partition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResetOffsetsTo<'a> {
+    /// Reset to the earliest offset of the partitions.
+    Earliest,
+    /// Reset to the latest offset of the partitions.
+    Latest,
+    /// Reset to the given offset.
+    Offset(Offset),
+    /// Shift the committed offsets by N, which may be negative.
+    ShiftBy(i64),
+    /// Reset to the earliest offset whose timestamp is greater than or equal to the given timestamp,
+    /// or the latest offset if there is no such record.
+    Datetime(Timestamp),
+    /// Reset the partitions to the given offset of each partition,
+    /// only the partitions in the offsets are reset.
+    Offsets(HashMap<TopicPartition<'a>, Offset>),
+}
+
+impl<'a> ResetOffsetsTo<'a> {
+    /// Load the offsets of each partition from a CSV file.
+    ///
+    /// Each line of the file is in the `topic,partition,offset` format,
+    /// which is the same as the one exported by `kafka-consumer-groups.sh --reset-offsets --export`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Load the offsets of each partition in the `topic,partition,offset` format.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut offsets = HashMap::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields = line.rsplitn(3, ',');
+
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(offset), Some(partition_id), Some(topic_name)) => {
+                    offsets.insert(
+                        topic_partition!(topic_name.trim().to_owned(), partition_id.trim().parse::<PartitionId>()?),
+                        offset.trim().parse::<Offset>()?,
+                    );
+                }
+                _ => bail!(ErrorKind::IllegalArgument(format!("invalid offset line: {}", line))),
+            }
+        }
+
+        Ok(ResetOffsetsTo::Offsets(offsets))
+    }
+
+    /// Resolve the new offset of the partition.
+    fn resolve(
+        &self,
+        tp: &TopicPartition<'a>,
+        committed: Option<Offset>,
+        earliest: Offset,
+        latest: Offset,
+        by_time: Option<Offset>,
+    ) -> Result<Offset> {
+        let offset = match *self {
+            ResetOffsetsTo::Earliest => earliest,
+            ResetOffsetsTo::Latest => latest,
+            ResetOffsetsTo::Offset(offset) => offset,
+            ResetOffsetsTo::ShiftBy(n) => {
+                committed.ok_or_else(|| ErrorKind::NoOffsetForPartition(tp.topic_name.to_string(), tp.partition_id))?
+                    + n
+            }
+            ResetOffsetsTo::Datetime(_) => by_time.unwrap_or(latest),
+            ResetOffsetsTo::Offsets(ref offsets) => *offsets
+                .get(tp)
+                .ok_or_else(|| ErrorKind::IllegalArgument(format!("missing offset of partition {}", tp)))?,
+        };
+
+        Ok(cmp::max(earliest, cmp::min(offset, latest)))
+    }
+}
+
+/// Options for `AdminClient::reset_offsets`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResetOffsetsOptions {
+    /// Only calculate the new offsets without committing them.
+    pub dry_run: bool,
+}
+
+impl ResetOffsetsOptions {
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Ensure the group has no active member, which would overwrite the reset offsets.
+pub fn ensure_group_inactive(group_id: &str, mut groups: HashMap<String, DescribedGroup>) -> Result<()> {
+    match groups.remove(group_id) {
+        Some(ref group) if group.error_code != KafkaCode::None => bail!(ErrorKind::KafkaError(group.error_code)),
+        Some(ref group) if group.state != GROUP_STATE_EMPTY && group.state != GROUP_STATE_DEAD => {
+            bail!(ErrorKind::IllegalState(format!(
+                "offsets can only be reset if the group `{}` is inactive, but the current state is {}",
+                group_id, group.state
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The partitions to reset, which are derived from the offsets of the `Offsets` target,
+/// like `kafka-consumer-groups.sh --reset-offsets --from-file`.
+pub fn partitions_to_reset<'a>(
+    target: &ResetOffsetsTo<'a>,
+    partitions: Vec<TopicPartition<'a>>,
+) -> Vec<TopicPartition<'a>> {
+    match *target {
+        ResetOffsetsTo::Offsets(ref offsets) => offsets.keys().cloned().collect(),
+        _ => partitions,
+    }
+}
+
+/// Collect the offsets of the partitions which have been committed.
+pub fn committed_offsets<'a>(
+    topics: HashMap<String, Vec<FetchedOffset>>,
+) -> Result<HashMap<TopicPartition<'a>, Offset>> {
+    let mut offsets = HashMap::new();
+
+    for (topic_name, partitions) in topics {
+        for partition in partitions {
+            if partition.error_code != KafkaCode::None {
+                bail!(ErrorKind::KafkaError(partition.error_code))
+            }
+            if partition.offset >= 0 {
+                offsets.insert(topic_partition!(topic_name.clone(), partition.partition_id), partition.offset);
+            }
+        }
+    }
+
+    Ok(offsets)
+}
+
+/// Calculate the new offsets of the partitions.
+pub fn plan_reset_offsets<'a>(
+    target: &ResetOffsetsTo<'a>,
+    partitions: Vec<TopicPartition<'a>>,
+    committed: &HashMap<TopicPartition<'a>, Offset>,
+    earliest: &HashMap<TopicPartition<'a>, Offset>,
+    latest: &HashMap<TopicPartition<'a>, Offset>,
+    by_time: &HashMap<TopicPartition<'a>, Offset>,
+) -> Result<HashMap<TopicPartition<'a>, ResetOffset>> {
+    let mut offsets = HashMap::new();
+
+    for tp in partitions {
+        let (earliest, latest) = match (earliest.get(&tp), latest.get(&tp)) {
+            (Some(&earliest), Some(&latest)) => (earliest, latest),
+            _ => bail!(ErrorKind::NoOffsetForPartition(tp.topic_name.to_string(), tp.partition_id)),
+        };
+        let previous_offset = committed.get(&tp).cloned();
+        let by_time = by_time.get(&tp).cloned().and_then(|offset| if offset < 0 { None } else { Some(offset) });
+
+        let offset = target.resolve(&tp, previous_offset, earliest, latest, by_time)?;
+
+        offsets.insert(tp, ResetOffset { previous_offset, offset });
+    }
+
+    Ok(offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(target: ResetOffsetsTo<'static>, committed: Option<Offset>) -> Result<Offset> {
+        let tp = topic_partition!("topic", 0);
+        let offsets = |offset: Option<Offset>| offset.map(|offset| (tp.clone(), offset)).into_iter().collect();
+
+        plan_reset_offsets(
+            &target,
+            vec![tp.clone()],
+            &offsets(committed),
+            &offsets(Some(100)),
+            &offsets(Some(200)),
+            &offsets(Some(150)),
+        ).map(|mut offsets| offsets.remove(&tp).unwrap().offset)
+    }
+
+    #[test]
+    fn test_plan_reset_offsets() {
+        assert_eq!(plan(ResetOffsetsTo::Earliest, Some(120)).unwrap(), 100);
+        assert_eq!(plan(ResetOffsetsTo::Latest, Some(120)).unwrap(), 200);
+        assert_eq!(plan(ResetOffsetsTo::Offset(123), None).unwrap(), 123);
+        assert_eq!(plan(ResetOffsetsTo::Offset(50), None).unwrap(), 100);
+        assert_eq!(plan(ResetOffsetsTo::Offset(500), None).unwrap(), 200);
+        assert_eq!(plan(ResetOffsetsTo::ShiftBy(-10), Some(120)).unwrap(), 110);
+        assert_eq!(plan(ResetOffsetsTo::ShiftBy(100), Some(120)).unwrap(), 200);
+        assert!(plan(ResetOffsetsTo::ShiftBy(10), None).is_err());
+        assert_eq!(plan(ResetOffsetsTo::Datetime(1000), None).unwrap(), 150);
+
+        let offsets = vec![(topic_partition!("topic", 0), 180)].into_iter().collect();
+
+        assert_eq!(plan(ResetOffsetsTo::Offsets(offsets), None).unwrap(), 180);
+        assert!(plan(ResetOffsetsTo::Offsets(HashMap::new()), None).is_err());
+    }
+
+    #[test]
+    fn test_partitions_to_reset() {
+        let partitions = vec![topic_partition!("topic", 0), topic_partition!("topic", 1)];
+
+        assert_eq!(partitions_to_reset(&ResetOffsetsTo::Earliest, partitions.clone()), partitions);
+
+        let target = ResetOffsetsTo::from_reader(&b"topic,1,100\nother,0,200\n"[..]).unwrap();
+        let mut partitions = partitions_to_reset(&target, partitions);
+
+        partitions.sort();
+
+        assert_eq!(partitions, vec![topic_partition!("other", 0), topic_partition!("topic", 1)]);
+    }
+
+    #[test]
+    fn test_reset_offsets_from_reader() {
+        let data = b"topic,0,100\nother,topic,1,200\n\n";
+
+        assert_eq!(
+            ResetOffsetsTo::from_reader(&data[..]).unwrap(),
+            ResetOffsetsTo::Offsets(
+                vec![(topic_partition!("topic", 0), 100), (topic_partition!("other,topic", 1), 200)]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        assert!(ResetOffsetsTo::from_reader(&b"topic,0"[..]).is_err());
+        assert!(ResetOffsetsTo::from_reader(&b"topic,x,100"[..]).is_err());
+    }
+}
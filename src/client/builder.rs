@@ -1,11 +1,14 @@
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::time::Duration;
 
 use tokio_core::reactor::Handle;
 
 use client::{ClientConfig, KafkaClient, KafkaVersion};
 use errors::{ErrorKind, Result};
+use network::{SaslMechanism, SecurityProtocol, TokenProvider};
 use protocol::ToMilliseconds;
 
 /// A `KafkaClient` builder easing the process of setting up various
@@ -14,6 +17,7 @@ use protocol::ToMilliseconds;
 pub struct ClientBuilder<'a> {
     config: ClientConfig,
     handle: Option<Handle>,
+    token_provider: Option<Rc<TokenProvider>>,
     phantom: PhantomData<&'a u8>,
 }
 
@@ -37,6 +41,7 @@ impl<'a> ClientBuilder<'a> {
         ClientBuilder {
             config,
             handle: Some(handle),
+            token_provider: None,
             phantom: PhantomData,
         }
     }
@@ -66,6 +71,26 @@ impl<'a> ClientBuilder<'a> {
         self
     }
 
+    /// Sets the maximum number of unacknowledged requests the client will send on a single
+    /// connection before blocking.
+    pub fn with_max_in_flight_requests_per_connection(mut self, max_in_flight_requests: usize) -> Self {
+        self.config.max_in_flight_requests_per_connection = max_in_flight_requests;
+        self
+    }
+
+    /// Sets the base amount of time to wait before attempting to reconnect to a given broker.
+    pub fn with_reconnect_backoff(mut self, reconnect_backoff: Duration) -> Self {
+        self.config.reconnect_backoff = reconnect_backoff.as_millis();
+        self
+    }
+
+    /// Sets the maximum amount of time to wait when reconnecting to a broker
+    /// that has repeatedly failed to connect.
+    pub fn with_reconnect_backoff_max(mut self, reconnect_backoff_max: Duration) -> Self {
+        self.config.reconnect_backoff_max = reconnect_backoff_max.as_millis();
+        self
+    }
+
     /// Sets the maximum amount of time the client will wait for the response
     /// of a request.
     pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
@@ -98,6 +123,62 @@ impl<'a> ClientBuilder<'a> {
         self.config.metrics = true;
         self
     }
+
+    /// Sets the protocol used to communicate with brokers.
+    pub fn with_security_protocol(mut self, security_protocol: SecurityProtocol) -> Self {
+        self.config.security_protocol = security_protocol;
+        self
+    }
+
+    /// Sets the CA certificates in PEM format to verify the broker's certificate.
+    pub fn with_ssl_ca_location<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.ssl_ca_location = Some(path.into());
+        self
+    }
+
+    /// Sets the client's certificate and PKCS#8 private key in PEM format.
+    pub fn with_ssl_certificate<P: Into<PathBuf>>(mut self, certificate: P, key: P) -> Self {
+        self.config.ssl_certificate_location = Some(certificate.into());
+        self.config.ssl_key_location = Some(key.into());
+        self
+    }
+
+    /// Sets the client's keystore in PKCS#12 format.
+    pub fn with_ssl_keystore<P: Into<PathBuf>>(mut self, path: P, password: Option<String>) -> Self {
+        self.config.ssl_keystore_location = Some(path.into());
+        self.config.ssl_keystore_password = password;
+        self
+    }
+
+    /// Sets to skip verifying the broker's hostname against its certificate.
+    pub fn without_ssl_hostname_verification(mut self) -> Self {
+        self.config.ssl_hostname_verification = false;
+        self
+    }
+
+    /// Sets the hostname used for SNI and certificate verification.
+    pub fn with_ssl_sni_hostname(mut self, hostname: String) -> Self {
+        self.config.ssl_sni_hostname = Some(hostname);
+        self
+    }
+
+    /// Sets the SASL mechanism and credentials to authenticate the connections.
+    pub fn with_sasl_credentials(mut self, mechanism: SaslMechanism, username: String, password: String) -> Self {
+        self.config.sasl_mechanism = mechanism;
+        self.config.sasl_username = Some(username);
+        self.config.sasl_password = Some(password);
+        self
+    }
+
+    /// Sets the provider of bearer tokens to authenticate the connections with the `OAUTHBEARER` mechanism.
+    pub fn with_token_provider<P>(mut self, token_provider: P) -> Self
+    where
+        P: TokenProvider + 'static,
+    {
+        self.config.sasl_mechanism = SaslMechanism::OAuthBearer;
+        self.token_provider = Some(Rc::new(token_provider));
+        self
+    }
 }
 
 impl<'a> ClientBuilder<'a>
@@ -107,6 +188,11 @@ where
     pub fn build(self) -> Result<KafkaClient<'a>> {
         let handle = self.handle.ok_or(ErrorKind::ConfigError("missed handle"))?;
 
-        Ok(KafkaClient::new(self.config, handle))
+        if let Some(tls) = self.config.tls() {
+            tls.connector()?;
+        }
+        self.config.sasl(self.token_provider.clone())?;
+
+        Ok(KafkaClient::with_token_provider(self.config, handle, self.token_provider))
     }
 }
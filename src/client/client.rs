@@ -5,7 +5,7 @@ use std::error::Error as StdError;
 use std::iter::{self, FromIterator};
 use std::mem;
 use std::cmp;
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::ops::Deref;
 use std::rc::Rc;
 use std::time::Duration;
@@ -25,14 +25,16 @@ use ns_std_threaded::ThreadedResolver;
 use abstract_ns::HostResolve;
 
 use client::middleware::Timeout as TimeoutMiddleware;
-use client::{Broker, BrokerRef, ClientBuilder, ClientConfig, Cluster, FutureResponse, InFlightMiddleware,
+use client::{Broker, BrokerRef, ClientBuilder, ClientConfig, Cluster, FetchSessions, FutureResponse, InFlightMiddleware,
              KafkaService, Metadata, Metrics};
 use errors::{Error, Result};
 use errors::ErrorKind::{self, *};
-use network::{KafkaRequest, KafkaResponse, OffsetAndMetadata, TopicPartition, DEFAULT_PORT};
-use protocol::{ApiKeys, ApiVersion, CorrelationId, ErrorCode, FetchOffset, FetchPartition, FetchTopic, FetchTopicData,
-               GenerationId, JoinGroupMember, JoinGroupProtocol, KafkaCode, Message, MessageSet, Offset, PartitionId,
-               RequiredAcks, SyncGroupAssignment, Timestamp, UsableApiVersions, DEFAULT_RESPONSE_MAX_BYTES};
+use network::{ConfigResource, ConnectionStates, CorrelationIds, KafkaConnector, KafkaRequest, KafkaResponse,
+              NewPartitions, NewTopic, OffsetAndMetadata, TokenProvider, TopicPartition, DEFAULT_PORT};
+use protocol::{AclBinding, AclBindingFilter, ApiKeys, ApiVersion, ConfigResourceType, ConfigSource, CoordinatorType,
+               CorrelationId, ErrorCode, FetchOffset, FetchTopicData, GenerationId, IsolationLevel, JoinGroupMember,
+               JoinGroupProtocol, KafkaCode, Message, MessageSet, NodeId, Offset, PartitionId, PatternType,
+               ProducerEpoch, ProducerId, RequiredAcks, SyncGroupAssignment, Timestamp, UsableApiVersions};
 
 /// A trait for communicating with the Kafka cluster.
 pub trait Client<'a>: 'static {
@@ -47,6 +49,7 @@ pub trait Client<'a>: 'static {
     /// the response information.
     fn produce_records(
         &self,
+        transactional_id: Option<Cow<'a, str>>,
         acks: RequiredAcks,
         timeout: Duration,
         topic_partition: TopicPartition<'a>,
@@ -60,12 +63,14 @@ pub trait Client<'a>: 'static {
         fetch_max_wait: Duration,
         fetch_min_bytes: usize,
         fetch_max_bytes: usize,
+        isolation_level: IsolationLevel,
+        rack_id: Option<Cow<'a, str>>,
         partitions: Vec<(TopicPartition<'a>, PartitionData)>,
     ) -> FetchRecords;
 
     /// Search the offsets by target times for the specified topics and return a future which
     /// will eventually contain the partition offset information.
-    fn list_offsets<I>(&self, partitions: I) -> ListOffsets
+    fn list_offsets<I>(&self, isolation_level: IsolationLevel, partitions: I) -> ListOffsets
     where
         I: 'static + IntoIterator<Item = (TopicPartition<'a>, FetchOffset)>;
 
@@ -91,6 +96,10 @@ pub trait Client<'a>: 'static {
     where
         I: 'static + IntoIterator<Item = TopicPartition<'a>>;
 
+    /// Fetch all the committed offsets of the consumer group from the coordinator,
+    /// which requires OffsetFetch v2 or later.
+    fn group_offsets(&self, coordinator: BrokerRef, group_id: Cow<'a, str>) -> OffsetFetch;
+
     /// Discover the current coordinator of the consumer group.
     fn group_coordinator(&self, group_id: Cow<'a, str>) -> GroupCoordinator;
 
@@ -119,6 +128,116 @@ pub trait Client<'a>: 'static {
         generation: Generation,
         group_assignment: Option<Vec<ConsumerGroupAssignment<'a>>>,
     ) -> SyncGroup;
+
+    /// Discover the current transaction coordinator of the transactional producer.
+    fn transaction_coordinator(&self, transactional_id: Cow<'a, str>) -> TransactionCoordinator;
+
+    /// Initialize the producer id and epoch of the idempotent or transactional producer.
+    ///
+    /// The request of a transactional producer must be sent to its transaction coordinator.
+    fn init_producer_id(
+        &self,
+        coordinator: Option<BrokerRef>,
+        transactional_id: Option<Cow<'a, str>>,
+        transaction_timeout: Duration,
+    ) -> InitProducerId;
+
+    /// Add the partitions to the ongoing transaction.
+    fn add_partitions_to_txn<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        partitions: I,
+    ) -> AddPartitionsToTxn
+    where
+        I: 'static + IntoIterator<Item = TopicPartition<'a>>;
+
+    /// Add the offsets of the consumer group to the ongoing transaction.
+    fn add_offsets_to_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        group_id: Cow<'a, str>,
+    ) -> AddOffsetsToTxn;
+
+    /// Commit or abort the ongoing transaction.
+    fn end_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        committed: bool,
+    ) -> EndTxn;
+
+    /// Commit the offsets of the consumer group to its group coordinator as a part of the ongoing transaction.
+    fn txn_offset_commit<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        group_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        offsets: I,
+    ) -> TxnOffsetCommit
+    where
+        I: 'static + IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>;
+
+    /// Create the topics on the controller of the Kafka cluster.
+    ///
+    /// When `validate_only` is set, the request is validated but the topics are not created.
+    fn create_topics(&self, topics: Vec<NewTopic<'a>>, timeout: Duration, validate_only: bool) -> CreateTopics;
+
+    /// Delete the topics on the controller of the Kafka cluster.
+    fn delete_topics(&self, topic_names: Vec<Cow<'a, str>>, timeout: Duration) -> DeleteTopics;
+
+    /// Increase the number of partitions of the topics on the controller of the Kafka cluster.
+    fn create_partitions(
+        &self,
+        topics: Vec<(Cow<'a, str>, NewPartitions)>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> CreatePartitions;
+
+    /// Delete the records of the partitions before the given offsets on their leaders.
+    fn delete_records<I>(&self, offsets: I, timeout: Duration) -> DeleteRecords
+    where
+        I: 'static + IntoIterator<Item = (TopicPartition<'a>, Offset)>;
+
+    /// Describe the ACL bindings which match the filter.
+    fn describe_acls(&self, filter: AclBindingFilter<'a>) -> DescribeAcls;
+
+    /// Create the ACL bindings.
+    fn create_acls(&self, acls: Vec<AclBinding<'a>>) -> CreateAcls;
+
+    /// Delete the ACL bindings which match the filters.
+    fn delete_acls(&self, filters: Vec<AclBindingFilter<'a>>) -> DeleteAcls;
+
+    /// Describe the configurations of the topics or brokers.
+    fn describe_configs(&self, resources: Vec<ConfigResource<'a>>) -> DescribeConfigs;
+
+    /// Update the configurations of the topics or brokers.
+    ///
+    /// The configurations replace all the existing dynamic configurations of each resource,
+    /// when `validate_only` is set, the request is validated but the configurations are not updated.
+    fn alter_configs(
+        &self,
+        configs: Vec<(ConfigResource<'a>, HashMap<String, String>)>,
+        validate_only: bool,
+    ) -> AlterConfigs;
+
+    /// List the groups managed by all the brokers of the Kafka cluster.
+    fn list_groups(&self) -> ListGroups;
+
+    /// Describe the groups on their coordinators.
+    fn describe_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DescribeGroups;
+
+    /// Delete the empty groups on their coordinators.
+    fn delete_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DeleteGroups;
+
+    /// Describe the log directories of all the brokers with the replicas of the partitions,
+    /// or all the replicas on the brokers if the partitions are not specified.
+    fn describe_log_dirs(&self, partitions: Option<Vec<TopicPartition<'a>>>) -> DescribeLogDirs;
 }
 
 /// The future of producing records.
@@ -148,8 +267,16 @@ pub struct FetchedRecords {
     pub fetch_offset: Offset,
     /// The offset at the end of the log for this partition.
     pub high_watermark: Offset,
+    /// The last stable offset (LSO) of the partition, or `None` if the broker doesn't support it.
+    pub last_stable_offset: Option<Offset>,
+    /// The log start offset of the partition, or `None` if the broker doesn't support it.
+    pub log_start_offset: Option<Offset>,
+    /// The replica which the broker suggests to fetch from, or `None` to fetch from the leader.
+    pub preferred_read_replica: Option<NodeId>,
     /// The message data fetched from this partition, in the format described
     /// above.
+    ///
+    /// The transaction markers and the records of the aborted transactions have been skipped.
     pub messages: Vec<Message>,
 }
 
@@ -190,6 +317,8 @@ pub struct PartitionData {
     pub offset: Offset,
     /// Maximum bytes to fetch.
     pub max_bytes: Option<i32>,
+    /// The replica to fetch from, or `None` to fetch from the leader.
+    pub read_replica: Option<NodeId>,
 }
 
 pub type OffsetCommit = StaticBoxFuture<HashMap<String, Vec<CommittedOffset>>>;
@@ -290,6 +419,255 @@ pub type ConsumerGroupAssignment<'a> = SyncGroupAssignment<'a>;
 /// The future of sync consumer group.
 pub type SyncGroup = StaticBoxFuture<Bytes>;
 
+/// The future of initialize producer id.
+pub type InitProducerId = StaticBoxFuture<ProducerIdAndEpoch>;
+
+/// The producer id and epoch assigned by the broker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProducerIdAndEpoch {
+    /// The producer id
+    pub producer_id: ProducerId,
+    /// The epoch of the producer id
+    pub producer_epoch: ProducerEpoch,
+}
+
+/// The future of discover transaction coodinator
+pub type TransactionCoordinator = StaticBoxFuture<Broker>;
+
+/// The future of adding partitions to the transaction.
+pub type AddPartitionsToTxn = StaticBoxFuture;
+
+/// The future of adding offsets to the transaction.
+pub type AddOffsetsToTxn = StaticBoxFuture;
+
+/// The future of committing or aborting the transaction.
+pub type EndTxn = StaticBoxFuture;
+
+/// The future of committing offsets in the transaction.
+pub type TxnOffsetCommit = StaticBoxFuture<HashMap<String, Vec<CommittedOffset>>>;
+
+/// The future of discover the controller of the Kafka cluster.
+pub type Controller = StaticBoxFuture<Broker>;
+
+/// The future of creating topics.
+pub type CreateTopics = StaticBoxFuture<HashMap<String, CreatedTopic>>;
+
+/// The result of creating topic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatedTopic {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message, or `None` if the broker doesn't support it.
+    pub error_message: Option<String>,
+}
+
+/// The future of deleting topics.
+pub type DeleteTopics = StaticBoxFuture<HashMap<String, KafkaCode>>;
+
+/// The future of creating partitions.
+pub type CreatePartitions = StaticBoxFuture<HashMap<String, CreatedPartitions>>;
+
+/// The result of creating partitions of the topic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatedPartitions {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+}
+
+/// The future of deleting records.
+pub type DeleteRecords = StaticBoxFuture<HashMap<String, Vec<DeletedRecords>>>;
+
+/// The result of deleting records of the partition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeletedRecords {
+    /// The partition id
+    pub partition_id: PartitionId,
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The low watermark of the partition after the records have been deleted
+    pub low_watermark: Offset,
+}
+
+/// The future of describing ACL bindings.
+pub type DescribeAcls = StaticBoxFuture<Vec<AclBinding<'static>>>;
+
+/// The future of creating ACL bindings.
+pub type CreateAcls = StaticBoxFuture<Vec<CreatedAcl>>;
+
+/// The result of creating ACL binding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatedAcl {
+    /// The ACL binding
+    pub acl: AclBinding<'static>,
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+}
+
+/// The future of deleting ACL bindings.
+pub type DeleteAcls = StaticBoxFuture<Vec<DeletedAcls>>;
+
+/// The result of deleting the ACL bindings which match the filter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeletedAcls {
+    /// The filter
+    pub filter: AclBindingFilter<'static>,
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+    /// The ACL bindings which matched the filter
+    pub matching_acls: Vec<MatchingAcl>,
+}
+
+/// The ACL binding which matched the filter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchingAcl {
+    /// The ACL binding
+    pub acl: AclBinding<'static>,
+    /// The error code of deleting the ACL binding
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+}
+
+/// The future of describing configurations.
+pub type DescribeConfigs = StaticBoxFuture<HashMap<ConfigResource<'static>, DescribedConfigs>>;
+
+/// The configurations of the resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribedConfigs {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+    /// The configuration entries
+    pub entries: Vec<ConfigEntry>,
+}
+
+impl DescribedConfigs {
+    /// Find the configuration entry by name.
+    pub fn get(&self, name: &str) -> Option<&ConfigEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// The configuration entry of the resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigEntry {
+    /// The configuration name
+    pub name: String,
+    /// The configuration value, or `None` if it is sensitive.
+    pub value: Option<String>,
+    /// The source of the configuration value
+    pub source: ConfigSource,
+    /// Whether the configuration is sensitive
+    pub is_sensitive: bool,
+    /// Whether the configuration is read-only
+    pub read_only: bool,
+}
+
+impl ConfigEntry {
+    /// Whether the configuration is set to the default value.
+    pub fn is_default(&self) -> bool {
+        self.source == ConfigSource::DefaultConfig
+    }
+}
+
+/// The future of altering configurations.
+pub type AlterConfigs = StaticBoxFuture<HashMap<ConfigResource<'static>, AlteredConfigs>>;
+
+/// The result of altering configurations of the resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlteredConfigs {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The error message
+    pub error_message: Option<String>,
+}
+
+/// The future of listing groups.
+pub type ListGroups = StaticBoxFuture<Vec<ListedGroup>>;
+
+/// The group managed by a coordinator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListedGroup {
+    /// The unique group id
+    pub group_id: String,
+    /// The group protocol type, or empty if there is no active group
+    pub protocol_type: String,
+    /// The coordinator of the group
+    pub coordinator: BrokerRef,
+}
+
+/// The future of describing groups.
+pub type DescribeGroups = StaticBoxFuture<HashMap<String, DescribedGroup>>;
+
+/// The state and members of the group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribedGroup {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The current state of the group
+    pub state: String,
+    /// The group protocol type
+    pub protocol_type: String,
+    /// The group protocol, or the assignment strategy of a consumer group
+    pub protocol: String,
+    /// The members of the group
+    pub members: Vec<DescribedGroupMember>,
+}
+
+/// The member of the group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribedGroupMember {
+    /// The member id assigned by the coordinator
+    pub member_id: String,
+    /// The client id used in the member's latest join group request
+    pub client_id: String,
+    /// The client host used in the request session
+    pub client_host: String,
+    /// The metadata supplied in the member's join group request
+    pub member_metadata: Bytes,
+    /// The current assignment provided by the group leader
+    pub member_assignment: Bytes,
+}
+
+/// The future of deleting groups.
+pub type DeleteGroups = StaticBoxFuture<HashMap<String, KafkaCode>>;
+
+/// The future of describing log directories.
+pub type DescribeLogDirs = StaticBoxFuture<HashMap<BrokerRef, Vec<DescribedLogDir>>>;
+
+/// The log directory of a broker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribedLogDir {
+    /// The error code
+    pub error_code: KafkaCode,
+    /// The absolute path of the log directory
+    pub log_dir: String,
+    /// The replicas in the log directory
+    pub replicas: HashMap<TopicPartition<'static>, DescribedReplica>,
+}
+
+/// The replica of a partition in a log directory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DescribedReplica {
+    /// The size of the log segments of the replica in bytes
+    pub size: i64,
+    /// The lag of the log end offset behind the high watermark of the partition,
+    /// or behind the current replica if it is a future replica
+    pub offset_lag: i64,
+    /// Whether the replica is a future replica which is being moved to the log directory
+    pub is_future: bool,
+}
+
+/// The future of discovering the coordinators of groups.
+type GroupsByCoordinator<'a> = StaticBoxFuture<HashMap<BrokerRef, Vec<Cow<'a, str>>>>;
+
 /// A Kafka client that communicate with the Kafka cluster.
 #[derive(Clone)]
 pub struct KafkaClient<'a> {
@@ -302,13 +680,16 @@ struct Inner<'a> {
     service: Rc<InFlightMiddleware<TimeoutMiddleware<KafkaService<'a>>>>,
     timer: Rc<Timer>,
     router: Rc<Router>,
+    connection_states: ConnectionStates<SocketAddr>,
     metrics: Option<Rc<Metrics>>,
+    correlation_ids: CorrelationIds,
+    /// The incremental fetch sessions with the brokers, which are shared by the fetch requests of the client.
+    fetch_sessions: FetchSessions,
     state: Rc<RefCell<State>>,
 }
 
 #[derive(Default)]
 struct State {
-    correlation_id: CorrelationId,
     metadata_status: MetadataStatus,
 }
 
@@ -336,6 +717,15 @@ where
     Self: 'static,
 {
     pub fn new(config: ClientConfig, handle: Handle) -> KafkaClient<'a> {
+        Self::with_token_provider(config, handle, None)
+    }
+
+    /// Construct a client which authenticates with the bearer tokens from the provider.
+    pub fn with_token_provider(
+        config: ClientConfig,
+        handle: Handle,
+        token_provider: Option<Rc<TokenProvider>>,
+    ) -> KafkaClient<'a> {
         trace!("create client from config: {:?}", config);
 
         let metrics = if config.metrics {
@@ -343,7 +733,10 @@ where
         } else {
             None
         };
+        let tls = config.tls();
+        let sasl = config.sasl(token_provider).expect("invalid SASL config");
         let timer = Rc::new(config.timer());
+        let connection_states = ConnectionStates::new(config.reconnect_backoff(), config.reconnect_backoff_max());
         let router = Rc::new(Router::from_config(
             &RouterConfig::new()
                 .set_fallthrough(
@@ -354,11 +747,22 @@ where
                 .done(),
             &handle,
         ));
+        let correlation_ids = CorrelationIds::default();
         let service = Rc::new(InFlightMiddleware::new(TimeoutMiddleware::new(
             KafkaService::new(
                 handle.clone(),
-                router.clone(),
+                KafkaConnector::new(
+                    handle.clone(),
+                    router.clone(),
+                    config.client_id.clone(),
+                    correlation_ids.clone(),
+                    config.security_protocol,
+                    tls,
+                    sasl,
+                ).expect("invalid TLS config"),
                 config.max_connection_idle(),
+                config.max_in_flight_requests_per_connection,
+                connection_states.clone(),
                 metrics.clone(),
             ),
             config.timer(),
@@ -370,7 +774,10 @@ where
             service,
             timer,
             router,
+            connection_states,
             metrics,
+            correlation_ids,
+            fetch_sessions: FetchSessions::default(),
             state: Rc::new(RefCell::new(State::default())),
         });
 
@@ -452,6 +859,7 @@ where
 
     fn produce_records(
         &self,
+        transactional_id: Option<Cow<'a, str>>,
         required_acks: RequiredAcks,
         timeout: Duration,
         tp: TopicPartition<'a>,
@@ -459,7 +867,9 @@ where
     ) -> ProduceRecords {
         let inner = self.inner.clone();
         self.metadata()
-            .and_then(move |metadata| inner.produce_records(&metadata, required_acks, timeout, &tp, records))
+            .and_then(move |metadata| {
+                inner.produce_records(&metadata, transactional_id, required_acks, timeout, &tp, records)
+            })
             .static_boxed()
     }
 
@@ -468,22 +878,31 @@ where
         fetch_max_wait: Duration,
         fetch_min_bytes: usize,
         fetch_max_bytes: usize,
+        isolation_level: IsolationLevel,
+        rack_id: Option<Cow<'a, str>>,
         partitions: Vec<(TopicPartition<'a>, PartitionData)>,
     ) -> FetchRecords {
         let inner = self.inner.clone();
         self.metadata()
             .and_then(move |metadata| {
                 inner
-                    .topics_by_broker(ApiKeys::Fetch, &metadata, partitions)
+                    .replicas_by_broker(&metadata, partitions)
                     .into_future()
                     .and_then(move |topics| {
-                        inner.fetch_records(fetch_max_wait, fetch_min_bytes, fetch_max_bytes, topics)
+                        inner.fetch_records(
+                            fetch_max_wait,
+                            fetch_min_bytes,
+                            fetch_max_bytes,
+                            isolation_level,
+                            rack_id,
+                            topics,
+                        )
                     })
             })
             .static_boxed()
     }
 
-    fn list_offsets<I>(&self, partitions: I) -> ListOffsets
+    fn list_offsets<I>(&self, isolation_level: IsolationLevel, partitions: I) -> ListOffsets
     where
         I: 'static + IntoIterator<Item = (TopicPartition<'a>, FetchOffset)>,
     {
@@ -493,7 +912,7 @@ where
                 inner
                     .topics_by_broker(ApiKeys::ListOffsets, &metadata, partitions)
                     .into_future()
-                    .and_then(move |topics| inner.list_offsets(topics))
+                    .and_then(move |topics| inner.list_offsets(isolation_level, topics))
             })
             .static_boxed()
     }
@@ -570,7 +989,21 @@ where
             .and_then(move |metadata| {
                 metadata
                     .find_broker(coordinator)
-                    .map(move |coordinator| inner.offset_fetch(coordinator, generation.group_id.into(), partitions))
+                    .map(move |coordinator| {
+                        inner.offset_fetch(coordinator, generation.group_id.into(), Some(partitions))
+                    })
+                    .unwrap_or_else(|| BrokerNotFound(coordinator).into())
+            })
+            .static_boxed()
+    }
+
+    fn group_offsets(&self, coordinator: BrokerRef, group_id: Cow<'a, str>) -> OffsetFetch {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                metadata
+                    .find_broker(coordinator)
+                    .map(move |coordinator| inner.offset_fetch(coordinator, group_id, None::<Vec<_>>))
                     .unwrap_or_else(|| BrokerNotFound(coordinator).into())
             })
             .static_boxed()
@@ -579,7 +1012,7 @@ where
     fn group_coordinator(&self, group_id: Cow<'a, str>) -> GroupCoordinator {
         let inner = self.inner.clone();
         self.metadata()
-            .and_then(move |metadata| inner.group_coordinator(&metadata, group_id))
+            .and_then(move |metadata| inner.find_coordinator(&metadata, group_id, CoordinatorType::Group))
             .static_boxed()
     }
 
@@ -671,51 +1104,308 @@ where
             })
             .static_boxed()
     }
-}
 
-impl<'a> Inner<'a>
-where
-    Self: 'static,
-{
-    fn next_correlation_id(&self) -> CorrelationId {
-        (*self.state).borrow_mut().next_correlation_id()
+    fn transaction_coordinator(&self, transactional_id: Cow<'a, str>) -> TransactionCoordinator {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.find_coordinator(&metadata, transactional_id, CoordinatorType::Transaction))
+            .static_boxed()
     }
 
-    fn client_id(&self) -> Option<Cow<'a, str>> {
-        self.config.client_id.clone().map(Cow::from)
+    fn init_producer_id(
+        &self,
+        coordinator: Option<BrokerRef>,
+        transactional_id: Option<Cow<'a, str>>,
+        transaction_timeout: Duration,
+    ) -> InitProducerId {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                inner.init_producer_id(&metadata, coordinator, transactional_id, transaction_timeout)
+            })
+            .static_boxed()
     }
 
-    pub fn metadata(&self) -> GetMetadata {
-        (*self.state).borrow().metadata()
+    fn add_partitions_to_txn<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        partitions: I,
+    ) -> AddPartitionsToTxn
+    where
+        I: 'static + IntoIterator<Item = TopicPartition<'a>>,
+    {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                metadata
+                    .find_broker(coordinator)
+                    .map(move |coordinator| {
+                        inner.add_partitions_to_txn(coordinator, transactional_id, producer, partitions)
+                    })
+                    .unwrap_or_else(|| BrokerNotFound(coordinator).into())
+            })
+            .static_boxed()
     }
 
-    fn send_request<'n, N>(&self, host: N, req: KafkaRequest<'a>) -> FutureResponse
+    fn add_offsets_to_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        group_id: Cow<'a, str>,
+    ) -> AddOffsetsToTxn {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                metadata
+                    .find_broker(coordinator)
+                    .map(move |coordinator| inner.add_offsets_to_txn(coordinator, transactional_id, producer, group_id))
+                    .unwrap_or_else(|| BrokerNotFound(coordinator).into())
+            })
+            .static_boxed()
+    }
+
+    fn end_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        committed: bool,
+    ) -> EndTxn {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                metadata
+                    .find_broker(coordinator)
+                    .map(move |coordinator| inner.end_txn(coordinator, transactional_id, producer, committed))
+                    .unwrap_or_else(|| BrokerNotFound(coordinator).into())
+            })
+            .static_boxed()
+    }
+
+    fn txn_offset_commit<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        group_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        offsets: I,
+    ) -> TxnOffsetCommit
     where
-        N: Into<AutoName<'n>>,
+        I: 'static + IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>,
     {
-        let service = self.service.clone();
-        self.router
-            .resolve_auto(host, DEFAULT_PORT)
-            .from_err()
-            .map(|addrs| addrs.pick_one().unwrap())
-            .and_then(move |addr| service.call((addr, req)))
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                metadata
+                    .find_broker(coordinator)
+                    .map(move |coordinator| {
+                        inner.txn_offset_commit(coordinator, transactional_id, group_id, producer, offsets)
+                    })
+                    .unwrap_or_else(|| BrokerNotFound(coordinator).into())
+            })
             .static_boxed()
     }
 
-    /// Choose the node with the fewest outstanding requests which is at least eligible for
-    /// connection.
-    pub fn least_loaded_broker(&self, metadata: &Metadata) -> Result<(SocketAddr, BrokerRef)> {
-        let mut brokers = metadata.brokers().to_vec();
-
-        trace!("choose least broker from: {:?}", brokers);
+    fn create_topics(&self, topics: Vec<NewTopic<'a>>, timeout: Duration, validate_only: bool) -> CreateTopics {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                let inner1 = inner.clone();
 
-        rand::thread_rng().shuffle(&mut brokers);
+                inner
+                    .find_controller(&metadata)
+                    .and_then(move |controller| inner1.create_topics(&controller, topics, timeout, validate_only))
+            })
+            .static_boxed()
+    }
 
-        let mut in_flight_requests = usize::MAX;
-        let mut found = None;
+    fn delete_topics(&self, topic_names: Vec<Cow<'a, str>>, timeout: Duration) -> DeleteTopics {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                let inner1 = inner.clone();
+
+                inner
+                    .find_controller(&metadata)
+                    .and_then(move |controller| inner1.delete_topics(&controller, topic_names, timeout))
+            })
+            .static_boxed()
+    }
+
+    fn create_partitions(
+        &self,
+        topics: Vec<(Cow<'a, str>, NewPartitions)>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> CreatePartitions {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                let inner1 = inner.clone();
+
+                inner.find_controller(&metadata).and_then(move |controller| {
+                    inner1.create_partitions(&controller, topics, timeout, validate_only)
+                })
+            })
+            .static_boxed()
+    }
+
+    fn delete_records<I>(&self, offsets: I, timeout: Duration) -> DeleteRecords
+    where
+        I: 'static + IntoIterator<Item = (TopicPartition<'a>, Offset)>,
+    {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                inner
+                    .topics_by_broker(ApiKeys::DeleteRecords, &metadata, offsets)
+                    .into_future()
+                    .and_then(move |topics| inner.delete_records(topics, timeout))
+            })
+            .static_boxed()
+    }
+
+    fn describe_acls(&self, filter: AclBindingFilter<'a>) -> DescribeAcls {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.describe_acls(&metadata, filter))
+            .static_boxed()
+    }
+
+    fn create_acls(&self, acls: Vec<AclBinding<'a>>) -> CreateAcls {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.create_acls(&metadata, acls))
+            .static_boxed()
+    }
+
+    fn delete_acls(&self, filters: Vec<AclBindingFilter<'a>>) -> DeleteAcls {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.delete_acls(&metadata, filters))
+            .static_boxed()
+    }
+
+    fn describe_configs(&self, resources: Vec<ConfigResource<'a>>) -> DescribeConfigs {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.describe_configs(&metadata, resources))
+            .static_boxed()
+    }
+
+    fn alter_configs(
+        &self,
+        configs: Vec<(ConfigResource<'a>, HashMap<String, String>)>,
+        validate_only: bool,
+    ) -> AlterConfigs {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.alter_configs(&metadata, configs, validate_only))
+            .static_boxed()
+    }
+
+    fn list_groups(&self) -> ListGroups {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.list_groups(&metadata))
+            .static_boxed()
+    }
+
+    fn describe_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DescribeGroups {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                let inner1 = inner.clone();
+
+                inner
+                    .groups_by_coordinator(&metadata, group_ids)
+                    .and_then(move |groups| inner1.describe_groups(&metadata, groups))
+            })
+            .static_boxed()
+    }
+
+    fn delete_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DeleteGroups {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| {
+                let inner1 = inner.clone();
+
+                inner
+                    .groups_by_coordinator(&metadata, group_ids)
+                    .and_then(move |groups| inner1.delete_groups(&metadata, groups))
+            })
+            .static_boxed()
+    }
+
+    fn describe_log_dirs(&self, partitions: Option<Vec<TopicPartition<'a>>>) -> DescribeLogDirs {
+        let inner = self.inner.clone();
+        self.metadata()
+            .and_then(move |metadata| inner.describe_log_dirs(&metadata, partitions))
+            .static_boxed()
+    }
+}
+
+impl<'a> Inner<'a>
+where
+    Self: 'static,
+{
+    fn next_correlation_id(&self) -> CorrelationId {
+        self.correlation_ids.next_correlation_id()
+    }
+
+    fn client_id(&self) -> Option<Cow<'a, str>> {
+        self.config.client_id.clone().map(Cow::from)
+    }
+
+    pub fn metadata(&self) -> GetMetadata {
+        (*self.state).borrow().metadata()
+    }
+
+    fn send_request<'n, N>(&self, host: N, req: KafkaRequest<'a>) -> FutureResponse
+    where
+        N: Into<AutoName<'n>>,
+    {
+        let service = self.service.clone();
+        let name = host.into();
+        // the hostname is used to verify the broker's certificate after the address was resolved
+        let hostname = hostname(&name);
+
+        self.router
+            .resolve_auto(name, DEFAULT_PORT)
+            .from_err()
+            .map(|addrs| addrs.pick_one().unwrap())
+            .and_then(move |addr| service.call((addr, hostname, req)))
+            .static_boxed()
+    }
+
+    /// Choose the node with the fewest outstanding requests which is at least eligible for
+    /// connection.
+    pub fn least_loaded_broker(&self, metadata: &Metadata) -> Result<(SocketAddr, BrokerRef)> {
+        let mut brokers = metadata.brokers().to_vec();
+
+        trace!("choose least broker from: {:?}", brokers);
+
+        rand::thread_rng().shuffle(&mut brokers);
+
+        let mut in_flight_requests = usize::MAX;
+        let mut found = None;
+
+        for broker in brokers {
+            for addr in broker.addr().to_socket_addrs()? {
+                if let Some(backoff) = self.connection_states.remaining_backoff(&addr) {
+                    trace!(
+                        "skip broker #{} @ {} which is backing off for {:?}",
+                        broker.id(),
+                        addr,
+                        backoff
+                    );
+
+                    continue;
+                }
 
-        for broker in brokers {
-            for addr in broker.addr().to_socket_addrs()? {
                 match self.service.in_flight_requests(&addr) {
                     Some(0) => {
                         trace!(
@@ -747,17 +1437,27 @@ where
                 (addr, broker)
             })
             .or_else(|| {
-                metadata.brokers().first().map(|broker| {
-                    let addr = broker.addr().to_socket_addrs().unwrap().next().unwrap();
+                let brokers = metadata.brokers();
 
-                    trace!(
-                        "not found any alive broker, use a random broker # {} @ {}",
-                        broker.id(),
-                        addr
-                    );
+                brokers
+                    .iter()
+                    .find(|broker| {
+                        broker.addr().to_socket_addrs().map_or(false, |mut addrs| {
+                            addrs.all(|addr| !self.connection_states.is_backing_off(&addr))
+                        })
+                    })
+                    .or_else(|| brokers.first())
+                    .map(|broker| {
+                        let addr = broker.addr().to_socket_addrs().unwrap().next().unwrap();
 
-                    (addr, broker.as_ref())
-                })
+                        trace!(
+                            "not found any alive broker, use a random broker # {} @ {}",
+                            broker.id(),
+                            addr
+                        );
+
+                        (addr, broker.as_ref())
+                    })
             })
             .ok_or_else(|| {
                 warn!("not found any broker");
@@ -852,6 +1552,7 @@ where
     fn produce_records(
         &self,
         metadata: &Metadata,
+        transactional_id: Option<Cow<'a, str>>,
         required_acks: RequiredAcks,
         timeout: Duration,
         tp: &TopicPartition<'a>,
@@ -871,6 +1572,7 @@ where
             api_version,
             self.next_correlation_id(),
             self.client_id(),
+            transactional_id,
             required_acks,
             timeout,
             tp,
@@ -935,31 +1637,48 @@ where
         Ok(topics)
     }
 
+    /// Group the partitions by the replica to fetch from, which is the leader unless another replica is preferred.
+    fn replicas_by_broker(
+        &self,
+        metadata: &Metadata,
+        partitions: Vec<(TopicPartition<'a>, PartitionData)>,
+    ) -> Result<TopicsByBroker<'a, PartitionData>> {
+        let mut topics = HashMap::new();
+
+        for (tp, fetch_data) in partitions {
+            let broker = fetch_data
+                .read_replica
+                .and_then(|node_id| metadata.find_broker(BrokerRef::new(node_id)))
+                .or_else(|| metadata.leader_for(&tp))
+                .ok_or_else(|| KafkaError(KafkaCode::NotLeaderForPartition))?;
+            let api_version = broker.api_version(ApiKeys::Fetch).unwrap_or_default();
+
+            topics
+                .entry(((broker.host().to_owned(), broker.port()), api_version))
+                .or_insert_with(HashMap::new)
+                .entry(tp.topic_name)
+                .or_insert_with(Vec::new)
+                .push((tp.partition_id, fetch_data));
+        }
+
+        Ok(topics)
+    }
+
     fn fetch_records(
         &self,
         fetch_max_wait: Duration,
         fetch_min_bytes: usize,
         fetch_max_bytes: usize,
+        isolation_level: IsolationLevel,
+        rack_id: Option<Cow<'a, str>>,
         topics: TopicsByBroker<'a, PartitionData>,
     ) -> FetchRecords {
+        let sessions = &self.fetch_sessions;
         let requests = {
             let mut requests = Vec::new();
 
-            for (((host, port), api_version), offsets_by_topic) in topics {
-                let fetch_topics = offsets_by_topic
-                    .iter()
-                    .map(|(topic_name, partitions)| FetchTopic {
-                        topic_name: topic_name.to_owned(),
-                        partitions: partitions
-                            .iter()
-                            .map(|&(partition_id, ref fetch_data)| FetchPartition {
-                                partition_id,
-                                fetch_offset: fetch_data.offset,
-                                max_bytes: fetch_data.max_bytes.unwrap_or(DEFAULT_RESPONSE_MAX_BYTES),
-                            })
-                            .collect(),
-                    })
-                    .collect();
+            for ((broker, api_version), offsets_by_topic) in topics {
+                let session = sessions.build(&broker, api_version, &offsets_by_topic);
 
                 let request = KafkaRequest::fetch_records(
                     api_version,
@@ -968,15 +1687,37 @@ where
                     fetch_max_wait,
                     fetch_min_bytes as i32,
                     fetch_max_bytes as i32,
-                    fetch_topics,
+                    isolation_level,
+                    session.session_id,
+                    session.session_epoch,
+                    session.topics,
+                    session.forgotten_topics,
+                    rack_id.clone().unwrap_or_default(),
                 );
-                let request = self.send_request(AutoName::HostPort(&host, port), request)
-                    .and_then(|res| {
-                        if let KafkaResponse::Fetch(res) = res {
+                let response = self.send_request(AutoName::HostPort(&broker.0, broker.1), request);
+                let sessions = sessions.clone();
+                let request = response
+                    .then(move |res| match res {
+                        Ok(KafkaResponse::Fetch(res)) => {
+                            sessions.handle_response(
+                                &broker,
+                                api_version,
+                                res.error_code.unwrap_or_default().into(),
+                                res.session_id,
+                            );
+
                             Ok((res.throttle_time, res.topics))
-                        } else {
+                        }
+                        Ok(res) => {
+                            sessions.reset(&broker);
+
                             bail!(UnexpectedResponse(res.api_key()))
                         }
+                        Err(err) => {
+                            sessions.reset(&broker);
+
+                            Err(err)
+                        }
                     })
                     .map(|(throttle_time, topics)| {
                         (
@@ -1051,7 +1792,16 @@ where
                                 error_code: data.error_code.into(),
                                 fetch_offset: fetch.offset,
                                 high_watermark: data.high_watermark,
-                                messages: data.message_set.messages,
+                                last_stable_offset: data.last_stable_offset,
+                                log_start_offset: data.log_start_offset,
+                                preferred_read_replica: data.preferred_read_replica.and_then(|node_id| {
+                                    if node_id < 0 {
+                                        None
+                                    } else {
+                                        Some(node_id)
+                                    }
+                                }),
+                                messages: data.into_messages(),
                             })
                         })
                         .collect()
@@ -1062,15 +1812,20 @@ where
             .collect()
     }
 
-    fn list_offsets(&self, topics: TopicsByBroker<'a, FetchOffset>) -> ListOffsets {
+    fn list_offsets(&self, isolation_level: IsolationLevel, topics: TopicsByBroker<'a, FetchOffset>) -> ListOffsets {
         debug!("list offsets of topics: {:?}", topics);
 
         let requests = {
             let mut requests = Vec::new();
 
             for (((host, port), api_version), topics) in topics {
-                let request =
-                    KafkaRequest::list_offsets(api_version, self.next_correlation_id(), self.client_id(), topics);
+                let request = KafkaRequest::list_offsets(
+                    api_version,
+                    self.next_correlation_id(),
+                    self.client_id(),
+                    isolation_level,
+                    topics,
+                );
                 let request = self.send_request(AutoName::HostPort(&host, port), request)
                     .and_then(|res| {
                         if let KafkaResponse::ListOffsets(res) = res {
@@ -1179,7 +1934,8 @@ where
             .static_boxed()
     }
 
-    fn offset_fetch<I>(&self, coordinator: &Broker, group_id: Cow<'a, str>, partitions: I) -> OffsetFetch
+    /// Fetch the committed offsets of the partitions, or all the committed offsets of the group if `None`.
+    fn offset_fetch<I>(&self, coordinator: &Broker, group_id: Cow<'a, str>, partitions: Option<I>) -> OffsetFetch
     where
         I: IntoIterator<Item = TopicPartition<'a>>,
     {
@@ -1189,6 +1945,10 @@ where
 
         let api_version = coordinator.api_version(ApiKeys::OffsetFetch).unwrap_or_default();
 
+        if partitions.is_none() && api_version < 2 {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
         let request = KafkaRequest::offset_fetch(
             api_version,
             self.next_correlation_id(),
@@ -1200,7 +1960,12 @@ where
         self.send_request(addr, request)
             .and_then(|res| {
                 if let KafkaResponse::OffsetFetch(res) = res {
-                    Ok(res.topics)
+                    match res.error_code {
+                        Some(error_code) if error_code != KafkaCode::None as ErrorCode => {
+                            bail!(KafkaError(error_code.into()))
+                        }
+                        _ => Ok(res.topics),
+                    }
                 } else {
                     bail!(UnexpectedResponse(res.api_key()))
                 }
@@ -1227,27 +1992,44 @@ where
             .static_boxed()
     }
 
-    fn group_coordinator(&self, metadata: &Metadata, group_id: Cow<'a, str>) -> GroupCoordinator {
-        debug!("disover group coordinator of group `{}`", group_id);
+    fn find_coordinator(
+        &self,
+        metadata: &Metadata,
+        coordinator_key: Cow<'a, str>,
+        coordinator_type: CoordinatorType,
+    ) -> GroupCoordinator {
+        debug!("disover {:?} coordinator of `{}`", coordinator_type, coordinator_key);
 
-        let addr = {
+        let (addr, hostname, api_version) = {
             match self.least_loaded_broker(metadata) {
-                Ok((addr, _)) => addr,
+                Ok((addr, broker)) => (
+                    addr,
+                    metadata.find_broker(broker).map(|broker| broker.host().to_owned()),
+                    metadata
+                        .find_broker(broker)
+                        .and_then(|broker| broker.api_version(ApiKeys::GroupCoordinator))
+                        .unwrap_or_default(),
+                ),
                 Err(err) => {
                     return err.into();
                 }
             }
         };
 
+        if coordinator_type != CoordinatorType::Group && api_version < 1 {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
         let request = KafkaRequest::group_coordinator(
-            0, // api_version,
+            api_version,
             self.next_correlation_id(),
             self.client_id(),
-            group_id,
+            coordinator_key,
+            coordinator_type,
         );
 
         self.service
-            .call((addr, request))
+            .call((addr, hostname, request))
             .and_then(|res| {
                 if let KafkaResponse::GroupCoordinator(res) = res {
                     Ok(res)
@@ -1433,49 +2215,1141 @@ where
             })
             .static_boxed()
     }
-}
 
-pub type FetchMetadata = StaticBoxFuture<Rc<Metadata>>;
-pub type FetchApiVersions = StaticBoxFuture<UsableApiVersions>;
-pub type LoadApiVersions = StaticBoxFuture<HashMap<BrokerRef, UsableApiVersions>>;
+    fn init_producer_id(
+        &self,
+        metadata: &Metadata,
+        coordinator: Option<BrokerRef>,
+        transactional_id: Option<Cow<'a, str>>,
+        transaction_timeout: Duration,
+    ) -> InitProducerId {
+        debug!("initialize producer id of the `{:?}` transaction", transactional_id);
+
+        let coordinator = match coordinator {
+            Some(coordinator) => coordinator,
+            None => match self.least_loaded_broker(metadata) {
+                Ok((_, broker)) => broker,
+                Err(err) => {
+                    return err.into();
+                }
+            },
+        };
 
-type TopicsByBroker<'a, T> = HashMap<((String, u16), ApiVersion), HashMap<Cow<'a, str>, Vec<(PartitionId, T)>>>;
+        let broker = match metadata.find_broker(coordinator) {
+            Some(broker) => broker,
+            None => {
+                return BrokerNotFound(coordinator).into();
+            }
+        };
 
-impl State {
-    pub fn next_correlation_id(&mut self) -> CorrelationId {
-        self.correlation_id = self.correlation_id.wrapping_add(1);
-        self.correlation_id - 1
-    }
+        let addr = AutoName::HostPort(broker.host(), broker.port());
 
-    pub fn metadata(&self) -> GetMetadata {
-        match self.metadata_status {
-            MetadataStatus::Loading(ref senders) => {
-                let (sender, receiver) = oneshot::channel();
-                senders.borrow_mut().push(sender);
-                GetMetadata::Loading(receiver)
-            }
-            MetadataStatus::Loaded(ref metadata) => GetMetadata::Loaded(metadata.clone()),
-        }
-    }
+        let api_version = broker.api_version(ApiKeys::InitProducerId).unwrap_or_default();
 
-    pub fn refresh_metadata(&mut self) {
-        if let MetadataStatus::Loaded(_) = self.metadata_status {
-            self.metadata_status = MetadataStatus::Loading(Default::default());
-        }
+        let request = KafkaRequest::init_producer_id(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            transactional_id,
+            transaction_timeout,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::InitProducerId(res) = res {
+                    Ok(res)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(|res| {
+                if res.error_code == KafkaCode::None as ErrorCode {
+                    Ok(ProducerIdAndEpoch {
+                        producer_id: res.producer_id,
+                        producer_epoch: res.producer_epoch,
+                    })
+                } else {
+                    bail!(KafkaError(res.error_code.into()))
+                }
+            })
+            .static_boxed()
     }
 
-    pub fn update_metadata(&mut self, metadata: &Rc<Metadata>) {
-        let status = mem::replace(&mut self.metadata_status, MetadataStatus::Loaded(metadata.clone()));
+    fn add_partitions_to_txn<I>(
+        &self,
+        coordinator: &Broker,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        partitions: I,
+    ) -> AddPartitionsToTxn
+    where
+        I: IntoIterator<Item = TopicPartition<'a>>,
+    {
+        debug!("add partitions to the `{}` transaction", transactional_id);
 
-        if let MetadataStatus::Loading(senders) = status {
-            for sender in senders.into_inner() {
-                drop(sender.send(metadata.clone()));
-            }
-        }
-    }
-}
+        let addr = AutoName::HostPort(coordinator.host(), coordinator.port());
 
-/// The future of loaded metadata
+        let api_version = coordinator.api_version(ApiKeys::AddPartitionsToTxn).unwrap_or_default();
+
+        let request = KafkaRequest::add_partitions_to_txn(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            transactional_id,
+            producer.producer_id,
+            producer.producer_epoch,
+            partitions,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::AddPartitionsToTxn(res) = res {
+                    Ok(res.topics)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(|topics| {
+                let error_code = topics
+                    .iter()
+                    .flat_map(|topic| topic.partitions.iter())
+                    .map(|partition| partition.error_code)
+                    .find(|&error_code| error_code != KafkaCode::None as ErrorCode);
+
+                if let Some(error_code) = error_code {
+                    bail!(KafkaError(error_code.into()))
+                } else {
+                    Ok(())
+                }
+            })
+            .static_boxed()
+    }
+
+    fn add_offsets_to_txn(
+        &self,
+        coordinator: &Broker,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        group_id: Cow<'a, str>,
+    ) -> AddOffsetsToTxn {
+        debug!(
+            "add offsets of the `{}` group to the `{}` transaction",
+            group_id, transactional_id
+        );
+
+        let addr = AutoName::HostPort(coordinator.host(), coordinator.port());
+
+        let api_version = coordinator.api_version(ApiKeys::AddOffsetsToTxn).unwrap_or_default();
+
+        let request = KafkaRequest::add_offsets_to_txn(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            transactional_id,
+            producer.producer_id,
+            producer.producer_epoch,
+            group_id,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::AddOffsetsToTxn(res) = res {
+                    Ok(res.error_code)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(|error_code| {
+                if error_code == KafkaCode::None as ErrorCode {
+                    Ok(())
+                } else {
+                    bail!(KafkaError(error_code.into()))
+                }
+            })
+            .static_boxed()
+    }
+
+    fn end_txn(
+        &self,
+        coordinator: &Broker,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        committed: bool,
+    ) -> EndTxn {
+        debug!(
+            "{} the `{}` transaction",
+            if committed { "commit" } else { "abort" },
+            transactional_id
+        );
+
+        let addr = AutoName::HostPort(coordinator.host(), coordinator.port());
+
+        let api_version = coordinator.api_version(ApiKeys::EndTxn).unwrap_or_default();
+
+        let request = KafkaRequest::end_txn(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            transactional_id,
+            producer.producer_id,
+            producer.producer_epoch,
+            committed,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::EndTxn(res) = res {
+                    Ok(res.error_code)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(|error_code| {
+                if error_code == KafkaCode::None as ErrorCode {
+                    Ok(())
+                } else {
+                    bail!(KafkaError(error_code.into()))
+                }
+            })
+            .static_boxed()
+    }
+
+    fn txn_offset_commit<I>(
+        &self,
+        coordinator: &Broker,
+        transactional_id: Cow<'a, str>,
+        group_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        offsets: I,
+    ) -> TxnOffsetCommit
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>,
+    {
+        debug!(
+            "commit offsets to the `{}` group in the `{}` transaction",
+            group_id, transactional_id
+        );
+
+        let addr = AutoName::HostPort(coordinator.host(), coordinator.port());
+
+        let api_version = coordinator.api_version(ApiKeys::TxnOffsetCommit).unwrap_or_default();
+
+        let request = KafkaRequest::txn_offset_commit(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            transactional_id,
+            group_id,
+            producer.producer_id,
+            producer.producer_epoch,
+            offsets,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::TxnOffsetCommit(res) = res {
+                    Ok(res.topics)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(|topics| {
+                topics
+                    .into_iter()
+                    .map(|status| {
+                        let partitions = status
+                            .partitions
+                            .into_iter()
+                            .map(|partition| CommittedOffset {
+                                partition_id: partition.partition_id,
+                                error_code: partition.error_code.into(),
+                            })
+                            .collect();
+
+                        (status.topic_name, partitions)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn find_controller(&self, metadata: &Metadata) -> Controller {
+        debug!("discover the controller of the cluster");
+
+        let (addr, hostname, api_version) = {
+            match self.least_loaded_broker(metadata) {
+                Ok((addr, broker)) => (
+                    addr,
+                    metadata.find_broker(broker).map(|broker| broker.host().to_owned()),
+                    metadata
+                        .find_broker(broker)
+                        .and_then(|broker| broker.api_version(ApiKeys::Metadata))
+                        .unwrap_or_default(),
+                ),
+                Err(err) => {
+                    return err.into();
+                }
+            }
+        };
+
+        if api_version < 1 {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
+        let request = KafkaRequest::fetch_metadata(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            &[] as &[String],
+        );
+
+        let brokers = metadata.brokers().to_vec();
+
+        self.service
+            .call((addr, hostname, request))
+            .and_then(|res| {
+                if let KafkaResponse::Metadata(res) = res {
+                    Ok(res)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(move |res| {
+                let controller_id = match res.controller_id {
+                    Some(controller_id) if controller_id >= 0 => controller_id,
+                    _ => bail!(KafkaError(KafkaCode::BrokerNotAvailable)),
+                };
+                let controller = BrokerRef::new(controller_id);
+
+                brokers
+                    .into_iter()
+                    .find(|broker| broker.as_ref() == controller)
+                    .ok_or_else(|| BrokerNotFound(controller).into())
+            })
+            .static_boxed()
+    }
+
+    fn create_topics(
+        &self,
+        controller: &Broker,
+        topics: Vec<NewTopic<'a>>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> CreateTopics {
+        debug!(
+            "create topics {:?} on the controller #{}",
+            topics.iter().map(|topic| &topic.topic_name).collect::<Vec<_>>(),
+            controller.id()
+        );
+
+        let api_version = match controller.api_version(ApiKeys::CreateTopics) {
+            Some(api_version) if !validate_only || api_version >= 1 => api_version,
+            _ => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+        };
+
+        let addr = AutoName::HostPort(controller.host(), controller.port());
+
+        let request = KafkaRequest::create_topics(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            topics,
+            timeout,
+            validate_only,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::CreateTopics(res) = res {
+                    Ok(res.topics)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(|topics| {
+                topics
+                    .into_iter()
+                    .map(|status| {
+                        let topic = CreatedTopic {
+                            error_code: status.error_code.into(),
+                            error_message: status.error_message,
+                        };
+
+                        (status.topic_name, topic)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn delete_topics(&self, controller: &Broker, topic_names: Vec<Cow<'a, str>>, timeout: Duration) -> DeleteTopics {
+        debug!("delete topics {:?} on the controller #{}", topic_names, controller.id());
+
+        let api_version = match controller.api_version(ApiKeys::DeleteTopics) {
+            Some(api_version) => api_version,
+            None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+        };
+
+        let addr = AutoName::HostPort(controller.host(), controller.port());
+
+        let request = KafkaRequest::delete_topics(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            topic_names,
+            timeout,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::DeleteTopics(res) = res {
+                    Ok(res.topics)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(|topics| {
+                topics
+                    .into_iter()
+                    .map(|status| (status.topic_name, status.error_code.into()))
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn create_partitions(
+        &self,
+        controller: &Broker,
+        topics: Vec<(Cow<'a, str>, NewPartitions)>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> CreatePartitions {
+        debug!("create partitions {:?} on the controller #{}", topics, controller.id());
+
+        let api_version = match controller.api_version(ApiKeys::CreatePartitions) {
+            Some(api_version) => api_version,
+            None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+        };
+
+        let addr = AutoName::HostPort(controller.host(), controller.port());
+
+        let request = KafkaRequest::create_partitions(
+            api_version,
+            self.next_correlation_id(),
+            self.client_id(),
+            topics,
+            timeout,
+            validate_only,
+        );
+
+        self.send_request(addr, request)
+            .and_then(|res| {
+                if let KafkaResponse::CreatePartitions(res) = res {
+                    Ok(res.topics)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(|topics| {
+                topics
+                    .into_iter()
+                    .map(|status| {
+                        let partitions = CreatedPartitions {
+                            error_code: status.error_code.into(),
+                            error_message: status.error_message,
+                        };
+
+                        (status.topic_name, partitions)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn delete_records(&self, topics: TopicsByBroker<'a, Offset>, timeout: Duration) -> DeleteRecords {
+        debug!("delete records of topics: {:?}", topics);
+
+        let requests = {
+            let mut requests = Vec::new();
+
+            for (((host, port), api_version), topics) in topics {
+                let request = KafkaRequest::delete_records(
+                    api_version,
+                    self.next_correlation_id(),
+                    self.client_id(),
+                    topics,
+                    timeout,
+                );
+                let request = self.send_request(AutoName::HostPort(&host, port), request)
+                    .and_then(|res| {
+                        if let KafkaResponse::DeleteRecords(res) = res {
+                            Ok(res.topics)
+                        } else {
+                            bail!(UnexpectedResponse(res.api_key()))
+                        }
+                    })
+                    .map(|topics| {
+                        topics
+                            .into_iter()
+                            .map(|topic| {
+                                let partitions = topic
+                                    .partitions
+                                    .into_iter()
+                                    .map(|partition| DeletedRecords {
+                                        partition_id: partition.partition_id,
+                                        error_code: partition.error_code.into(),
+                                        low_watermark: partition.low_watermark,
+                                    })
+                                    .collect::<Vec<_>>();
+
+                                (topic.topic_name, partitions)
+                            })
+                            .collect::<Vec<(String, Vec<DeletedRecords>)>>()
+                    });
+
+                requests.push(request);
+            }
+
+            requests
+        };
+
+        future::join_all(requests)
+            .map(|responses| {
+                responses.into_iter().fold(HashMap::new(), |mut records, response| {
+                    for (topic_name, mut partitions) in response {
+                        records
+                            .entry(topic_name)
+                            .or_insert_with(Vec::new)
+                            .append(&mut partitions)
+                    }
+                    records
+                })
+            })
+            .static_boxed()
+    }
+
+    /// Choose the least loaded broker which supports the API, returns its address, hostname and the API version.
+    fn least_loaded_broker_for(
+        &self,
+        metadata: &Metadata,
+        api_key: ApiKeys,
+    ) -> Result<(SocketAddr, Option<String>, ApiVersion)> {
+        let (addr, broker) = self.least_loaded_broker(metadata)?;
+        let broker = metadata.find_broker(broker);
+        let api_version = broker
+            .and_then(|broker| broker.api_version(api_key))
+            .ok_or_else(|| KafkaError(KafkaCode::UnsupportedVersion))?;
+
+        Ok((addr, broker.map(|broker| broker.host().to_owned()), api_version))
+    }
+
+    fn describe_acls(&self, metadata: &Metadata, filter: AclBindingFilter<'a>) -> DescribeAcls {
+        debug!("describe ACLs match {:?}", filter);
+
+        let (addr, hostname, api_version) = match self.least_loaded_broker_for(metadata, ApiKeys::DescribeAcls) {
+            Ok(broker) => broker,
+            Err(err) => return err.into(),
+        };
+
+        if api_version < 1 && filter.pattern_type != PatternType::Literal && filter.pattern_type != PatternType::Any {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
+        let request = KafkaRequest::describe_acls(api_version, self.next_correlation_id(), self.client_id(), filter);
+
+        self.service
+            .call((addr, hostname, request))
+            .and_then(|res| {
+                if let KafkaResponse::DescribeAcls(res) = res {
+                    Ok(res)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .and_then(|res| {
+                if res.error_code == KafkaCode::None as ErrorCode {
+                    Ok(res.resources
+                        .into_iter()
+                        .flat_map(|resource| {
+                            let pattern = resource.pattern;
+
+                            resource
+                                .acls
+                                .into_iter()
+                                .map(move |entry| AclBinding::new(pattern.clone(), entry))
+                        })
+                        .collect())
+                } else {
+                    bail!(KafkaError(res.error_code.into()))
+                }
+            })
+            .static_boxed()
+    }
+
+    fn create_acls(&self, metadata: &Metadata, acls: Vec<AclBinding<'a>>) -> CreateAcls {
+        debug!("create ACLs {:?}", acls);
+
+        let (addr, hostname, api_version) = match self.least_loaded_broker_for(metadata, ApiKeys::CreateAcls) {
+            Ok(broker) => broker,
+            Err(err) => return err.into(),
+        };
+
+        if api_version < 1 && acls.iter().any(|acl| acl.pattern.pattern_type != PatternType::Literal) {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
+        let created_acls = acls.iter().cloned().map(AclBinding::into_owned).collect::<Vec<_>>();
+
+        let request = KafkaRequest::create_acls(api_version, self.next_correlation_id(), self.client_id(), acls);
+
+        self.service
+            .call((addr, hostname, request))
+            .and_then(|res| {
+                if let KafkaResponse::CreateAcls(res) = res {
+                    Ok(res.creation_responses)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(move |creation_responses| {
+                created_acls
+                    .into_iter()
+                    .zip(creation_responses)
+                    .map(|(acl, status)| CreatedAcl {
+                        acl,
+                        error_code: status.error_code.into(),
+                        error_message: status.error_message,
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn delete_acls(&self, metadata: &Metadata, filters: Vec<AclBindingFilter<'a>>) -> DeleteAcls {
+        debug!("delete ACLs match {:?}", filters);
+
+        let (addr, hostname, api_version) = match self.least_loaded_broker_for(metadata, ApiKeys::DeleteAcls) {
+            Ok(broker) => broker,
+            Err(err) => return err.into(),
+        };
+
+        if api_version < 1 && filters.iter().any(|filter| {
+            filter.pattern_type != PatternType::Literal && filter.pattern_type != PatternType::Any
+        }) {
+            return KafkaError(KafkaCode::UnsupportedVersion).into();
+        }
+
+        let deleted_filters = filters
+            .iter()
+            .cloned()
+            .map(AclBindingFilter::into_owned)
+            .collect::<Vec<_>>();
+
+        let request = KafkaRequest::delete_acls(api_version, self.next_correlation_id(), self.client_id(), filters);
+
+        self.service
+            .call((addr, hostname, request))
+            .and_then(|res| {
+                if let KafkaResponse::DeleteAcls(res) = res {
+                    Ok(res.filter_responses)
+                } else {
+                    bail!(UnexpectedResponse(res.api_key()))
+                }
+            })
+            .map(move |filter_responses| {
+                deleted_filters
+                    .into_iter()
+                    .zip(filter_responses)
+                    .map(|(filter, status)| DeletedAcls {
+                        filter,
+                        error_code: status.error_code.into(),
+                        error_message: status.error_message,
+                        matching_acls: status
+                            .matching_acls
+                            .into_iter()
+                            .map(|matching| MatchingAcl {
+                                acl: matching.acl,
+                                error_code: matching.error_code.into(),
+                                error_message: matching.error_message,
+                            })
+                            .collect(),
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    /// Group the resources by broker.
+    ///
+    /// The request for a broker configuration must be sent to the broker itself,
+    /// the others are sent to the least loaded broker.
+    fn config_resources_by_broker<T, I, F>(
+        &self,
+        metadata: &Metadata,
+        resources: I,
+        resource_of: F,
+    ) -> Result<HashMap<BrokerRef, Vec<T>>>
+    where
+        I: IntoIterator<Item = T>,
+        F: Fn(&T) -> &ConfigResource<'a>,
+    {
+        let (_, least_loaded_broker) = self.least_loaded_broker(metadata)?;
+
+        let mut resources_by_broker = HashMap::new();
+
+        for item in resources {
+            let broker = {
+                let resource = resource_of(&item);
+
+                if resource.resource_type == ConfigResourceType::Broker {
+                    let broker_id = resource.resource_name.parse::<NodeId>().map_err(|_| {
+                        ErrorKind::IllegalArgument(format!("invalid broker id: {}", resource.resource_name))
+                    })?;
+
+                    BrokerRef::new(broker_id)
+                } else {
+                    least_loaded_broker
+                }
+            };
+
+            resources_by_broker.entry(broker).or_insert_with(Vec::new).push(item);
+        }
+
+        Ok(resources_by_broker)
+    }
+
+    fn describe_configs(&self, metadata: &Metadata, resources: Vec<ConfigResource<'a>>) -> DescribeConfigs {
+        debug!("describe configs of {:?}", resources);
+
+        let resources_by_broker = match self.config_resources_by_broker(metadata, resources, |resource| resource) {
+            Ok(resources_by_broker) => resources_by_broker,
+            Err(err) => return err.into(),
+        };
+
+        let mut responses = Vec::new();
+
+        for (broker_ref, resources) in resources_by_broker {
+            let broker = match metadata.find_broker(broker_ref) {
+                Some(broker) => broker,
+                None => return BrokerNotFound(broker_ref).into(),
+            };
+            let api_version = match broker.api_version(ApiKeys::DescribeConfigs) {
+                Some(api_version) => api_version,
+                None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+            };
+
+            let request =
+                KafkaRequest::describe_configs(api_version, self.next_correlation_id(), self.client_id(), resources);
+
+            let response = self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                .and_then(|res| {
+                    if let KafkaResponse::DescribeConfigs(res) = res {
+                        Ok(res.resources)
+                    } else {
+                        bail!(UnexpectedResponse(res.api_key()))
+                    }
+                });
+
+            responses.push(response);
+        }
+
+        future::join_all(responses)
+            .map(|responses| {
+                responses
+                    .into_iter()
+                    .flat_map(|resources| resources)
+                    .map(|status| {
+                        let resource = ConfigResource {
+                            resource_type: status.resource_type,
+                            resource_name: Cow::Owned(status.resource_name),
+                        };
+                        let configs = DescribedConfigs {
+                            error_code: status.error_code.into(),
+                            error_message: status.error_message,
+                            entries: status
+                                .config_entries
+                                .into_iter()
+                                .map(|entry| ConfigEntry {
+                                    name: entry.config_name,
+                                    value: entry.config_value,
+                                    source: entry.config_source,
+                                    is_sensitive: entry.is_sensitive,
+                                    read_only: entry.read_only,
+                                })
+                                .collect(),
+                        };
+
+                        (resource, configs)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn alter_configs(
+        &self,
+        metadata: &Metadata,
+        configs: Vec<(ConfigResource<'a>, HashMap<String, String>)>,
+        validate_only: bool,
+    ) -> AlterConfigs {
+        debug!("alter configs {:?}", configs);
+
+        let configs_by_broker = match self.config_resources_by_broker(metadata, configs, |&(ref resource, _)| resource)
+        {
+            Ok(configs_by_broker) => configs_by_broker,
+            Err(err) => return err.into(),
+        };
+
+        let mut responses = Vec::new();
+
+        for (broker_ref, configs) in configs_by_broker {
+            let broker = match metadata.find_broker(broker_ref) {
+                Some(broker) => broker,
+                None => return BrokerNotFound(broker_ref).into(),
+            };
+            let api_version = match broker.api_version(ApiKeys::AlterConfigs) {
+                Some(api_version) => api_version,
+                None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+            };
+
+            let request = KafkaRequest::alter_configs(
+                api_version,
+                self.next_correlation_id(),
+                self.client_id(),
+                configs,
+                validate_only,
+            );
+
+            let response = self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                .and_then(|res| {
+                    if let KafkaResponse::AlterConfigs(res) = res {
+                        Ok(res.resources)
+                    } else {
+                        bail!(UnexpectedResponse(res.api_key()))
+                    }
+                });
+
+            responses.push(response);
+        }
+
+        future::join_all(responses)
+            .map(|responses| {
+                responses
+                    .into_iter()
+                    .flat_map(|resources| resources)
+                    .map(|status| {
+                        let resource = ConfigResource {
+                            resource_type: status.resource_type,
+                            resource_name: Cow::Owned(status.resource_name),
+                        };
+                        let configs = AlteredConfigs {
+                            error_code: status.error_code.into(),
+                            error_message: status.error_message,
+                        };
+
+                        (resource, configs)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn list_groups(&self, metadata: &Metadata) -> ListGroups {
+        debug!("list groups of all brokers");
+
+        let responses = metadata
+            .brokers()
+            .iter()
+            .map(|broker| {
+                let coordinator = broker.as_ref();
+                let api_version = broker.api_version(ApiKeys::ListGroups).unwrap_or_default();
+
+                let request = KafkaRequest::list_groups(api_version, self.next_correlation_id(), self.client_id());
+
+                self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                    .and_then(|res| {
+                        if let KafkaResponse::ListGroups(res) = res {
+                            Ok(res)
+                        } else {
+                            bail!(UnexpectedResponse(res.api_key()))
+                        }
+                    })
+                    .and_then(move |res| {
+                        if res.error_code == KafkaCode::None as ErrorCode {
+                            Ok(res.groups
+                                .into_iter()
+                                .map(|group| ListedGroup {
+                                    group_id: group.group_id,
+                                    protocol_type: group.protocol_type,
+                                    coordinator,
+                                })
+                                .collect::<Vec<_>>())
+                        } else {
+                            bail!(KafkaError(res.error_code.into()))
+                        }
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        future::join_all(responses)
+            .map(|responses| responses.into_iter().flat_map(|groups| groups).collect())
+            .static_boxed()
+    }
+
+    /// Group the groups by their coordinators.
+    fn groups_by_coordinator(&self, metadata: &Metadata, group_ids: Vec<Cow<'a, str>>) -> GroupsByCoordinator<'a> {
+        let coordinators = group_ids
+            .into_iter()
+            .map(|group_id| {
+                self.find_coordinator(metadata, group_id.clone(), CoordinatorType::Group)
+                    .map(move |coordinator| (coordinator.as_ref(), group_id))
+            })
+            .collect::<Vec<_>>();
+
+        future::join_all(coordinators)
+            .map(|coordinators| {
+                let mut groups_by_coordinator = HashMap::new();
+
+                for (coordinator, group_id) in coordinators {
+                    groups_by_coordinator
+                        .entry(coordinator)
+                        .or_insert_with(Vec::new)
+                        .push(group_id);
+                }
+
+                groups_by_coordinator
+            })
+            .static_boxed()
+    }
+
+    fn describe_groups(&self, metadata: &Metadata, groups: HashMap<BrokerRef, Vec<Cow<'a, str>>>) -> DescribeGroups {
+        debug!("describe groups {:?}", groups);
+
+        let mut responses = Vec::new();
+
+        for (coordinator, group_ids) in groups {
+            let broker = match metadata.find_broker(coordinator) {
+                Some(broker) => broker,
+                None => return BrokerNotFound(coordinator).into(),
+            };
+            let api_version = broker.api_version(ApiKeys::DescribeGroups).unwrap_or_default();
+
+            let request =
+                KafkaRequest::describe_groups(api_version, self.next_correlation_id(), self.client_id(), group_ids);
+
+            let response = self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                .and_then(|res| {
+                    if let KafkaResponse::DescribeGroups(res) = res {
+                        Ok(res.groups)
+                    } else {
+                        bail!(UnexpectedResponse(res.api_key()))
+                    }
+                });
+
+            responses.push(response);
+        }
+
+        future::join_all(responses)
+            .map(|responses| {
+                responses
+                    .into_iter()
+                    .flat_map(|groups| groups)
+                    .map(|status| {
+                        let group = DescribedGroup {
+                            error_code: status.error_code.into(),
+                            state: status.state,
+                            protocol_type: status.protocol_type,
+                            protocol: status.protocol,
+                            members: status
+                                .members
+                                .into_iter()
+                                .map(|member| DescribedGroupMember {
+                                    member_id: member.member_id,
+                                    client_id: member.client_id,
+                                    client_host: member.client_host,
+                                    member_metadata: member.member_metadata,
+                                    member_assignment: member.member_assignment,
+                                })
+                                .collect(),
+                        };
+
+                        (status.group_id, group)
+                    })
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn delete_groups(&self, metadata: &Metadata, groups: HashMap<BrokerRef, Vec<Cow<'a, str>>>) -> DeleteGroups {
+        debug!("delete groups {:?}", groups);
+
+        let mut responses = Vec::new();
+
+        for (coordinator, group_ids) in groups {
+            let broker = match metadata.find_broker(coordinator) {
+                Some(broker) => broker,
+                None => return BrokerNotFound(coordinator).into(),
+            };
+            let api_version = match broker.api_version(ApiKeys::DeleteGroups) {
+                Some(api_version) => api_version,
+                None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+            };
+
+            let request =
+                KafkaRequest::delete_groups(api_version, self.next_correlation_id(), self.client_id(), group_ids);
+
+            let response = self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                .and_then(|res| {
+                    if let KafkaResponse::DeleteGroups(res) = res {
+                        Ok(res.groups)
+                    } else {
+                        bail!(UnexpectedResponse(res.api_key()))
+                    }
+                });
+
+            responses.push(response);
+        }
+
+        future::join_all(responses)
+            .map(|responses| {
+                responses
+                    .into_iter()
+                    .flat_map(|groups| groups)
+                    .map(|status| (status.group_id, status.error_code.into()))
+                    .collect()
+            })
+            .static_boxed()
+    }
+
+    fn describe_log_dirs(&self, metadata: &Metadata, partitions: Option<Vec<TopicPartition<'a>>>) -> DescribeLogDirs {
+        debug!("describe log dirs of {:?}", partitions);
+
+        let topics = partitions.map(|partitions| {
+            let mut topics = HashMap::new();
+
+            for tp in partitions {
+                topics
+                    .entry(tp.topic_name)
+                    .or_insert_with(Vec::new)
+                    .push(tp.partition_id);
+            }
+
+            topics
+        });
+
+        let mut responses = Vec::new();
+
+        for broker in metadata.brokers() {
+            let broker_ref = broker.as_ref();
+            let api_version = match broker.api_version(ApiKeys::DescribeLogDirs) {
+                Some(api_version) => api_version,
+                None => return KafkaError(KafkaCode::UnsupportedVersion).into(),
+            };
+
+            let request = KafkaRequest::describe_log_dirs(
+                api_version,
+                self.next_correlation_id(),
+                self.client_id(),
+                topics.clone(),
+            );
+
+            let response = self.send_request(AutoName::HostPort(broker.host(), broker.port()), request)
+                .and_then(move |res| {
+                    if let KafkaResponse::DescribeLogDirs(res) = res {
+                        let log_dirs = res.results
+                            .into_iter()
+                            .map(|result| DescribedLogDir {
+                                error_code: result.error_code.into(),
+                                log_dir: result.log_dir,
+                                replicas: result
+                                    .topics
+                                    .into_iter()
+                                    .flat_map(|topic| {
+                                        let topic_name = topic.name;
+
+                                        topic.partitions.into_iter().map(move |partition| {
+                                            (
+                                                topic_partition!(topic_name.clone(), partition.partition_index),
+                                                DescribedReplica {
+                                                    size: partition.partition_size,
+                                                    offset_lag: partition.offset_lag,
+                                                    is_future: partition.is_future_key,
+                                                },
+                                            )
+                                        })
+                                    })
+                                    .collect(),
+                            })
+                            .collect();
+
+                        Ok((broker_ref, log_dirs))
+                    } else {
+                        bail!(UnexpectedResponse(res.api_key()))
+                    }
+                });
+
+            responses.push(response);
+        }
+
+        future::join_all(responses)
+            .map(|responses| responses.into_iter().collect())
+            .static_boxed()
+    }
+}
+
+pub type FetchMetadata = StaticBoxFuture<Rc<Metadata>>;
+pub type FetchApiVersions = StaticBoxFuture<UsableApiVersions>;
+pub type LoadApiVersions = StaticBoxFuture<HashMap<BrokerRef, UsableApiVersions>>;
+
+/// The hostname of the broker, or `None` if the broker is addressed by IP.
+fn hostname(name: &AutoName) -> Option<String> {
+    let host = match *name {
+        AutoName::Auto(host) if host.parse::<SocketAddr>().is_err() && host.parse::<IpAddr>().is_err() => {
+            host.rsplitn(2, ':').last().unwrap_or(host)
+        }
+        AutoName::HostPort(host, _) => host,
+        _ => return None,
+    };
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_owned())
+    }
+}
+
+type TopicsByBroker<'a, T> = HashMap<((String, u16), ApiVersion), HashMap<Cow<'a, str>, Vec<(PartitionId, T)>>>;
+
+impl State {
+    pub fn metadata(&self) -> GetMetadata {
+        match self.metadata_status {
+            MetadataStatus::Loading(ref senders) => {
+                let (sender, receiver) = oneshot::channel();
+                senders.borrow_mut().push(sender);
+                GetMetadata::Loading(receiver)
+            }
+            MetadataStatus::Loaded(ref metadata) => GetMetadata::Loaded(metadata.clone()),
+        }
+    }
+
+    pub fn refresh_metadata(&mut self) {
+        if let MetadataStatus::Loaded(_) = self.metadata_status {
+            self.metadata_status = MetadataStatus::Loading(Default::default());
+        }
+    }
+
+    pub fn update_metadata(&mut self, metadata: &Rc<Metadata>) {
+        let status = mem::replace(&mut self.metadata_status, MetadataStatus::Loaded(metadata.clone()));
+
+        if let MetadataStatus::Loading(senders) = status {
+            for sender in senders.into_inner() {
+                drop(sender.send(metadata.clone()));
+            }
+        }
+    }
+}
+
+/// The future of loaded metadata
 pub struct LoadMetadata<'a> {
     state: Loading,
     inner: Rc<Inner<'a>>,
@@ -1621,3 +3495,18 @@ where
         StaticBoxFuture::new(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hostname() {
+        assert_eq!(hostname(&AutoName::Auto("kafka:9093")), Some("kafka".to_owned()));
+        assert_eq!(hostname(&AutoName::Auto("kafka")), Some("kafka".to_owned()));
+        assert_eq!(hostname(&AutoName::HostPort("kafka", 9093)), Some("kafka".to_owned()));
+        assert_eq!(hostname(&AutoName::Auto("127.0.0.1:9093")), None);
+        assert_eq!(hostname(&AutoName::Auto("::1")), None);
+        assert_eq!(hostname(&AutoName::SocketAddr("127.0.0.1:9093".parse().unwrap())), None);
+    }
+}
@@ -48,6 +48,9 @@ pub struct Broker {
     /// The port for this node
     port: u16,
 
+    /// The rack of this broker, which is used to select the closest replica to fetch from.
+    rack: Option<String>,
+
     /// The version ranges of requests supported by the broker.
     api_versions: Option<UsableApiVersions>,
 }
@@ -58,10 +61,17 @@ impl Broker {
             node_id: id,
             host: host.to_owned(),
             port,
+            rack: None,
             api_versions: None,
         }
     }
 
+    /// Sets the rack of this broker.
+    pub fn with_rack(mut self, rack: Option<String>) -> Self {
+        self.rack = rack;
+        self
+    }
+
     /// Retrives the node_id of this broker as identified with the
     /// remote Kafka cluster.
     pub fn id(&self) -> NodeId {
@@ -80,6 +90,11 @@ impl Broker {
         self.port
     }
 
+    /// The rack of this broker, or `None` if the broker doesn't have a rack.
+    pub fn rack(&self) -> Option<&str> {
+        self.rack.as_ref().map(|rack| rack.as_str())
+    }
+
     /// Retrieves the host:port of the this Kafka broker.
     pub fn addr(&self) -> (&str, u16) {
         (&self.host, self.port)
@@ -90,9 +105,6 @@ impl Broker {
     }
 
     pub fn api_version(&self, api_key: ApiKeys) -> Option<ApiVersion> {
-        if api_key == ApiKeys::Fetch {
-            return Some(1);
-        }
         let supported_version: i16 = SUPPORTED_API_VERSIONS
             .find(api_key).map(|v| v.max_version).unwrap_or(0);
         self.api_versions
@@ -111,6 +123,7 @@ impl Broker {
             node_id: self.node_id,
             host: self.host.clone(),
             port: self.port,
+            rack: self.rack.clone(),
             api_versions,
         }
     }
@@ -1,9 +1,14 @@
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::time::Duration;
 
 use tokio_retry::strategy::{jitter, ExponentialBackoff};
 use tokio_timer::{wheel, Timer};
 
 use client::KafkaVersion;
+use errors::{ErrorKind, Result};
+use network::{SaslConfig, SaslCredentials, SaslMechanism, SecurityProtocol, TlsConfig, TokenProvider,
+              UnsecuredJwtProvider};
 
 /// The default milliseconds after which we close the idle connections.
 ///
@@ -11,6 +16,12 @@ use client::KafkaVersion;
 /// [`ClientConfig::max_connection_idle`](struct.ClientConfig.html#max_connection_idle.v)
 pub const DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS: u64 = 5000;
 
+/// The default maximum number of unacknowledged requests the client will send on a single connection.
+///
+/// Defaults to 5, see
+/// [`ClientConfig::max_in_flight_requests_per_connection`](struct.ClientConfig.html#max_in_flight_requests_per_connection.v)
+pub const DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION: usize = 5;
+
 /// The default milliseconds the client will wait for the response of a request.
 ///
 /// Defaults to 30 seconds, see
@@ -34,6 +45,18 @@ pub const DEFAULT_TIMER_TICK_MILLS: u64 = 100;
 /// [`ClientConfig::retry_backoff`](struct.ClientConfig.html#retry_backoff.v)
 pub const DEFAULT_RETRY_BACKOFF_MILLIS: u64 = 100;
 
+/// The default base amount of time to wait before attempting to reconnect to a given broker.
+///
+/// Defaults to 50 ms, see
+/// [`ClientConfig::reconnect_backoff`](struct.ClientConfig.html#reconnect_backoff.v)
+pub const DEFAULT_RECONNECT_BACKOFF_MILLIS: u64 = 50;
+
+/// The default maximum amount of time to wait before attempting to reconnect to a given broker.
+///
+/// Defaults to 1 second, see
+/// [`ClientConfig::reconnect_backoff_max`](struct.ClientConfig.html#reconnect_backoff_max.v)
+pub const DEFAULT_RECONNECT_BACKOFF_MAX_MILLIS: u64 = 1000;
+
 /// Configuration for the Kafka Client.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
@@ -55,6 +78,13 @@ pub struct ClientConfig {
     #[serde(rename = "connection.max.idle.ms")]
     pub max_connection_idle: u64,
 
+    /// The maximum number of unacknowledged requests the client will send on a single connection
+    /// before blocking.
+    ///
+    /// The requests are pipelined on the connection, and the responses are matched by the correlation id.
+    #[serde(rename = "max.in.flight.requests.per.connection")]
+    pub max_in_flight_requests_per_connection: usize,
+
     /// The maximum amount of time the client will wait for the response of a
     /// request.
     #[serde(rename = "request.timeout.ms")]
@@ -72,7 +102,7 @@ pub struct ClientConfig {
     /// the client will automatically adjust its feature set accordingly if the
     /// ApiVersionRequest fails (or is disabled).
     /// The fallback broker version will be used for api.version.fallback.ms. Valid values are:
-    /// 0.9.0, 0.8.2, 0.8.1, 0.8.0.
+    /// 2.0, 1.1, 1.0, 0.11.0, 0.10.2, 0.10.1, 0.10.0, 0.9.0, 0.8.2, 0.8.1, 0.8.0.
     #[serde(rename = "broker.version.fallback")]
     pub broker_version_fallback: KafkaVersion,
 
@@ -94,6 +124,79 @@ pub struct ClientConfig {
     /// This avoids repeatedly sending requests in a tight loop under some failure scenarios.
     #[serde(rename = "retry.backoff.ms")]
     pub retry_backoff: u64,
+
+    /// The base amount of time to wait before attempting to reconnect to a given broker.
+    /// This avoids repeatedly connecting to a broker in a tight loop.
+    #[serde(rename = "reconnect.backoff.ms")]
+    pub reconnect_backoff: u64,
+
+    /// The maximum amount of time to wait when reconnecting to a broker that has repeatedly failed to connect.
+    ///
+    /// The backoff per broker will increase exponentially for each consecutive connection failure,
+    /// up to this maximum.
+    #[serde(rename = "reconnect.backoff.max.ms")]
+    pub reconnect_backoff_max: u64,
+
+    /// Protocol used to communicate with brokers.
+    ///
+    /// Valid values are: PLAINTEXT, SSL, SASL_PLAINTEXT, SASL_SSL.
+    #[serde(rename = "security.protocol")]
+    pub security_protocol: SecurityProtocol,
+
+    /// File path to CA certificates in PEM format for verifying the broker's certificate.
+    ///
+    /// Defaults to the system trust store.
+    #[serde(rename = "ssl.ca.location", skip_serializing_if = "Option::is_none")]
+    pub ssl_ca_location: Option<PathBuf>,
+
+    /// File path to the client's public certificate in PEM format.
+    #[serde(rename = "ssl.certificate.location", skip_serializing_if = "Option::is_none")]
+    pub ssl_certificate_location: Option<PathBuf>,
+
+    /// File path to the client's private key in PKCS#8 PEM format.
+    #[serde(rename = "ssl.key.location", skip_serializing_if = "Option::is_none")]
+    pub ssl_key_location: Option<PathBuf>,
+
+    /// File path to the client's keystore in PKCS#12 format,
+    /// which is an alternative to the certificate and private key.
+    #[serde(rename = "ssl.keystore.location", skip_serializing_if = "Option::is_none")]
+    pub ssl_keystore_location: Option<PathBuf>,
+
+    /// The password of the client's keystore.
+    #[serde(rename = "ssl.keystore.password", skip_serializing_if = "Option::is_none")]
+    pub ssl_keystore_password: Option<String>,
+
+    /// Verify the broker's hostname against its certificate.
+    #[serde(rename = "enable.ssl.hostname.verification")]
+    pub ssl_hostname_verification: bool,
+
+    /// The hostname used for SNI and certificate verification instead of the broker hostname.
+    #[serde(rename = "ssl.sni.hostname", skip_serializing_if = "Option::is_none")]
+    pub ssl_sni_hostname: Option<String>,
+
+    /// SASL mechanism used for client connections.
+    ///
+    /// SASL authentication requires Kafka 0.10.0 or later, and re-authentication requires Kafka 2.2.0 or later.
+    #[serde(rename = "sasl.mechanism")]
+    pub sasl_mechanism: SaslMechanism,
+
+    /// SASL username for use with the PLAIN and SCRAM mechanisms.
+    #[serde(rename = "sasl.username", skip_serializing_if = "Option::is_none")]
+    pub sasl_username: Option<String>,
+
+    /// SASL password for use with the PLAIN and SCRAM mechanisms.
+    #[serde(rename = "sasl.password", skip_serializing_if = "Option::is_none")]
+    pub sasl_password: Option<String>,
+
+    /// The options of the unsecured JWT for use with the OAUTHBEARER mechanism,
+    /// in the format of `principal=<name> [scope=<scope1>,<scope2>] [lifeSeconds=<seconds>]`.
+    #[serde(rename = "sasl.oauthbearer.config", skip_serializing_if = "Option::is_none")]
+    pub sasl_oauthbearer_config: Option<String>,
+
+    /// Enable the builtin unsecured JWT provider of the OAUTHBEARER mechanism,
+    /// which should only be used for development and testing.
+    #[serde(rename = "enable.sasl.oauthbearer.unsecure.jwt")]
+    pub enable_sasl_oauthbearer_unsecure_jwt: bool,
 }
 
 impl Default for ClientConfig {
@@ -102,6 +205,7 @@ impl Default for ClientConfig {
             hosts: vec![],
             client_id: None,
             max_connection_idle: DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS,
+            max_in_flight_requests_per_connection: DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
             request_timeout: DEFAULT_REQUEST_TIMEOUT_MILLS,
             api_version_request: false,
             broker_version_fallback: KafkaVersion::default(),
@@ -109,6 +213,21 @@ impl Default for ClientConfig {
             metrics: false,
             retries: 0,
             retry_backoff: DEFAULT_RETRY_BACKOFF_MILLIS,
+            reconnect_backoff: DEFAULT_RECONNECT_BACKOFF_MILLIS,
+            reconnect_backoff_max: DEFAULT_RECONNECT_BACKOFF_MAX_MILLIS,
+            security_protocol: SecurityProtocol::default(),
+            ssl_ca_location: None,
+            ssl_certificate_location: None,
+            ssl_key_location: None,
+            ssl_keystore_location: None,
+            ssl_keystore_password: None,
+            ssl_hostname_verification: true,
+            ssl_sni_hostname: None,
+            sasl_mechanism: SaslMechanism::default(),
+            sasl_username: None,
+            sasl_password: None,
+            sasl_oauthbearer_config: None,
+            enable_sasl_oauthbearer_unsecure_jwt: false,
         }
     }
 }
@@ -158,6 +277,72 @@ impl ClientConfig {
         Duration::from_millis(self.retry_backoff)
     }
 
+    /// The base amount of time to wait before attempting to reconnect to a given broker.
+    pub fn reconnect_backoff(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff)
+    }
+
+    /// The maximum amount of time to wait when reconnecting to a broker that has repeatedly failed to connect.
+    pub fn reconnect_backoff_max(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff_max)
+    }
+
+    /// The TLS settings to encrypt the connections,
+    /// or `None` if the security protocol doesn't require TLS.
+    pub fn tls(&self) -> Option<TlsConfig> {
+        if !self.security_protocol.is_ssl() {
+            return None;
+        }
+
+        Some(TlsConfig {
+            ca_location: self.ssl_ca_location.clone(),
+            certificate_location: self.ssl_certificate_location.clone(),
+            key_location: self.ssl_key_location.clone(),
+            keystore_location: self.ssl_keystore_location.clone(),
+            keystore_password: self.ssl_keystore_password.clone(),
+            hostname_verification: self.ssl_hostname_verification,
+            sni_hostname: self.ssl_sni_hostname.clone(),
+        })
+    }
+
+    /// The SASL settings to authenticate the connections,
+    /// or `None` if the security protocol doesn't require SASL.
+    ///
+    /// The `OAUTHBEARER` mechanism uses the token provider,
+    /// or the unsecured JWT provider if it has been enabled.
+    pub fn sasl(&self, token_provider: Option<Rc<TokenProvider>>) -> Result<Option<SaslConfig>> {
+        if !self.security_protocol.is_sasl() {
+            return Ok(None);
+        }
+
+        let credentials = if self.sasl_mechanism == SaslMechanism::OAuthBearer {
+            match token_provider {
+                Some(token_provider) => SaslCredentials::Token(token_provider),
+                None if self.enable_sasl_oauthbearer_unsecure_jwt => {
+                    let config = self.sasl_oauthbearer_config
+                        .as_ref()
+                        .ok_or(ErrorKind::ConfigError("SASL OAUTHBEARER config is required"))?;
+
+                    SaslCredentials::Token(Rc::new(UnsecuredJwtProvider::from_config(config)?))
+                }
+                None => bail!(ErrorKind::ConfigError("SASL OAUTHBEARER token provider is required")),
+            }
+        } else {
+            match (self.sasl_username.as_ref(), self.sasl_password.as_ref()) {
+                (Some(username), Some(password)) => SaslCredentials::Password {
+                    username: username.clone(),
+                    password: password.clone(),
+                },
+                _ => bail!(ErrorKind::ConfigError("SASL username and password are required")),
+            }
+        };
+
+        Ok(Some(SaslConfig {
+            mechanism: self.sasl_mechanism,
+            credentials,
+        }))
+    }
+
     /// The retry strategy when request failed
     pub fn retry_strategy(&self) -> Vec<Duration> {
         ExponentialBackoff::from_millis(self.retry_backoff)
@@ -192,7 +377,73 @@ mod tests {
             config.metadata_max_age(),
             Duration::from_millis(DEFAULT_METADATA_MAX_AGE_MILLS)
         );
+        assert_eq!(
+            config.reconnect_backoff(),
+            Duration::from_millis(DEFAULT_RECONNECT_BACKOFF_MILLIS)
+        );
+        assert_eq!(
+            config.reconnect_backoff_max(),
+            Duration::from_millis(DEFAULT_RECONNECT_BACKOFF_MAX_MILLIS)
+        );
         assert_eq!(config.retry_strategy().len(), 3);
+        assert!(config.tls().is_none());
+        assert!(config.sasl(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tls() {
+        let config = ClientConfig {
+            security_protocol: SecurityProtocol::SaslSsl,
+            ssl_ca_location: Some("ca.pem".into()),
+            ssl_sni_hostname: Some("kafka.example.com".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.tls(),
+            Some(TlsConfig {
+                ca_location: Some("ca.pem".into()),
+                sni_hostname: Some("kafka.example.com".to_owned()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_sasl() {
+        let mut config = ClientConfig {
+            security_protocol: SecurityProtocol::SaslPlaintext,
+            sasl_username: Some("user".to_owned()),
+            ..Default::default()
+        };
+
+        assert!(config.sasl(None).is_err());
+
+        config.sasl_password = Some("pass".to_owned());
+
+        let sasl = config.sasl(None).unwrap().unwrap();
+
+        assert_eq!(sasl.mechanism, SaslMechanism::Plain);
+        match sasl.credentials {
+            SaslCredentials::Password { username, password } => {
+                assert_eq!(username, "user");
+                assert_eq!(password, "pass");
+            }
+            credentials => panic!("unexpected credentials: {:?}", credentials),
+        }
+
+        config.sasl_mechanism = SaslMechanism::OAuthBearer;
+
+        assert!(config.sasl(None).is_err());
+        assert!(config.sasl(Some(Rc::new(UnsecuredJwtProvider::new("admin")))).is_ok());
+
+        config.enable_sasl_oauthbearer_unsecure_jwt = true;
+        config.sasl_oauthbearer_config = Some("principal=admin".to_owned());
+
+        let sasl = config.sasl(None).unwrap().unwrap();
+
+        assert_eq!(sasl.mechanism, SaslMechanism::OAuthBearer);
+        assert_eq!(sasl.client().unwrap().mechanism(), "OAUTHBEARER");
     }
 
     #[test]
@@ -208,13 +459,20 @@ mod tests {
   ],
   "client.id": "tokio-kafka",
   "connection.max.idle.ms": 5000,
+  "max.in.flight.requests.per.connection": 5,
   "request.timeout.ms": 30000,
   "api.version.request": false,
   "broker.version.fallback": "0.9.0",
   "metadata.max.age.ms": 300000,
   "metrics": false,
   "retries": 0,
-  "retry.backoff.ms": 100
+  "retry.backoff.ms": 100,
+  "reconnect.backoff.ms": 50,
+  "reconnect.backoff.max.ms": 1000,
+  "security.protocol": "PLAINTEXT",
+  "enable.ssl.hostname.verification": true,
+  "sasl.mechanism": "PLAIN",
+  "enable.sasl.oauthbearer.unsecure.jwt": false
 }"#;
 
         assert_eq!(serde_json::to_string_pretty(&config).unwrap(), json);
@@ -154,7 +154,9 @@ impl From<MetadataResponse> for Metadata {
         Metadata {
             brokers: md.brokers
                 .iter()
-                .map(|broker| Broker::new(broker.node_id, &broker.host, broker.port as u16))
+                .map(|broker| {
+                    Broker::new(broker.node_id, &broker.host, broker.port as u16).with_rack(broker.rack.clone())
+                })
                 .collect(),
             topic_partitions: HashMap::from_iter(md.topics.iter().map(|topic| {
                 (
@@ -94,6 +94,12 @@ impl<T> WithAddr for (SocketAddr, T) {
     }
 }
 
+impl<H, T> WithAddr for (SocketAddr, H, T) {
+    fn addr(&self) -> SocketAddr {
+        self.0
+    }
+}
+
 /// Abort requests that are taking too long
 #[derive(Clone)]
 pub struct Timeout<S> {
@@ -12,14 +12,17 @@ use typemap::{Key, TypeMap};
 
 use tokio_core::reactor::Handle;
 
-use client::{Broker, BrokerRef, Client, Cluster, ConsumerGroup, ConsumerGroupAssignment, ConsumerGroupProtocol,
-             FetchRecords, Generation, GetMetadata, GroupCoordinator, Heartbeat, JoinGroup, LeaveGroup, ListOffsets,
-             LoadMetadata, Metadata, OffsetCommit, OffsetFetch, PartitionData, ProduceRecords, SyncGroup,
-             ToStaticBoxFuture};
+use client::{AddOffsetsToTxn, AddPartitionsToTxn, AlterConfigs, Broker, BrokerRef, Client, Cluster, ConsumerGroup,
+             ConsumerGroupAssignment, ConsumerGroupProtocol, CreateAcls, CreatePartitions, CreateTopics, DeleteAcls,
+             DeleteGroups, DeleteRecords, DeleteTopics, DescribeAcls, DescribeConfigs, DescribeGroups, DescribeLogDirs,
+             EndTxn, FetchRecords, Generation, GetMetadata, GroupCoordinator, Heartbeat, InitProducerId, JoinGroup,
+             LeaveGroup, ListGroups, ListOffsets, LoadMetadata, Metadata, OffsetCommit, OffsetFetch, PartitionData,
+             ProduceRecords, ProducerIdAndEpoch, SyncGroup, ToStaticBoxFuture, TransactionCoordinator, TxnOffsetCommit};
 use consumer::Assignment;
 use errors::{ErrorKind, Result};
-use network::{OffsetAndMetadata, TopicPartition};
-use protocol::{FetchOffset, KafkaCode, MessageSet, RequiredAcks, Schema};
+use network::{ConfigResource, NewPartitions, NewTopic, OffsetAndMetadata, TopicPartition};
+use protocol::{AclBinding, AclBindingFilter, FetchOffset, IsolationLevel, KafkaCode, MessageSet, Offset, RequiredAcks,
+               Schema};
 
 #[derive(Clone)]
 pub struct MockClient<'a> {
@@ -117,6 +120,7 @@ where
 
     fn produce_records(
         &self,
+        transactional_id: Option<Cow<'a, str>>,
         acks: RequiredAcks,
         timeout: Duration,
         topic_partition: TopicPartition<'a>,
@@ -130,12 +134,14 @@ where
         fetch_max_wait: Duration,
         fetch_min_bytes: usize,
         fetch_max_bytes: usize,
+        isolation_level: IsolationLevel,
+        rack_id: Option<Cow<'a, str>>,
         partitions: Vec<(TopicPartition<'a>, PartitionData)>,
     ) -> FetchRecords {
         unimplemented!()
     }
 
-    fn list_offsets<I>(&self, partitions: I) -> ListOffsets
+    fn list_offsets<I>(&self, isolation_level: IsolationLevel, partitions: I) -> ListOffsets
     where
         I: IntoIterator<Item = (TopicPartition<'a>, FetchOffset)>,
     {
@@ -166,6 +172,10 @@ where
         unimplemented!()
     }
 
+    fn group_offsets(&self, coordinator: BrokerRef, group_id: Cow<'a, str>) -> OffsetFetch {
+        unimplemented!()
+    }
+
     fn group_coordinator(&self, group_id: Cow<'a, str>) -> GroupCoordinator {
         if let Some(callback) = self.future_responses.borrow_mut().remove::<GroupCoordinator>() {
             callback(String::from(group_id)).static_boxed()
@@ -237,4 +247,126 @@ where
             Err(ErrorKind::KafkaError(KafkaCode::NotCoordinator).into())
         }.static_boxed()
     }
+
+    fn transaction_coordinator(&self, transactional_id: Cow<'a, str>) -> TransactionCoordinator {
+        unimplemented!()
+    }
+
+    fn init_producer_id(
+        &self,
+        coordinator: Option<BrokerRef>,
+        transactional_id: Option<Cow<'a, str>>,
+        transaction_timeout: Duration,
+    ) -> InitProducerId {
+        unimplemented!()
+    }
+
+    fn add_partitions_to_txn<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        partitions: I,
+    ) -> AddPartitionsToTxn
+    where
+        I: IntoIterator<Item = TopicPartition<'a>>,
+    {
+        unimplemented!()
+    }
+
+    fn add_offsets_to_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        group_id: Cow<'a, str>,
+    ) -> AddOffsetsToTxn {
+        unimplemented!()
+    }
+
+    fn end_txn(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        committed: bool,
+    ) -> EndTxn {
+        unimplemented!()
+    }
+
+    fn txn_offset_commit<I>(
+        &self,
+        coordinator: BrokerRef,
+        transactional_id: Cow<'a, str>,
+        group_id: Cow<'a, str>,
+        producer: ProducerIdAndEpoch,
+        offsets: I,
+    ) -> TxnOffsetCommit
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>,
+    {
+        unimplemented!()
+    }
+    fn create_topics(&self, topics: Vec<NewTopic<'a>>, timeout: Duration, validate_only: bool) -> CreateTopics {
+        unimplemented!()
+    }
+
+    fn delete_topics(&self, topic_names: Vec<Cow<'a, str>>, timeout: Duration) -> DeleteTopics {
+        unimplemented!()
+    }
+    fn create_partitions(
+        &self,
+        topics: Vec<(Cow<'a, str>, NewPartitions)>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> CreatePartitions {
+        unimplemented!()
+    }
+
+    fn delete_records<I>(&self, offsets: I, timeout: Duration) -> DeleteRecords
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, Offset)>,
+    {
+        unimplemented!()
+    }
+
+    fn describe_acls(&self, filter: AclBindingFilter<'a>) -> DescribeAcls {
+        unimplemented!()
+    }
+
+    fn create_acls(&self, acls: Vec<AclBinding<'a>>) -> CreateAcls {
+        unimplemented!()
+    }
+
+    fn delete_acls(&self, filters: Vec<AclBindingFilter<'a>>) -> DeleteAcls {
+        unimplemented!()
+    }
+
+    fn describe_configs(&self, resources: Vec<ConfigResource<'a>>) -> DescribeConfigs {
+        unimplemented!()
+    }
+
+    fn alter_configs(
+        &self,
+        configs: Vec<(ConfigResource<'a>, HashMap<String, String>)>,
+        validate_only: bool,
+    ) -> AlterConfigs {
+        unimplemented!()
+    }
+
+    fn list_groups(&self) -> ListGroups {
+        unimplemented!()
+    }
+
+    fn describe_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DescribeGroups {
+        unimplemented!()
+    }
+
+    fn delete_groups(&self, group_ids: Vec<Cow<'a, str>>) -> DeleteGroups {
+        unimplemented!()
+    }
+
+    fn describe_log_dirs(&self, partitions: Option<Vec<TopicPartition<'a>>>) -> DescribeLogDirs {
+        unimplemented!()
+    }
 }
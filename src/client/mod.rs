@@ -7,24 +7,34 @@ mod metrics;
 mod middleware;
 mod record;
 mod service;
+mod session;
 mod version;
 
 #[cfg(test)]
 mod mock;
 
 pub use self::builder::ClientBuilder;
-pub use self::client::{Client, ConsumerGroup, ConsumerGroupAssignment, ConsumerGroupMember, ConsumerGroupProtocol,
-                       FetchRecords, FetchedRecords, Generation, GetMetadata, GroupCoordinator, Heartbeat, JoinGroup,
-                       KafkaClient, LeaveGroup, ListOffsets, ListedOffset, LoadMetadata, OffsetCommit, OffsetFetch,
-                       PartitionData, ProduceRecords, StaticBoxFuture, SyncGroup, ToStaticBoxFuture};
+pub use self::client::{AddOffsetsToTxn, AddPartitionsToTxn, AlterConfigs, AlteredConfigs, Client, ConfigEntry,
+                       ConsumerGroup, ConsumerGroupAssignment, ConsumerGroupMember, ConsumerGroupProtocol, Controller,
+                       CreateAcls, CreatePartitions, CreateTopics, CreatedAcl, CreatedPartitions, CreatedTopic,
+                       DeleteAcls, DeleteGroups, DeleteRecords, DeleteTopics, DeletedAcls, DeletedRecords, DescribeAcls,
+                       DescribeConfigs, DescribeGroups, DescribeLogDirs, DescribedConfigs, DescribedGroup,
+                       DescribedGroupMember, DescribedLogDir, DescribedReplica, EndTxn, FetchRecords, FetchedOffset,
+                       FetchedRecords, Generation, GetMetadata, GroupCoordinator, Heartbeat, InitProducerId, JoinGroup,
+                       KafkaClient, LeaveGroup, ListGroups, ListOffsets, ListedGroup, ListedOffset, LoadMetadata,
+                       MatchingAcl, OffsetCommit, OffsetFetch, PartitionData, ProduceRecords, ProducerIdAndEpoch,
+                       StaticBoxFuture, SyncGroup, ToStaticBoxFuture, TransactionCoordinator, TxnOffsetCommit};
 pub use self::cluster::{Broker, BrokerRef, Cluster, PartitionInfo};
-pub use self::config::{ClientConfig, DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS, DEFAULT_METADATA_MAX_AGE_MILLS,
+pub use self::config::{ClientConfig, DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS,
+                       DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION, DEFAULT_METADATA_MAX_AGE_MILLS,
+                       DEFAULT_RECONNECT_BACKOFF_MAX_MILLIS, DEFAULT_RECONNECT_BACKOFF_MILLIS,
                        DEFAULT_REQUEST_TIMEOUT_MILLS, DEFAULT_RETRY_BACKOFF_MILLIS};
 pub use self::metadata::{Metadata, TopicPartitions};
-pub use self::metrics::Metrics;
+pub use self::metrics::{Metrics, NAMESPACE_KAFKA, SUBSYSTEM_CLIENT};
 pub use self::middleware::InFlightMiddleware;
 pub use self::record::{PartitionRecord, TopicRecord};
 pub use self::service::{FutureResponse, KafkaService};
+pub use self::session::{FetchSessionRequest, FetchSessions};
 pub use self::version::KafkaVersion;
 
 #[cfg(test)]
@@ -5,9 +5,9 @@ use std::net::SocketAddr;
 use std::rc::Rc;
 use std::time::Duration;
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 
-use futures::future::Future;
+use futures::future::{self, Future};
 use futures::unsync::oneshot;
 use futures::{Async, Poll, Stream};
 use tokio_core::reactor::Handle;
@@ -17,11 +17,11 @@ use tokio_proto::streaming::pipeline::ClientProto;
 use tokio_proto::streaming::{Body, Message};
 use tokio_proto::util::client_proxy::ClientProxy;
 use tokio_service::Service;
-use ns_router::{AutoName, Router};
 
 use client::{Metrics, StaticBoxFuture, ToStaticBoxFuture};
-use errors::Error;
-use network::{ConnectionId, KafkaCodec, KafkaConnection, KafkaConnector, KafkaRequest, KafkaResponse, Pool, Pooled};
+use errors::{Error, ErrorKind};
+use network::{ConnectionId, ConnectionStates, KafkaCodec, KafkaConnection, KafkaConnector, KafkaRequest, KafkaResponse,
+              Pool, Pooled, SaslTransport, SendRequest, SendToken};
 
 #[derive(Debug, Default)]
 struct State {
@@ -39,6 +39,7 @@ pub struct KafkaService<'a> {
     handle: Handle,
     pool: Pool<SocketAddr, TokioClient<'a>>,
     connector: KafkaConnector,
+    connection_states: ConnectionStates<SocketAddr>,
     metrics: Option<Rc<Metrics>>,
     state: Rc<RefCell<State>>,
 }
@@ -46,14 +47,17 @@ pub struct KafkaService<'a> {
 impl<'a> KafkaService<'a> {
     pub fn new(
         handle: Handle,
-        router: Rc<Router>,
+        connector: KafkaConnector,
         max_connection_idle: Duration,
+        max_in_flight_requests: usize,
+        connection_states: ConnectionStates<SocketAddr>,
         metrics: Option<Rc<Metrics>>,
     ) -> Self {
         KafkaService {
-            handle: handle.clone(),
-            pool: Pool::new(max_connection_idle),
-            connector: KafkaConnector::new(handle, router),
+            handle,
+            pool: Pool::new(max_connection_idle, max_in_flight_requests),
+            connector,
+            connection_states,
             metrics,
             state: Rc::new(RefCell::new(State::default())),
         }
@@ -64,13 +68,20 @@ impl<'a> Service for KafkaService<'a>
 where
     Self: 'static,
 {
-    type Request = (SocketAddr, KafkaRequest<'a>);
+    /// The resolved address and hostname of the broker, and the request sent to it.
+    type Request = (SocketAddr, Option<String>, KafkaRequest<'a>);
     type Response = KafkaResponse;
     type Error = Error;
     type Future = FutureResponse;
 
     fn call(&self, req: Self::Request) -> Self::Future {
-        let (addr, request) = req;
+        let (addr, hostname, request) = req;
+
+        if let Some(backoff) = self.connection_states.remaining_backoff(&addr) {
+            debug!("skip sending request to {} which is backing off for {:?}", addr, backoff);
+
+            return ErrorKind::ReconnectBackoff(addr, backoff).into();
+        }
 
         self.metrics
             .as_ref()
@@ -81,17 +92,31 @@ where
             let handle = self.handle.clone();
             let connection_id = self.state.borrow_mut().next_connection_id();
             let pool = self.pool.clone();
-
-            self.connector.tcp(AutoName::SocketAddr(addr)).map(move |io| {
-                let (tx, rx) = oneshot::channel();
-                let client = RemoteClient {
-                    connection_id,
-                    client_rx: RefCell::new(Some(rx)),
-                }.bind_client(&handle, io);
-                let pooled = pool.pooled(addr, client);
-                drop(tx.send(pooled.clone()));
-                pooled
-            })
+            let connection_states = self.connection_states.clone();
+            let failed_states = self.connection_states.clone();
+
+            self.connection_states.connecting(addr);
+
+            self.connector
+                .connect(addr, hostname)
+                .map_err(move |err| {
+                    failed_states.failed(addr, err.kind() == io::ErrorKind::PermissionDenied);
+
+                    err
+                })
+                .map(move |(io, session)| {
+                    connection_states.connected(addr);
+
+                    let (tx, rx) = oneshot::channel();
+                    let client = RemoteClient {
+                        connection_id,
+                        client_rx: RefCell::new(Some(rx)),
+                    }.bind_client(&handle, io);
+                    let pooled = pool.pooled(addr, client);
+                    pooled.set_session(session);
+                    drop(tx.send(pooled.clone()));
+                    pooled
+                })
         };
 
         let race = checkout
@@ -107,9 +132,32 @@ where
                 err
             });
 
+        let connector = self.connector.clone();
+        let reauthenticate = race.and_then(move |client| -> Box<Future<Item = PooledClient<'a>, Error = io::Error>> {
+            // the SASL requests can't be interleaved with other requests,
+            // a busy connection is drained by the pool before it's re-authenticated.
+            if client.needs_reauthentication() && client.in_flight_requests() == 0 {
+                client.set_authenticating(true);
+
+                // a failed connection is never put back to the pool, the broker will close it.
+                Box::new(
+                    connector
+                        .reauthenticate(client, addr)
+                        .map(|(client, session)| {
+                            client.set_session(session);
+                            client.set_authenticating(false);
+                            client
+                        }),
+                )
+            } else {
+                Box::new(future::ok(client))
+            }
+        });
+
         let metrics = self.metrics.clone();
 
-        race.and_then(move |client| client.call(Message::WithoutBody(request)))
+        reauthenticate
+            .and_then(move |client| client.call(Message::WithoutBody(request)))
             .map(|msg| {
                 debug!("received message: {:?}", msg);
 
@@ -146,6 +194,26 @@ type TokioClient<'a> = ClientProxy<Message<KafkaRequest<'a>, KafkaBody>, Message
 
 type PooledClient<'a> = Pooled<SocketAddr, TokioClient<'a>>;
 
+impl<'a> SaslTransport for PooledClient<'a>
+where
+    Self: 'static,
+{
+    fn send_request(self, request: KafkaRequest<'static>) -> SendRequest<Self> {
+        Box::new(self.call(Message::WithoutBody(request)).map(move |msg| match msg {
+            Message::WithoutBody(res) | Message::WithBody(res, _) => (res, self),
+        }))
+    }
+
+    fn send_token(self, _token: Bytes) -> SendToken<Self> {
+        // The pooled connection is framed by the pipeline, only the sessions of `SaslAuthenticate` v1
+        // need re-authentication, so the raw tokens of `SaslHandshake` v0 are never sent on it.
+        Box::new(future::err(io::Error::new(
+            io::ErrorKind::Other,
+            "raw SASL token can't be sent on a pooled connection",
+        )))
+    }
+}
+
 struct RemoteClient<'a> {
     connection_id: u32,
     client_rx: RefCell<Option<oneshot::Receiver<PooledClient<'a>>>>,
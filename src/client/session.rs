@@ -0,0 +1,326 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::i32;
+use std::mem;
+use std::rc::Rc;
+
+use client::PartitionData;
+use protocol::{ApiVersion, FetchPartition, FetchTopic, ForgottenTopic, KafkaCode, PartitionId,
+               DEFAULT_RESPONSE_MAX_BYTES, FINAL_SESSION_EPOCH, INITIAL_SESSION_EPOCH, INVALID_LOG_START_OFFSET,
+               INVALID_SESSION_ID, NO_PARTITION_LEADER_EPOCH};
+
+/// The lowest version of `FetchRequest` which supports the incremental fetch sessions (KIP-227).
+const MIN_FETCH_SESSION_VERSION: ApiVersion = 7;
+
+/// The incremental fetch sessions (KIP-227) with the brokers.
+///
+/// The first fetch request to a broker is a full request, which lists all the partitions and creates a session.
+/// The following requests in the session only contain the partitions which have been added or changed,
+/// and the partitions which are no longer fetched from the broker.
+#[derive(Clone, Debug, Default)]
+pub struct FetchSessions {
+    sessions: Rc<RefCell<HashMap<(String, u16), FetchSession>>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct FetchSession {
+    /// The session id assigned by the broker, or `INVALID_SESSION_ID` if the session hasn't been created.
+    session_id: i32,
+    /// The epoch of the next request in the session.
+    epoch: i32,
+    /// The partitions in the session with the fetch data last sent to the broker.
+    partitions: HashMap<(String, PartitionId), PartitionData>,
+}
+
+/// The fetch request in a session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FetchSessionRequest<'a> {
+    /// The fetch session id.
+    pub session_id: i32,
+    /// The fetch session epoch.
+    pub session_epoch: i32,
+    /// The partitions to fetch.
+    pub topics: Vec<FetchTopic<'a>>,
+    /// The partitions to remove from the fetch session.
+    pub forgotten_topics: Vec<ForgottenTopic<'a>>,
+}
+
+impl FetchSessions {
+    /// Build the fetch request to the broker from the partitions to fetch.
+    pub fn build<'a>(
+        &self,
+        broker: &(String, u16),
+        api_version: ApiVersion,
+        partitions: &HashMap<Cow<'a, str>, Vec<(PartitionId, PartitionData)>>,
+    ) -> FetchSessionRequest<'a> {
+        if api_version < MIN_FETCH_SESSION_VERSION {
+            return FetchSessionRequest {
+                session_id: INVALID_SESSION_ID,
+                session_epoch: FINAL_SESSION_EPOCH,
+                topics: fetch_topics(partitions, |_, _| true),
+                forgotten_topics: vec![],
+            };
+        }
+
+        let mut sessions = self.sessions.borrow_mut();
+        let session = sessions.entry(broker.clone()).or_insert_with(Default::default);
+
+        let next = partitions
+            .iter()
+            .flat_map(|(topic_name, partitions)| {
+                partitions.iter().map(move |&(partition_id, ref fetch_data)| {
+                    ((topic_name.to_string(), partition_id), fetch_data.clone())
+                })
+            })
+            .collect::<HashMap<_, _>>();
+
+        let prev = mem::replace(&mut session.partitions, next);
+
+        if session.session_id == INVALID_SESSION_ID {
+            trace!("build full fetch request to {}:{}", broker.0, broker.1);
+
+            FetchSessionRequest {
+                session_id: INVALID_SESSION_ID,
+                session_epoch: INITIAL_SESSION_EPOCH,
+                topics: fetch_topics(partitions, |_, _| true),
+                forgotten_topics: vec![],
+            }
+        } else {
+            let mut forgotten = HashMap::new();
+
+            for &(ref topic_name, partition_id) in prev.keys() {
+                if !session.partitions.contains_key(&(topic_name.clone(), partition_id)) {
+                    forgotten
+                        .entry(topic_name.clone())
+                        .or_insert_with(Vec::new)
+                        .push(partition_id);
+                }
+            }
+
+            trace!(
+                "build incremental fetch request to {}:{} in session {} with epoch {}",
+                broker.0,
+                broker.1,
+                session.session_id,
+                session.epoch
+            );
+
+            FetchSessionRequest {
+                session_id: session.session_id,
+                session_epoch: session.epoch,
+                topics: fetch_topics(partitions, |topic_name, &(partition_id, ref fetch_data)| {
+                    prev.get(&(topic_name.to_owned(), partition_id)) != Some(fetch_data)
+                }),
+                forgotten_topics: forgotten
+                    .into_iter()
+                    .map(|(topic_name, mut partitions)| {
+                        partitions.sort();
+
+                        ForgottenTopic {
+                            topic_name: topic_name.into(),
+                            partitions,
+                        }
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    /// Update the session with the top level error code and session id of the fetch response.
+    pub fn handle_response(
+        &self,
+        broker: &(String, u16),
+        api_version: ApiVersion,
+        error_code: KafkaCode,
+        session_id: Option<i32>,
+    ) {
+        if api_version < MIN_FETCH_SESSION_VERSION {
+            return;
+        }
+
+        let mut sessions = self.sessions.borrow_mut();
+
+        match error_code {
+            KafkaCode::None => {
+                if let Some(session) = sessions.get_mut(broker) {
+                    let session_id = session_id.unwrap_or(INVALID_SESSION_ID);
+
+                    if session.session_id == INVALID_SESSION_ID {
+                        if session_id == INVALID_SESSION_ID {
+                            trace!("broker {}:{} doesn't create fetch session", broker.0, broker.1);
+                        } else {
+                            debug!("created fetch session {} with broker {}:{}", session_id, broker.0, broker.1);
+
+                            session.session_id = session_id;
+                            session.epoch = next_epoch(INITIAL_SESSION_EPOCH);
+                        }
+                    } else {
+                        session.epoch = next_epoch(session.epoch);
+                    }
+                }
+            }
+            _ => {
+                if let Some(session) = sessions.remove(broker) {
+                    debug!(
+                        "fall back to full fetch request since the fetch session {} with broker {}:{} failed, {:?}",
+                        session.session_id, broker.0, broker.1, error_code
+                    );
+                }
+            }
+        }
+    }
+
+    /// Close the session after the fetch request failed, the next fetch request will be a full request.
+    pub fn reset(&self, broker: &(String, u16)) {
+        self.sessions.borrow_mut().remove(broker);
+    }
+}
+
+fn next_epoch(epoch: i32) -> i32 {
+    if epoch == i32::MAX {
+        1
+    } else {
+        epoch + 1
+    }
+}
+
+fn fetch_topics<'a, F>(
+    partitions: &HashMap<Cow<'a, str>, Vec<(PartitionId, PartitionData)>>,
+    predicate: F,
+) -> Vec<FetchTopic<'a>>
+where
+    F: Fn(&str, &(PartitionId, PartitionData)) -> bool,
+{
+    partitions
+        .iter()
+        .map(|(topic_name, partitions)| FetchTopic {
+            topic_name: topic_name.clone(),
+            partitions: partitions
+                .iter()
+                .filter(|partition| predicate(topic_name, partition))
+                .map(|&(partition_id, ref fetch_data)| FetchPartition {
+                    partition_id,
+                    current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+                    fetch_offset: fetch_data.offset,
+                    log_start_offset: INVALID_LOG_START_OFFSET,
+                    max_bytes: fetch_data.max_bytes.unwrap_or(DEFAULT_RESPONSE_MAX_BYTES),
+                })
+                .collect(),
+        })
+        .filter(|topic: &FetchTopic| !topic.partitions.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partitions<'a>(offsets: &[(PartitionId, i64)]) -> HashMap<Cow<'a, str>, Vec<(PartitionId, PartitionData)>> {
+        let mut partitions = HashMap::new();
+
+        partitions.insert(
+            "topic".into(),
+            offsets
+                .iter()
+                .map(|&(partition_id, offset)| {
+                    (
+                        partition_id,
+                        PartitionData {
+                            offset,
+                            max_bytes: Some(1024),
+                            read_replica: None,
+                        },
+                    )
+                })
+                .collect(),
+        );
+
+        partitions
+    }
+
+    fn fetched(request: &FetchSessionRequest) -> Vec<(PartitionId, i64)> {
+        request
+            .topics
+            .iter()
+            .flat_map(|topic| topic.partitions.iter())
+            .map(|partition| (partition.partition_id, partition.fetch_offset))
+            .collect()
+    }
+
+    #[test]
+    fn test_sessionless_fetch() {
+        let sessions = FetchSessions::default();
+        let broker = ("localhost".to_owned(), 9092);
+
+        let request = sessions.build(&broker, 6, &partitions(&[(0, 10)]));
+
+        assert_eq!(request.session_id, INVALID_SESSION_ID);
+        assert_eq!(request.session_epoch, FINAL_SESSION_EPOCH);
+        assert_eq!(fetched(&request), vec![(0, 10)]);
+
+        sessions.handle_response(&broker, 6, KafkaCode::None, None);
+
+        let request = sessions.build(&broker, 6, &partitions(&[(0, 10)]));
+
+        assert_eq!(request.session_epoch, FINAL_SESSION_EPOCH);
+        assert_eq!(fetched(&request), vec![(0, 10)]);
+    }
+
+    #[test]
+    fn test_incremental_fetch() {
+        let sessions = FetchSessions::default();
+        let broker = ("localhost".to_owned(), 9092);
+
+        let request = sessions.build(&broker, 7, &partitions(&[(0, 10), (1, 20)]));
+
+        assert_eq!(request.session_id, INVALID_SESSION_ID);
+        assert_eq!(request.session_epoch, INITIAL_SESSION_EPOCH);
+        assert_eq!(fetched(&request), vec![(0, 10), (1, 20)]);
+
+        sessions.handle_response(&broker, 7, KafkaCode::None, Some(123));
+
+        // only the changed partitions are sent
+        let request = sessions.build(&broker, 7, &partitions(&[(0, 15), (1, 20)]));
+
+        assert_eq!(request.session_id, 123);
+        assert_eq!(request.session_epoch, 1);
+        assert_eq!(fetched(&request), vec![(0, 15)]);
+        assert!(request.forgotten_topics.is_empty());
+
+        sessions.handle_response(&broker, 7, KafkaCode::None, Some(123));
+
+        // the removed partitions are forgotten
+        let request = sessions.build(&broker, 7, &partitions(&[(0, 15), (2, 30)]));
+
+        assert_eq!(request.session_id, 123);
+        assert_eq!(request.session_epoch, 2);
+        assert_eq!(fetched(&request), vec![(2, 30)]);
+        assert_eq!(
+            request.forgotten_topics,
+            vec![
+                ForgottenTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fall_back_to_full_fetch() {
+        let sessions = FetchSessions::default();
+        let broker = ("localhost".to_owned(), 9092);
+
+        sessions.build(&broker, 7, &partitions(&[(0, 10), (1, 20)]));
+        sessions.handle_response(&broker, 7, KafkaCode::None, Some(123));
+        sessions.build(&broker, 7, &partitions(&[(0, 10), (1, 20)]));
+        sessions.handle_response(&broker, 7, KafkaCode::FetchSessionIdNotFound, Some(INVALID_SESSION_ID));
+
+        let request = sessions.build(&broker, 7, &partitions(&[(0, 10), (1, 20)]));
+
+        assert_eq!(request.session_id, INVALID_SESSION_ID);
+        assert_eq!(request.session_epoch, INITIAL_SESSION_EPOCH);
+        assert_eq!(fetched(&request), vec![(0, 10), (1, 20)]);
+    }
+}
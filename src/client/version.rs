@@ -25,6 +25,20 @@ pub enum KafkaVersion {
     KAFKA_0_8_2 = 802,
     /// Kafka 0.9.0.0, 0.9.0.1
     KAFKA_0_9_0 = 900,
+    /// Kafka 0.10.0.0, 0.10.0.1
+    KAFKA_0_10_0 = 1000,
+    /// Kafka 0.10.1.0, 0.10.1.1
+    KAFKA_0_10_1 = 1001,
+    /// Kafka 0.10.2.0, 0.10.2.1
+    KAFKA_0_10_2 = 1002,
+    /// Kafka 0.11.0.0, 0.11.0.1, 0.11.0.2
+    KAFKA_0_11_0 = 1100,
+    /// Kafka 1.0.0, 1.0.1
+    KAFKA_1_0 = 10000,
+    /// Kafka 1.1.0, 1.1.1
+    KAFKA_1_1 = 10100,
+    /// Kafka 2.0.0 or later 2.x releases
+    KAFKA_2_0 = 20000,
 }
 
 impl KafkaVersion {
@@ -34,6 +48,13 @@ impl KafkaVersion {
             KafkaVersion::KAFKA_0_8_1 => "0.8.1",
             KafkaVersion::KAFKA_0_8_2 => "0.8.2",
             KafkaVersion::KAFKA_0_9_0 => "0.9.0",
+            KafkaVersion::KAFKA_0_10_0 => "0.10.0",
+            KafkaVersion::KAFKA_0_10_1 => "0.10.1",
+            KafkaVersion::KAFKA_0_10_2 => "0.10.2",
+            KafkaVersion::KAFKA_0_11_0 => "0.11.0",
+            KafkaVersion::KAFKA_1_0 => "1.0",
+            KafkaVersion::KAFKA_1_1 => "1.1",
+            KafkaVersion::KAFKA_2_0 => "2.0",
         }
     }
 
@@ -47,6 +68,13 @@ impl KafkaVersion {
             KafkaVersion::KAFKA_0_8_1 => &*KAFKA_0_8_1_VERSIONS,
             KafkaVersion::KAFKA_0_8_2 => &*KAFKA_0_8_2_VERSIONS,
             KafkaVersion::KAFKA_0_9_0 => &*KAFKA_0_9_0_VERSIONS,
+            KafkaVersion::KAFKA_0_10_0 => &*KAFKA_0_10_0_VERSIONS,
+            KafkaVersion::KAFKA_0_10_1 => &*KAFKA_0_10_1_VERSIONS,
+            KafkaVersion::KAFKA_0_10_2 => &*KAFKA_0_10_2_VERSIONS,
+            KafkaVersion::KAFKA_0_11_0 => &*KAFKA_0_11_0_VERSIONS,
+            KafkaVersion::KAFKA_1_0 => &*KAFKA_1_0_VERSIONS,
+            KafkaVersion::KAFKA_1_1 => &*KAFKA_1_1_VERSIONS,
+            KafkaVersion::KAFKA_2_0 => &*KAFKA_2_0_VERSIONS,
         }
     }
 }
@@ -72,6 +100,13 @@ impl FromStr for KafkaVersion {
             "0.8.1" => Ok(KafkaVersion::KAFKA_0_8_1),
             "0.8.2" => Ok(KafkaVersion::KAFKA_0_8_2),
             "0.9.0" => Ok(KafkaVersion::KAFKA_0_9_0),
+            "0.10.0" => Ok(KafkaVersion::KAFKA_0_10_0),
+            "0.10.1" => Ok(KafkaVersion::KAFKA_0_10_1),
+            "0.10.2" => Ok(KafkaVersion::KAFKA_0_10_2),
+            "0.11.0" => Ok(KafkaVersion::KAFKA_0_11_0),
+            "1.0" => Ok(KafkaVersion::KAFKA_1_0),
+            "1.1" => Ok(KafkaVersion::KAFKA_1_1),
+            "2.0" => Ok(KafkaVersion::KAFKA_2_0),
             _ => bail!(ErrorKind::ParseError(format!("unknown kafka version: {}", s))),
         }
     }
@@ -103,7 +138,9 @@ impl<'de> Deserialize<'de> for KafkaVersion {
             type Value = KafkaVersion;
 
             fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                f.write_str("Valid values are: 0.9.0, 0.8.2, 0.8.1, 0.8.0.")
+                f.write_str(
+                    "Valid values are: 2.0, 1.1, 1.0, 0.11.0, 0.10.2, 0.10.1, 0.10.0, 0.9.0, 0.8.2, 0.8.1, 0.8.0.",
+                )
             }
 
             fn visit_str<E>(self, v: &str) -> StdResult<Self::Value, E>
@@ -367,4 +404,1159 @@ lazy_static! {
             max_version: 0,
         },
     ]);
+    static ref KAFKA_0_10_0_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_0_10_1_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_0_10_2_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_0_11_0_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteRecords,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::InitProducerId,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetForLeaderEpoch,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddPartitionsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddOffsetsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::EndTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::WriteTxnMarkers,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::TxnOffsetCommit,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_1_0_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 6,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteRecords,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::InitProducerId,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetForLeaderEpoch,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddPartitionsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddOffsetsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::EndTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::WriteTxnMarkers,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::TxnOffsetCommit,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterReplicaLogDirs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeLogDirs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslAuthenticate,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreatePartitions,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_1_1_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 7,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteRecords,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::InitProducerId,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetForLeaderEpoch,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddPartitionsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddOffsetsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::EndTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::WriteTxnMarkers,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::TxnOffsetCommit,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteAcls,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeConfigs,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterReplicaLogDirs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeLogDirs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslAuthenticate,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreatePartitions,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateDelegationToken,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::RenewDelegationToken,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ExpireDelegationToken,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeDelegationToken,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+    ]);
+    static ref KAFKA_2_0_VERSIONS: UsableApiVersions = UsableApiVersions::new(vec![
+        UsableApiVersion {
+            api_key: ApiKeys::Produce,
+            min_version: 0,
+            max_version: 6,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Fetch,
+            min_version: 0,
+            max_version: 8,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListOffsets,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
+            max_version: 6,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaderAndIsr,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::StopReplica,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::UpdateMetadata,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ControlledShutdown,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetCommit,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetFetch,
+            min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::JoinGroup,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Heartbeat,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::LeaveGroup,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SyncGroup,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeGroups,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ListGroups,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ApiVersions,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 3,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteRecords,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::InitProducerId,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::OffsetForLeaderEpoch,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddPartitionsToTxn,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddOffsetsToTxn,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::EndTxn,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::WriteTxnMarkers,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::TxnOffsetCommit,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeConfigs,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterConfigs,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterReplicaLogDirs,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeLogDirs,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslAuthenticate,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreatePartitions,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateDelegationToken,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::RenewDelegationToken,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::ExpireDelegationToken,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeDelegationToken,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteGroups,
+            min_version: 0,
+            max_version: 1,
+        },
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+
+    #[test]
+    fn test_kafka_version() {
+        for version in &[
+            KafkaVersion::KAFKA_0_8_0,
+            KafkaVersion::KAFKA_0_9_0,
+            KafkaVersion::KAFKA_0_10_2,
+            KafkaVersion::KAFKA_0_11_0,
+            KafkaVersion::KAFKA_1_1,
+            KafkaVersion::KAFKA_2_0,
+        ] {
+            assert_eq!(version.version().parse::<KafkaVersion>().unwrap(), *version);
+            assert_eq!(KafkaVersion::from(version.value()), *version);
+            assert_eq!(
+                serde_json::from_str::<KafkaVersion>(&serde_json::to_string(version).unwrap()).unwrap(),
+                *version
+            );
+        }
+
+        assert!("0.12.0".parse::<KafkaVersion>().is_err());
+    }
+
+    #[test]
+    fn test_fallback_api_versions() {
+        let find = |version: KafkaVersion, api_key: ApiKeys| {
+            version
+                .api_versions()
+                .find(api_key)
+                .map(|api_version| api_version.max_version)
+        };
+
+        assert_eq!(find(KafkaVersion::KAFKA_0_9_0, ApiKeys::ApiVersions), None);
+        assert_eq!(find(KafkaVersion::KAFKA_0_10_0, ApiKeys::ApiVersions), Some(0));
+        assert_eq!(find(KafkaVersion::KAFKA_0_10_1, ApiKeys::Fetch), Some(3));
+        assert_eq!(find(KafkaVersion::KAFKA_0_11_0, ApiKeys::Produce), Some(3));
+        assert_eq!(find(KafkaVersion::KAFKA_1_0, ApiKeys::SaslAuthenticate), Some(0));
+        assert_eq!(find(KafkaVersion::KAFKA_1_1, ApiKeys::DeleteGroups), Some(0));
+        assert_eq!(find(KafkaVersion::KAFKA_2_0, ApiKeys::Fetch), Some(8));
+    }
 }
@@ -88,4 +88,26 @@ impl Compression {
             }
         }
     }
+
+    pub fn uncompress(&self, api_version: ApiVersion, src: &[u8]) -> Result<Vec<u8>> {
+        match *self {
+            Compression::None => Ok(src.to_vec()),
+
+            #[cfg(feature = "gzip")]
+            Compression::GZIP => gzip::uncompress(src),
+
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => snappy::uncompress(src),
+
+            #[cfg(feature = "lz4")]
+            Compression::LZ4 => {
+                let mut uncompressed = Vec::new();
+                {
+                    let mut reader = lz4::Lz4Reader::new(src, true, api_version < 2)?;
+                    reader.read_to_end(&mut uncompressed)?;
+                }
+                Ok(uncompressed)
+            }
+        }
+    }
 }
@@ -91,6 +91,19 @@ fn test_validate_stream() {
     assert_eq!(rest, &[0x56]);
 }
 
+/// Uncompress either a stream produced by `SnappyOutputStream` or a raw snappy block.
+pub fn uncompress(src: &[u8]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+
+    if src.starts_with(MAGIC) {
+        SnappyReader::new(src)?._read_to_end(&mut buf)?;
+    } else {
+        uncompress_to(src, &mut buf)?;
+    }
+
+    Ok(buf)
+}
+
 // ~ An implementation of a reader over a stream of snappy compressed
 // chunks as produced by org.xerial.snappy.SnappyOutputStream
 // (https://github.com/xerial/snappy-java/ version: 1.1.1.*)
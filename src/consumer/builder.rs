@@ -6,7 +6,7 @@ use tokio_core::reactor::Handle;
 use client::{KafkaClient, KafkaVersion};
 use consumer::{AssignmentStrategy, ConsumerConfig, KafkaConsumer, OffsetResetStrategy};
 use errors::{ErrorKind, Result};
-use protocol::ToMilliseconds;
+use protocol::{IsolationLevel, ToMilliseconds};
 use serialization::{Deserializer, NoopDeserializer};
 
 /// A `KafkaConsumer` builder easing the process of setting up various
@@ -98,6 +98,26 @@ impl<'a, K, V> ConsumerBuilder<'a, K, V> {
         self
     }
 
+    /// Sets the maximum number of unacknowledged requests the client will send on a single
+    /// connection before blocking.
+    pub fn with_max_in_flight_requests_per_connection(mut self, max_in_flight_requests: usize) -> Self {
+        self.config.max_in_flight_requests_per_connection = max_in_flight_requests;
+        self
+    }
+
+    /// Sets the base amount of time to wait before attempting to reconnect to a given broker.
+    pub fn with_reconnect_backoff(mut self, reconnect_backoff: Duration) -> Self {
+        self.config.reconnect_backoff = reconnect_backoff.as_millis();
+        self
+    }
+
+    /// Sets the maximum amount of time to wait when reconnecting to a broker
+    /// that has repeatedly failed to connect.
+    pub fn with_reconnect_backoff_max(mut self, reconnect_backoff_max: Duration) -> Self {
+        self.config.reconnect_backoff_max = reconnect_backoff_max.as_millis();
+        self
+    }
+
     /// Sets the maximum amount of time the client will wait for the response
     /// of a request.
     pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
@@ -148,6 +168,21 @@ impl<'a, K, V> ConsumerBuilder<'a, K, V> {
         self
     }
 
+    /// Sets how to read messages written transactionally.
+    pub fn with_isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.config.isolation_level = isolation_level;
+        self
+    }
+
+    /// Sets the rack identifier of the consumer to fetch from the closest replica.
+    pub fn with_client_rack<S>(mut self, client_rack: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.config.client_rack = Some(client_rack.into());
+        self
+    }
+
     /// Sets to disable the consumer's offset will be periodically committed in
     /// the background.
     pub fn without_auto_commit(mut self) -> Self {
@@ -3,6 +3,7 @@ use std::time::Duration;
 
 use client::ClientConfig;
 use consumer::{AssignmentStrategy, OffsetResetStrategy};
+use protocol::IsolationLevel;
 
 /// The default milliseconds that the consumer offsets are auto-committed to Kafka.
 ///
@@ -177,6 +178,23 @@ pub struct ConsumerConfig {
     /// (broker config) or `max.message.bytes` (topic config).
     #[serde(rename = "max.partition.fetch.bytes")]
     pub partition_fetch_bytes: usize,
+
+    /// Controls how to read messages written transactionally.
+    ///
+    /// - read_committed: only return transactional messages which have been committed
+    /// - read_uncommitted: return all messages, even transactional messages which have been aborted
+    ///
+    /// Non-transactional messages will be returned unconditionally in either mode.
+    #[serde(rename = "isolation.level")]
+    pub isolation_level: IsolationLevel,
+
+    /// A rack identifier for this client.
+    ///
+    /// This can be any string value which indicates where this client is physically located.
+    /// It corresponds with the broker config `broker.rack`, and lets the consumer fetch from
+    /// the closest replica if the broker has configured a replica selector (KIP-392).
+    #[serde(rename = "client.rack", skip_serializing_if = "Option::is_none")]
+    pub client_rack: Option<String>,
 }
 
 impl Deref for ConsumerConfig {
@@ -211,6 +229,8 @@ impl Default for ConsumerConfig {
             fetch_max_wait: DEFAULT_FETCH_MAX_WAIT_MILLIS,
             fetch_error_backoff: DEFAULT_FETCH_ERROR_BACKOFF_MILLIS,
             partition_fetch_bytes: DEFAULT_PARTITION_FETCH_BYTES,
+            isolation_level: IsolationLevel::default(),
+            client_rack: None,
         }
     }
 }
@@ -309,13 +329,20 @@ mod tests {
     "bootstrap.servers": [],
     "client.id": null,
     "connection.max.idle.ms": 5000,
+    "max.in.flight.requests.per.connection": 5,
     "request.timeout.ms": 30000,
     "api.version.request": false,
     "broker.version.fallback": "0.9.0",
     "metadata.max.age.ms": 300000,
     "metrics": false,
     "retries": 0,
-    "retry.backoff.ms": 100
+    "retry.backoff.ms": 100,
+    "reconnect.backoff.ms": 50,
+    "reconnect.backoff.max.ms": 1000,
+    "security.protocol": "PLAINTEXT",
+    "enable.ssl.hostname.verification": true,
+    "sasl.mechanism": "PLAIN",
+    "enable.sasl.oauthbearer.unsecure.jwt": false
   },
   "group.id": null,
   "enable.auto.commit": true,
@@ -333,7 +360,8 @@ mod tests {
   "fetch.max.bytes": 52428800,
   "fetch.max.wait.ms": 500,
   "fetch.error.backoff.ms": 500,
-  "max.partition.fetch.bytes": 1048576
+  "max.partition.fetch.bytes": 1048576,
+  "isolation.level": "read_uncommitted"
 }"#;
 
         assert_eq!(serde_json::to_string_pretty(&config).unwrap(), json);
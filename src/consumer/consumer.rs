@@ -10,7 +10,7 @@ use tokio_core::reactor::Handle;
 use client::{Client, Cluster, KafkaClient, StaticBoxFuture, ToStaticBoxFuture};
 use consumer::{ConsumerBuilder, ConsumerConfig, ConsumerCoordinator, Fetcher, SubscribedTopics, Subscriptions};
 use errors::{Error, ErrorKind};
-use protocol::{MessageTimestamp, Offset, PartitionId};
+use protocol::{MessageTimestamp, Offset, PartitionId, RecordHeader};
 use serialization::Deserializer;
 
 /// A trait for consuming records from a Kafka cluster.
@@ -49,6 +49,11 @@ pub struct ConsumerRecord<'a, K, V> {
     pub value: Option<V>,
     /// The timestamp of this record
     pub timestamp: Option<MessageTimestamp>,
+    /// The headers of this record
+    ///
+    /// The headers are only returned by Fetch v4 or later,
+    /// older versions down-convert the records to a message format without headers.
+    pub headers: Vec<RecordHeader>,
 }
 
 pub type Subscribe<T> = StaticBoxFuture<T>;
@@ -151,6 +156,9 @@ where
         let fetch_max_bytes = self.inner.config.fetch_max_bytes;
         let fetch_max_wait = self.inner.config.fetch_max_wait();
         let partition_fetch_bytes = self.inner.config.partition_fetch_bytes;
+        let isolation_level = self.inner.config.isolation_level;
+        let client_rack = self.inner.config.client_rack.clone();
+        let metadata_max_age = self.inner.config.metadata_max_age();
         let auto_commit_interval = self.inner.config.auto_commit_interval();
         let assignors = self.inner
             .config
@@ -200,6 +208,9 @@ where
                     fetch_max_bytes,
                     fetch_max_wait,
                     partition_fetch_bytes,
+                    isolation_level,
+                    client_rack,
+                    metadata_max_age,
                 ));
 
                 SubscribedTopics::new(KafkaConsumer { inner }, subscriptions, coordinator, fetcher, timer)
@@ -1,9 +1,10 @@
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::iter::IntoIterator;
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::{Async, Future, Poll};
 
@@ -11,7 +12,7 @@ use client::{Client, FetchRecords, KafkaClient, ListOffsets, PartitionData, Stat
 use consumer::{OffsetResetStrategy, SeekTo, Subscriptions};
 use errors::{Error, ErrorKind};
 use network::TopicPartition;
-use protocol::{FetchOffset, KafkaCode, Offset};
+use protocol::{FetchOffset, IsolationLevel, KafkaCode, Offset};
 
 pub struct Fetcher<'a> {
     client: KafkaClient<'a>,
@@ -20,6 +21,9 @@ pub struct Fetcher<'a> {
     fetch_max_bytes: usize,
     fetch_max_wait: Duration,
     partition_fetch_bytes: usize,
+    isolation_level: IsolationLevel,
+    client_rack: Option<String>,
+    metadata_max_age: Duration,
 }
 
 impl<'a> Fetcher<'a>
@@ -33,6 +37,9 @@ where
         fetch_max_bytes: usize,
         fetch_max_wait: Duration,
         partition_fetch_bytes: usize,
+        isolation_level: IsolationLevel,
+        client_rack: Option<String>,
+        metadata_max_age: Duration,
     ) -> Self {
         Fetcher {
             client,
@@ -41,6 +48,9 @@ where
             fetch_max_bytes,
             fetch_max_wait,
             partition_fetch_bytes,
+            isolation_level,
+            client_rack,
+            metadata_max_age,
         }
     }
 
@@ -107,7 +117,7 @@ where
         let subscriptions = self.subscriptions.clone();
 
         self.client
-            .list_offsets(offset_resets)
+            .list_offsets(self.isolation_level, offset_resets)
             .and_then(move |offsets| {
                 for (topic_name, partitions) in offsets {
                     for partition in partitions {
@@ -138,28 +148,50 @@ where
     {
         let subscriptions = self.subscriptions.clone();
         let default_reset_strategy = self.subscriptions.borrow().default_reset_strategy();
+        let metadata_max_age = self.metadata_max_age;
+        let now = Instant::now();
 
         let fetch_partitions = partitions
             .into_iter()
             .flat_map(|tp| {
-                subscriptions.borrow().assigned_state(&tp).map(|state| {
+                subscriptions.borrow_mut().assigned_state_mut(&tp).map(|state| {
                     let fetch_data = PartitionData {
                         offset: state.position.unwrap(),
                         max_bytes: Some(self.partition_fetch_bytes as i32),
+                        read_replica: state.preferred_read_replica(now),
                     };
 
                     (tp, fetch_data)
                 })
             })
-            .collect();
+            .collect::<Vec<_>>();
+
+        let fetch_from_replicas = fetch_partitions
+            .iter()
+            .filter(|&&(_, ref fetch_data)| fetch_data.read_replica.is_some())
+            .map(|&(ref tp, _)| tp.clone())
+            .collect::<Vec<_>>();
+        let replica_subscriptions = self.subscriptions.clone();
 
         self.client
             .fetch_records(
                 self.fetch_max_wait,
                 self.fetch_min_bytes,
                 self.fetch_max_bytes,
+                self.isolation_level,
+                self.client_rack.clone().map(Cow::from),
                 fetch_partitions,
             )
+            .map_err(move |err| {
+                // fall back to fetch from the leader if the preferred replica failed
+                for tp in fetch_from_replicas {
+                    if let Some(state) = replica_subscriptions.borrow_mut().assigned_state_mut(&tp) {
+                        state.clear_preferred_read_replica();
+                    }
+                }
+
+                err
+            })
             .and_then(move |(throttle_time, records)| {
                 for (topic_name, records) in &records {
                     for record in records {
@@ -177,15 +209,38 @@ where
                                         }
 
                                         state.high_watermark = record.high_watermark;
+
+                                        if let Some(node_id) = record.preferred_read_replica {
+                                            debug!("fetch {} from the preferred replica {}", tp, node_id);
+
+                                            state.update_preferred_read_replica(
+                                                node_id,
+                                                Instant::now() + metadata_max_age,
+                                            );
+                                        }
                                     }
                                     KafkaCode::OffsetOutOfRange => {
                                         if state.position != Some(record.fetch_offset) {
                                             debug!("discarding stale fetch response for {} since its offset {} does not match the expected offset {:?}", tp, record.fetch_offset, state.position);
+                                        } else if let Some(node_id) = state.clear_preferred_read_replica() {
+                                            debug!(
+                                                "fetch {} from leader since offset {} is out of range of replica {}",
+                                                tp, record.fetch_offset, node_id
+                                            );
                                         } else {
                                             state.need_offset_reset(default_reset_strategy);
                                         }
                                     }
-                                    _ => bail!(ErrorKind::KafkaError(record.error_code)),
+                                    _ => {
+                                        if let Some(node_id) = state.clear_preferred_read_replica() {
+                                            debug!(
+                                                "fetch {} from the leader since the replica {} failed, {:?}",
+                                                tp, node_id, record.error_code
+                                            );
+                                        } else {
+                                            bail!(ErrorKind::KafkaError(record.error_code))
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -198,7 +253,7 @@ where
     }
 
     pub fn retrieve_offsets<T>(&self, partitions: Vec<(TopicPartition<'a>, FetchOffset)>) -> RetrieveOffsets<'a, T> {
-        RetrieveOffsets::new(self.client.list_offsets(partitions))
+        RetrieveOffsets::new(self.client.list_offsets(self.isolation_level, partitions))
     }
 }
 
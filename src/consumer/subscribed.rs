@@ -262,6 +262,7 @@ where
                                     .ok()
                             }),
                             timestamp: message.timestamp.clone(),
+                            headers: message.headers.clone(),
                         }
                     })
                 })
@@ -438,7 +439,7 @@ where
                 .static_boxed()
         } else {
             self.consumer
-                .list_offsets(vec![(tp, FetchOffset::Latest)])
+                .list_offsets(self.consumer.config().isolation_level, vec![(tp, FetchOffset::Latest)])
                 .and_then(move |mut offsets| {
                     offsets
                         .remove(&topic_name)
@@ -3,10 +3,11 @@ use std::hash::Hash;
 use std::iter::FromIterator;
 use std::mem;
 use std::str::FromStr;
+use std::time::Instant;
 
 use errors::{Error, ErrorKind, Result};
 use network::{OffsetAndMetadata, TopicPartition};
-use protocol::Offset;
+use protocol::{NodeId, Offset};
 
 #[derive(Debug, Default)]
 pub struct Subscriptions<'a> {
@@ -213,6 +214,8 @@ pub struct TopicPartitionState {
     pub committed: Option<OffsetAndMetadata>,
     /// the strategy to use if the offset needs resetting
     pub reset_strategy: Option<OffsetResetStrategy>,
+    /// the replica suggested by the broker to fetch from, and when the suggestion expires
+    pub preferred_read_replica: Option<(NodeId, Instant)>,
 }
 
 impl TopicPartitionState {
@@ -236,6 +239,27 @@ impl TopicPartitionState {
         mem::replace(&mut self.reset_strategy, Some(reset_strategy))
     }
 
+    /// The replica to fetch from if the suggestion of the broker hasn't expired.
+    pub fn preferred_read_replica(&mut self, now: Instant) -> Option<NodeId> {
+        match self.preferred_read_replica {
+            Some((node_id, expires)) if now < expires => Some(node_id),
+            Some(_) => {
+                self.preferred_read_replica = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn update_preferred_read_replica(&mut self, node_id: NodeId, expires: Instant) {
+        self.preferred_read_replica = Some((node_id, expires));
+    }
+
+    /// Clear the preferred replica to fetch from the leader.
+    pub fn clear_preferred_read_replica(&mut self) -> Option<NodeId> {
+        self.preferred_read_replica.take().map(|(node_id, _)| node_id)
+    }
+
     pub fn seek(&mut self, offset: Offset) -> Offset {
         self.position = Some(offset);
         self.reset_strategy = None;
@@ -1,6 +1,8 @@
 use std::borrow::{Borrow, Cow};
 use std::error::Error as StdError;
 use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
 
 use serde::{de, ser};
 
@@ -41,6 +43,10 @@ error_chain!{
             description("invalid argument")
             display("invalid argument, {}", reason)
         }
+        IllegalState(reason: String) {
+            description("illegal state")
+            display("illegal state, {}", reason)
+        }
         UnexpectedResponse(api_key: ApiKeys) {
             description("unexpected response")
             display("unexpected response, {:?}", api_key)
@@ -49,6 +55,10 @@ error_chain!{
             description("task canceled")
             display("task canceled, {}", task)
         }
+        AuthenticationFailed(reason: String) {
+            description("authentication failed")
+            display("authentication failed, {}", reason)
+        }
         KafkaError(code: KafkaCode) {
             description("kafka error")
             display("kafka error, {:?}, {}", code, code.reason())
@@ -92,10 +102,18 @@ error_chain!{
             description("broker not found")
             display("broker `{}` not found", broker.index())
         }
+        ReconnectBackoff(addr: SocketAddr, backoff: Duration) {
+            description("reconnect backoff")
+            display("reconnect to {} after {:?}", addr, backoff)
+        }
         SchemaError(reason: String) {
             description("schema error")
             display("schema error, {}", reason)
         }
+        TransactionError(reason: String) {
+            description("transaction error")
+            display("transaction error, {}", reason)
+        }
     }
 }
 
@@ -28,6 +28,9 @@ extern crate serde_derive;
 extern crate serde_json;
 #[macro_use]
 extern crate prometheus;
+extern crate base64;
+extern crate hmac;
+extern crate sha2;
 extern crate abstract_ns;
 extern crate ns_router;
 extern crate ns_std_threaded;
@@ -56,6 +59,8 @@ extern crate lz4_compress;
 #[cfg(test)]
 extern crate pretty_env_logger;
 #[cfg(test)]
+extern crate rcgen;
+#[cfg(test)]
 extern crate typemap;
 
 #[macro_use]
@@ -70,21 +75,34 @@ mod network;
 mod client;
 mod consumer;
 mod producer;
+mod admin;
 
-pub use client::{Broker, BrokerRef, Client, ClientBuilder, ClientConfig, Cluster, KafkaClient, KafkaVersion,
-                 ListOffsets, ListedOffset, LoadMetadata, Metadata, PartitionRecord, ProduceRecords,
-                 ToStaticBoxFuture, TopicRecord, DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS,
-                 DEFAULT_METADATA_MAX_AGE_MILLS, DEFAULT_REQUEST_TIMEOUT_MILLS, DEFAULT_RETRY_BACKOFF_MILLIS};
+pub use admin::{AdminClient, AlterConfigsOptions, ConsumerGroupDescription, ConsumerLag, CreatePartitionsOptions,
+                CreateTopicsOptions, DeleteRecordsOptions, DeleteTopicsOptions, DescribeConsumerGroups, LagExporter,
+                MemberDescription, PartitionLag, ResetOffset, ResetOffsets, ResetOffsetsOptions, ResetOffsetsTo};
+pub use client::{AlterConfigs, AlteredConfigs, Broker, BrokerRef, Client, ClientBuilder, ClientConfig, Cluster,
+                 ConfigEntry, CreateAcls, CreatePartitions, CreateTopics, CreatedAcl, CreatedPartitions, CreatedTopic,
+                 DeleteAcls, DeleteGroups, DeleteRecords, DeleteTopics, DeletedAcls, DeletedRecords, DescribeAcls,
+                 DescribeConfigs, DescribeGroups, DescribeLogDirs, DescribedConfigs, DescribedGroup,
+                 DescribedGroupMember, DescribedLogDir, DescribedReplica, KafkaClient, KafkaVersion, ListGroups,
+                 ListOffsets, ListedGroup, ListedOffset, LoadMetadata, MatchingAcl, Metadata, PartitionRecord,
+                 ProduceRecords, ToStaticBoxFuture, TopicRecord,
+                 DEFAULT_MAX_CONNECTION_IDLE_TIMEOUT_MILLIS, DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
+                 DEFAULT_METADATA_MAX_AGE_MILLS, DEFAULT_RECONNECT_BACKOFF_MAX_MILLIS, DEFAULT_RECONNECT_BACKOFF_MILLIS,
+                 DEFAULT_REQUEST_TIMEOUT_MILLS, DEFAULT_RETRY_BACKOFF_MILLIS};
 pub use compression::Compression;
-pub use consumer::{Consumer, ConsumerBuilder, KafkaConsumer, OffsetResetStrategy, SeekTo, Subscribed};
+pub use consumer::{Assignment, Consumer, ConsumerBuilder, KafkaConsumer, OffsetResetStrategy, SeekTo, Subscribed};
 pub use errors::{Error, ErrorKind, Result};
-pub use network::{OffsetAndMetadata, OffsetAndTimestamp, TopicPartition, DEFAULT_PORT};
-pub use producer::{DefaultPartitioner, GetTopic, KafkaProducer, Partitioner, Producer, ProducerBuilder,
-                   ProducerConfig, ProducerInterceptor, ProducerPartition, ProducerRecord, ProducerTopic,
-                   RecordMetadata, SendRecord, DEFAULT_ACK_TIMEOUT_MILLIS, DEFAULT_BATCH_SIZE, DEFAULT_LINGER_MILLIS,
-                   DEFAULT_MAX_REQUEST_SIZE};
-pub use protocol::{ApiKey, ApiKeys, ErrorCode, FetchOffset, KafkaCode, Offset, PartitionId, RequiredAcks, Timestamp,
-                   ToMilliseconds, UsableApiVersion, UsableApiVersions};
+pub use network::{ConfigResource, NewPartitions, NewTopic, OAuthBearerToken, OffsetAndMetadata, OffsetAndTimestamp,
+                  SaslMechanism, SecurityProtocol, TokenProvider, TopicPartition, UnsecuredJwtProvider, DEFAULT_PORT};
+pub use producer::{DefaultPartitioner, GetTopic, KafkaProducer, Partitioner, Producer, ProducerBuilder, ProducerConfig,
+                   ProducerInterceptor, ProducerPartition, ProducerRecord, ProducerTopic, RecordMetadata, SendRecord,
+                   DEFAULT_ACK_TIMEOUT_MILLIS, DEFAULT_BATCH_SIZE, DEFAULT_LINGER_MILLIS, DEFAULT_MAX_REQUEST_SIZE,
+                   DEFAULT_TRANSACTION_TIMEOUT_MILLIS, MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE};
+pub use protocol::{AccessControlEntry, AclBinding, AclBindingFilter, AclOperation, AclPermissionType, ApiKey, ApiKeys,
+                   ConfigResourceType, ConfigSource, ErrorCode, FetchOffset, IsolationLevel, KafkaCode, Offset,
+                   PartitionId, PatternType, RecordHeader, RequiredAcks, ResourcePattern, ResourceType, Timestamp,
+                   ToMilliseconds, UsableApiVersion, UsableApiVersions, UNKNOWN_OFFSET};
 pub use serialization::{BytesDeserializer, BytesSerializer, Deserializer, NoopDeserializer, NoopSerializer,
                         RawDeserializer, RawSerializer, Serializer, StringDeserializer, StringSerializer};
 #[cfg(feature = "json")]
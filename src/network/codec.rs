@@ -1,7 +1,9 @@
-use std::collections::VecDeque;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::io;
 use std::marker::PhantomData;
 use std::mem;
+use std::rc::Rc;
 
 use bytes::{BigEndian, BufMut, ByteOrder, BytesMut};
 
@@ -10,16 +12,30 @@ use tokio_io::codec::{Decoder, Encoder};
 use network::{KafkaRequest, KafkaResponse};
 use protocol::{ApiKeys, ApiVersion, CorrelationId, Encodable, Record, RequestHeader};
 
+/// The generator of correlation ids shared by all the requests of a client,
+/// so the pipelined requests on a connection never have the same correlation id.
+#[derive(Clone, Debug, Default)]
+pub struct CorrelationIds(Rc<Cell<CorrelationId>>);
+
+impl CorrelationIds {
+    pub fn next_correlation_id(&self) -> CorrelationId {
+        let correlation_id = self.0.get();
+        self.0.set(correlation_id.wrapping_add(1));
+        correlation_id
+    }
+}
+
 #[derive(Debug)]
 pub struct KafkaCodec<'a> {
-    requests: VecDeque<(ApiKeys, ApiVersion, CorrelationId)>,
+    /// The pipelined requests which are waiting for the response.
+    requests: HashMap<CorrelationId, (ApiKeys, ApiVersion)>,
     phantom: PhantomData<&'a u8>,
 }
 
 impl<'a> KafkaCodec<'a> {
     pub fn new() -> Self {
         KafkaCodec {
-            requests: VecDeque::new(),
+            requests: HashMap::new(),
             phantom: PhantomData,
         }
     }
@@ -32,6 +48,7 @@ impl<'a> Encoder for KafkaCodec<'a> {
     fn encode(&mut self, request: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
         let off = dst.len();
 
+        let expects_response = request.expects_response();
         let &RequestHeader {
             api_key,
             api_version,
@@ -57,8 +74,10 @@ impl<'a> Encoder for KafkaCodec<'a> {
             hexdump!(&dst[..])
         );
 
-        self.requests
-            .push_back((ApiKeys::from(api_key), api_version, correlation_id));
+        if expects_response {
+            self.requests
+                .insert(correlation_id, (ApiKeys::from(api_key), api_version));
+        }
 
         Ok(())
     }
@@ -83,16 +102,115 @@ impl<'a> Decoder for KafkaCodec<'a> {
 
                 let buf = src.split_to(size + size_header_len).split_off(size_header_len).freeze();
 
-                if let Some((api_key, api_version, correlation_id)) = self.requests.pop_front() {
-                    if BigEndian::read_i32(&buf[..]) != correlation_id {
-                        Err(io::Error::new(io::ErrorKind::InvalidData, "correlation id mismatch"))
-                    } else {
-                        KafkaResponse::parse(&buf[..], api_key, api_version)
-                    }
+                let correlation_id = BigEndian::read_i32(&buf[..]);
+
+                if let Some((api_key, api_version)) = self.requests.remove(&correlation_id) {
+                    KafkaResponse::parse(&buf[..], api_key, api_version)
                 } else {
-                    Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected response"))
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected response with correlation id {}", correlation_id),
+                    ))
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::time::Duration;
+
+    use super::*;
+    use protocol::{MessageSet, RequiredAcks};
+
+    fn produce_request<'a>(correlation_id: CorrelationId, required_acks: RequiredAcks) -> KafkaRequest<'a> {
+        KafkaRequest::produce_records(
+            0,
+            correlation_id,
+            None,
+            None,
+            required_acks,
+            Duration::from_secs(1),
+            &topic_partition!("topic", 0),
+            vec![Cow::Owned(MessageSet {
+                messages: vec![],
+                batches: vec![],
+            })],
+        )
+    }
+
+    fn produce_response(correlation_id: CorrelationId) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(12);
+
+        buf.put_i32::<BigEndian>(8);
+        buf.put_i32::<BigEndian>(correlation_id);
+        buf.put_i32::<BigEndian>(0); // topics
+
+        buf
+    }
+
+    #[test]
+    fn test_request_without_response() {
+        let mut codec = KafkaCodec::new();
+        let mut buf = BytesMut::with_capacity(256);
+
+        codec.encode(produce_request(1, RequiredAcks::None), &mut buf).unwrap();
+        codec.encode(produce_request(2, RequiredAcks::One), &mut buf).unwrap();
+
+        assert!(!codec.requests.contains_key(&1));
+        assert_eq!(codec.requests.get(&2), Some(&(ApiKeys::Produce, 0)));
+
+        match codec.decode(&mut produce_response(2)) {
+            Ok(Some(KafkaResponse::Produce(res))) => assert_eq!(res.header.correlation_id, 2),
+            res => panic!("unexpected response: {:?}", res),
+        }
+
+        assert!(codec.requests.is_empty());
+
+        // the broker never responds to the produce request with `acks=0`
+        assert!(codec.decode(&mut produce_response(1)).is_err());
+    }
+
+    #[test]
+    fn test_describe_log_dirs() {
+        let mut codec = KafkaCodec::new();
+        let mut buf = BytesMut::with_capacity(256);
+
+        codec
+            .encode(KafkaRequest::describe_log_dirs(2, 123, None, None), &mut buf)
+            .unwrap();
+
+        assert_eq!(codec.requests.get(&123), Some(&(ApiKeys::DescribeLogDirs, 2)));
+        assert_eq!(
+            &buf[..],
+            &[
+                0, 0, 0, 13 /* size */, 0, 35 /* api_key */, 0, 2 /* api_version */, 0, 0, 0,
+                123 /* correlation_id */, 255, 255 /* client_id */, 0 /* tagged_fields */, 0 /* topics */,
+                0 /* tagged_fields */,
+            ][..]
+        );
+
+        let mut buf = BytesMut::from(
+            &[
+                0, 0, 0, 28 /* size */, 0, 0, 0, 123 /* correlation_id */, 0 /* tagged_fields */, 0, 0, 0,
+                0 /* throttle_time_ms */, /* results */ 2, 0, 0 /* error_code */, 5, b'/', b'l', b'o',
+                b'g' /* log_dir */, /* topics */ 2, 6, b't', b'o', b'p', b'i', b'c' /* name */,
+                /* partitions */ 1, 0 /* tagged_fields */, 0 /* tagged_fields */, 0 /* tagged_fields */,
+            ][..],
+        );
+
+        match codec.decode(&mut buf) {
+            Ok(Some(KafkaResponse::DescribeLogDirs(res))) => {
+                assert_eq!(res.header.correlation_id, 123);
+                assert_eq!(res.results.len(), 1);
+                assert_eq!(res.results[0].log_dir, "/log");
+                assert_eq!(res.results[0].topics[0].name, "topic");
+            }
+            res => panic!("unexpected response: {:?}", res),
+        }
+
+        assert!(codec.requests.is_empty());
+    }
+}
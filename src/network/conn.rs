@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::io;
 use std::io::prelude::*;
 use std::ops::{Deref, DerefMut};
@@ -7,12 +8,13 @@ use bytes::BytesMut;
 
 use futures::sink::Sink;
 use futures::stream::Stream;
-use futures::{AsyncSink, Poll, StartSend};
+use futures::{task, Async, AsyncSink, Poll, StartSend};
 use tokio_io::codec::Framed;
 use tokio_io::{AsyncRead, AsyncWrite};
 use tokio_proto::streaming::pipeline::{Frame, Transport};
 
 use network::{ConnectionId, KafkaCodec, KafkaRequest, KafkaResponse};
+use protocol::{CorrelationId, ProduceResponse, ResponseHeader};
 
 #[derive(Clone, Copy, Debug)]
 pub enum Status {
@@ -26,11 +28,15 @@ pub trait KeepAlive {
     fn busy(&mut self);
     fn close(&mut self);
     fn idle(&mut self);
+    fn sent(&mut self);
+    fn received(&mut self);
 }
 
 #[derive(Debug)]
 struct State<K> {
     keep_alive: K,
+    /// The pipelined requests in order, with the correlation id if the request doesn't expect a response.
+    requests: VecDeque<Option<CorrelationId>>,
 }
 
 #[derive(Debug)]
@@ -111,16 +117,43 @@ where
 impl<'a, I, K> Stream for KafkaConnection<'a, I, K>
 where
     I: AsyncRead + AsyncWrite,
+    K: KeepAlive,
 {
     type Item = Frame<KafkaResponse, BytesMut, io::Error>;
     type Error = io::Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        if let Some(&Some(correlation_id)) = self.state.requests.front() {
+            trace!("complete request #{} without response", correlation_id);
+
+            self.state.requests.pop_front();
+            self.state.keep_alive.received();
+
+            // the broker doesn't respond to the produce request with `acks=0`
+            let response = KafkaResponse::Produce(ProduceResponse {
+                header: ResponseHeader { correlation_id },
+                topics: vec![],
+                throttle_time: None,
+            });
+
+            return Ok(Async::Ready(Some(Frame::Message {
+                message: response,
+                body: false,
+            })));
+        }
+
+        let state = &mut self.state;
+
         self.stream.poll().map(|res| {
             res.map(|res| {
-                res.map(|res| Frame::Message {
-                    message: res,
-                    body: false,
+                res.map(|res| {
+                    state.requests.pop_front();
+                    state.keep_alive.received();
+
+                    Frame::Message {
+                        message: res,
+                        body: false,
+                    }
                 })
             })
         })
@@ -139,10 +172,29 @@ where
         trace!("send request: {:?}", frame);
 
         match frame {
-            Frame::Message { message: request, body } => self.stream.start_send(request).map(|async| match async {
-                AsyncSink::Ready => AsyncSink::Ready,
-                AsyncSink::NotReady(request) => AsyncSink::NotReady(Frame::Message { message: request, body }),
-            }),
+            Frame::Message { message: request, body } => {
+                let state = &mut self.state;
+                let no_response = if request.expects_response() {
+                    None
+                } else {
+                    Some(request.header().correlation_id)
+                };
+
+                self.stream.start_send(request).map(|async| match async {
+                    AsyncSink::Ready => {
+                        state.requests.push_back(no_response);
+                        state.keep_alive.sent();
+
+                        if no_response.is_some() {
+                            // poll the transport again to complete the request
+                            task::current().notify();
+                        }
+
+                        AsyncSink::Ready
+                    }
+                    AsyncSink::NotReady(request) => AsyncSink::NotReady(Frame::Message { message: request, body }),
+                })
+            }
             Frame::Body { .. } | Frame::Error { .. } => Ok(AsyncSink::Ready),
         }
     }
@@ -173,7 +225,10 @@ where
         KafkaConnection {
             id,
             stream: stream.framed(codec),
-            state: State { keep_alive },
+            state: State {
+                keep_alive,
+                requests: VecDeque::new(),
+            },
         }
     }
 
@@ -181,3 +236,193 @@ where
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use bytes::{BigEndian, BufMut};
+
+    use futures::{future, Future};
+
+    use super::*;
+    use protocol::{MessageSet, RequiredAcks};
+
+    /// The transport which counts the reads, and returns the prepared responses.
+    struct MockStream {
+        responses: io::Cursor<Vec<u8>>,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads.set(self.reads.get() + 1);
+
+            match self.responses.read(buf)? {
+                0 => Err(io::ErrorKind::WouldBlock.into()),
+                size => Ok(size),
+            }
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl AsyncRead for MockStream {}
+
+    impl AsyncWrite for MockStream {
+        fn shutdown(&mut self) -> Poll<(), io::Error> {
+            Ok(Async::Ready(()))
+        }
+    }
+
+    #[derive(Default)]
+    struct MockKeepAlive {
+        sent: usize,
+        received: usize,
+    }
+
+    impl KeepAlive for MockKeepAlive {
+        fn status(&self) -> Status {
+            Status::Busy
+        }
+
+        fn busy(&mut self) {}
+
+        fn close(&mut self) {}
+
+        fn idle(&mut self) {}
+
+        fn sent(&mut self) {
+            self.sent += 1;
+        }
+
+        fn received(&mut self) {
+            self.received += 1;
+        }
+    }
+
+    fn produce_request<'a>(
+        correlation_id: CorrelationId,
+        required_acks: RequiredAcks,
+    ) -> Frame<KafkaRequest<'a>, BytesMut, io::Error> {
+        Frame::Message {
+            message: KafkaRequest::produce_records(
+                0,
+                correlation_id,
+                None,
+                None,
+                required_acks,
+                Duration::from_secs(1),
+                &topic_partition!("topic", 0),
+                vec![Cow::Owned(MessageSet {
+                    messages: vec![],
+                    batches: vec![],
+                })],
+            ),
+            body: false,
+        }
+    }
+
+    fn produce_responses(correlation_ids: &[CorrelationId]) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for &correlation_id in correlation_ids {
+            buf.put_i32::<BigEndian>(8);
+            buf.put_i32::<BigEndian>(correlation_id);
+            buf.put_i32::<BigEndian>(0); // topics
+        }
+
+        buf
+    }
+
+    fn build_connection<'a>(
+        responses: Vec<u8>,
+        reads: Rc<Cell<usize>>,
+    ) -> KafkaConnection<'a, MockStream, MockKeepAlive> {
+        let stream = MockStream {
+            responses: io::Cursor::new(responses),
+            reads,
+        };
+
+        KafkaConnection::new(0, stream, KafkaCodec::new(), MockKeepAlive::default())
+    }
+
+    fn poll_response<'a>(conn: &mut KafkaConnection<'a, MockStream, MockKeepAlive>) -> ProduceResponse {
+        match conn.poll() {
+            Ok(Async::Ready(Some(Frame::Message {
+                message: KafkaResponse::Produce(res),
+                ..
+            }))) => res,
+            res => panic!("unexpected response: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_complete_request_without_response() {
+        let reads = Rc::new(Cell::new(0));
+        let mut conn = build_connection(vec![], reads.clone());
+
+        future::lazy(|| {
+            assert!(conn.start_send(produce_request(1, RequiredAcks::None)).unwrap().is_ready());
+
+            let res = poll_response(&mut conn);
+
+            assert_eq!(res.header.correlation_id, 1);
+            assert!(res.topics.is_empty());
+            assert_eq!(reads.get(), 0);
+
+            assert_eq!(conn.state.keep_alive.sent, 1);
+            assert_eq!(conn.state.keep_alive.received, 1);
+            assert!(conn.state.requests.is_empty());
+
+            Ok::<(), ()>(())
+        }).wait()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_requests_without_response() {
+        let reads = Rc::new(Cell::new(0));
+        let mut conn = build_connection(produce_responses(&[1, 3]), reads.clone());
+
+        future::lazy(|| {
+            for (correlation_id, required_acks) in vec![
+                (1, RequiredAcks::One),
+                (2, RequiredAcks::None),
+                (3, RequiredAcks::One),
+            ] {
+                assert!(conn.start_send(produce_request(correlation_id, required_acks)).unwrap().is_ready());
+            }
+
+            assert_eq!(poll_response(&mut conn).header.correlation_id, 1);
+            assert!(reads.get() > 0);
+
+            let reads_before = reads.get();
+
+            assert_eq!(poll_response(&mut conn).header.correlation_id, 2);
+            assert_eq!(reads.get(), reads_before);
+
+            assert_eq!(poll_response(&mut conn).header.correlation_id, 3);
+
+            assert!(conn.poll().unwrap().is_not_ready());
+
+            assert_eq!(conn.state.keep_alive.sent, 3);
+            assert_eq!(conn.state.keep_alive.received, 3);
+            assert!(conn.state.requests.is_empty());
+
+            Ok::<(), ()>(())
+        }).wait()
+            .unwrap();
+    }
+}
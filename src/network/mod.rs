@@ -2,26 +2,89 @@
 mod request;
 mod codec;
 mod conn;
+mod oauth;
 mod pool;
 mod response;
+mod sasl;
+mod scram;
+mod state;
 mod stream;
+mod tls;
 
-pub use self::codec::KafkaCodec;
+pub use self::codec::{CorrelationIds, KafkaCodec};
 pub use self::conn::{KafkaConnection, KeepAlive, Status};
+pub use self::oauth::{OAuthBearerClient, OAuthBearerToken, TokenProvider, UnsecuredJwtProvider};
 pub use self::pool::{Pool, Pooled};
 pub use self::request::KafkaRequest;
 pub use self::response::KafkaResponse;
-pub use self::stream::{Connect, KafkaConnector, KafkaStream};
+pub use self::sasl::{authenticate, handshake, Authenticate, Handshake, PlainClient, SaslClient, SaslConfig,
+                     SaslCredentials, SaslMechanism, SaslSession, SaslTransport, SendRequest, SendToken};
+pub use self::scram::{ScramClient, ScramMechanism};
+pub use self::state::{ConnectionState, ConnectionStates};
+pub use self::stream::{Connect, Establish, KafkaConnector, KafkaStream};
+pub use self::tls::TlsConfig;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
-use protocol::{Offset, PartitionId, Timestamp};
+use errors::{Error, ErrorKind, Result};
+use protocol::{ConfigResourceType, NodeId, Offset, PartitionId, Timestamp};
 
 pub const DEFAULT_PORT: u16 = 9092;
 
 pub type ConnectionId = u32;
 
+/// The protocol used to communicate with brokers.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SecurityProtocol {
+    /// Un-authenticated, non-encrypted channel
+    #[serde(rename = "PLAINTEXT")]
+    Plaintext,
+    /// TLS channel
+    #[serde(rename = "SSL")]
+    Ssl,
+    /// SASL authenticated, non-encrypted channel
+    #[serde(rename = "SASL_PLAINTEXT")]
+    SaslPlaintext,
+    /// SASL authenticated, TLS channel
+    #[serde(rename = "SASL_SSL")]
+    SaslSsl,
+}
+
+impl Default for SecurityProtocol {
+    fn default() -> Self {
+        SecurityProtocol::Plaintext
+    }
+}
+
+impl SecurityProtocol {
+    /// The channel is encrypted with TLS.
+    pub fn is_ssl(&self) -> bool {
+        *self == SecurityProtocol::Ssl || *self == SecurityProtocol::SaslSsl
+    }
+
+    /// The connections must be authenticated with SASL.
+    pub fn is_sasl(&self) -> bool {
+        *self == SecurityProtocol::SaslPlaintext || *self == SecurityProtocol::SaslSsl
+    }
+}
+
+impl FromStr for SecurityProtocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "PLAINTEXT" => Ok(SecurityProtocol::Plaintext),
+            "SSL" => Ok(SecurityProtocol::Ssl),
+            "SASL_PLAINTEXT" => Ok(SecurityProtocol::SaslPlaintext),
+            "SASL_SSL" => Ok(SecurityProtocol::SaslSsl),
+            _ => bail!(ErrorKind::ParseError(format!("unknown security protocol: {}", s))),
+        }
+    }
+}
+
 /// A topic name and partition number
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TopicPartition<'a> {
@@ -79,3 +142,122 @@ impl OffsetAndTimestamp {
         OffsetAndTimestamp { offset, timestamp }
     }
 }
+
+/// A new topic to be created
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTopic<'a> {
+    /// The name of the topic to be created.
+    pub topic_name: Cow<'a, str>,
+    /// The number of partitions, or -1 if the replica assignment has been specified.
+    pub num_partitions: i32,
+    /// The replication factor, or -1 if the replica assignment has been specified.
+    pub replication_factor: i16,
+    /// The replicas of each partition, the first replica is the preferred leader.
+    pub replica_assignment: HashMap<PartitionId, Vec<NodeId>>,
+    /// The topic level configuration.
+    pub configs: HashMap<String, String>,
+}
+
+impl<'a> NewTopic<'a> {
+    /// A new topic with the specified number of partitions and replication factor.
+    pub fn new<S>(topic_name: S, num_partitions: i32, replication_factor: i16) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        NewTopic {
+            topic_name: topic_name.into(),
+            num_partitions,
+            replication_factor,
+            replica_assignment: HashMap::new(),
+            configs: HashMap::new(),
+        }
+    }
+
+    /// A new topic with the specified replica assignment of each partition.
+    pub fn with_replica_assignment<S, I>(topic_name: S, replica_assignment: I) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+        I: IntoIterator<Item = (PartitionId, Vec<NodeId>)>,
+    {
+        NewTopic {
+            topic_name: topic_name.into(),
+            num_partitions: -1,
+            replication_factor: -1,
+            replica_assignment: replica_assignment.into_iter().collect(),
+            configs: HashMap::new(),
+        }
+    }
+
+    /// Sets the topic level configuration.
+    pub fn with_config<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.configs.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// The new partitions to be added to an existing topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPartitions {
+    /// The total number of partitions after the operation succeeds.
+    pub total_count: i32,
+    /// The replicas of each new partition, the first replica is the preferred leader.
+    pub new_assignments: Option<Vec<Vec<NodeId>>>,
+}
+
+impl NewPartitions {
+    /// Increase the partition count of the topic to the given total count.
+    pub fn increase_to(total_count: i32) -> Self {
+        NewPartitions {
+            total_count,
+            new_assignments: None,
+        }
+    }
+
+    /// Increase the partition count of the topic with the given replica assignment of the new partitions.
+    pub fn with_assignments(total_count: i32, new_assignments: Vec<Vec<NodeId>>) -> Self {
+        NewPartitions {
+            total_count,
+            new_assignments: Some(new_assignments),
+        }
+    }
+}
+
+/// A resource that has the configuration, such as a topic or a broker.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ConfigResource<'a> {
+    /// The type of the resource.
+    pub resource_type: ConfigResourceType,
+    /// The name of the resource, the broker id for a broker.
+    pub resource_name: Cow<'a, str>,
+}
+
+impl<'a> ConfigResource<'a> {
+    /// The configuration of the topic.
+    pub fn topic<S>(topic_name: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        ConfigResource {
+            resource_type: ConfigResourceType::Topic,
+            resource_name: topic_name.into(),
+        }
+    }
+
+    /// The configuration of the broker.
+    pub fn broker(broker_id: NodeId) -> Self {
+        ConfigResource {
+            resource_type: ConfigResourceType::Broker,
+            resource_name: broker_id.to_string().into(),
+        }
+    }
+}
+
+impl<'a> fmt::Display for ConfigResource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}({})", self.resource_type, self.resource_name)
+    }
+}
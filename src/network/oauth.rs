@@ -0,0 +1,265 @@
+use std::rc::Rc;
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64;
+use bytes::Bytes;
+
+use errors::{ErrorKind, Result};
+use network::{SaslClient, SaslMechanism};
+
+/// The default lifetime of the unsecured JWT.
+const DEFAULT_UNSECURED_JWT_LIFETIME_SECS: u64 = 3600;
+
+/// The separator of the key/value pairs in the OAUTHBEARER client response.
+const KVSEP: &str = "\x01";
+
+/// A bearer token used to authenticate with the `OAUTHBEARER` mechanism.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuthBearerToken {
+    /// The compact serialization of the token.
+    pub value: String,
+    /// The name of the principal the token was issued to.
+    pub principal_name: String,
+    /// The time when the token expires.
+    pub expires_at: SystemTime,
+}
+
+impl OAuthBearerToken {
+    /// The remaining lifetime of the token, or `None` if the token has expired.
+    pub fn lifetime(&self) -> Option<Duration> {
+        self.expires_at.duration_since(SystemTime::now()).ok()
+    }
+}
+
+/// A provider which retrieves the bearer token for the `OAUTHBEARER` mechanism.
+///
+/// The provider is called for every new connection and every re-authentication,
+/// so it should cache the token and refresh it before the token expires.
+pub trait TokenProvider {
+    /// Retrieve a valid bearer token.
+    fn token(&self) -> Result<OAuthBearerToken>;
+}
+
+/// The client of the `OAUTHBEARER` mechanism, see [RFC 7628](https://tools.ietf.org/html/rfc7628).
+pub struct OAuthBearerClient {
+    provider: Rc<TokenProvider>,
+    error: Option<String>,
+}
+
+impl OAuthBearerClient {
+    pub fn new(provider: Rc<TokenProvider>) -> Self {
+        OAuthBearerClient { provider, error: None }
+    }
+}
+
+impl SaslClient for OAuthBearerClient {
+    fn mechanism(&self) -> &'static str {
+        SaslMechanism::OAuthBearer.name()
+    }
+
+    fn initial_response(&mut self) -> Result<Bytes> {
+        let token = self.provider.token()?;
+
+        if token.lifetime().is_none() {
+            bail!(ErrorKind::AuthenticationFailed(format!(
+                "the token of `{}` has expired",
+                token.principal_name
+            )))
+        }
+
+        self.error = None;
+
+        Ok(Bytes::from(format!("n,,{}auth=Bearer {}{}{}", KVSEP, token.value, KVSEP, KVSEP)))
+    }
+
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> Result<Option<Bytes>> {
+        if challenge.is_empty() {
+            return Ok(None);
+        }
+
+        match self.error.take() {
+            None => {
+                // the server sends the error as a JSON challenge, the client must acknowledge it,
+                // then the server fails the authentication.
+                self.error = Some(String::from_utf8_lossy(challenge).into_owned());
+
+                Ok(Some(Bytes::from(KVSEP)))
+            }
+            Some(error) => bail!(ErrorKind::AuthenticationFailed(error)),
+        }
+    }
+}
+
+/// A provider of unsecured JSON Web Token, which should only be used for development and testing.
+///
+/// The token is signed with `alg=none`, and the broker must be configured with
+/// the `OAuthBearerUnsecuredValidatorCallbackHandler` to accept it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsecuredJwtProvider {
+    principal_name: String,
+    scope: Vec<String>,
+    lifetime: Duration,
+}
+
+impl UnsecuredJwtProvider {
+    pub fn new<S: Into<String>>(principal_name: S) -> Self {
+        UnsecuredJwtProvider {
+            principal_name: principal_name.into(),
+            scope: vec![],
+            lifetime: Duration::from_secs(DEFAULT_UNSECURED_JWT_LIFETIME_SECS),
+        }
+    }
+
+    /// Sets the scope claim of the token.
+    pub fn with_scope<I, S>(mut self, scope: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scope = scope.into_iter().map(|s| s.into()).collect();
+        self
+    }
+
+    /// Sets the lifetime of the token.
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Parse the `principal=<name> [scope=<scope1>,<scope2>] [lifeSeconds=<seconds>]` config.
+    pub fn from_config(config: &str) -> Result<Self> {
+        let mut principal_name = None;
+        let mut scope = vec![];
+        let mut lifetime = Duration::from_secs(DEFAULT_UNSECURED_JWT_LIFETIME_SECS);
+
+        for pair in config.split_whitespace() {
+            let mut kv = pair.splitn(2, '=');
+
+            match (kv.next(), kv.next()) {
+                (Some("principal"), Some(value)) => principal_name = Some(value.to_owned()),
+                (Some("scope"), Some(value)) => scope = value.split(',').map(|s| s.to_owned()).collect(),
+                (Some("lifeSeconds"), Some(value)) => lifetime = Duration::from_secs(value.parse()?),
+                _ => bail!(ErrorKind::ParseError(format!("invalid unsecured JWT config: {}", pair))),
+            }
+        }
+
+        let principal_name =
+            principal_name.ok_or_else(|| ErrorKind::ParseError("missing principal of unsecured JWT".to_owned()))?;
+
+        Ok(UnsecuredJwtProvider {
+            principal_name,
+            scope,
+            lifetime,
+        })
+    }
+}
+
+impl TokenProvider for UnsecuredJwtProvider {
+    fn token(&self) -> Result<OAuthBearerToken> {
+        let issued_at = SystemTime::now();
+        let expires_at = issued_at + self.lifetime;
+
+        let header = r#"{"alg":"none"}"#;
+        let claims = format!(
+            r#"{{"sub":{},"iat":{},"exp":{},"scope":[{}]}}"#,
+            json_string(&self.principal_name),
+            issued_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            expires_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            self.scope.iter().map(|s| json_string(s)).collect::<Vec<_>>().join(",")
+        );
+
+        Ok(OAuthBearerToken {
+            value: format!(
+                "{}.{}.",
+                base64::encode_config(header.as_bytes(), base64::URL_SAFE_NO_PAD),
+                base64::encode_config(claims.as_bytes(), base64::URL_SAFE_NO_PAD)
+            ),
+            principal_name: self.principal_name.clone(),
+            expires_at,
+        })
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsecured_jwt_provider() {
+        let provider = UnsecuredJwtProvider::from_config("principal=admin scope=read,write lifeSeconds=60").unwrap();
+
+        assert_eq!(
+            provider,
+            UnsecuredJwtProvider::new("admin")
+                .with_scope(vec!["read", "write"])
+                .with_lifetime(Duration::from_secs(60))
+        );
+
+        let token = provider.token().unwrap();
+        let parts = token.value.split('.').collect::<Vec<_>>();
+
+        assert_eq!(token.principal_name, "admin");
+        assert!(token.lifetime().unwrap() <= Duration::from_secs(60));
+        assert_eq!(parts.len(), 3);
+        assert_eq!(
+            base64::decode_config(parts[0], base64::URL_SAFE_NO_PAD).unwrap(),
+            br#"{"alg":"none"}"#.to_vec()
+        );
+
+        let claims = String::from_utf8(base64::decode_config(parts[1], base64::URL_SAFE_NO_PAD).unwrap()).unwrap();
+
+        assert!(claims.starts_with(r#"{"sub":"admin","iat":"#));
+        assert!(claims.ends_with(r#","scope":["read","write"]}"#));
+        assert_eq!(parts[2], "");
+
+        assert!(UnsecuredJwtProvider::from_config("scope=read").is_err());
+        assert!(UnsecuredJwtProvider::from_config("principal=admin lifeSeconds=x").is_err());
+    }
+
+    #[test]
+    fn test_oauth_bearer_client() {
+        let provider = UnsecuredJwtProvider::new("admin");
+        let token = provider.token().unwrap();
+        let mut client = OAuthBearerClient::new(Rc::new(provider));
+
+        assert_eq!(client.mechanism(), "OAUTHBEARER");
+
+        let response = client.initial_response().unwrap();
+        let response = str::from_utf8(&response).unwrap();
+
+        assert!(response.starts_with("n,,\x01auth=Bearer "));
+        assert!(response.ends_with("\x01\x01"));
+        assert!(response.contains(token.value.split('.').next().unwrap()));
+
+        assert_eq!(client.evaluate_challenge(b"").unwrap(), None);
+        assert_eq!(
+            client.evaluate_challenge(br#"{"status":"invalid_token"}"#).unwrap(),
+            Some(Bytes::from(KVSEP))
+        );
+        assert!(client.evaluate_challenge(b"error").is_err());
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("admin"), r#""admin""#);
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+}
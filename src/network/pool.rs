@@ -1,4 +1,5 @@
 use std::cell::{Cell, RefCell};
+use std::cmp;
 use std::collections::HashMap;
 use std::collections::vec_deque::VecDeque;
 use std::fmt::Debug;
@@ -11,7 +12,7 @@ use std::time::{Duration, Instant};
 use futures::unsync::oneshot;
 use futures::{Async, Future, Poll};
 
-use network::{KeepAlive, Status};
+use network::{KeepAlive, SaslSession, Status};
 
 #[derive(Clone, Debug)]
 pub struct Pool<K, T>
@@ -30,6 +31,7 @@ where
 {
     enabled: bool,
     timeout: Option<Duration>,
+    max_in_flight_requests: usize,
     idle: HashMap<K, Vec<Entry<T>>>,
     parked: HashMap<K, VecDeque<oneshot::Sender<Entry<T>>>>,
 }
@@ -39,11 +41,12 @@ where
     K: Clone + Debug + Hash + Eq,
     T: Clone,
 {
-    pub fn new(timeout: Duration) -> Self {
+    pub fn new(timeout: Duration, max_in_flight_requests: usize) -> Self {
         Pool {
             inner: Rc::new(RefCell::new(PoolInner {
                 enabled: true,
                 timeout: Some(timeout),
+                max_in_flight_requests: cmp::max(max_in_flight_requests, 1),
                 idle: HashMap::new(),
                 parked: HashMap::new(),
             })),
@@ -66,6 +69,11 @@ where
         self.inner.borrow().timeout
     }
 
+    /// The maximum number of requests pipelined on a connection which are waiting for the response.
+    pub fn max_in_flight_requests(&self) -> usize {
+        self.inner.borrow().max_in_flight_requests
+    }
+
     pub fn checkout(&self, key: K) -> Checkout<K, T> {
         Checkout {
             key,
@@ -114,6 +122,9 @@ where
                 value,
                 reused: false,
                 status: Rc::new(Cell::new(Status::Busy)),
+                in_flight_requests: Rc::new(Cell::new(0)),
+                session: Rc::new(Cell::new(None)),
+                authenticating: Rc::new(Cell::new(false)),
             },
             key,
             pool: self.clone(),
@@ -163,6 +174,45 @@ where
     value: T,
     reused: bool,
     status: Rc<Cell<Status>>,
+    in_flight_requests: Rc<Cell<usize>>,
+    session: Rc<Cell<Option<SaslSession>>>,
+    authenticating: Rc<Cell<bool>>,
+}
+
+impl<K, T> Pooled<K, T>
+where
+    K: Clone + Hash + Eq,
+    T: Clone,
+{
+    /// The SASL session of the connection, or `None` if the connection never expires.
+    pub fn session(&self) -> Option<SaslSession> {
+        self.entry.session.get()
+    }
+
+    /// Update the SASL session after the connection has been (re-)authenticated.
+    pub fn set_session(&self, session: Option<SaslSession>) {
+        self.entry.session.set(session)
+    }
+
+    /// The connection should be re-authenticated before sending the next request (KIP-368).
+    pub fn needs_reauthentication(&self) -> bool {
+        self.session().map_or(false, |session| session.needs_reauthentication())
+    }
+
+    /// The connection is being re-authenticated, it's kept out of the pool until the exchange completed.
+    pub fn is_authenticating(&self) -> bool {
+        self.entry.authenticating.get()
+    }
+
+    /// Start or complete the re-authentication of the connection.
+    pub fn set_authenticating(&self, authenticating: bool) {
+        self.entry.authenticating.set(authenticating)
+    }
+
+    /// The number of requests sent on the connection which are waiting for the response.
+    pub fn in_flight_requests(&self) -> usize {
+        self.entry.in_flight_requests.get()
+    }
 }
 
 impl<K, T> Deref for Pooled<K, T>
@@ -208,6 +258,12 @@ where
     }
 
     fn idle(&mut self) {
+        if self.is_authenticating() {
+            trace!("re-authenticating, {:?}", self);
+
+            // the connection is still busy, it will be put back to the pool after the next request
+            return;
+        }
         let previous = self.status();
         self.entry.status.set(Status::Idle(Instant::now()));
         if let Status::Idle(..) = previous {
@@ -216,12 +272,48 @@ where
             return;
         }
         self.entry.reused = true;
+        if self.in_flight_requests() >= self.pool.max_in_flight_requests() {
+            trace!("too many in flight requests, {:?}", self);
+
+            // the connection will be put back to the pool after it received a response
+            return;
+        }
+        if self.needs_reauthentication() && self.in_flight_requests() > 0 {
+            trace!("wait for the in flight requests before re-authentication, {:?}", self);
+
+            // the connection will be put back to the pool after it received all the responses
+            return;
+        }
         if self.pool.is_enabled() {
             trace!("idle, {:?}", self);
 
             self.pool.put(self.key.clone(), self.entry.clone());
         }
     }
+
+    fn sent(&mut self) {
+        self.entry.in_flight_requests.set(self.in_flight_requests() + 1)
+    }
+
+    fn received(&mut self) {
+        let in_flight_requests = self.in_flight_requests().saturating_sub(1);
+        self.entry.in_flight_requests.set(in_flight_requests);
+        let released = if self.needs_reauthentication() {
+            in_flight_requests == 0
+        } else {
+            in_flight_requests + 1 == self.pool.max_in_flight_requests()
+        };
+        if !released || self.is_authenticating() {
+            return;
+        }
+        if let Status::Idle(..) = self.status() {
+            if self.pool.is_enabled() {
+                trace!("idle after received response, {:?}", self);
+
+                self.pool.put(self.key.clone(), self.entry.clone());
+            }
+        }
+    }
 }
 
 pub struct Checkout<K, T>
@@ -276,7 +368,10 @@ where
 
             while let Some(entry) = list.pop() {
                 match entry.status.get() {
-                    Status::Idle(idle_at) if !expiration.expires(idle_at) => {
+                    Status::Idle(idle_at)
+                        if !expiration.expires(idle_at) && !entry.authenticating.get()
+                            && !entry.session.get().map_or(false, |session| session.is_expired()) =>
+                    {
                         trace!("found idle for {:?}", key);
 
                         should_remove = list.is_empty();
@@ -293,6 +388,8 @@ where
                         // 1. Idle but expired
                         // 2. Busy (something else somehow took it?)
                         // 3. Disabled don't reuse of course
+                        // 4. SASL session expired, the broker has closed it
+                        // 5. Re-authenticating, it will be put back after the exchange completed
                     }
                 }
             }
@@ -340,3 +437,70 @@ impl Expiration {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_in_flight_requests() {
+        let pool = Pool::new(Duration::from_secs(5), 2);
+        let mut pooled = pool.pooled("broker", "connection");
+
+        pooled.sent();
+        pooled.idle();
+
+        // the connection could be shared since it has only one in flight request
+        let mut pooled = pool.checkout("broker").wait().unwrap();
+
+        assert_eq!(pooled.in_flight_requests(), 1);
+
+        pooled.sent();
+        pooled.idle();
+
+        // the connection is held until it received a response
+        assert!(pool.inner.borrow().idle.get("broker").is_none());
+
+        pooled.received();
+
+        assert_eq!(pooled.in_flight_requests(), 1);
+        assert_eq!(pool.inner.borrow().idle["broker"].len(), 1);
+    }
+
+    #[test]
+    fn test_reauthenticate_without_in_flight_requests() {
+        let pool = Pool::new(Duration::from_secs(5), 2);
+        let mut pooled = pool.pooled("broker", "connection");
+
+        pooled.set_session(Some(SaslSession {
+            reauthenticate_at: Instant::now(),
+            expires_at: Instant::now() + Duration::from_secs(60),
+        }));
+        pooled.sent();
+        pooled.idle();
+
+        // the connection is drained before re-authentication
+        assert!(pool.inner.borrow().idle.get("broker").is_none());
+
+        pooled.received();
+
+        let mut pooled = pool.checkout("broker").wait().unwrap();
+
+        assert_eq!(pooled.in_flight_requests(), 0);
+        assert!(pooled.needs_reauthentication());
+
+        pooled.set_authenticating(true);
+        pooled.sent();
+        pooled.idle();
+        pooled.received();
+
+        // the SASL requests don't share the connection
+        assert!(pool.inner.borrow().idle.get("broker").is_none());
+
+        pooled.set_session(None);
+        pooled.set_authenticating(false);
+        pooled.idle();
+
+        assert_eq!(pool.inner.borrow().idle["broker"].len(), 1);
+    }
+}
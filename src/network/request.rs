@@ -2,18 +2,27 @@ use std::borrow::{Cow, ToOwned};
 use std::collections::HashMap;
 use std::time::Duration;
 
-use bytes::{ByteOrder, BytesMut};
+use bytes::{ByteOrder, Bytes, BytesMut};
 
 use errors::Result;
-use network::{OffsetAndMetadata, TopicPartition};
-use protocol::{ApiKey, ApiKeys, ApiVersion, ApiVersionsRequest, CorrelationId, DescribeGroupsRequest, Encodable,
-               FetchOffset, FetchRequest, FetchTopic, GenerationId, GroupCoordinatorRequest, HeartbeatRequest,
-               JoinGroupProtocol, JoinGroupRequest, LeaveGroupRequest, ListGroupsRequest, ListOffsetRequest,
-               ListPartitionOffset, ListTopicOffset, MessageSet, MetadataRequest, OffsetCommitPartition,
-               OffsetCommitRequest, OffsetCommitTopic, OffsetFetchPartition, OffsetFetchRequest, OffsetFetchTopic,
-               PartitionId, ProducePartitionData, ProduceRequest, ProduceTopicData, Record, RequestHeader,
-               RequiredAck, RequiredAcks, SyncGroupAssignment, SyncGroupRequest, ToMilliseconds, CONSUMER_REPLICA_ID,
-               DEFAULT_TIMESTAMP};
+use network::{ConfigResource, NewPartitions, NewTopic, OffsetAndMetadata, TopicPartition};
+use protocol::messages::describe_log_dirs_request::DescribableLogDirTopic;
+use protocol::messages::DescribeLogDirsRequest;
+use protocol::{AclBinding, AclBindingFilter, AddOffsetsToTxnRequest, AddPartitionsToTxnRequest, AddPartitionsToTxnTopic,
+               AlterConfigsRequest, AlterConfigsResource, ApiKey, ApiKeys, ApiVersion, ApiVersionsRequest, ConfigEntry,
+               CoordinatorType, CorrelationId, CreateAclsRequest, CreatePartitionsRequest, CreatePartitionsTopic,
+               CreateTopicsRequest, CreateTopicsTopic, DeleteAclsRequest, DeleteGroupsRequest, DeleteRecordsPartition,
+               DeleteRecordsRequest, DeleteRecordsTopic, DeleteTopicsRequest, DescribeAclsRequest,
+               DescribeConfigsRequest, DescribeConfigsResource, DescribeGroupsRequest, Encodable, EndTxnRequest,
+               FetchOffset, FetchRequest, FetchTopic, ForgottenTopic, GenerationId, GroupCoordinatorRequest,
+               HeartbeatRequest, InitProducerIdRequest, IsolationLevel, JoinGroupProtocol, JoinGroupRequest,
+               LeaveGroupRequest, ListGroupsRequest, ListOffsetRequest, ListPartitionOffset, ListTopicOffset,
+               MessageSet, MetadataRequest, Offset, OffsetCommitPartition, OffsetCommitRequest, OffsetCommitTopic,
+               OffsetFetchPartition, OffsetFetchRequest, OffsetFetchTopic, PartitionId, ProducePartitionData,
+               ProduceRequest, ProduceTopicData, ProducerEpoch, ProducerId, Record, ReplicaAssignment, RequestHeader,
+               RequiredAck, RequiredAcks, SaslAuthenticateRequest, SaslHandshakeRequest, SyncGroupAssignment,
+               SyncGroupRequest, ToMilliseconds, TxnOffsetCommitPartition, TxnOffsetCommitRequest, TxnOffsetCommitTopic,
+               CONSUMER_REPLICA_ID, DEFAULT_TIMESTAMP, NO_PARTITION_LEADER_EPOCH};
 
 #[derive(Debug)]
 pub enum KafkaRequest<'a> {
@@ -30,7 +39,25 @@ pub enum KafkaRequest<'a> {
     SyncGroup(SyncGroupRequest<'a>),
     DescribeGroups(DescribeGroupsRequest<'a>),
     ListGroups(ListGroupsRequest<'a>),
+    SaslHandshake(SaslHandshakeRequest<'a>),
     ApiVersions(ApiVersionsRequest<'a>),
+    CreateTopics(CreateTopicsRequest<'a>),
+    DeleteTopics(DeleteTopicsRequest<'a>),
+    DescribeConfigs(DescribeConfigsRequest<'a>),
+    AlterConfigs(AlterConfigsRequest<'a>),
+    SaslAuthenticate(SaslAuthenticateRequest<'a>),
+    CreatePartitions(CreatePartitionsRequest<'a>),
+    DeleteRecords(DeleteRecordsRequest<'a>),
+    DescribeAcls(DescribeAclsRequest<'a>),
+    CreateAcls(CreateAclsRequest<'a>),
+    DeleteAcls(DeleteAclsRequest<'a>),
+    DeleteGroups(DeleteGroupsRequest<'a>),
+    InitProducerId(InitProducerIdRequest<'a>),
+    AddPartitionsToTxn(AddPartitionsToTxnRequest<'a>),
+    AddOffsetsToTxn(AddOffsetsToTxnRequest<'a>),
+    EndTxn(EndTxnRequest<'a>),
+    TxnOffsetCommit(TxnOffsetCommitRequest<'a>),
+    DescribeLogDirs(DescribeLogDirsRequest<'a>),
 }
 
 impl<'a> KafkaRequest<'a> {
@@ -49,7 +76,33 @@ impl<'a> KafkaRequest<'a> {
             KafkaRequest::SyncGroup(ref req) => &req.header,
             KafkaRequest::DescribeGroups(ref req) => &req.header,
             KafkaRequest::ListGroups(ref req) => &req.header,
+            KafkaRequest::SaslHandshake(ref req) => &req.header,
             KafkaRequest::ApiVersions(ref req) => &req.header,
+            KafkaRequest::CreateTopics(ref req) => &req.header,
+            KafkaRequest::DeleteTopics(ref req) => &req.header,
+            KafkaRequest::DescribeConfigs(ref req) => &req.header,
+            KafkaRequest::AlterConfigs(ref req) => &req.header,
+            KafkaRequest::SaslAuthenticate(ref req) => &req.header,
+            KafkaRequest::CreatePartitions(ref req) => &req.header,
+            KafkaRequest::DeleteRecords(ref req) => &req.header,
+            KafkaRequest::DescribeAcls(ref req) => &req.header,
+            KafkaRequest::CreateAcls(ref req) => &req.header,
+            KafkaRequest::DeleteAcls(ref req) => &req.header,
+            KafkaRequest::DeleteGroups(ref req) => &req.header,
+            KafkaRequest::InitProducerId(ref req) => &req.header,
+            KafkaRequest::AddPartitionsToTxn(ref req) => &req.header,
+            KafkaRequest::AddOffsetsToTxn(ref req) => &req.header,
+            KafkaRequest::EndTxn(ref req) => &req.header,
+            KafkaRequest::TxnOffsetCommit(ref req) => &req.header,
+            KafkaRequest::DescribeLogDirs(ref req) => &req.header,
+        }
+    }
+
+    /// The broker doesn't send the response of a produce request with `acks=0`.
+    pub fn expects_response(&self) -> bool {
+        match *self {
+            KafkaRequest::Produce(ref req) => req.required_acks != RequiredAcks::None as RequiredAck,
+            _ => true,
         }
     }
 
@@ -57,6 +110,7 @@ impl<'a> KafkaRequest<'a> {
         api_version: ApiVersion,
         correlation_id: CorrelationId,
         client_id: Option<Cow<'a, str>>,
+        transactional_id: Option<Cow<'a, str>>,
         required_acks: RequiredAcks,
         ack_timeout: Duration,
         tp: &TopicPartition<'a>,
@@ -82,6 +136,7 @@ impl<'a> KafkaRequest<'a> {
                 correlation_id,
                 client_id,
             },
+            transactional_id,
             required_acks: required_acks as RequiredAck,
             ack_timeout: ack_timeout.as_millis() as i32,
             topics,
@@ -97,7 +152,12 @@ impl<'a> KafkaRequest<'a> {
         max_wait_time: Duration,
         min_bytes: i32,
         max_bytes: i32,
+        isolation_level: IsolationLevel,
+        session_id: i32,
+        session_epoch: i32,
         topics: Vec<FetchTopic<'a>>,
+        forgotten_topics: Vec<ForgottenTopic<'a>>,
+        rack_id: Cow<'a, str>,
     ) -> KafkaRequest<'a> {
         let request = FetchRequest {
             header: RequestHeader {
@@ -110,7 +170,12 @@ impl<'a> KafkaRequest<'a> {
             max_wait_time: max_wait_time.as_millis() as i32,
             min_bytes,
             max_bytes,
+            isolation_level,
+            session_id,
+            session_epoch,
             topics,
+            forgotten_topics,
+            rack_id,
         };
 
         KafkaRequest::Fetch(request)
@@ -120,6 +185,7 @@ impl<'a> KafkaRequest<'a> {
         api_version: ApiVersion,
         correlation_id: CorrelationId,
         client_id: Option<Cow<'a, str>>,
+        isolation_level: IsolationLevel,
         topics: HashMap<Cow<'a, str>, Vec<(PartitionId, FetchOffset)>>,
     ) -> KafkaRequest<'a> {
         let topics = topics
@@ -130,6 +196,7 @@ impl<'a> KafkaRequest<'a> {
                     .into_iter()
                     .map(|(id, offset)| ListPartitionOffset {
                         partition_id: id,
+                        current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
                         timestamp: offset.into(),
                         max_number_of_offsets: 16,
                     })
@@ -145,6 +212,7 @@ impl<'a> KafkaRequest<'a> {
                 client_id,
             },
             replica_id: -1,
+            isolation_level,
             topics,
         };
 
@@ -192,6 +260,7 @@ impl<'a> KafkaRequest<'a> {
                     .push(OffsetCommitPartition {
                         partition_id: tp.partition_id,
                         offset: offset.offset,
+                        leader_epoch: NO_PARTITION_LEADER_EPOCH,
                         timestamp: DEFAULT_TIMESTAMP,
                         metadata: offset.metadata.map(|s| s.into()),
                     });
@@ -223,25 +292,27 @@ impl<'a> KafkaRequest<'a> {
         correlation_id: CorrelationId,
         client_id: Option<Cow<'a, str>>,
         group_id: Cow<'a, str>,
-        partitions: I,
+        partitions: Option<I>,
     ) -> KafkaRequest<'a>
     where
         I: IntoIterator<Item = TopicPartition<'a>>,
     {
-        let topics = partitions
-            .into_iter()
-            .fold(HashMap::new(), |mut topics, tp| {
-                topics
-                    .entry(tp.topic_name)
-                    .or_insert_with(Vec::new)
-                    .push(OffsetFetchPartition {
-                        partition_id: tp.partition_id,
-                    });
-                topics
-            })
-            .into_iter()
-            .map(|(topic_name, partitions)| OffsetFetchTopic { topic_name, partitions })
-            .collect();
+        let topics = partitions.map(|partitions| {
+            partitions
+                .into_iter()
+                .fold(HashMap::new(), |mut topics, tp| {
+                    topics
+                        .entry(tp.topic_name)
+                        .or_insert_with(Vec::new)
+                        .push(OffsetFetchPartition {
+                            partition_id: tp.partition_id,
+                        });
+                    topics
+                })
+                .into_iter()
+                .map(|(topic_name, partitions)| OffsetFetchTopic { topic_name, partitions })
+                .collect()
+        });
         let request = OffsetFetchRequest {
             header: RequestHeader {
                 api_key: ApiKeys::OffsetFetch as ApiKey,
@@ -261,6 +332,7 @@ impl<'a> KafkaRequest<'a> {
         correlation_id: CorrelationId,
         client_id: Option<Cow<'a, str>>,
         group_id: Cow<'a, str>,
+        coordinator_type: CoordinatorType,
     ) -> KafkaRequest<'a> {
         let request = GroupCoordinatorRequest {
             header: RequestHeader {
@@ -270,6 +342,7 @@ impl<'a> KafkaRequest<'a> {
                 client_id,
             },
             group_id,
+            coordinator_type,
         };
 
         KafkaRequest::GroupCoordinator(request)
@@ -376,6 +449,99 @@ impl<'a> KafkaRequest<'a> {
         KafkaRequest::SyncGroup(request)
     }
 
+    pub fn describe_groups(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        groups: Vec<Cow<'a, str>>,
+    ) -> KafkaRequest<'a> {
+        let request = DescribeGroupsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeGroups as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            groups,
+        };
+
+        KafkaRequest::DescribeGroups(request)
+    }
+
+    pub fn list_groups(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+    ) -> KafkaRequest<'a> {
+        let request = ListGroupsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::ListGroups as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+        };
+
+        KafkaRequest::ListGroups(request)
+    }
+
+    pub fn delete_groups(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        groups: Vec<Cow<'a, str>>,
+    ) -> KafkaRequest<'a> {
+        let request = DeleteGroupsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteGroups as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            groups,
+        };
+
+        KafkaRequest::DeleteGroups(request)
+    }
+
+    pub fn sasl_handshake(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        mechanism: Cow<'a, str>,
+    ) -> KafkaRequest<'a> {
+        let request = SaslHandshakeRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::SaslHandshake as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            mechanism,
+        };
+
+        KafkaRequest::SaslHandshake(request)
+    }
+
+    pub fn sasl_authenticate(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        auth_bytes: Bytes,
+    ) -> KafkaRequest<'a> {
+        let request = SaslAuthenticateRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::SaslAuthenticate as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            auth_bytes,
+        };
+
+        KafkaRequest::SaslAuthenticate(request)
+    }
+
     pub fn api_versions(correlation_id: CorrelationId, client_id: Option<Cow<'a, str>>) -> KafkaRequest<'a> {
         let request = ApiVersionsRequest {
             header: RequestHeader {
@@ -388,6 +554,442 @@ impl<'a> KafkaRequest<'a> {
 
         KafkaRequest::ApiVersions(request)
     }
+
+    pub fn create_topics<I>(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        topics: I,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> KafkaRequest<'a>
+    where
+        I: IntoIterator<Item = NewTopic<'a>>,
+    {
+        let topics = topics
+            .into_iter()
+            .map(|topic| CreateTopicsTopic {
+                topic_name: topic.topic_name,
+                num_partitions: topic.num_partitions,
+                replication_factor: topic.replication_factor,
+                replica_assignment: topic
+                    .replica_assignment
+                    .into_iter()
+                    .map(|(partition_id, replicas)| ReplicaAssignment { partition_id, replicas })
+                    .collect(),
+                configs: topic
+                    .configs
+                    .into_iter()
+                    .map(|(name, value)| ConfigEntry {
+                        config_name: name.into(),
+                        config_value: Some(value.into()),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let request = CreateTopicsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreateTopics as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            topics,
+            timeout: timeout.as_millis() as i32,
+            validate_only,
+        };
+
+        KafkaRequest::CreateTopics(request)
+    }
+
+    pub fn delete_topics(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        topic_names: Vec<Cow<'a, str>>,
+        timeout: Duration,
+    ) -> KafkaRequest<'a> {
+        let request = DeleteTopicsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteTopics as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            topic_names,
+            timeout: timeout.as_millis() as i32,
+        };
+
+        KafkaRequest::DeleteTopics(request)
+    }
+
+    pub fn describe_configs(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        resources: Vec<ConfigResource<'a>>,
+    ) -> KafkaRequest<'a> {
+        let request = DescribeConfigsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeConfigs as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            resources: resources
+                .into_iter()
+                .map(|resource| DescribeConfigsResource {
+                    resource_type: resource.resource_type,
+                    resource_name: resource.resource_name,
+                    config_names: None,
+                })
+                .collect(),
+            include_synonyms: false,
+        };
+
+        KafkaRequest::DescribeConfigs(request)
+    }
+
+    pub fn alter_configs(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        configs: Vec<(ConfigResource<'a>, HashMap<String, String>)>,
+        validate_only: bool,
+    ) -> KafkaRequest<'a> {
+        let request = AlterConfigsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AlterConfigs as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            resources: configs
+                .into_iter()
+                .map(|(resource, configs)| AlterConfigsResource {
+                    resource_type: resource.resource_type,
+                    resource_name: resource.resource_name,
+                    config_entries: configs
+                        .into_iter()
+                        .map(|(name, value)| ConfigEntry {
+                            config_name: name.into(),
+                            config_value: Some(value.into()),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            validate_only,
+        };
+
+        KafkaRequest::AlterConfigs(request)
+    }
+
+    pub fn create_partitions(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        topics: Vec<(Cow<'a, str>, NewPartitions)>,
+        timeout: Duration,
+        validate_only: bool,
+    ) -> KafkaRequest<'a> {
+        let request = CreatePartitionsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreatePartitions as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            topics: topics
+                .into_iter()
+                .map(|(topic_name, partitions)| CreatePartitionsTopic {
+                    topic_name,
+                    count: partitions.total_count,
+                    assignment: partitions.new_assignments,
+                })
+                .collect(),
+            timeout: timeout.as_millis() as i32,
+            validate_only,
+        };
+
+        KafkaRequest::CreatePartitions(request)
+    }
+
+    pub fn delete_records(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        topics: HashMap<Cow<'a, str>, Vec<(PartitionId, Offset)>>,
+        timeout: Duration,
+    ) -> KafkaRequest<'a> {
+        let request = DeleteRecordsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteRecords as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            topics: topics
+                .into_iter()
+                .map(|(topic_name, partitions)| DeleteRecordsTopic {
+                    topic_name,
+                    partitions: partitions
+                        .into_iter()
+                        .map(|(partition_id, offset)| DeleteRecordsPartition { partition_id, offset })
+                        .collect(),
+                })
+                .collect(),
+            timeout: timeout.as_millis() as i32,
+        };
+
+        KafkaRequest::DeleteRecords(request)
+    }
+
+    /// Describe the log directories of the partitions, or all the partitions if `topics` is `None`.
+    pub fn describe_log_dirs(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        topics: Option<HashMap<Cow<'a, str>, Vec<PartitionId>>>,
+    ) -> KafkaRequest<'a> {
+        let request = DescribeLogDirsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeLogDirs as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            topics: topics.map(|topics| {
+                topics
+                    .into_iter()
+                    .map(|(topic, partition_index)| DescribableLogDirTopic {
+                        topic,
+                        partition_index,
+                        unknown_tagged_fields: vec![],
+                    })
+                    .collect()
+            }),
+            unknown_tagged_fields: vec![],
+        };
+
+        KafkaRequest::DescribeLogDirs(request)
+    }
+
+    pub fn describe_acls(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        filter: AclBindingFilter<'a>,
+    ) -> KafkaRequest<'a> {
+        let request = DescribeAclsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeAcls as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            filter,
+        };
+
+        KafkaRequest::DescribeAcls(request)
+    }
+
+    pub fn create_acls(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        creations: Vec<AclBinding<'a>>,
+    ) -> KafkaRequest<'a> {
+        let request = CreateAclsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreateAcls as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            creations,
+        };
+
+        KafkaRequest::CreateAcls(request)
+    }
+
+    pub fn delete_acls(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        filters: Vec<AclBindingFilter<'a>>,
+    ) -> KafkaRequest<'a> {
+        let request = DeleteAclsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteAcls as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            filters,
+        };
+
+        KafkaRequest::DeleteAcls(request)
+    }
+
+    pub fn init_producer_id(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        transactional_id: Option<Cow<'a, str>>,
+        transaction_timeout: Duration,
+    ) -> KafkaRequest<'a> {
+        let request = InitProducerIdRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::InitProducerId as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            transactional_id,
+            transaction_timeout: transaction_timeout.as_millis() as i32,
+        };
+
+        KafkaRequest::InitProducerId(request)
+    }
+
+    pub fn add_partitions_to_txn<I>(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        transactional_id: Cow<'a, str>,
+        producer_id: ProducerId,
+        producer_epoch: ProducerEpoch,
+        partitions: I,
+    ) -> KafkaRequest<'a>
+    where
+        I: IntoIterator<Item = TopicPartition<'a>>,
+    {
+        let topics = partitions
+            .into_iter()
+            .fold(HashMap::new(), |mut topics, tp| {
+                topics
+                    .entry(tp.topic_name)
+                    .or_insert_with(Vec::new)
+                    .push(tp.partition_id);
+                topics
+            })
+            .into_iter()
+            .map(|(topic_name, partitions)| AddPartitionsToTxnTopic { topic_name, partitions })
+            .collect();
+
+        let request = AddPartitionsToTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AddPartitionsToTxn as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            transactional_id,
+            producer_id,
+            producer_epoch,
+            topics,
+        };
+
+        KafkaRequest::AddPartitionsToTxn(request)
+    }
+
+    pub fn add_offsets_to_txn(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        transactional_id: Cow<'a, str>,
+        producer_id: ProducerId,
+        producer_epoch: ProducerEpoch,
+        group_id: Cow<'a, str>,
+    ) -> KafkaRequest<'a> {
+        let request = AddOffsetsToTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AddOffsetsToTxn as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            transactional_id,
+            producer_id,
+            producer_epoch,
+            group_id,
+        };
+
+        KafkaRequest::AddOffsetsToTxn(request)
+    }
+
+    pub fn end_txn(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        transactional_id: Cow<'a, str>,
+        producer_id: ProducerId,
+        producer_epoch: ProducerEpoch,
+        committed: bool,
+    ) -> KafkaRequest<'a> {
+        let request = EndTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::EndTxn as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            transactional_id,
+            producer_id,
+            producer_epoch,
+            transaction_result: committed,
+        };
+
+        KafkaRequest::EndTxn(request)
+    }
+
+    pub fn txn_offset_commit<I>(
+        api_version: ApiVersion,
+        correlation_id: CorrelationId,
+        client_id: Option<Cow<'a, str>>,
+        transactional_id: Cow<'a, str>,
+        group_id: Cow<'a, str>,
+        producer_id: ProducerId,
+        producer_epoch: ProducerEpoch,
+        offsets: I,
+    ) -> KafkaRequest<'a>
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>,
+    {
+        let topics = offsets
+            .into_iter()
+            .fold(HashMap::new(), |mut topics, (tp, offset)| {
+                topics
+                    .entry(tp.topic_name)
+                    .or_insert_with(Vec::new)
+                    .push(TxnOffsetCommitPartition {
+                        partition_id: tp.partition_id,
+                        offset: offset.offset,
+                        metadata: offset.metadata.map(|s| s.into()),
+                    });
+                topics
+            })
+            .into_iter()
+            .map(|(topic_name, partitions)| TxnOffsetCommitTopic { topic_name, partitions })
+            .collect();
+
+        let request = TxnOffsetCommitRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::TxnOffsetCommit as ApiKey,
+                api_version,
+                correlation_id,
+                client_id,
+            },
+            transactional_id,
+            group_id,
+            producer_id,
+            producer_epoch,
+            topics,
+        };
+
+        KafkaRequest::TxnOffsetCommit(request)
+    }
 }
 
 impl<'a> Record for KafkaRequest<'a> {
@@ -406,7 +1008,25 @@ impl<'a> Record for KafkaRequest<'a> {
             KafkaRequest::SyncGroup(ref req) => req.size(api_version),
             KafkaRequest::DescribeGroups(ref req) => req.size(api_version),
             KafkaRequest::ListGroups(ref req) => req.size(api_version),
+            KafkaRequest::SaslHandshake(ref req) => req.size(api_version),
             KafkaRequest::ApiVersions(ref req) => req.size(api_version),
+            KafkaRequest::CreateTopics(ref req) => req.size(api_version),
+            KafkaRequest::DeleteTopics(ref req) => req.size(api_version),
+            KafkaRequest::DescribeConfigs(ref req) => req.size(api_version),
+            KafkaRequest::AlterConfigs(ref req) => req.size(api_version),
+            KafkaRequest::SaslAuthenticate(ref req) => req.size(api_version),
+            KafkaRequest::CreatePartitions(ref req) => req.size(api_version),
+            KafkaRequest::DeleteRecords(ref req) => req.size(api_version),
+            KafkaRequest::DescribeAcls(ref req) => req.size(api_version),
+            KafkaRequest::CreateAcls(ref req) => req.size(api_version),
+            KafkaRequest::DeleteAcls(ref req) => req.size(api_version),
+            KafkaRequest::DeleteGroups(ref req) => req.size(api_version),
+            KafkaRequest::InitProducerId(ref req) => req.size(api_version),
+            KafkaRequest::AddPartitionsToTxn(ref req) => req.size(api_version),
+            KafkaRequest::AddOffsetsToTxn(ref req) => req.size(api_version),
+            KafkaRequest::EndTxn(ref req) => req.size(api_version),
+            KafkaRequest::TxnOffsetCommit(ref req) => req.size(api_version),
+            KafkaRequest::DescribeLogDirs(ref req) => req.size(api_version),
         }
     }
 }
@@ -427,7 +1047,25 @@ impl<'a> Encodable for KafkaRequest<'a> {
             KafkaRequest::SyncGroup(ref req) => req.encode::<T>(dst),
             KafkaRequest::DescribeGroups(ref req) => req.encode::<T>(dst),
             KafkaRequest::ListGroups(ref req) => req.encode::<T>(dst),
+            KafkaRequest::SaslHandshake(ref req) => req.encode::<T>(dst),
             KafkaRequest::ApiVersions(ref req) => req.encode::<T>(dst),
+            KafkaRequest::CreateTopics(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DeleteTopics(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DescribeConfigs(ref req) => req.encode::<T>(dst),
+            KafkaRequest::AlterConfigs(ref req) => req.encode::<T>(dst),
+            KafkaRequest::SaslAuthenticate(ref req) => req.encode::<T>(dst),
+            KafkaRequest::CreatePartitions(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DeleteRecords(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DescribeAcls(ref req) => req.encode::<T>(dst),
+            KafkaRequest::CreateAcls(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DeleteAcls(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DeleteGroups(ref req) => req.encode::<T>(dst),
+            KafkaRequest::InitProducerId(ref req) => req.encode::<T>(dst),
+            KafkaRequest::AddPartitionsToTxn(ref req) => req.encode::<T>(dst),
+            KafkaRequest::AddOffsetsToTxn(ref req) => req.encode::<T>(dst),
+            KafkaRequest::EndTxn(ref req) => req.encode::<T>(dst),
+            KafkaRequest::TxnOffsetCommit(ref req) => req.encode::<T>(dst),
+            KafkaRequest::DescribeLogDirs(ref req) => req.encode::<T>(dst),
         }
     }
 }
@@ -4,10 +4,15 @@ use log::Level::Debug;
 
 use nom::{self, ErrorKind, IResult, Needed};
 
-use protocol::{display_parse_error, ApiKeys, ApiVersion, ApiVersionsResponse, DescribeGroupsResponse, FetchResponse,
-               GroupCoordinatorResponse, HeartbeatResponse, JoinGroupResponse, LeaveGroupResponse, ListGroupsResponse,
-               ListOffsetResponse, MetadataResponse, OffsetCommitResponse, OffsetFetchResponse, ParseTag,
-               ProduceResponse, SyncGroupResponse};
+use protocol::messages::DescribeLogDirsResponse;
+use protocol::{display_parse_error, AddOffsetsToTxnResponse, AddPartitionsToTxnResponse, AlterConfigsResponse, ApiKeys,
+               ApiVersion, ApiVersionsResponse, CreateAclsResponse, CreatePartitionsResponse, CreateTopicsResponse,
+               DeleteAclsResponse, DeleteGroupsResponse, DeleteRecordsResponse, DeleteTopicsResponse,
+               DescribeAclsResponse, DescribeConfigsResponse, DescribeGroupsResponse, EndTxnResponse, FetchResponse,
+               GroupCoordinatorResponse, HeartbeatResponse, InitProducerIdResponse, JoinGroupResponse,
+               LeaveGroupResponse, ListGroupsResponse, ListOffsetResponse, MetadataResponse, OffsetCommitResponse,
+               OffsetFetchResponse, ParseTag, ProduceResponse, SaslAuthenticateResponse, SaslHandshakeResponse,
+               SyncGroupResponse, TxnOffsetCommitResponse};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum KafkaResponse {
@@ -24,7 +29,25 @@ pub enum KafkaResponse {
     SyncGroup(SyncGroupResponse),
     DescribeGroups(DescribeGroupsResponse),
     ListGroups(ListGroupsResponse),
+    SaslHandshake(SaslHandshakeResponse),
     ApiVersions(ApiVersionsResponse),
+    CreateTopics(CreateTopicsResponse),
+    DeleteTopics(DeleteTopicsResponse),
+    DescribeConfigs(DescribeConfigsResponse),
+    AlterConfigs(AlterConfigsResponse),
+    SaslAuthenticate(SaslAuthenticateResponse),
+    CreatePartitions(CreatePartitionsResponse),
+    DeleteRecords(DeleteRecordsResponse),
+    DescribeAcls(DescribeAclsResponse),
+    CreateAcls(CreateAclsResponse),
+    DeleteAcls(DeleteAclsResponse),
+    DeleteGroups(DeleteGroupsResponse),
+    InitProducerId(InitProducerIdResponse),
+    AddPartitionsToTxn(AddPartitionsToTxnResponse),
+    AddOffsetsToTxn(AddOffsetsToTxnResponse),
+    EndTxn(EndTxnResponse),
+    TxnOffsetCommit(TxnOffsetCommitResponse),
+    DescribeLogDirs(DescribeLogDirsResponse),
 }
 
 impl KafkaResponse {
@@ -43,7 +66,25 @@ impl KafkaResponse {
             KafkaResponse::SyncGroup(_) => ApiKeys::SyncGroup,
             KafkaResponse::DescribeGroups(_) => ApiKeys::DescribeGroups,
             KafkaResponse::ListGroups(_) => ApiKeys::ListGroups,
+            KafkaResponse::SaslHandshake(_) => ApiKeys::SaslHandshake,
             KafkaResponse::ApiVersions(_) => ApiKeys::ApiVersions,
+            KafkaResponse::CreateTopics(_) => ApiKeys::CreateTopics,
+            KafkaResponse::DeleteTopics(_) => ApiKeys::DeleteTopics,
+            KafkaResponse::DescribeConfigs(_) => ApiKeys::DescribeConfigs,
+            KafkaResponse::AlterConfigs(_) => ApiKeys::AlterConfigs,
+            KafkaResponse::SaslAuthenticate(_) => ApiKeys::SaslAuthenticate,
+            KafkaResponse::CreatePartitions(_) => ApiKeys::CreatePartitions,
+            KafkaResponse::DeleteRecords(_) => ApiKeys::DeleteRecords,
+            KafkaResponse::DescribeAcls(_) => ApiKeys::DescribeAcls,
+            KafkaResponse::CreateAcls(_) => ApiKeys::CreateAcls,
+            KafkaResponse::DeleteAcls(_) => ApiKeys::DeleteAcls,
+            KafkaResponse::DeleteGroups(_) => ApiKeys::DeleteGroups,
+            KafkaResponse::InitProducerId(_) => ApiKeys::InitProducerId,
+            KafkaResponse::AddPartitionsToTxn(_) => ApiKeys::AddPartitionsToTxn,
+            KafkaResponse::AddOffsetsToTxn(_) => ApiKeys::AddOffsetsToTxn,
+            KafkaResponse::EndTxn(_) => ApiKeys::EndTxn,
+            KafkaResponse::TxnOffsetCommit(_) => ApiKeys::TxnOffsetCommit,
+            KafkaResponse::DescribeLogDirs(_) => ApiKeys::DescribeLogDirs,
         }
     }
 
@@ -61,17 +102,45 @@ impl KafkaResponse {
             ApiKeys::Produce => ProduceResponse::parse(buf, api_version).map(KafkaResponse::Produce),
             ApiKeys::Fetch => FetchResponse::parse(buf, api_version).map(KafkaResponse::Fetch),
             ApiKeys::ListOffsets => ListOffsetResponse::parse(buf, api_version).map(KafkaResponse::ListOffsets),
-            ApiKeys::Metadata => MetadataResponse::parse(buf).map(KafkaResponse::Metadata),
-            ApiKeys::OffsetCommit => OffsetCommitResponse::parse(buf).map(KafkaResponse::OffsetCommit),
-            ApiKeys::OffsetFetch => OffsetFetchResponse::parse(buf).map(KafkaResponse::OffsetFetch),
-            ApiKeys::GroupCoordinator => GroupCoordinatorResponse::parse(buf).map(KafkaResponse::GroupCoordinator),
+            ApiKeys::Metadata => MetadataResponse::parse(buf, api_version).map(KafkaResponse::Metadata),
+            ApiKeys::OffsetCommit => OffsetCommitResponse::parse(buf, api_version).map(KafkaResponse::OffsetCommit),
+            ApiKeys::OffsetFetch => OffsetFetchResponse::parse(buf, api_version).map(KafkaResponse::OffsetFetch),
+            ApiKeys::GroupCoordinator => {
+                GroupCoordinatorResponse::parse(buf, api_version).map(KafkaResponse::GroupCoordinator)
+            }
             ApiKeys::JoinGroup => JoinGroupResponse::parse(buf).map(KafkaResponse::JoinGroup),
             ApiKeys::Heartbeat => HeartbeatResponse::parse(buf).map(KafkaResponse::Heartbeat),
             ApiKeys::LeaveGroup => LeaveGroupResponse::parse(buf).map(KafkaResponse::LeaveGroup),
             ApiKeys::SyncGroup => SyncGroupResponse::parse(buf).map(KafkaResponse::SyncGroup),
             ApiKeys::DescribeGroups => DescribeGroupsResponse::parse(buf).map(KafkaResponse::DescribeGroups),
             ApiKeys::ListGroups => ListGroupsResponse::parse(buf).map(KafkaResponse::ListGroups),
+            ApiKeys::SaslHandshake => SaslHandshakeResponse::parse(buf).map(KafkaResponse::SaslHandshake),
             ApiKeys::ApiVersions => ApiVersionsResponse::parse(buf).map(KafkaResponse::ApiVersions),
+            ApiKeys::CreateTopics => CreateTopicsResponse::parse(buf, api_version).map(KafkaResponse::CreateTopics),
+            ApiKeys::DeleteTopics => DeleteTopicsResponse::parse(buf, api_version).map(KafkaResponse::DeleteTopics),
+            ApiKeys::DescribeConfigs => {
+                DescribeConfigsResponse::parse(buf, api_version).map(KafkaResponse::DescribeConfigs)
+            }
+            ApiKeys::AlterConfigs => AlterConfigsResponse::parse(buf).map(KafkaResponse::AlterConfigs),
+            ApiKeys::SaslAuthenticate => {
+                SaslAuthenticateResponse::parse(buf, api_version).map(KafkaResponse::SaslAuthenticate)
+            }
+            ApiKeys::CreatePartitions => CreatePartitionsResponse::parse(buf).map(KafkaResponse::CreatePartitions),
+            ApiKeys::DeleteRecords => DeleteRecordsResponse::parse(buf).map(KafkaResponse::DeleteRecords),
+            ApiKeys::DescribeAcls => DescribeAclsResponse::parse(buf, api_version).map(KafkaResponse::DescribeAcls),
+            ApiKeys::CreateAcls => CreateAclsResponse::parse(buf).map(KafkaResponse::CreateAcls),
+            ApiKeys::DeleteAcls => DeleteAclsResponse::parse(buf, api_version).map(KafkaResponse::DeleteAcls),
+            ApiKeys::DeleteGroups => DeleteGroupsResponse::parse(buf).map(KafkaResponse::DeleteGroups),
+            ApiKeys::InitProducerId => InitProducerIdResponse::parse(buf).map(KafkaResponse::InitProducerId),
+            ApiKeys::AddPartitionsToTxn => {
+                AddPartitionsToTxnResponse::parse(buf).map(KafkaResponse::AddPartitionsToTxn)
+            }
+            ApiKeys::AddOffsetsToTxn => AddOffsetsToTxnResponse::parse(buf).map(KafkaResponse::AddOffsetsToTxn),
+            ApiKeys::EndTxn => EndTxnResponse::parse(buf).map(KafkaResponse::EndTxn),
+            ApiKeys::TxnOffsetCommit => TxnOffsetCommitResponse::parse(buf).map(KafkaResponse::TxnOffsetCommit),
+            ApiKeys::DescribeLogDirs => {
+                DescribeLogDirsResponse::parse(buf, api_version).map(KafkaResponse::DescribeLogDirs)
+            }
             _ => IResult::Error(nom::Err::Code(ErrorKind::Custom(ParseTag::ApiKey as u32))),
         };
 
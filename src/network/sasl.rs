@@ -0,0 +1,626 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+use rand::{self, Rng};
+
+use futures::future::{self, Future, Loop};
+use futures::{Sink, Stream};
+use tokio_io::AsyncRead;
+use tokio_io::codec::Framed;
+use tokio_io::io::{read_exact, write_all};
+
+use errors::{Error, ErrorKind, Result};
+use network::{CorrelationIds, KafkaCodec, KafkaRequest, KafkaResponse, KafkaStream, OAuthBearerClient, ScramClient, ScramMechanism,
+              TokenProvider};
+use protocol::{ApiKeys, ApiVersion, CorrelationId, KafkaCode};
+
+/// The highest version of `SaslHandshake` request, v1 means the tokens are wrapped in `SaslAuthenticate` requests,
+/// while v0 sends the raw tokens on the socket for the brokers before 1.0.0 (KIP-152).
+const SASL_HANDSHAKE_VERSION: ApiVersion = 1;
+/// The highest version of `SaslAuthenticate` request, v1 returns the session lifetime (KIP-368).
+const SASL_AUTHENTICATE_VERSION: ApiVersion = 1;
+
+/// The connection will be re-authenticated at a random time between 85% and 95% of the session lifetime.
+const REAUTHENTICATE_WINDOW: (f64, f64) = (0.85, 0.95);
+
+/// The SASL mechanism used to authenticate the client connections.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SaslMechanism {
+    /// Simple username/password authentication, which should be used with TLS.
+    #[serde(rename = "PLAIN")]
+    Plain,
+    /// Salted challenge response authentication with SHA-256.
+    #[serde(rename = "SCRAM-SHA-256")]
+    ScramSha256,
+    /// Salted challenge response authentication with SHA-512.
+    #[serde(rename = "SCRAM-SHA-512")]
+    ScramSha512,
+    /// Bearer token authentication with OAuth 2.
+    #[serde(rename = "OAUTHBEARER")]
+    OAuthBearer,
+}
+
+impl Default for SaslMechanism {
+    fn default() -> Self {
+        SaslMechanism::Plain
+    }
+}
+
+impl SaslMechanism {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            SaslMechanism::Plain => "PLAIN",
+            SaslMechanism::ScramSha256 => "SCRAM-SHA-256",
+            SaslMechanism::ScramSha512 => "SCRAM-SHA-512",
+            SaslMechanism::OAuthBearer => "OAUTHBEARER",
+        }
+    }
+}
+
+impl fmt::Display for SaslMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SaslMechanism {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "PLAIN" => Ok(SaslMechanism::Plain),
+            "SCRAM-SHA-256" => Ok(SaslMechanism::ScramSha256),
+            "SCRAM-SHA-512" => Ok(SaslMechanism::ScramSha512),
+            "OAUTHBEARER" => Ok(SaslMechanism::OAuthBearer),
+            _ => bail!(ErrorKind::ParseError(format!("unknown SASL mechanism: {}", s))),
+        }
+    }
+}
+
+/// The SASL settings of the client connections.
+#[derive(Clone, Debug)]
+pub struct SaslConfig {
+    /// The SASL mechanism used to authenticate
+    pub mechanism: SaslMechanism,
+    /// The credentials of the mechanism
+    pub credentials: SaslCredentials,
+}
+
+/// The credentials used to authenticate with the SASL mechanism.
+#[derive(Clone)]
+pub enum SaslCredentials {
+    /// The username and password of `PLAIN` and `SCRAM` mechanisms.
+    Password { username: String, password: String },
+    /// The provider of bearer tokens for the `OAUTHBEARER` mechanism.
+    Token(Rc<TokenProvider>),
+}
+
+impl fmt::Debug for SaslCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaslCredentials::Password { ref username, .. } => f.debug_struct("Password")
+                .field("username", username)
+                .field("password", &"******")
+                .finish(),
+            SaslCredentials::Token(_) => f.debug_tuple("Token").field(&"TokenProvider").finish(),
+        }
+    }
+}
+
+impl SaslConfig {
+    /// Construct a `SaslClient` to authenticate a new connection.
+    pub fn client(&self) -> Result<Box<SaslClient>> {
+        match (self.mechanism, &self.credentials) {
+            (SaslMechanism::Plain, &SaslCredentials::Password { ref username, ref password }) => {
+                Ok(Box::new(PlainClient::new(username.clone(), password.clone())))
+            }
+            (SaslMechanism::ScramSha256, &SaslCredentials::Password { ref username, ref password }) => Ok(Box::new(
+                ScramClient::new(ScramMechanism::Sha256, username.clone(), password.clone()),
+            )),
+            (SaslMechanism::ScramSha512, &SaslCredentials::Password { ref username, ref password }) => Ok(Box::new(
+                ScramClient::new(ScramMechanism::Sha512, username.clone(), password.clone()),
+            )),
+            (SaslMechanism::OAuthBearer, &SaslCredentials::Token(ref provider)) => {
+                Ok(Box::new(OAuthBearerClient::new(provider.clone())))
+            }
+            (mechanism, credentials) => bail!(ErrorKind::IllegalArgument(format!(
+                "`{}` mechanism can't authenticate with {:?}",
+                mechanism, credentials
+            ))),
+        }
+    }
+}
+
+/// The authenticated session of a connection.
+///
+/// The broker returns the session lifetime when it has `connections.max.reauth.ms` configured,
+/// and closes the connection if it isn't re-authenticated before the session expires (KIP-368).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SaslSession {
+    /// The time when the connection should be re-authenticated
+    pub reauthenticate_at: Instant,
+    /// The time when the session expires
+    pub expires_at: Instant,
+}
+
+impl SaslSession {
+    pub fn new(lifetime: Duration) -> Self {
+        let now = Instant::now();
+        let lifetime_ms = lifetime.as_secs() * 1000 + u64::from(lifetime.subsec_nanos() / 1_000_000);
+        let ratio = rand::thread_rng().gen_range(REAUTHENTICATE_WINDOW.0, REAUTHENTICATE_WINDOW.1);
+
+        SaslSession {
+            reauthenticate_at: now + Duration::from_millis((lifetime_ms as f64 * ratio) as u64),
+            expires_at: now + lifetime,
+        }
+    }
+
+    /// The connection should be re-authenticated before sending the next request.
+    pub fn needs_reauthentication(&self) -> bool {
+        Instant::now() >= self.reauthenticate_at
+    }
+
+    /// The session has expired, the connection will be closed by the broker.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at
+    }
+}
+
+/// The client side of a SASL mechanism.
+pub trait SaslClient {
+    /// The name of the SASL mechanism.
+    fn mechanism(&self) -> &'static str;
+
+    /// The first token sent to the broker.
+    fn initial_response(&mut self) -> Result<Bytes>;
+
+    /// Evaluate the token from the broker,
+    /// returns the next token to send, or `None` if the authentication has been completed.
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> Result<Option<Bytes>>;
+}
+
+/// The client of the `PLAIN` mechanism, see [RFC 4616](https://tools.ietf.org/html/rfc4616).
+pub struct PlainClient {
+    username: String,
+    password: String,
+}
+
+impl PlainClient {
+    pub fn new(username: String, password: String) -> Self {
+        PlainClient { username, password }
+    }
+}
+
+impl SaslClient for PlainClient {
+    fn mechanism(&self) -> &'static str {
+        SaslMechanism::Plain.name()
+    }
+
+    fn initial_response(&mut self) -> Result<Bytes> {
+        let mut buf = BytesMut::with_capacity(self.username.len() + self.password.len() + 2);
+
+        buf.put_u8(0);
+        buf.put_slice(self.username.as_bytes());
+        buf.put_u8(0);
+        buf.put_slice(self.password.as_bytes());
+
+        Ok(buf.freeze())
+    }
+
+    fn evaluate_challenge(&mut self, _challenge: &[u8]) -> Result<Option<Bytes>> {
+        Ok(None)
+    }
+}
+
+/// The future of authenticating a new connection.
+pub type Authenticate = Box<Future<Item = (KafkaStream, Option<SaslSession>), Error = io::Error>>;
+
+/// The future of a SASL exchange on the transport.
+pub type Handshake<T> = Box<Future<Item = (T, Option<SaslSession>), Error = io::Error>>;
+
+/// The future of sending a SASL request on the transport.
+pub type SendRequest<T> = Box<Future<Item = (KafkaResponse, T), Error = io::Error>>;
+
+/// The future of sending a raw SASL token on the transport, returns the token from the broker.
+pub type SendToken<T> = Box<Future<Item = (Bytes, T), Error = io::Error>>;
+
+/// A transport which could send the SASL requests, either a new connection or a pooled one.
+pub trait SaslTransport: Sized {
+    /// Send the request and wait for its response.
+    fn send_request(self, request: KafkaRequest<'static>) -> SendRequest<Self>;
+
+    /// Send a size delimited SASL token without the Kafka request header, and wait for the token from the broker.
+    ///
+    /// The raw tokens are only used after the `SaslHandshake` v0 request.
+    fn send_token(self, token: Bytes) -> SendToken<Self>;
+}
+
+impl SaslTransport for Framed<KafkaStream, KafkaCodec<'static>> {
+    fn send_request(self, request: KafkaRequest<'static>) -> SendRequest<Self> {
+        Box::new(self.send(request).and_then(|transport| {
+            transport
+                .into_future()
+                .map_err(|(err, _)| err)
+                .and_then(|(response, transport)| match response {
+                    Some(response) => Ok((response, transport)),
+                    None => Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "connection closed during SASL authentication",
+                    )),
+                })
+        }))
+    }
+
+    fn send_token(self, token: Bytes) -> SendToken<Self> {
+        let mut buf = BytesMut::with_capacity(4 + token.len());
+
+        buf.put_u32::<BigEndian>(token.len() as u32);
+        buf.put_slice(&token);
+
+        // the codec has nothing buffered between the requests, so the stream could be written directly.
+        let stream = self.into_inner();
+
+        Box::new(
+            write_all(stream, buf)
+                .and_then(|(stream, _)| read_exact(stream, [0u8; 4]))
+                .and_then(|(stream, size)| read_exact(stream, vec![0u8; BigEndian::read_u32(&size) as usize]))
+                .map(|(stream, token)| (Bytes::from(token), stream.framed(KafkaCodec::new()))),
+        )
+    }
+}
+
+/// Authenticate a new connection, the connection could only be used for other requests
+/// after the authentication completed.
+pub fn authenticate(
+    stream: KafkaStream,
+    client_id: Option<String>,
+    correlation_ids: CorrelationIds,
+    client: Box<SaslClient>,
+) -> Authenticate {
+    let peer_addr = *stream.addr();
+
+    Box::new(
+        handshake(
+            stream.framed(KafkaCodec::new()),
+            peer_addr,
+            client_id,
+            correlation_ids,
+            client,
+        )
+            .map(|(transport, session)| (transport.into_inner(), session)),
+    )
+}
+
+/// Authenticate the transport with the SASL handshake and a sequence of `SaslAuthenticate` requests.
+///
+/// The versions of SASL requests are negotiated with `ApiVersions` request first,
+/// so the same exchange could re-authenticate an established connection.
+/// The brokers before 1.0.0 don't support `SaslAuthenticate` request, the raw tokens are sent after
+/// the `SaslHandshake` v0 request instead, which requires Kafka 0.10.0 or later.
+pub fn handshake<T>(
+    transport: T,
+    peer_addr: SocketAddr,
+    client_id: Option<String>,
+    correlation_ids: CorrelationIds,
+    mut client: Box<SaslClient>,
+) -> Handshake<T>
+where
+    T: SaslTransport + 'static,
+{
+    let mechanism = client.mechanism();
+
+    trace!("start SASL handshake to {} with `{}` mechanism", peer_addr, mechanism);
+
+    let api_versions = KafkaRequest::api_versions(
+        correlation_ids.next_correlation_id(),
+        client_id.clone().map(Cow::Owned),
+    );
+    let handshake_client_id = client_id.clone();
+    let handshake_correlation_ids = correlation_ids.clone();
+
+    let authenticate = transport
+        .send_request(api_versions)
+        .and_then(|(response, transport)| match response {
+            KafkaResponse::ApiVersions(res) => {
+                let max_version = |api_key| {
+                    res.api_versions
+                        .iter()
+                        .find(|version| version.api_key == api_key)
+                        .map(|version| version.max_version)
+                };
+
+                match (
+                    max_version(ApiKeys::SaslHandshake),
+                    max_version(ApiKeys::SaslAuthenticate),
+                ) {
+                    (Some(handshake_version), Some(authenticate_version)) if handshake_version > 0 => Ok((
+                        transport,
+                        cmp::min(handshake_version, SASL_HANDSHAKE_VERSION),
+                        Some(cmp::min(authenticate_version, SASL_AUTHENTICATE_VERSION)),
+                    )),
+                    (Some(_), _) => Ok((transport, 0, None)),
+                    _ => Err(auth_error(
+                        "broker doesn't support `SaslHandshake` request, requires Kafka 0.10.0 or later".to_owned(),
+                    )),
+                }
+            }
+            res => Err(unexpected_response(&res)),
+        })
+        .and_then(move |(transport, handshake_version, authenticate_version)| {
+            let request = KafkaRequest::sasl_handshake(
+                handshake_version,
+                handshake_correlation_ids.next_correlation_id(),
+                handshake_client_id.map(Cow::Owned),
+                mechanism.into(),
+            );
+
+            transport
+                .send_request(request)
+                .and_then(move |(response, transport)| match response {
+                    KafkaResponse::SaslHandshake(res) => match KafkaCode::from(res.error_code) {
+                        KafkaCode::None => Ok((transport, authenticate_version)),
+                        code => Err(auth_error(format!(
+                            "fail to handshake `{}` mechanism, {}, enabled mechanisms: {:?}",
+                            mechanism,
+                            code.reason(),
+                            res.enabled_mechanisms
+                        ))),
+                    },
+                    res => Err(unexpected_response(&res)),
+                })
+        })
+        .and_then(move |(transport, authenticate_version)| {
+            client
+                .initial_response()
+                .map(|token| (transport, authenticate_version, client, token))
+                .map_err(|err| auth_error(err.to_string()))
+        })
+        .and_then(move |(transport, authenticate_version, client, token)| {
+            future::loop_fn(
+                (transport, client, token),
+                move |(transport, mut client, token)| {
+                    let exchange = match authenticate_version {
+                        Some(authenticate_version) => authenticate_request(
+                            transport,
+                            authenticate_version,
+                            correlation_ids.next_correlation_id(),
+                            client_id.clone(),
+                            token,
+                        ),
+                        None => Box::new(
+                            transport
+                                .send_token(token)
+                                .map(|(challenge, transport)| (challenge, None, transport)),
+                        ),
+                    };
+
+                    exchange.and_then(move |(challenge, session, transport)| {
+                        match client.evaluate_challenge(&challenge) {
+                            Ok(Some(token)) => Ok(Loop::Continue((transport, client, token))),
+                            Ok(None) => Ok(Loop::Break((transport, session))),
+                            Err(err) => Err(auth_error(err.to_string())),
+                        }
+                    })
+                },
+            )
+        })
+        .map(move |(transport, session)| {
+            debug!(
+                "SASL authenticated to {} with `{}` mechanism, session: {:?}",
+                peer_addr, mechanism, session
+            );
+
+            (transport, session)
+        });
+
+    Box::new(authenticate)
+}
+
+/// Send the token wrapped in a `SaslAuthenticate` request, returns the token and session from the broker.
+fn authenticate_request<T>(
+    transport: T,
+    api_version: ApiVersion,
+    correlation_id: CorrelationId,
+    client_id: Option<String>,
+    token: Bytes,
+) -> Box<Future<Item = (Bytes, Option<SaslSession>, T), Error = io::Error>>
+where
+    T: SaslTransport + 'static,
+{
+    let request = KafkaRequest::sasl_authenticate(api_version, correlation_id, client_id.map(Cow::Owned), token);
+
+    Box::new(
+        transport
+            .send_request(request)
+            .and_then(|(response, transport)| match response {
+                KafkaResponse::SaslAuthenticate(res) => match KafkaCode::from(res.error_code) {
+                    KafkaCode::None => Ok((res.auth_bytes, session(res.session_lifetime), transport)),
+                    code => Err(auth_error(format!(
+                        "{}, {}",
+                        code.reason(),
+                        res.error_message.unwrap_or_default()
+                    ))),
+                },
+                res => Err(unexpected_response(&res)),
+            }),
+    )
+}
+
+fn session(session_lifetime: Option<i64>) -> Option<SaslSession> {
+    match session_lifetime {
+        Some(lifetime) if lifetime > 0 => Some(SaslSession::new(Duration::from_millis(lifetime as u64))),
+        _ => None,
+    }
+}
+
+fn auth_error(reason: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        Error::from(ErrorKind::AuthenticationFailed(reason)).to_string(),
+    )
+}
+
+fn unexpected_response(response: &KafkaResponse) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        Error::from(ErrorKind::UnexpectedResponse(response.api_key())).to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_core::net::{TcpListener, TcpStream};
+    use tokio_core::reactor::Core;
+
+    use network::KafkaStream;
+
+    use super::*;
+
+    #[test]
+    fn test_plain_client() {
+        let mut client = PlainClient::new("user".to_owned(), "pass".to_owned());
+
+        assert_eq!(client.mechanism(), "PLAIN");
+        assert_eq!(client.initial_response().unwrap(), Bytes::from(&b"\0user\0pass"[..]));
+        assert_eq!(client.evaluate_challenge(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_sasl_mechanism() {
+        assert_eq!("PLAIN".parse::<SaslMechanism>().unwrap(), SaslMechanism::Plain);
+        assert_eq!("plain".parse::<SaslMechanism>().unwrap(), SaslMechanism::Plain);
+        assert_eq!(
+            "SCRAM-SHA-512".parse::<SaslMechanism>().unwrap(),
+            SaslMechanism::ScramSha512
+        );
+        assert_eq!(
+            "oauthbearer".parse::<SaslMechanism>().unwrap(),
+            SaslMechanism::OAuthBearer
+        );
+        assert!("GSSAPI".parse::<SaslMechanism>().is_err());
+    }
+
+    #[test]
+    fn test_sasl_config() {
+        let config = SaslConfig {
+            mechanism: SaslMechanism::ScramSha256,
+            credentials: SaslCredentials::Password {
+                username: "user".to_owned(),
+                password: "pass".to_owned(),
+            },
+        };
+
+        assert_eq!(config.client().unwrap().mechanism(), "SCRAM-SHA-256");
+        assert!(!format!("{:?}", config).contains("\"pass\""));
+
+        let config = SaslConfig {
+            mechanism: SaslMechanism::OAuthBearer,
+            ..config
+        };
+
+        assert!(config.client().is_err());
+    }
+
+    #[test]
+    fn test_sasl_session() {
+        let session = SaslSession::new(Duration::from_secs(100));
+
+        assert!(session.reauthenticate_at >= session.expires_at - Duration::from_secs(15));
+        assert!(session.reauthenticate_at <= session.expires_at - Duration::from_secs(5));
+        assert!(!session.needs_reauthentication());
+        assert!(!session.is_expired());
+
+        let session = SaslSession::new(Duration::from_secs(0));
+
+        assert!(session.needs_reauthentication());
+        assert!(session.is_expired());
+    }
+
+    /// Handle a request frame of a broker before 1.0.0, which only supports `SaslHandshake` v0.
+    fn handle_legacy_request(frame: &[u8]) -> Vec<u8> {
+        let api_key = BigEndian::read_i16(&frame[0..]);
+        let api_version = BigEndian::read_i16(&frame[2..]);
+        let correlation_id = BigEndian::read_i32(&frame[4..]);
+
+        let mut buf = BytesMut::with_capacity(64);
+
+        buf.put_i32::<BigEndian>(0);
+        buf.put_i32::<BigEndian>(correlation_id);
+
+        if api_key == ApiKeys::ApiVersions as i16 {
+            buf.put_i16::<BigEndian>(KafkaCode::None as i16);
+            buf.put_i32::<BigEndian>(1);
+            buf.put_i16::<BigEndian>(ApiKeys::SaslHandshake as i16);
+            buf.put_i16::<BigEndian>(0);
+            buf.put_i16::<BigEndian>(0);
+        } else {
+            assert_eq!(api_key, ApiKeys::SaslHandshake as i16);
+            assert_eq!(api_version, 0);
+
+            buf.put_i16::<BigEndian>(KafkaCode::None as i16);
+            buf.put_i32::<BigEndian>(1);
+            buf.put_i16::<BigEndian>(5);
+            buf.put_slice(b"PLAIN");
+        }
+
+        let size = buf.len() - 4;
+        BigEndian::write_i32(&mut buf[..4], size as i32);
+
+        buf.to_vec()
+    }
+
+    #[test]
+    fn test_authenticate_with_raw_tokens() {
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap(), &handle).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(move |(conn, _)| {
+                let (stream, _) = conn.unwrap();
+
+                future::loop_fn((stream, 0), |(stream, requests)| {
+                    read_exact(stream, [0u8; 4])
+                        .and_then(|(stream, size)| read_exact(stream, vec![0u8; BigEndian::read_i32(&size) as usize]))
+                        .and_then(move |(stream, frame)| {
+                            if requests < 2 {
+                                Box::new(
+                                    write_all(stream, handle_legacy_request(&frame))
+                                        .map(move |(stream, _)| Loop::Continue((stream, requests + 1))),
+                                ) as Box<Future<Item = _, Error = _>>
+                            } else {
+                                // the raw token without the request header
+                                assert_eq!(frame, b"\0user\0pass");
+
+                                Box::new(write_all(stream, [0u8; 4]).map(|(_, _)| Loop::Break(())))
+                            }
+                        })
+                })
+            });
+
+        let client = TcpStream::connect(&addr, &handle).and_then(move |stream| {
+            authenticate(
+                KafkaStream::Tcp(addr, stream),
+                Some("client".to_owned()),
+                CorrelationIds::default(),
+                Box::new(PlainClient::new("user".to_owned(), "pass".to_owned())),
+            )
+        });
+
+        let (_, (_, session)) = core.run(server.join(client)).unwrap();
+
+        assert_eq!(session, None);
+    }
+}
@@ -0,0 +1,529 @@
+use std::mem;
+use std::str;
+
+use base64;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use rand::{self, Rng};
+use sha2::{Digest, Sha256, Sha512};
+
+use errors::{ErrorKind, Result};
+use network::{SaslClient, SaslMechanism};
+
+/// The length of the client nonce.
+const NONCE_LEN: usize = 24;
+
+/// The minimum iterations accepted by Kafka, a lower value from server is considered as a downgrade attack.
+const MIN_ITERATIONS: u32 = 4096;
+
+/// The GS2 header without channel binding and authorization identity.
+const GS2_HEADER: &str = "n,,";
+
+/// The hash function of the SCRAM mechanism.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScramMechanism {
+    Sha256,
+    Sha512,
+}
+
+impl ScramMechanism {
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match *self {
+            ScramMechanism::Sha256 => Sha256::digest(data).to_vec(),
+            ScramMechanism::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match *self {
+            ScramMechanism::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts key of any size");
+                mac.input(data);
+                mac.result().code().to_vec()
+            }
+            ScramMechanism::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_varkey(key).expect("HMAC accepts key of any size");
+                mac.input(data);
+                mac.result().code().to_vec()
+            }
+        }
+    }
+
+    /// The `Hi` function of RFC 5802, which is PBKDF2 with HMAC as the pseudorandom function.
+    fn hi(&self, password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+        let mut salt = salt.to_vec();
+        salt.extend_from_slice(&[0, 0, 0, 1]);
+
+        let mut u = self.hmac(password, &salt);
+        let mut result = u.clone();
+
+        for _ in 1..iterations {
+            u = self.hmac(password, &u);
+
+            for (r, b) in result.iter_mut().zip(u.iter()) {
+                *r ^= *b;
+            }
+        }
+
+        result
+    }
+}
+
+enum State {
+    Initial,
+    ClientFirst {
+        client_first_bare: String,
+    },
+    ClientFinal {
+        server_signature: Vec<u8>,
+    },
+    Completed,
+}
+
+/// The client of the `SCRAM-SHA-256` and `SCRAM-SHA-512` mechanisms,
+/// see [RFC 5802](https://tools.ietf.org/html/rfc5802) and [RFC 7677](https://tools.ietf.org/html/rfc7677).
+pub struct ScramClient {
+    mechanism: ScramMechanism,
+    username: String,
+    password: String,
+    nonce: String,
+    state: State,
+}
+
+impl ScramClient {
+    pub fn new(mechanism: ScramMechanism, username: String, password: String) -> Self {
+        let nonce = rand::thread_rng().gen_ascii_chars().take(NONCE_LEN).collect();
+
+        Self::with_nonce(mechanism, username, password, nonce)
+    }
+
+    fn with_nonce(mechanism: ScramMechanism, username: String, password: String, nonce: String) -> Self {
+        ScramClient {
+            mechanism,
+            username,
+            password,
+            nonce,
+            state: State::Initial,
+        }
+    }
+
+    fn client_final(
+        &self,
+        server_first: &str,
+        client_first_bare: &str,
+    ) -> Result<(String, Vec<u8>)> {
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+
+        for attr in server_first.split(',') {
+            let mut kv = attr.splitn(2, '=');
+
+            match (kv.next(), kv.next()) {
+                (Some("r"), Some(value)) => nonce = Some(value),
+                (Some("s"), Some(value)) => {
+                    salt = Some(base64::decode(value)
+                        .map_err(|err| ErrorKind::AuthenticationFailed(format!("invalid salt, {}", err)))?)
+                }
+                (Some("i"), Some(value)) => iterations = Some(value.parse::<u32>()?),
+                (Some("e"), Some(value)) => bail!(ErrorKind::AuthenticationFailed(format!("server error, {}", value))),
+                _ => {}
+            }
+        }
+
+        let (nonce, salt, iterations) = match (nonce, salt, iterations) {
+            (Some(nonce), Some(salt), Some(iterations)) => (nonce, salt, iterations),
+            _ => bail!(ErrorKind::AuthenticationFailed(format!(
+                "invalid server first message: {}",
+                server_first
+            ))),
+        };
+
+        if !nonce.starts_with(&self.nonce) || nonce.len() == self.nonce.len() {
+            bail!(ErrorKind::AuthenticationFailed("invalid server nonce".to_owned()))
+        }
+        if iterations < MIN_ITERATIONS {
+            bail!(ErrorKind::AuthenticationFailed(format!(
+                "requested iterations {} is less than the minimum {}",
+                iterations, MIN_ITERATIONS
+            )))
+        }
+
+        let client_final_without_proof = format!("c={},r={}", base64::encode(GS2_HEADER.as_bytes()), nonce);
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, client_final_without_proof);
+
+        let salted_password = self.mechanism.hi(self.password.as_bytes(), &salt, iterations);
+        let client_key = self.mechanism.hmac(&salted_password, b"Client Key");
+        let stored_key = self.mechanism.hash(&client_key);
+        let client_signature = self.mechanism.hmac(&stored_key, auth_message.as_bytes());
+        let client_proof = client_key
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(k, s)| k ^ s)
+            .collect::<Vec<u8>>();
+
+        let server_key = self.mechanism.hmac(&salted_password, b"Server Key");
+        let server_signature = self.mechanism.hmac(&server_key, auth_message.as_bytes());
+
+        Ok((
+            format!("{},p={}", client_final_without_proof, base64::encode(&client_proof)),
+            server_signature,
+        ))
+    }
+}
+
+impl SaslClient for ScramClient {
+    fn mechanism(&self) -> &'static str {
+        match self.mechanism {
+            ScramMechanism::Sha256 => SaslMechanism::ScramSha256.name(),
+            ScramMechanism::Sha512 => SaslMechanism::ScramSha512.name(),
+        }
+    }
+
+    fn initial_response(&mut self) -> Result<Bytes> {
+        let client_first_bare = format!("n={},r={}", escape_username(&self.username), self.nonce);
+        let client_first = format!("{}{}", GS2_HEADER, client_first_bare);
+
+        self.state = State::ClientFirst { client_first_bare };
+
+        Ok(Bytes::from(client_first))
+    }
+
+    fn evaluate_challenge(&mut self, challenge: &[u8]) -> Result<Option<Bytes>> {
+        let challenge = str::from_utf8(challenge)?;
+
+        // the authentication could not be continued after any failure
+        match mem::replace(&mut self.state, State::Completed) {
+            State::ClientFirst { client_first_bare } => {
+                let (client_final, server_signature) = self.client_final(challenge, &client_first_bare)?;
+
+                self.state = State::ClientFinal { server_signature };
+
+                Ok(Some(Bytes::from(client_final)))
+            }
+            State::ClientFinal { server_signature } => {
+                if challenge.starts_with("e=") {
+                    bail!(ErrorKind::AuthenticationFailed(format!("server error, {}", &challenge[2..])))
+                }
+
+                let signature = if challenge.starts_with("v=") {
+                    base64::decode(&challenge[2..])
+                        .map_err(|err| ErrorKind::AuthenticationFailed(format!("invalid server signature, {}", err)))?
+                } else {
+                    bail!(ErrorKind::AuthenticationFailed(format!(
+                        "invalid server final message: {}",
+                        challenge
+                    )))
+                };
+
+                if signature != server_signature {
+                    bail!(ErrorKind::AuthenticationFailed("server signature mismatch".to_owned()))
+                }
+
+                Ok(None)
+            }
+            State::Initial | State::Completed => bail!(ErrorKind::IllegalState(
+                "unexpected challenge of SCRAM authentication".to_owned()
+            )),
+        }
+    }
+}
+
+/// Escape the `=` and `,` characters of the username as `saslname` of RFC 5802.
+fn escape_username(username: &str) -> String {
+    username.chars().fold(String::with_capacity(username.len()), |mut s, c| {
+        match c {
+            '=' => s.push_str("=3D"),
+            ',' => s.push_str("=2C"),
+            _ => s.push(c),
+        }
+        s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use bytes::{BigEndian, BufMut, ByteOrder, BytesMut};
+
+    use futures::future::{self, Future, Loop};
+    use futures::Stream;
+    use tokio_core::net::{TcpListener, TcpStream};
+    use tokio_core::reactor::Core;
+    use tokio_io::io::{read_exact, write_all};
+
+    use network::{authenticate, CorrelationIds, KafkaStream, SaslSession};
+    use protocol::{ApiKeys, KafkaCode};
+
+    use super::*;
+
+    const SALT: &[u8] = b"tokio-kafka";
+    const SESSION_LIFETIME_MS: i64 = 3_600_000;
+
+    #[test]
+    fn test_scram_sha256_rfc7677() {
+        let mut client = ScramClient::with_nonce(
+            ScramMechanism::Sha256,
+            "user".to_owned(),
+            "pencil".to_owned(),
+            "rOprNGfwEbeRWgbNEkqO".to_owned(),
+        );
+
+        assert_eq!(client.mechanism(), "SCRAM-SHA-256");
+        assert_eq!(
+            client.initial_response().unwrap(),
+            Bytes::from(&b"n,,n=user,r=rOprNGfwEbeRWgbNEkqO"[..])
+        );
+        assert_eq!(
+            client
+                .evaluate_challenge(
+                    b"r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096"
+                )
+                .unwrap(),
+            Some(Bytes::from(
+                &b"c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                   p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="[..]
+            ))
+        );
+        assert_eq!(
+            client
+                .evaluate_challenge(b"v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scram_reject_invalid_server() {
+        let mut client = ScramClient::with_nonce(
+            ScramMechanism::Sha256,
+            "user".to_owned(),
+            "pencil".to_owned(),
+            "nonce".to_owned(),
+        );
+
+        client.initial_response().unwrap();
+
+        assert!(client.evaluate_challenge(b"r=other,s=c2FsdA==,i=4096").is_err());
+
+        client.initial_response().unwrap();
+
+        assert!(client.evaluate_challenge(b"r=nonceserver,s=c2FsdA==,i=1024").is_err());
+
+        client.initial_response().unwrap();
+        client.evaluate_challenge(b"r=nonceserver,s=c2FsdA==,i=4096").unwrap();
+
+        assert!(client.evaluate_challenge(b"v=c2lnbmF0dXJl").is_err());
+    }
+
+    #[test]
+    fn test_escape_username() {
+        assert_eq!(escape_username("user"), "user");
+        assert_eq!(escape_username("a=b,c"), "a=3Db=2Cc");
+    }
+
+    /// A stand-in broker which only speaks the `ApiVersions`, SASL handshake and SCRAM authentication.
+    struct StandInServer {
+        mechanism: ScramMechanism,
+        salted_password: Vec<u8>,
+        auth_message: Option<String>,
+    }
+
+    impl StandInServer {
+        fn new(mechanism: ScramMechanism, password: &str) -> Self {
+            StandInServer {
+                mechanism,
+                salted_password: mechanism.hi(password.as_bytes(), SALT, MIN_ITERATIONS),
+                auth_message: None,
+            }
+        }
+
+        /// Handle a request frame, returns the response frame and whether the exchange has been completed.
+        fn handle(&mut self, frame: &[u8]) -> (Vec<u8>, bool) {
+            let api_key = BigEndian::read_i16(&frame[0..]);
+            let api_version = BigEndian::read_i16(&frame[2..]);
+            let correlation_id = BigEndian::read_i32(&frame[4..]);
+            let client_id_len = BigEndian::read_i16(&frame[8..]) as usize;
+            let body = &frame[10 + client_id_len..];
+
+            let mut buf = BytesMut::with_capacity(256);
+
+            buf.put_i32::<BigEndian>(0);
+            buf.put_i32::<BigEndian>(correlation_id);
+
+            let completed = if api_key == ApiKeys::ApiVersions as i16 {
+                buf.put_i16::<BigEndian>(KafkaCode::None as i16);
+                buf.put_i32::<BigEndian>(2);
+                for &api_key in &[ApiKeys::SaslHandshake, ApiKeys::SaslAuthenticate] {
+                    buf.put_i16::<BigEndian>(api_key as i16);
+                    buf.put_i16::<BigEndian>(0);
+                    buf.put_i16::<BigEndian>(1);
+                }
+
+                false
+            } else if api_key == ApiKeys::SaslHandshake as i16 {
+                let mechanism = match self.mechanism {
+                    ScramMechanism::Sha256 => "SCRAM-SHA-256",
+                    ScramMechanism::Sha512 => "SCRAM-SHA-512",
+                };
+
+                assert_eq!(&body[2..], mechanism.as_bytes());
+
+                buf.put_i16::<BigEndian>(KafkaCode::None as i16);
+                buf.put_i32::<BigEndian>(1);
+                buf.put_i16::<BigEndian>(mechanism.len() as i16);
+                buf.put_slice(mechanism.as_bytes());
+
+                false
+            } else {
+                assert_eq!(api_key, ApiKeys::SaslAuthenticate as i16);
+
+                let token = str::from_utf8(&body[4..]).unwrap();
+
+                match self.evaluate(token) {
+                    Some(token) => {
+                        buf.put_i16::<BigEndian>(KafkaCode::None as i16);
+                        buf.put_i16::<BigEndian>(-1);
+                        buf.put_i32::<BigEndian>(token.len() as i32);
+                        buf.put_slice(token.as_bytes());
+                        if api_version > 0 {
+                            buf.put_i64::<BigEndian>(SESSION_LIFETIME_MS);
+                        }
+
+                        token.starts_with("v=")
+                    }
+                    None => {
+                        let reason = "invalid credentials";
+
+                        buf.put_i16::<BigEndian>(KafkaCode::SaslAuthenticationFailed as i16);
+                        buf.put_i16::<BigEndian>(reason.len() as i16);
+                        buf.put_slice(reason.as_bytes());
+                        buf.put_i32::<BigEndian>(0);
+                        if api_version > 0 {
+                            buf.put_i64::<BigEndian>(0);
+                        }
+
+                        true
+                    }
+                }
+            };
+
+            let size = buf.len() - 4;
+            BigEndian::write_i32(&mut buf[..4], size as i32);
+
+            (buf.to_vec(), completed)
+        }
+
+        fn evaluate(&mut self, token: &str) -> Option<String> {
+            match self.auth_message.take() {
+                None => {
+                    let client_first_bare = &token[GS2_HEADER.len()..];
+                    let client_nonce = client_first_bare.split(",r=").nth(1).unwrap();
+                    let server_first = format!(
+                        "r={}server,s={},i={}",
+                        client_nonce,
+                        base64::encode(SALT),
+                        MIN_ITERATIONS
+                    );
+
+                    self.auth_message = Some(format!("{},{}", client_first_bare, server_first));
+
+                    Some(server_first)
+                }
+                Some(auth_message) => {
+                    let (client_final_without_proof, proof) = token.split_at(token.find(",p=").unwrap());
+                    let proof = base64::decode(&proof[3..]).unwrap();
+                    let auth_message = format!("{},{}", auth_message, client_final_without_proof);
+
+                    let client_key = self.mechanism.hmac(&self.salted_password, b"Client Key");
+                    let stored_key = self.mechanism.hash(&client_key);
+                    let client_signature = self.mechanism.hmac(&stored_key, auth_message.as_bytes());
+                    let client_key = proof
+                        .iter()
+                        .zip(client_signature.iter())
+                        .map(|(p, s)| p ^ s)
+                        .collect::<Vec<u8>>();
+
+                    if self.mechanism.hash(&client_key) != stored_key {
+                        return None;
+                    }
+
+                    let server_key = self.mechanism.hmac(&self.salted_password, b"Server Key");
+                    let server_signature = self.mechanism.hmac(&server_key, auth_message.as_bytes());
+
+                    Some(format!("v={}", base64::encode(&server_signature)))
+                }
+            }
+        }
+    }
+
+    fn authenticate_with_stand_in_server(mechanism: ScramMechanism, password: &str) -> io::Result<Option<SaslSession>> {
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap(), &handle).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = listener
+            .incoming()
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(move |(conn, _)| {
+                let (stream, _) = conn.unwrap();
+
+                future::loop_fn(
+                    (stream, StandInServer::new(mechanism, "pencil")),
+                    |(stream, mut server)| {
+                        read_exact(stream, [0u8; 4])
+                            .and_then(|(stream, size)| {
+                                read_exact(stream, vec![0u8; BigEndian::read_i32(&size) as usize])
+                            })
+                            .and_then(move |(stream, frame)| {
+                                let (response, completed) = server.handle(&frame);
+
+                                write_all(stream, response).map(move |(stream, _)| {
+                                    if completed {
+                                        Loop::Break(())
+                                    } else {
+                                        Loop::Continue((stream, server))
+                                    }
+                                })
+                            })
+                    },
+                )
+            });
+
+        let client = ScramClient::new(mechanism, "user".to_owned(), password.to_owned());
+        let client = TcpStream::connect(&addr, &handle).and_then(move |stream| {
+            authenticate(
+                KafkaStream::Tcp(addr, stream),
+                Some("client".to_owned()),
+                CorrelationIds::default(),
+                Box::new(client),
+            )
+        });
+
+        core.run(server.join(client)).map(|(_, (_, session))| session)
+    }
+
+    #[test]
+    fn test_authenticate_with_stand_in_server() {
+        let session = authenticate_with_stand_in_server(ScramMechanism::Sha256, "pencil")
+            .unwrap()
+            .unwrap();
+
+        assert!(session.expires_at > session.reauthenticate_at);
+        assert!(!session.needs_reauthentication());
+
+        assert!(
+            authenticate_with_stand_in_server(ScramMechanism::Sha512, "pencil")
+                .unwrap()
+                .is_some()
+        );
+
+        let err = authenticate_with_stand_in_server(ScramMechanism::Sha256, "wrong").unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+}
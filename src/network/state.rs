@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use rand::{self, Rng};
+
+use protocol::ToMilliseconds;
+
+/// The random factor applied to the reconnect backoff, which avoids the clients reconnecting at the same time.
+const RECONNECT_BACKOFF_JITTER: f64 = 0.2;
+
+/// The state of the connection to a broker.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// The connection is being established.
+    Connecting,
+    /// The connection has been established.
+    Connected,
+    /// The connection failed, and the client won't reconnect before the deadline.
+    BackingOff(Instant),
+    /// The SASL authentication failed, and the client won't reconnect before the deadline.
+    AuthenticationFailed(Instant),
+}
+
+/// The states of the connections to the brokers, which backs off the reconnection
+/// to a broker exponentially after the consecutive connection failures.
+#[derive(Clone, Debug)]
+pub struct ConnectionStates<K>
+where
+    K: Clone + Hash + Eq,
+{
+    inner: Rc<RefCell<ConnectionStatesInner<K>>>,
+}
+
+#[derive(Debug)]
+struct ConnectionStatesInner<K>
+where
+    K: Clone + Hash + Eq,
+{
+    reconnect_backoff: Duration,
+    reconnect_backoff_max: Duration,
+    states: HashMap<K, NodeConnectionState>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct NodeConnectionState {
+    state: ConnectionState,
+    failures: u32,
+}
+
+impl<K> ConnectionStates<K>
+where
+    K: Clone + Debug + Hash + Eq,
+{
+    pub fn new(reconnect_backoff: Duration, reconnect_backoff_max: Duration) -> Self {
+        ConnectionStates {
+            inner: Rc::new(RefCell::new(ConnectionStatesInner {
+                reconnect_backoff,
+                reconnect_backoff_max,
+                states: HashMap::new(),
+            })),
+        }
+    }
+
+    /// The state of the connection to the broker, or `None` if the client never connected to it.
+    pub fn state(&self, key: &K) -> Option<ConnectionState> {
+        self.inner.borrow().states.get(key).map(|node| node.state)
+    }
+
+    /// The remaining time before the client could reconnect to the broker,
+    /// or `None` if the broker isn't backing off.
+    pub fn remaining_backoff(&self, key: &K) -> Option<Duration> {
+        let now = Instant::now();
+
+        match self.state(key) {
+            Some(ConnectionState::BackingOff(deadline)) | Some(ConnectionState::AuthenticationFailed(deadline))
+                if deadline > now =>
+            {
+                Some(deadline - now)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the client is backing off the reconnection to the broker.
+    pub fn is_backing_off(&self, key: &K) -> bool {
+        self.remaining_backoff(key).is_some()
+    }
+
+    /// Enter the connecting state when the client starts to connect to the broker.
+    pub fn connecting(&self, key: K) {
+        let mut inner = self.inner.borrow_mut();
+        let node = inner.states.entry(key).or_insert(NodeConnectionState {
+            state: ConnectionState::Connecting,
+            failures: 0,
+        });
+
+        if node.state != ConnectionState::Connected {
+            node.state = ConnectionState::Connecting;
+        }
+    }
+
+    /// Enter the connected state and reset the consecutive failures.
+    pub fn connected(&self, key: K) {
+        trace!("connected to {:?}", key);
+
+        self.inner.borrow_mut().states.insert(
+            key,
+            NodeConnectionState {
+                state: ConnectionState::Connected,
+                failures: 0,
+            },
+        );
+    }
+
+    /// Back off the reconnection after the client failed to connect or authenticate to the broker.
+    pub fn failed(&self, key: K, authentication_failed: bool) {
+        let mut inner = self.inner.borrow_mut();
+        let reconnect_backoff = inner.reconnect_backoff;
+        let reconnect_backoff_max = inner.reconnect_backoff_max;
+        let node = inner.states.entry(key.clone()).or_insert(NodeConnectionState {
+            state: ConnectionState::Connecting,
+            failures: 0,
+        });
+
+        node.failures = node.failures.saturating_add(1);
+
+        let backoff = backoff(reconnect_backoff, reconnect_backoff_max, node.failures);
+        let deadline = Instant::now() + backoff;
+
+        debug!(
+            "back off {:?} before reconnecting to {:?} after {} consecutive failures",
+            backoff, key, node.failures
+        );
+
+        node.state = if authentication_failed {
+            ConnectionState::AuthenticationFailed(deadline)
+        } else {
+            ConnectionState::BackingOff(deadline)
+        };
+    }
+}
+
+/// The exponential backoff with jitter after the consecutive failures, up to the maximum backoff.
+fn backoff(reconnect_backoff: Duration, reconnect_backoff_max: Duration, failures: u32) -> Duration {
+    let max = reconnect_backoff_max.as_millis();
+    let millis = (1..failures).fold(cmp::min(reconnect_backoff.as_millis(), max), |millis, _| {
+        cmp::min(millis.saturating_mul(2), max)
+    });
+    let jitter = rand::thread_rng().gen_range(1.0 - RECONNECT_BACKOFF_JITTER, 1.0 + RECONNECT_BACKOFF_JITTER);
+
+    Duration::from_millis((millis as f64 * jitter) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_backoff(backoff: Duration, millis: u64) {
+        let min = Duration::from_millis((millis as f64 * (1.0 - RECONNECT_BACKOFF_JITTER)) as u64);
+        let max = Duration::from_millis((millis as f64 * (1.0 + RECONNECT_BACKOFF_JITTER)) as u64);
+
+        assert!(
+            backoff >= min && backoff <= max,
+            "backoff {:?} should be about {} ms",
+            backoff,
+            millis
+        );
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let base = Duration::from_millis(50);
+        let max = Duration::from_millis(1000);
+
+        assert_backoff(backoff(base, max, 1), 50);
+        assert_backoff(backoff(base, max, 2), 100);
+        assert_backoff(backoff(base, max, 3), 200);
+        assert_backoff(backoff(base, max, 5), 800);
+        assert_backoff(backoff(base, max, 6), 1000);
+        assert_backoff(backoff(base, max, 100), 1000);
+    }
+
+    #[test]
+    fn test_connection_states() {
+        let states = ConnectionStates::new(Duration::from_secs(60), Duration::from_secs(600));
+
+        assert_eq!(states.state(&"broker"), None);
+        assert!(!states.is_backing_off(&"broker"));
+
+        states.connecting("broker");
+
+        assert_eq!(states.state(&"broker"), Some(ConnectionState::Connecting));
+
+        states.failed("broker", false);
+
+        assert!(states.is_backing_off(&"broker"));
+        assert!(states.remaining_backoff(&"broker").unwrap() > Duration::from_secs(45));
+
+        states.failed("broker", true);
+
+        match states.state(&"broker") {
+            Some(ConnectionState::AuthenticationFailed(_)) => {}
+            state => panic!("unexpected state: {:?}", state),
+        }
+        assert!(states.remaining_backoff(&"broker").unwrap() > Duration::from_secs(90));
+
+        states.connected("broker");
+
+        assert_eq!(states.state(&"broker"), Some(ConnectionState::Connected));
+        assert!(!states.is_backing_off(&"broker"));
+
+        states.connecting("broker");
+
+        assert_eq!(states.state(&"broker"), Some(ConnectionState::Connected));
+    }
+}
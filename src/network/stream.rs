@@ -4,26 +4,116 @@ use std::rc::Rc;
 use std::io::prelude::*;
 use std::net::SocketAddr;
 
-use futures::future::Future;
+use futures::future::{self, Future};
 use futures::{Async, Poll};
 use native_tls::TlsConnector;
 use tokio_core::net::{TcpStream, TcpStreamNew};
 use tokio_core::reactor::Handle;
 use tokio_io::{AsyncRead, AsyncWrite};
-use tokio_tls::{ConnectAsync, TlsConnectorExt, TlsStream};
+use tokio_tls::{self, TlsStream};
 use ns_router::{AutoName, Router};
 use ns_router::future::ResolveFuture;
 
-use network::DEFAULT_PORT;
+use errors::Result;
+use network::{authenticate, handshake, CorrelationIds, Handshake, SaslConfig, SaslSession, SaslTransport,
+              SecurityProtocol, TlsConfig, DEFAULT_PORT};
 
+/// The future of establishing a connection with the security protocol of the connector,
+/// returns the SASL session if the connection has been authenticated.
+pub type Establish = Box<Future<Item = (KafkaStream, Option<SaslSession>), Error = io::Error>>;
+
+#[derive(Clone)]
 pub struct KafkaConnector {
     handle: Handle,
     router: Rc<Router>,
+    client_id: Option<String>,
+    correlation_ids: CorrelationIds,
+    security_protocol: SecurityProtocol,
+    tls: Option<(TlsConfig, TlsConnector)>,
+    sasl: Option<SaslConfig>,
 }
 
 impl KafkaConnector {
-    pub fn new(handle: Handle, router: Rc<Router>) -> Self {
-        KafkaConnector { handle, router }
+    pub fn new(
+        handle: Handle,
+        router: Rc<Router>,
+        client_id: Option<String>,
+        correlation_ids: CorrelationIds,
+        security_protocol: SecurityProtocol,
+        tls: Option<TlsConfig>,
+        sasl: Option<SaslConfig>,
+    ) -> Result<Self> {
+        let tls = if security_protocol.is_ssl() {
+            let tls = tls.unwrap_or_default();
+            let connector = tls.connector()?;
+
+            Some((tls, connector))
+        } else {
+            None
+        };
+
+        Ok(KafkaConnector {
+            handle,
+            router,
+            client_id,
+            correlation_ids,
+            security_protocol,
+            tls,
+            sasl,
+        })
+    }
+
+    /// Connect to the broker with the security protocol,
+    /// the connection is authenticated before returned if the protocol requires SASL.
+    ///
+    /// The hostname of the broker is used to verify its certificate, or the address if it's `None`.
+    pub fn connect(&self, addr: SocketAddr, hostname: Option<String>) -> Establish {
+        let connect = match self.tls {
+            Some((ref tls, ref connector)) => self.tls(
+                AutoName::SocketAddr(addr),
+                connector.clone(),
+                tls.domain(hostname.as_ref().map(|s| s.as_str()), &addr),
+            ),
+            None => self.tcp(AutoName::SocketAddr(addr)),
+        };
+
+        match self.sasl {
+            Some(ref sasl) if self.security_protocol.is_sasl() => {
+                let client_id = self.client_id.clone();
+                let correlation_ids = self.correlation_ids.clone();
+                let client = match sasl.client() {
+                    Ok(client) => client,
+                    Err(err) => return Box::new(future::err(io::Error::new(io::ErrorKind::Other, err.to_string()))),
+                };
+
+                Box::new(connect.and_then(move |stream| authenticate(stream, client_id, correlation_ids, client)))
+            }
+            _ => Box::new(connect.map(|stream| (stream, None))),
+        }
+    }
+
+    /// Re-authenticate an established connection before its SASL session expires.
+    pub fn reauthenticate<T>(&self, transport: T, addr: SocketAddr) -> Handshake<T>
+    where
+        T: SaslTransport + 'static,
+    {
+        match self.sasl {
+            Some(ref sasl) if self.security_protocol.is_sasl() => match sasl.client() {
+                Ok(client) => {
+                    trace!("re-authenticate connection to {}", addr);
+
+                    handshake(
+                        transport,
+                        addr,
+                        self.client_id.clone(),
+                        self.correlation_ids.clone(),
+                        client,
+                    )
+                }
+                Err(err) => Box::new(future::err(io::Error::new(io::ErrorKind::Other, err.to_string()))),
+            },
+            _ => Box::new(future::ok((transport, None))),
+        }
     }
 
     pub fn tcp<'n, N>(&self, addr: N) -> Connect
@@ -50,7 +140,7 @@ impl KafkaConnector {
         Connect {
             handle: self.handle.clone(),
             domain: Some(domain.into()),
-            connector: Some(connector),
+            connector: Some(tokio_tls::TlsConnector::from(connector)),
             state: State::Resolving(self.router.resolve_auto(addr, DEFAULT_PORT)),
         }
     }
@@ -59,13 +149,13 @@ impl KafkaConnector {
 enum State {
     Resolving(ResolveFuture),
     Connecting(TcpStreamNew, SocketAddr, Vec<SocketAddr>),
-    Handshaking(ConnectAsync<TcpStream>, SocketAddr),
+    Handshaking(tokio_tls::Connect<TcpStream>, SocketAddr),
 }
 
 pub struct Connect {
     handle: Handle,
     domain: Option<String>,
-    connector: Option<TlsConnector>,
+    connector: Option<tokio_tls::TlsConnector>,
     state: State,
 }
 
@@ -106,7 +196,7 @@ impl Future for Connect {
                         if let (&Some(ref domain), &Some(ref connector)) = (domain, connector) {
                             trace!("TCP connected to {}, start TLS handshake", peer_addr);
 
-                            State::Handshaking(connector.connect_async(domain, stream), peer_addr)
+                            State::Handshaking(connector.connect(domain, stream), peer_addr)
                         } else {
                             trace!("TCP connected to {}", peer_addr);
 
@@ -191,7 +281,7 @@ impl AsyncWrite for KafkaStream {
     fn shutdown(&mut self) -> Poll<(), io::Error> {
         match *self {
             KafkaStream::Tcp(_, ref mut stream) => AsyncWrite::shutdown(stream),
-            KafkaStream::Tls(_, ref mut stream) => stream.shutdown(),
+            KafkaStream::Tls(_, ref mut stream) => AsyncWrite::shutdown(stream),
         }
     }
 }
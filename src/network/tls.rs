@@ -0,0 +1,365 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str;
+
+use native_tls::{Certificate, Identity, TlsConnector};
+
+use errors::{ErrorKind, Result};
+
+const PEM_END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+
+/// The TLS settings of the client connections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsConfig {
+    /// The path of PEM file with the CA certificates to verify the broker's certificate,
+    /// or use the system trust store if it is `None`.
+    pub ca_location: Option<PathBuf>,
+    /// The path of PEM file with the client certificate.
+    pub certificate_location: Option<PathBuf>,
+    /// The path of PEM file with the PKCS#8 private key of the client certificate.
+    pub key_location: Option<PathBuf>,
+    /// The path of PKCS#12 archive with the client certificate and private key.
+    pub keystore_location: Option<PathBuf>,
+    /// The password of the PKCS#12 archive.
+    pub keystore_password: Option<String>,
+    /// Verify the broker's hostname against its certificate.
+    pub hostname_verification: bool,
+    /// The hostname used for SNI and certificate verification, instead of the broker hostname.
+    pub sni_hostname: Option<String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            ca_location: None,
+            certificate_location: None,
+            key_location: None,
+            keystore_location: None,
+            keystore_password: None,
+            hostname_verification: true,
+            sni_hostname: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Build a `TlsConnector` with the certificates and options.
+    pub fn connector(&self) -> Result<TlsConnector> {
+        let mut builder = TlsConnector::builder();
+
+        if let Some(ref path) = self.ca_location {
+            for certificate in pem_certificates(&read_file(path)?)? {
+                builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (
+            self.keystore_location.as_ref(),
+            self.certificate_location.as_ref(),
+            self.key_location.as_ref(),
+        ) {
+            (Some(keystore), None, None) => {
+                let password = self.keystore_password.as_ref().map_or("", |s| s.as_str());
+
+                builder.identity(Identity::from_pkcs12(&read_file(keystore)?, password)?);
+            }
+            (None, Some(certificate), Some(key)) => {
+                builder.identity(Identity::from_pkcs8(&read_file(certificate)?, &read_file(key)?)?);
+            }
+            (None, None, None) => {}
+            (Some(_), _, _) => bail!(ErrorKind::ConfigError(
+                "SSL keystore can't be used with the certificate and key"
+            )),
+            _ => bail!(ErrorKind::ConfigError("SSL certificate and key are required")),
+        }
+
+        builder.danger_accept_invalid_hostnames(!self.hostname_verification);
+
+        Ok(builder.build()?)
+    }
+
+    /// The hostname used for SNI and certificate verification when connecting to the broker.
+    ///
+    /// The SNI hostname overrides the hostname of the broker, and the IP address is only used
+    /// when the broker is addressed by IP, which must be in the subject alternative names of the certificate.
+    pub fn domain(&self, hostname: Option<&str>, addr: &SocketAddr) -> String {
+        self.sni_hostname
+            .as_ref()
+            .map(|s| s.as_str())
+            .or(hostname)
+            .map_or_else(|| addr.ip().to_string(), |s| s.to_owned())
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+
+    File::open(path)?.read_to_end(&mut buf)?;
+
+    Ok(buf)
+}
+
+/// Parse the certificates in a PEM bundle.
+fn pem_certificates(pem: &[u8]) -> Result<Vec<Certificate>> {
+    let pem = str::from_utf8(pem)?;
+    let mut certificates = Vec::new();
+
+    for block in pem.split(PEM_END_CERTIFICATE) {
+        if let Some(begin) = block.find("-----BEGIN") {
+            let certificate = format!("{}{}\n", &block[begin..], PEM_END_CERTIFICATE);
+
+            certificates.push(Certificate::from_pem(certificate.as_bytes())?);
+        }
+    }
+
+    if certificates.is_empty() {
+        bail!(ErrorKind::ConfigError("no certificate found in the SSL CA bundle"))
+    }
+
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::process;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use futures::{Future, Stream};
+    use native_tls::TlsAcceptor;
+    use rcgen;
+    use tokio_core::net::TcpListener;
+    use tokio_core::reactor::Core;
+    use tokio_tls;
+    use ns_router::{Config as RouterConfig, Router, SubscribeExt};
+    use ns_std_threaded::ThreadedResolver;
+
+    use network::{CorrelationIds, KafkaConnector, KafkaStream, SecurityProtocol};
+
+    use super::*;
+
+    /// The self-signed certificate and private key, which are written to the temporary directory.
+    struct Fixture {
+        certificate: Vec<u8>,
+        key: Vec<u8>,
+        certificate_location: PathBuf,
+        key_location: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str, hostname: &str) -> Self {
+            let cert = rcgen::generate_simple_self_signed(vec![hostname.to_owned()]).unwrap();
+            let certificate = cert.serialize_pem().unwrap().into_bytes();
+            let key = cert.serialize_private_key_pem().into_bytes();
+
+            let dir = env::temp_dir();
+            let certificate_location = dir.join(format!("tokio-kafka-{}-{}.crt", process::id(), name));
+            let key_location = dir.join(format!("tokio-kafka-{}-{}.key", process::id(), name));
+
+            fs::write(&certificate_location, &certificate).unwrap();
+            fs::write(&key_location, &key).unwrap();
+
+            Fixture {
+                certificate,
+                key,
+                certificate_location,
+                key_location,
+            }
+        }
+
+        fn acceptor(&self) -> TlsAcceptor {
+            TlsAcceptor::new(Identity::from_pkcs8(&self.certificate, &self.key).unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.certificate_location);
+            let _ = fs::remove_file(&self.key_location);
+        }
+    }
+
+    fn connect_to_stand_in_server(
+        fixture: &Fixture,
+        hostname: Option<&str>,
+        tls: TlsConfig,
+    ) -> io::Result<KafkaStream> {
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+
+        let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap(), &handle).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = tokio_tls::TlsAcceptor::from(fixture.acceptor());
+        let server = {
+            let handle = handle.clone();
+
+            listener.incoming().for_each(move |(stream, _)| {
+                handle.spawn(acceptor.accept(stream).map(|_| ()).map_err(|_| ()));
+
+                Ok(())
+            })
+        };
+
+        handle.spawn(server.map_err(|_| ()));
+
+        let router = Rc::new(Router::from_config(
+            &RouterConfig::new()
+                .set_fallthrough(
+                    ThreadedResolver::new()
+                        .null_service_resolver()
+                        .interval_subscriber(Duration::new(1, 0), &handle),
+                )
+                .done(),
+            &handle,
+        ));
+        let connector = KafkaConnector::new(
+            handle,
+            router,
+            None,
+            CorrelationIds::default(),
+            SecurityProtocol::Ssl,
+            Some(tls),
+            None,
+        ).unwrap();
+
+        core.run(connector.connect(addr, hostname.map(|s| s.to_owned()))).map(|(stream, _)| stream)
+    }
+
+    #[test]
+    fn test_pem_certificates() {
+        let fixture = Fixture::new("bundle", "localhost");
+        let mut bundle = fixture.certificate.clone();
+
+        bundle.extend_from_slice(&fixture.certificate);
+
+        assert_eq!(pem_certificates(&fixture.certificate).unwrap().len(), 1);
+        assert_eq!(pem_certificates(&bundle).unwrap().len(), 2);
+        assert!(pem_certificates(&fixture.key).is_err());
+    }
+
+    #[test]
+    fn test_tls_config() {
+        let fixture = Fixture::new("config", "localhost");
+        let addr = "127.0.0.1:9093".parse().unwrap();
+
+        let tls = TlsConfig {
+            ca_location: Some(fixture.certificate_location.clone()),
+            ..Default::default()
+        };
+
+        assert!(tls.connector().is_ok());
+        assert_eq!(tls.domain(None, &addr), "127.0.0.1");
+        assert_eq!(tls.domain(Some("kafka"), &addr), "kafka");
+
+        let tls = TlsConfig {
+            certificate_location: Some(fixture.certificate_location.clone()),
+            key_location: Some(fixture.key_location.clone()),
+            sni_hostname: Some("localhost".to_owned()),
+            ..tls
+        };
+
+        assert!(tls.connector().is_ok());
+        assert_eq!(tls.domain(Some("kafka"), &addr), "localhost");
+
+        let tls = TlsConfig {
+            key_location: None,
+            ..tls
+        };
+
+        assert!(tls.connector().is_err());
+
+        let tls = TlsConfig {
+            ca_location: Some(PathBuf::from("/nonexistent/ca.pem")),
+            certificate_location: None,
+            ..tls
+        };
+
+        assert!(tls.connector().is_err());
+    }
+
+    #[test]
+    fn test_connect_with_self_signed_certificate() {
+        let fixture = Fixture::new("server", "localhost");
+        let tls = TlsConfig {
+            ca_location: Some(fixture.certificate_location.clone()),
+            ..Default::default()
+        };
+
+        // the certificate was issued to `localhost` instead of the broker address
+        assert!(connect_to_stand_in_server(&fixture, None, tls.clone()).is_err());
+
+        let stream = connect_to_stand_in_server(
+            &fixture,
+            None,
+            TlsConfig {
+                sni_hostname: Some("localhost".to_owned()),
+                ..tls.clone()
+            },
+        ).unwrap();
+
+        match stream {
+            KafkaStream::Tls(..) => {}
+            _ => panic!("expected TLS stream"),
+        }
+
+        assert!(
+            connect_to_stand_in_server(
+                &fixture,
+                None,
+                TlsConfig {
+                    hostname_verification: false,
+                    ..tls.clone()
+                },
+            ).is_ok()
+        );
+
+        // the certificate isn't trusted without the CA
+        assert!(
+            connect_to_stand_in_server(
+                &fixture,
+                None,
+                TlsConfig {
+                    ca_location: None,
+                    sni_hostname: Some("localhost".to_owned()),
+                    ..tls
+                },
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_connect_by_hostname() {
+        let fixture = Fixture::new("hostname", "localhost");
+        let tls = TlsConfig {
+            ca_location: Some(fixture.certificate_location.clone()),
+            ..Default::default()
+        };
+
+        // the certificate is verified against the hostname of the broker
+        let stream = connect_to_stand_in_server(&fixture, Some("localhost"), tls.clone()).unwrap();
+
+        match stream {
+            KafkaStream::Tls(..) => {}
+            _ => panic!("expected TLS stream"),
+        }
+
+        assert!(connect_to_stand_in_server(&fixture, Some("kafka"), tls.clone()).is_err());
+
+        // the SNI hostname overrides the hostname of the broker
+        assert!(
+            connect_to_stand_in_server(
+                &fixture,
+                Some("kafka"),
+                TlsConfig {
+                    sni_hostname: Some("localhost".to_owned()),
+                    ..tls
+                },
+            ).is_ok()
+        );
+    }
+}
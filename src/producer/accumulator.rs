@@ -12,7 +12,7 @@ use compression::Compression;
 use errors::Error;
 use network::TopicPartition;
 use producer::{ProducerBatch, RecordMetadata};
-use protocol::{ApiVersion, Timestamp};
+use protocol::{ApiVersion, RecordHeader, Timestamp};
 
 /// Accumulator acts as a queue that accumulates records
 pub trait Accumulator<'a> {
@@ -23,6 +23,7 @@ pub trait Accumulator<'a> {
         timestamp: Timestamp,
         key: Option<Bytes>,
         value: Option<Bytes>,
+        headers: Vec<RecordHeader>,
         api_version: ApiVersion,
     ) -> PushRecord;
 
@@ -75,13 +76,14 @@ impl<'a> Accumulator<'a> for RecordAccumulator<'a> {
         timestamp: Timestamp,
         key: Option<Bytes>,
         value: Option<Bytes>,
+        headers: Vec<RecordHeader>,
         api_version: ApiVersion,
     ) -> PushRecord {
         let mut batches = self.batches.borrow_mut();
         let batches = batches.entry(tp).or_insert_with(VecDeque::new);
 
         if let Some(batch) = batches.back_mut() {
-            match batch.push_record(timestamp, key.clone(), value.clone()) {
+            match batch.push_record(timestamp, key.clone(), value.clone(), headers.clone()) {
                 Ok(push_recrod) => {
                     trace!("pushed record to latest batch, {:?}", batch);
 
@@ -95,7 +97,7 @@ impl<'a> Accumulator<'a> for RecordAccumulator<'a> {
 
         let mut batch = ProducerBatch::new(api_version, self.compression, self.batch_size);
 
-        match batch.push_record(timestamp, key, value) {
+        match batch.push_record(timestamp, key, value, headers) {
             Ok(push_recrod) => {
                 trace!("pushed record to a new batch, {:?}", batch);
 
@@ -12,7 +12,8 @@ use futures::{Async, Future, Poll};
 use compression::Compression;
 use errors::{Error, ErrorKind, Result};
 use producer::{ProducerInterceptor, ProducerInterceptors, RecordMetadata};
-use protocol::{ApiVersion, KafkaCode, MessageSet, MessageSetBuilder, Offset, PartitionId, Timestamp};
+use protocol::{ApiVersion, KafkaCode, MessageSet, MessageSetBuilder, Offset, PartitionId, RecordHeader, Timestamp,
+               UNKNOWN_OFFSET};
 
 #[derive(Debug)]
 pub struct Thunk {
@@ -40,7 +41,11 @@ impl Thunk {
             Ok(RecordMetadata {
                 topic_name: topic_name.to_owned(),
                 partition_id,
-                offset: base_offset + self.relative_offset,
+                offset: if base_offset == UNKNOWN_OFFSET {
+                    UNKNOWN_OFFSET
+                } else {
+                    base_offset + self.relative_offset
+                },
                 timestamp: self.timestamp,
                 serialized_key_size: self.key_size,
                 serialized_value_size: self.value_size,
@@ -98,11 +103,12 @@ impl ProducerBatch {
         timestamp: Timestamp,
         key: Option<Bytes>,
         value: Option<Bytes>,
+        headers: Vec<RecordHeader>,
     ) -> Result<FutureRecordMetadata> {
         let key_size = key.as_ref().map_or(0, |b| b.len());
         let value_size = value.as_ref().map_or(0, |b| b.len());
 
-        let relative_offset = self.builder.push(timestamp, key, value)?;
+        let relative_offset = self.builder.push(timestamp, key, value, headers)?;
 
         let (sender, receiver) = channel();
 
@@ -140,3 +146,52 @@ impl Future for FutureRecordMetadata {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_batch() -> (Vec<Thunk>, Vec<FutureRecordMetadata>) {
+        let mut batch = ProducerBatch::new(2, Compression::None, 1024);
+        let records = (0..2)
+            .map(|_| {
+                batch
+                    .push_record(123, Some(Bytes::from(&b"key"[..])), Some(Bytes::from(&b"value"[..])), vec![])
+                    .unwrap()
+            })
+            .collect();
+        let (thunks, _) = batch.build().unwrap();
+
+        (thunks, records)
+    }
+
+    #[test]
+    fn test_complete_records() {
+        let (thunks, records) = build_batch();
+
+        for thunk in thunks {
+            thunk.done::<(), ()>(None, "topic", 0, 100, KafkaCode::None).unwrap();
+        }
+
+        let offsets: Vec<Offset> = records.into_iter().map(|record| record.wait().unwrap().offset).collect();
+
+        assert_eq!(offsets, vec![100, 101]);
+    }
+
+    #[test]
+    fn test_complete_records_without_offset() {
+        let (thunks, records) = build_batch();
+
+        // the broker doesn't respond to the produce request with `acks=0`
+        for thunk in thunks {
+            thunk.done::<(), ()>(None, "topic", 0, UNKNOWN_OFFSET, KafkaCode::None).unwrap();
+        }
+
+        for record in records {
+            let metadata = record.wait().unwrap();
+
+            assert_eq!(metadata.topic_name, "topic");
+            assert_eq!(metadata.offset, UNKNOWN_OFFSET);
+        }
+    }
+}
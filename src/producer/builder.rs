@@ -10,7 +10,7 @@ use client::{KafkaClient, KafkaVersion};
 use compression::Compression;
 use errors::{ErrorKind, Result};
 use producer::{DefaultPartitioner, Interceptors, KafkaProducer, ProducerConfig, ProducerInterceptor,
-               ProducerInterceptors};
+               ProducerInterceptors, MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE};
 use protocol::{RequiredAcks, ToMilliseconds};
 use serialization::{NoopSerializer, Serializer};
 
@@ -144,6 +144,26 @@ where
         self
     }
 
+    /// Sets the maximum number of unacknowledged requests the client will send on a single
+    /// connection before blocking.
+    pub fn with_max_in_flight_requests_per_connection(mut self, max_in_flight_requests: usize) -> Self {
+        self.config.max_in_flight_requests_per_connection = max_in_flight_requests;
+        self
+    }
+
+    /// Sets the base amount of time to wait before attempting to reconnect to a given broker.
+    pub fn with_reconnect_backoff(mut self, reconnect_backoff: Duration) -> Self {
+        self.config.reconnect_backoff = reconnect_backoff.as_millis();
+        self
+    }
+
+    /// Sets the maximum amount of time to wait when reconnecting to a broker
+    /// that has repeatedly failed to connect.
+    pub fn with_reconnect_backoff_max(mut self, reconnect_backoff_max: Duration) -> Self {
+        self.config.reconnect_backoff_max = reconnect_backoff_max.as_millis();
+        self
+    }
+
     /// Sets the maximum size of a request in bytes.
     pub fn with_max_request_size(mut self, max_request_size: usize) -> Self {
         self.config.max_request_size = max_request_size;
@@ -217,6 +237,31 @@ where
         self
     }
 
+    /// Sets the producer to ensure that exactly one copy of each message is written in the stream.
+    ///
+    /// Enabling idempotence requires `acks` to be `all`,
+    /// and at most 5 in-flight requests per connection.
+    pub fn with_idempotence(mut self) -> Self {
+        self.config.idempotence = true;
+        self.config.acks = RequiredAcks::All;
+        self
+    }
+
+    /// Sets the transactional id of the producer, which enables the idempotence too.
+    pub fn with_transactional_id(mut self, transactional_id: String) -> Self {
+        self.config.transactional_id = Some(transactional_id);
+        self.config.idempotence = true;
+        self.config.acks = RequiredAcks::All;
+        self
+    }
+
+    /// Sets the maximum amount of time that the transaction coordinator will wait for a transaction to complete
+    /// before proactively aborting it.
+    pub fn with_transaction_timeout(mut self, transaction_timeout: Duration) -> Self {
+        self.config.transaction_timeout = transaction_timeout.as_millis();
+        self
+    }
+
     /// Sets the key serializer that serialize key to record
     pub fn with_key_serializer(mut self, key_serializer: K) -> Self {
         self.key_serializer = Some(key_serializer);
@@ -296,6 +341,20 @@ where
 {
     /// Construct a `KafkaProducer`
     pub fn build(self) -> Result<KafkaProducer<'a, K, V, P>> {
+        if self.config.idempotence && self.config.acks != RequiredAcks::All {
+            bail!(ErrorKind::ConfigError("idempotent producer requires acks=all"))
+        }
+        if self.config.idempotence
+            && self.config.max_in_flight_requests_per_connection > MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE
+        {
+            bail!(ErrorKind::ConfigError(
+                "idempotent producer requires max.in.flight.requests.per.connection <= 5"
+            ))
+        }
+        if self.config.transactional_id.is_some() && !self.config.idempotence {
+            bail!(ErrorKind::ConfigError("transactional producer requires enable.idempotence"))
+        }
+
         let client = if let Some(client) = self.client {
             client
         } else {
@@ -317,3 +376,22 @@ where
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use errors::Error;
+
+    use super::*;
+
+    #[test]
+    fn test_idempotence_max_in_flight_requests() {
+        let builder = ProducerBuilder::<NoopSerializer<()>, NoopSerializer<()>, DefaultPartitioner>::default()
+            .with_idempotence()
+            .with_max_in_flight_requests_per_connection(MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE + 1);
+
+        match builder.build() {
+            Err(Error(ErrorKind::ConfigError(reason), _)) => assert!(reason.contains("max.in.flight")),
+            _ => panic!("idempotent producer accepts more than 5 in-flight requests"),
+        }
+    }
+}
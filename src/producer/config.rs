@@ -28,6 +28,18 @@ pub const DEFAULT_MAX_REQUEST_SIZE: usize = 1024 * 1024;
 /// Defaults to 0 ms, see [`ProducerConfig::linger`](struct.ProducerConfig.html#linger.v)
 pub const DEFAULT_LINGER_MILLIS: u64 = 0;
 
+/// The default maximum amount of time that the transaction coordinator will wait for a transaction to complete
+/// before proactively aborting it.
+///
+/// Defaults to 60 seconds, see
+/// [`ProducerConfig::transaction_timeout`](struct.ProducerConfig.html#transaction_timeout.v)
+pub const DEFAULT_TRANSACTION_TIMEOUT_MILLIS: u64 = 60_000;
+
+/// The maximum number of unacknowledged requests per connection of the idempotent producer.
+///
+/// The broker only keeps the sequence numbers of the last 5 batches of a producer to detect the duplicates.
+pub const MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE: usize = 5;
+
 /// Configuration for the `KafkaProducer`.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
@@ -60,6 +72,24 @@ pub struct ProducerConfig {
     /// that arrive in between request transmissions into a single batched request.
     #[serde(rename = "linger.ms")]
     pub linger: u64,
+
+    /// When set to `true`, the producer will ensure that exactly one copy of each message is written in the stream.
+    ///
+    /// The idempotent producer requires `acks` to be `all`
+    /// and `max.in.flight.requests.per.connection` to be at most 5.
+    #[serde(rename = "enable.idempotence")]
+    pub idempotence: bool,
+
+    /// The transactional id of the producer, enables the transactions across partitions and sessions.
+    ///
+    /// The transactional producer requires `enable.idempotence` to be set.
+    #[serde(rename = "transactional.id")]
+    pub transactional_id: Option<String>,
+
+    /// The maximum amount of time that the transaction coordinator will wait for a transaction to complete
+    /// before proactively aborting it.
+    #[serde(rename = "transaction.timeout.ms")]
+    pub transaction_timeout: u64,
 }
 
 impl Deref for ProducerConfig {
@@ -86,6 +116,9 @@ impl Default for ProducerConfig {
             batch_size: DEFAULT_BATCH_SIZE,
             max_request_size: DEFAULT_MAX_REQUEST_SIZE,
             linger: DEFAULT_LINGER_MILLIS,
+            idempotence: false,
+            transactional_id: None,
+            transaction_timeout: DEFAULT_TRANSACTION_TIMEOUT_MILLIS,
         }
     }
 }
@@ -113,6 +146,12 @@ impl ProducerConfig {
     pub fn ack_timeout(&self) -> Duration {
         Duration::from_millis(self.ack_timeout)
     }
+
+    /// The maximum amount of time that the transaction coordinator will wait for a transaction to complete
+    /// before proactively aborting it.
+    pub fn transaction_timeout(&self) -> Duration {
+        Duration::from_millis(self.transaction_timeout)
+    }
 }
 
 #[cfg(test)]
@@ -127,6 +166,10 @@ mod tests {
 
         assert_eq!(config.linger(), Duration::from_millis(DEFAULT_LINGER_MILLIS));
         assert_eq!(config.ack_timeout(), Duration::from_millis(DEFAULT_ACK_TIMEOUT_MILLIS));
+        assert_eq!(
+            config.transaction_timeout(),
+            Duration::from_millis(DEFAULT_TRANSACTION_TIMEOUT_MILLIS)
+        );
     }
 
     #[test]
@@ -137,20 +180,30 @@ mod tests {
     "bootstrap.servers": [],
     "client.id": null,
     "connection.max.idle.ms": 5000,
+    "max.in.flight.requests.per.connection": 5,
     "request.timeout.ms": 30000,
     "api.version.request": false,
     "broker.version.fallback": "0.9.0",
     "metadata.max.age.ms": 300000,
     "metrics": false,
     "retries": 0,
-    "retry.backoff.ms": 100
+    "retry.backoff.ms": 100,
+    "reconnect.backoff.ms": 50,
+    "reconnect.backoff.max.ms": 1000,
+    "security.protocol": "PLAINTEXT",
+    "enable.ssl.hostname.verification": true,
+    "sasl.mechanism": "PLAIN",
+    "enable.sasl.oauthbearer.unsecure.jwt": false
   },
   "acks": "one",
   "timeout.ms": 30000,
   "compression.type": "none",
   "batch.size": 16384,
   "max.request.size": 1048576,
-  "linger.ms": 0
+  "linger.ms": 0,
+  "enable.idempotence": false,
+  "transactional.id": null,
+  "transaction.timeout.ms": 60000
 }"#;
 
         assert_eq!(serde_json::to_string_pretty(&config).unwrap(), json);
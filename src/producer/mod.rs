@@ -7,14 +7,18 @@ mod partitioner;
 mod producer;
 mod record;
 mod sender;
+mod transaction;
 
 pub use self::accumulator::{Accumulator, PushRecord, RecordAccumulator};
 pub use self::batch::{ProducerBatch, Thunk};
 pub use self::builder::ProducerBuilder;
 pub use self::config::{ProducerConfig, DEFAULT_ACK_TIMEOUT_MILLIS, DEFAULT_BATCH_SIZE, DEFAULT_LINGER_MILLIS,
-                       DEFAULT_MAX_REQUEST_SIZE};
+                       DEFAULT_MAX_REQUEST_SIZE, DEFAULT_TRANSACTION_TIMEOUT_MILLIS,
+                       MAX_IN_FLIGHT_REQUESTS_FOR_IDEMPOTENCE};
 pub use self::interceptor::{Interceptors, ProducerInterceptor, ProducerInterceptors};
 pub use self::partitioner::{DefaultPartitioner, Partitioner};
-pub use self::producer::{Flush, GetTopic, KafkaProducer, Producer, ProducerPartition, ProducerTopic, SendRecord};
+pub use self::producer::{AbortTransaction, BeginTransaction, CommitTransaction, Flush, GetTopic, KafkaProducer,
+                         Producer, ProducerPartition, ProducerTopic, SendOffsetsToTransaction, SendRecord};
 pub use self::record::{ProducerRecord, RecordMetadata};
 pub use self::sender::{SendBatch, Sender};
+pub use self::transaction::TransactionManager;
@@ -13,9 +13,11 @@ use tokio_core::reactor::{Handle, Timeout};
 use tokio_retry::Retry;
 
 use client::{Client, Cluster, KafkaClient, Metadata, PartitionRecord, StaticBoxFuture, ToStaticBoxFuture, TopicRecord};
-use errors::{Error, ErrorKind};
+use errors::{Error, ErrorKind, Result};
+use network::{OffsetAndMetadata, TopicPartition};
 use producer::{Accumulator, Interceptors, Partitioner, ProducerBuilder, ProducerConfig, ProducerInterceptor,
-               ProducerInterceptors, ProducerRecord, PushRecord, RecordAccumulator, RecordMetadata, Sender};
+               ProducerInterceptors, ProducerRecord, PushRecord, RecordAccumulator, RecordMetadata, Sender,
+               TransactionManager};
 use protocol::{ApiKeys, PartitionId, ToMilliseconds};
 use serialization::Serializer;
 
@@ -37,6 +39,21 @@ pub trait Producer<'a> {
 
     /// Get a `futures::Sink` to send records.
     fn topic(&self, topic_name: &str) -> GetTopic<Self::Topic>;
+
+    /// Begin a new transaction, the producer must be configured with a `transactional.id`.
+    fn begin_transaction(&mut self) -> BeginTransaction;
+
+    /// Add the consumed offsets of the consumer group to the ongoing transaction,
+    /// the offsets will be committed only if the transaction is committed.
+    fn send_offsets_to_transaction<I>(&mut self, offsets: I, group_id: &str) -> SendOffsetsToTransaction
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>;
+
+    /// Flush any accumulated records and commit the ongoing transaction.
+    fn commit_transaction(&mut self) -> CommitTransaction;
+
+    /// Flush any accumulated records and abort the ongoing transaction.
+    fn abort_transaction(&mut self) -> AbortTransaction;
 }
 
 /// The future of records metadata information.
@@ -48,6 +65,18 @@ pub type Flush = StaticBoxFuture;
 /// The future of `futures::Sink` to send records..
 pub type GetTopic<T> = StaticBoxFuture<T>;
 
+/// The future of beginning a transaction.
+pub type BeginTransaction = StaticBoxFuture;
+
+/// The future of adding the consumed offsets to the transaction.
+pub type SendOffsetsToTransaction = StaticBoxFuture;
+
+/// The future of committing a transaction.
+pub type CommitTransaction = StaticBoxFuture;
+
+/// The future of aborting a transaction.
+pub type AbortTransaction = StaticBoxFuture;
+
 /// A Kafka producer that publishes records to the Kafka cluster.
 #[derive(Clone)]
 pub struct KafkaProducer<'a, K, V, P>
@@ -72,6 +101,7 @@ where
     value_serializer: V,
     partitioner: P,
     interceptors: Interceptors<K::Item, V::Item>,
+    transaction_manager: Option<TransactionManager<'a>>,
 }
 
 impl<'a, K, V, P> Deref for KafkaProducer<'a, K, V, P>
@@ -103,6 +133,15 @@ where
         interceptors: Interceptors<K::Item, V::Item>,
     ) -> Self {
         let accumulator = RecordAccumulator::new(config.batch_size, config.compression, config.linger());
+        let transaction_manager = if config.idempotence {
+            Some(TransactionManager::new(
+                client.clone(),
+                config.transactional_id.clone(),
+                config.transaction_timeout(),
+            ))
+        } else {
+            None
+        };
 
         KafkaProducer {
             inner: Rc::new(Inner {
@@ -113,6 +152,7 @@ where
                 value_serializer,
                 partitioner,
                 interceptors,
+                transaction_manager,
             }),
         }
     }
@@ -218,6 +258,53 @@ where
             })
             .static_boxed()
     }
+
+    fn begin_transaction(&mut self) -> BeginTransaction {
+        match self.inner.transaction_manager() {
+            Ok(transaction_manager) => transaction_manager.begin_transaction(),
+            Err(err) => err.into(),
+        }
+    }
+
+    fn send_offsets_to_transaction<I>(&mut self, offsets: I, group_id: &str) -> SendOffsetsToTransaction
+    where
+        I: IntoIterator<Item = (TopicPartition<'a>, OffsetAndMetadata)>,
+    {
+        match self.inner.transaction_manager() {
+            Ok(transaction_manager) => {
+                let offsets = offsets.into_iter().collect();
+
+                transaction_manager.send_offsets_to_transaction(offsets, group_id.to_owned().into())
+            }
+            Err(err) => err.into(),
+        }
+    }
+
+    fn commit_transaction(&mut self) -> CommitTransaction {
+        match self.inner.transaction_manager() {
+            Ok(transaction_manager) => self.inner
+                .flush_batches(true)
+                .and_then(move |_| transaction_manager.commit_transaction())
+                .static_boxed(),
+            Err(err) => err.into(),
+        }
+    }
+
+    fn abort_transaction(&mut self) -> AbortTransaction {
+        match self.inner.transaction_manager() {
+            Ok(transaction_manager) => self.inner
+                .flush_batches(true)
+                .then(move |res| {
+                    if let Err(err) = res {
+                        warn!("fail to flush batches before aborting the transaction, {}", err);
+                    }
+
+                    transaction_manager.abort_transaction()
+                })
+                .static_boxed(),
+            Err(err) => err.into(),
+        }
+    }
 }
 
 impl<'a, K, V, P> Inner<'a, K, V, P>
@@ -247,6 +334,7 @@ where
             key,
             value,
             timestamp,
+            headers,
         } = record;
 
         let partition = self.partitioner
@@ -268,7 +356,16 @@ where
 
         trace!("use API version {} for {:?}", api_version, tp);
 
-        self.accumulator.push_record(tp, timestamp, key, value, api_version)
+        self.accumulator.push_record(tp, timestamp, key, value, headers, api_version)
+    }
+
+    fn transaction_manager(&self) -> Result<TransactionManager<'a>> {
+        match self.transaction_manager {
+            Some(ref transaction_manager) if transaction_manager.is_transactional() => Ok(transaction_manager.clone()),
+            _ => bail!(ErrorKind::TransactionError(
+                "producer is not transactional, `transactional.id` is required".to_owned()
+            )),
+        }
     }
 
     /// Flush full or expired batches
@@ -278,22 +375,75 @@ where
         let acks = self.config.acks;
         let ack_timeout = self.config.ack_timeout();
         let retry_strategy = self.config.retry_strategy();
+        let transaction_manager = self.transaction_manager.clone();
 
         self.accumulator
             .batches(force)
             .for_each(move |(tp, batch)| {
-                let sender = Sender::new(client.clone(), interceptor.clone(), acks, ack_timeout, tp, batch);
-
-                match sender {
-                    Ok(sender) => Retry::spawn(retry_strategy.clone(), move || sender.send_batch())
-                        .from_err()
-                        .static_boxed(),
-                    Err(err) => {
-                        warn!("fail to create sender, {}", err);
-
-                        err.into()
+                let client = client.clone();
+                let interceptor = interceptor.clone();
+                let retry_strategy = retry_strategy.clone();
+                let transaction_manager = transaction_manager.clone();
+
+                let prepare = match transaction_manager {
+                    Some(ref transaction_manager) => {
+                        let add_partition = {
+                            let transaction_manager = transaction_manager.clone();
+                            let tp = tp.clone();
+
+                            move |_| transaction_manager.maybe_add_partition(tp)
+                        };
+
+                        transaction_manager
+                            .init_producer_id()
+                            .and_then(add_partition)
+                            .static_boxed()
                     }
-                }
+                    None => StaticBoxFuture::ok(()),
+                };
+                let pending_batch = transaction_manager
+                    .as_ref()
+                    .and_then(|transaction_manager| transaction_manager.pending_batch());
+                let transaction_manager1 = transaction_manager.clone();
+
+                prepare
+                    .and_then(move |_| {
+                        let sender =
+                            Sender::new(client, interceptor, transaction_manager, acks, ack_timeout, tp, batch);
+
+                        match sender {
+                            Ok(sender) => {
+                                let sender = Rc::new(sender);
+                                let failed = sender.clone();
+
+                                Retry::spawn(retry_strategy, move || sender.send_batch())
+                                    .from_err()
+                                    .map_err(move |err| {
+                                        failed.reset_producer_id();
+
+                                        err
+                                    })
+                                    .static_boxed()
+                            }
+                            Err(err) => {
+                                warn!("fail to create sender, {}", err);
+
+                                err.into()
+                            }
+                        }
+                    })
+                    .then(move |res| {
+                        if let Err(ref err) = res {
+                            if let Some(ref transaction_manager) = transaction_manager1 {
+                                transaction_manager.fail_transaction(format!("fail to send batch, {}", err));
+                            }
+                        }
+                        if let Some(pending_batch) = pending_batch {
+                            let _ = pending_batch.send(());
+                        }
+
+                        res
+                    })
             })
             .static_boxed()
     }
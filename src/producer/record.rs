@@ -1,12 +1,14 @@
 use std::hash::Hash;
 
+use bytes::Bytes;
+
 use client::{PartitionRecord, TopicRecord};
-use protocol::{Offset, PartitionId, Timestamp};
+use protocol::{Offset, PartitionId, RecordHeader, Timestamp};
 
 /// A key/value pair to be sent to Kafka.
 ///
 /// This consists of a topic name to which the record is being sent,
-/// an optional partition number, and an optional key and value.
+/// an optional partition number, an optional key and value, and the headers.
 #[derive(Clone, Debug)]
 pub struct ProducerRecord<K, V>
 where
@@ -22,6 +24,8 @@ where
     pub value: Option<V>,
     /// The timestamp
     pub timestamp: Option<Timestamp>,
+    /// The headers
+    pub headers: Vec<RecordHeader>,
 }
 
 impl<K> ProducerRecord<K, ()>
@@ -36,6 +40,7 @@ where
             key: Some(key),
             value: None,
             timestamp: None,
+            headers: vec![],
         }
     }
 }
@@ -49,6 +54,7 @@ impl<V> ProducerRecord<(), V> {
             key: None,
             value: Some(value),
             timestamp: None,
+            headers: vec![],
         }
     }
 }
@@ -65,6 +71,7 @@ where
             key: Some(key),
             value: Some(value),
             timestamp: None,
+            headers: vec![],
         }
     }
 
@@ -79,6 +86,7 @@ where
             key: record.key,
             value: record.value,
             timestamp: record.timestamp,
+            headers: vec![],
         }
     }
 
@@ -89,6 +97,7 @@ where
             key: record.key,
             value: record.value,
             timestamp: record.timestamp,
+            headers: vec![],
         }
     }
 
@@ -103,6 +112,12 @@ where
         self.timestamp = Some(timestamp);
         self
     }
+
+    /// Creates a record with a header to be sent
+    pub fn with_header<S: Into<String>>(mut self, key: S, value: Option<Bytes>) -> Self {
+        self.headers.push(RecordHeader::new(key, value));
+        self
+    }
 }
 
 /// The metadata for a record that has been acknowledged by the server
@@ -112,7 +127,8 @@ pub struct RecordMetadata {
     pub topic_name: String,
     /// The partition the record was sent to
     pub partition_id: PartitionId,
-    /// The offset of the record in the topic/partition.
+    /// The offset of the record in the topic/partition,
+    /// or `UNKNOWN_OFFSET` if the record was sent with `acks=0`.
     pub offset: Offset,
     /// The timestamp of the record in the topic/partition.
     pub timestamp: Timestamp,
@@ -1,5 +1,5 @@
 use std::borrow::{Borrow, Cow};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::hash::Hash;
 use std::rc::Rc;
 use std::time::Duration;
@@ -7,19 +7,21 @@ use std::time::Duration;
 use futures::Future;
 
 use client::{Client, KafkaClient, StaticBoxFuture, ToStaticBoxFuture};
-use errors::Result;
+use errors::{ErrorKind, Result};
 use network::TopicPartition;
-use producer::{Interceptors, ProducerBatch, Thunk};
-use protocol::{MessageSet, RequiredAcks};
+use producer::{Interceptors, ProducerBatch, Thunk, TransactionManager};
+use protocol::{KafkaCode, MessageSet, RecordFormat, RequiredAcks, UNKNOWN_OFFSET};
 
 pub struct Sender<'a, K, V> {
     client: KafkaClient<'a>,
     interceptors: Interceptors<K, V>,
+    transaction_manager: Option<TransactionManager<'a>>,
     acks: RequiredAcks,
     ack_timeout: Duration,
     tp: TopicPartition<'a>,
     thunks: Rc<RefCell<Option<Vec<Thunk>>>>,
     message_set: MessageSet,
+    attempts: Cell<usize>,
 }
 
 pub type SendBatch = StaticBoxFuture;
@@ -32,27 +34,57 @@ where
     pub fn new(
         client: KafkaClient<'a>,
         interceptors: Interceptors<K, V>,
+        transaction_manager: Option<TransactionManager<'a>>,
         acks: RequiredAcks,
         ack_timeout: Duration,
         tp: TopicPartition<'a>,
         batch: ProducerBatch,
     ) -> Result<Sender<'a, K, V>> {
-        let (thunks, message_set) = batch.build()?;
+        let api_version = batch.api_version();
+        let (thunks, mut message_set) = batch.build()?;
+
+        if let Some(ref transaction_manager) = transaction_manager {
+            if RecordFormat::from_api_version(api_version) != RecordFormat::V2 {
+                bail!(ErrorKind::IllegalArgument(format!(
+                    "idempotent producer requires record batch v2, but produce API version is {}",
+                    api_version
+                )))
+            }
+
+            message_set.batches = vec![transaction_manager.next_batch_header(&tp, message_set.messages.len())?];
+        }
 
         Ok(Sender {
             client,
             interceptors,
+            transaction_manager,
             acks,
             ack_timeout,
             tp,
             thunks: Rc::new(RefCell::new(Some(thunks))),
             message_set,
+            attempts: Cell::new(0),
         })
     }
 
+    /// Reset the producer id after the batch failed without a response from the broker.
+    ///
+    /// The broker may have written the batch, so its sequence numbers can't be rolled back.
+    pub fn reset_producer_id(&self) {
+        if let (Some(transaction_manager), Some(header)) =
+            (self.transaction_manager.as_ref(), self.message_set.batches.first())
+        {
+            transaction_manager.reset_producer_id(header);
+        }
+    }
+
     pub fn send_batch(&self) -> SendBatch {
         trace!("sending batch to {:?}: {:?}", self.tp, self.message_set);
 
+        let retried = self.attempts.get() > 0;
+
+        self.attempts.set(self.attempts.get() + 1);
+
         let topic_name: String = String::from(self.tp.topic_name.borrow());
         let partition_id = self.tp.partition_id;
         let acks = self.acks;
@@ -61,20 +93,82 @@ where
         let thunks = self.thunks.clone();
         let thunks1 = self.thunks.clone();
         let interceptors = self.interceptors.clone();
+        let transaction_manager = self.transaction_manager.clone();
+        let tp = self.tp.clone();
+        let batch_header = self.message_set.batches.first().cloned();
+        let records = self.message_set.messages.len();
+        let transactional_id = self.transaction_manager
+            .as_ref()
+            .and_then(|transaction_manager| transaction_manager.transactional_id());
 
         self.client
             .produce_records(
+                transactional_id,
                 acks,
                 ack_timeout,
                 topic_partition!(topic_name.clone(), partition_id),
                 vec![message_set],
             )
             .map(move |responses| {
+                if acks == RequiredAcks::None {
+                    trace!("batch of {}#{} has been sent without acknowledgement", topic_name, partition_id);
+
+                    if let Some(thunks) = (*thunks).borrow_mut().take() {
+                        for thunk in thunks {
+                            match thunk.done(
+                                interceptors.clone(),
+                                &topic_name,
+                                partition_id,
+                                UNKNOWN_OFFSET,
+                                KafkaCode::None,
+                            ) {
+                                Ok(()) => {}
+                                Err(metadata) => warn!("fail to send record metadata, {:?}", metadata),
+                            }
+                        }
+                    }
+
+                    return;
+                }
+
                 responses.get(&topic_name).map(|partitions| {
                     partitions
                         .iter()
                         .find(|partition| partition.partition_id == partition_id)
                         .map(|partition| {
+                            let error_code = match partition.error_code {
+                                // Only the retried batch may have been written by the previous attempt,
+                                // a new batch with a duplicate sequence would be dropped by the broker.
+                                KafkaCode::DuplicateSequenceNumber if retried => {
+                                    debug!(
+                                        "batch of {}#{} has been written, ignore the duplicate",
+                                        topic_name, partition_id
+                                    );
+
+                                    KafkaCode::None
+                                }
+                                error_code => error_code,
+                            };
+
+                            if error_code != KafkaCode::None {
+                                if let Some(ref transaction_manager) = transaction_manager {
+                                    if let Some(ref header) = batch_header {
+                                        if is_rejected(error_code) {
+                                            transaction_manager.fail_batch(&tp, header, records);
+                                        } else {
+                                            // The batch may have been written, or the broker doesn't accept the
+                                            // producer id and sequence any more, the following batches will be
+                                            // sent with a new producer id.
+                                            transaction_manager.reset_producer_id(header);
+                                        }
+                                    }
+                                    transaction_manager.fail_transaction(format!(
+                                        "fail to send batch to {}#{}, {:?}",
+                                        topic_name, partition_id, error_code
+                                    ));
+                                }
+                            }
+
                             if let Some(thunks) = (*thunks).borrow_mut().take() {
                                 for thunk in thunks {
                                     match thunk.done(
@@ -82,7 +176,7 @@ where
                                         &topic_name,
                                         partition.partition_id,
                                         partition.base_offset,
-                                        partition.error_code,
+                                        error_code,
                                     ) {
                                         Ok(()) => {}
                                         Err(metadata) => warn!("fail to send record metadata, {:?}", metadata),
@@ -105,3 +199,23 @@ where
             .static_boxed()
     }
 }
+
+/// Whether the broker rejected the batch before appending it to the log.
+fn is_rejected(error_code: KafkaCode) -> bool {
+    match error_code {
+        KafkaCode::CorruptMessage
+        | KafkaCode::UnknownTopicOrPartition
+        | KafkaCode::NotLeaderForPartition
+        | KafkaCode::MessageSizeTooLarge
+        | KafkaCode::InvalidTopic
+        | KafkaCode::RecordListTooLarge
+        | KafkaCode::NotEnoughReplicas
+        | KafkaCode::InvalidRequiredAcks
+        | KafkaCode::TopicAuthorizationFailed
+        | KafkaCode::ClusterAuthorizationFailed
+        | KafkaCode::InvalidTimestamp
+        | KafkaCode::UnsupportedForMessageFormat
+        | KafkaCode::UnsupportedVersion => true,
+        _ => false,
+    }
+}
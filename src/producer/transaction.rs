@@ -0,0 +1,720 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::i32;
+use std::mem;
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures::unsync::oneshot;
+use futures::{future, Future};
+use tokio_retry::Retry;
+
+use client::{BrokerRef, Client, InitProducerId, KafkaClient, ProducerIdAndEpoch, StaticBoxFuture, ToStaticBoxFuture};
+use errors::{Error, ErrorKind, Result};
+use network::{OffsetAndMetadata, TopicPartition};
+use protocol::{KafkaCode, RecordBatchHeader, Sequence, TRANSACTIONAL_FLAG_MASK};
+
+/// Keeps the producer id, epoch and the sequence numbers of the partitions for an idempotent producer,
+/// and the state of the ongoing transaction for a transactional producer.
+///
+/// The broker uses the producer id and the sequence numbers to discard the duplicated batches
+/// which were sent by the retries.
+#[derive(Clone)]
+pub struct TransactionManager<'a> {
+    client: KafkaClient<'a>,
+    transactional_id: Option<Cow<'a, str>>,
+    transaction_timeout: Duration,
+    state: Rc<RefCell<State<'a>>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TransactionState {
+    /// No transaction is ongoing.
+    Ready,
+    /// The transaction has begun.
+    InTransaction,
+    /// The transaction is committing or aborting.
+    Completing,
+}
+
+impl Default for TransactionState {
+    fn default() -> Self {
+        TransactionState::Ready
+    }
+}
+
+#[derive(Default)]
+struct State<'a> {
+    producer_id_and_epoch: Option<ProducerIdAndEpoch>,
+    sequences: HashMap<TopicPartition<'a>, Sequence>,
+    /// The cached transaction coordinator.
+    coordinator: Option<BrokerRef>,
+    transaction: TransactionState,
+    /// The partitions which have been added to the ongoing transaction.
+    partitions: HashSet<TopicPartition<'a>>,
+    /// Whether the transaction coordinator knows about the ongoing transaction.
+    started: bool,
+    /// The batches of the ongoing transaction which are still in flight.
+    pending_batches: Vec<oneshot::Receiver<()>>,
+    /// The first failure of the ongoing transaction, the transaction can only be aborted.
+    error: Option<String>,
+}
+
+impl<'a> TransactionManager<'a>
+where
+    Self: 'static,
+{
+    pub fn new(client: KafkaClient<'a>, transactional_id: Option<String>, transaction_timeout: Duration) -> Self {
+        TransactionManager {
+            client,
+            transactional_id: transactional_id.map(Cow::from),
+            transaction_timeout,
+            state: Rc::new(RefCell::new(State::default())),
+        }
+    }
+
+    /// The transactional id of the producer, or `None` if the producer is not transactional.
+    pub fn transactional_id(&self) -> Option<Cow<'a, str>> {
+        self.transactional_id.clone()
+    }
+
+    /// Whether the producer is transactional.
+    pub fn is_transactional(&self) -> bool {
+        self.transactional_id.is_some()
+    }
+
+    /// The producer id and epoch, or `None` if it has not been assigned.
+    pub fn producer_id_and_epoch(&self) -> Option<ProducerIdAndEpoch> {
+        self.state.borrow().producer_id_and_epoch
+    }
+
+    /// Initialize the producer id and epoch if it has not been assigned.
+    ///
+    /// The transactional producer gets its producer id from the transaction coordinator,
+    /// which also fences the previous producers with the same transactional id.
+    pub fn init_producer_id(&self) -> InitProducerId {
+        if let Some(producer_id_and_epoch) = self.producer_id_and_epoch() {
+            return StaticBoxFuture::ok(producer_id_and_epoch);
+        }
+
+        let state = self.state.clone();
+        let init_producer_id = if self.is_transactional() {
+            let transaction_timeout = self.transaction_timeout;
+
+            self.send_to_coordinator(move |client, coordinator, transactional_id| {
+                client.init_producer_id(Some(coordinator), Some(transactional_id), transaction_timeout)
+            })
+        } else {
+            self.client.init_producer_id(None, None, Duration::default())
+        };
+
+        init_producer_id
+            .map(move |producer_id_and_epoch| {
+                let mut state = state.borrow_mut();
+
+                if state.producer_id_and_epoch.is_none() {
+                    debug!("producer id assigned, {:?}", producer_id_and_epoch);
+
+                    state.producer_id_and_epoch = Some(producer_id_and_epoch);
+                    state.sequences.clear();
+                }
+
+                state.producer_id_and_epoch.unwrap_or(producer_id_and_epoch)
+            })
+            .static_boxed()
+    }
+
+    /// Build the header of the next batch sent to the partition, and bump the sequence number of the
+    /// partition with the records count of the batch.
+    pub fn next_batch_header(&self, tp: &TopicPartition<'a>, records: usize) -> Result<RecordBatchHeader> {
+        let producer_id_and_epoch = self.assigned_producer_id()?;
+
+        let mut state = self.state.borrow_mut();
+        let sequence = state.sequences.entry(tp.clone()).or_insert(0);
+        let base_sequence = *sequence;
+
+        *sequence = increment_sequence(base_sequence, records as Sequence);
+
+        trace!(
+            "assigned sequence {} to the batch of {} with {} records",
+            base_sequence,
+            tp,
+            records
+        );
+
+        Ok(RecordBatchHeader {
+            attributes: if self.is_transactional() {
+                TRANSACTIONAL_FLAG_MASK
+            } else {
+                0
+            },
+            producer_id: producer_id_and_epoch.producer_id,
+            producer_epoch: producer_id_and_epoch.producer_epoch,
+            base_sequence,
+            ..Default::default()
+        })
+    }
+
+    /// Reset the producer id and the sequence numbers after the batch failed with an unknown outcome,
+    /// or the broker lost track of the producer, a new producer id will be assigned before sending the next batch.
+    ///
+    /// The batch may have been written, so its sequence numbers must not be reused by the following batches.
+    /// Nothing is reset if the producer id of the batch has already been replaced.
+    pub fn reset_producer_id(&self, header: &RecordBatchHeader) {
+        let mut state = self.state.borrow_mut();
+
+        if state.is_assigned(header) {
+            state.reset_producer_id();
+        }
+    }
+
+    /// Roll back the sequence number of the partition after the broker rejected the batch without writing it.
+    ///
+    /// Several batches of the partition may be in flight, and the broker would reject the ones built after
+    /// the failed batch for the gap of sequence numbers, so the producer id is reset instead if any batch
+    /// has been built after the failed one.
+    pub fn fail_batch(&self, tp: &TopicPartition<'a>, header: &RecordBatchHeader, records: usize) {
+        let mut state = self.state.borrow_mut();
+
+        if !state.is_assigned(header) {
+            return;
+        }
+
+        let next_sequence = increment_sequence(header.base_sequence, records as Sequence);
+        let rolled_back = match state.sequences.get_mut(tp) {
+            Some(sequence) if *sequence == next_sequence => {
+                *sequence = header.base_sequence;
+
+                true
+            }
+            _ => false,
+        };
+
+        if rolled_back {
+            debug!("roll back sequence of {} to {}", tp, header.base_sequence);
+        } else {
+            state.reset_producer_id();
+        }
+    }
+
+    /// Begin a new transaction, the producer id will be initialized if it has not been assigned.
+    pub fn begin_transaction(&self) -> StaticBoxFuture {
+        if let Err(err) = self.check_state("begin transaction", TransactionState::Ready) {
+            return err.into();
+        }
+
+        let state = self.state.clone();
+
+        self.init_producer_id()
+            .and_then(move |_| {
+                let mut state = state.borrow_mut();
+
+                if state.transaction != TransactionState::Ready {
+                    bail!(ErrorKind::TransactionError(format!(
+                        "fail to begin transaction in {:?} state",
+                        state.transaction
+                    )))
+                }
+
+                state.transaction = TransactionState::InTransaction;
+
+                Ok(())
+            })
+            .static_boxed()
+    }
+
+    /// Add the partition to the ongoing transaction before sending the first batch to it.
+    pub fn maybe_add_partition(&self, tp: TopicPartition<'a>) -> StaticBoxFuture {
+        if !self.is_transactional() || self.state.borrow().partitions.contains(&tp) {
+            return StaticBoxFuture::ok(());
+        }
+
+        let producer = match self.check_state("send records", TransactionState::InTransaction)
+            .and_then(|_| self.assigned_producer_id())
+        {
+            Ok(producer) => producer,
+            Err(err) => return err.into(),
+        };
+
+        let state = self.state.clone();
+        let add_partition = {
+            let tp = tp.clone();
+
+            self.send_to_coordinator(move |client, coordinator, transactional_id| {
+                client.add_partitions_to_txn(coordinator, transactional_id, producer, vec![tp.clone()])
+            })
+        };
+
+        add_partition
+            .map(move |_| {
+                debug!("added {} to the transaction", tp);
+
+                let mut state = state.borrow_mut();
+
+                state.partitions.insert(tp);
+                state.started = true;
+            })
+            .static_boxed()
+    }
+
+    /// Track a batch sent in the ongoing transaction,
+    /// the transaction will not be committed or aborted until the returned sender is completed or dropped.
+    pub fn pending_batch(&self) -> Option<oneshot::Sender<()>> {
+        if self.is_transactional() {
+            let (sender, receiver) = oneshot::channel();
+
+            self.state.borrow_mut().pending_batches.push(receiver);
+
+            Some(sender)
+        } else {
+            None
+        }
+    }
+
+    /// Mark the ongoing transaction as failed, it can only be aborted.
+    pub fn fail_transaction(&self, reason: String) {
+        if self.is_transactional() {
+            let mut state = self.state.borrow_mut();
+
+            if state.transaction == TransactionState::InTransaction && state.error.is_none() {
+                warn!("transaction failed, {}", reason);
+
+                state.error = Some(reason);
+            }
+        }
+    }
+
+    /// Add the consumed offsets of the consumer group to the ongoing transaction,
+    /// the offsets will be committed when the transaction is committed.
+    pub fn send_offsets_to_transaction(
+        &self,
+        offsets: Vec<(TopicPartition<'a>, OffsetAndMetadata)>,
+        group_id: Cow<'a, str>,
+    ) -> StaticBoxFuture {
+        let producer = match self.check_state("send offsets to transaction", TransactionState::InTransaction)
+            .and_then(|_| self.assigned_producer_id())
+        {
+            Ok(producer) => producer,
+            Err(err) => return err.into(),
+        };
+
+        let client = self.client.clone();
+        let state = self.state.clone();
+        let transactional_id = self.transactional_id.clone().unwrap_or_default();
+        let add_offsets = {
+            let group_id = group_id.clone();
+
+            self.send_to_coordinator(move |client, coordinator, transactional_id| {
+                client.add_offsets_to_txn(coordinator, transactional_id, producer, group_id.clone())
+            })
+        };
+
+        add_offsets
+            .and_then(move |_| {
+                state.borrow_mut().started = true;
+
+                Retry::spawn(client.retry_strategy(), move || {
+                    let client = client.clone();
+                    let transactional_id = transactional_id.clone();
+                    let group_id = group_id.clone();
+                    let offsets = offsets.clone();
+
+                    client
+                        .group_coordinator(group_id.clone())
+                        .and_then(move |coordinator| {
+                            client.txn_offset_commit(
+                                coordinator.as_ref(),
+                                transactional_id,
+                                group_id,
+                                producer,
+                                offsets,
+                            )
+                        })
+                        .and_then(|responses| {
+                            for (topic_name, partitions) in responses {
+                                for partition in partitions {
+                                    if partition.error_code != KafkaCode::None {
+                                        warn!(
+                                            "fail to commit offset of {}#{} in the transaction, {:?}",
+                                            topic_name, partition.partition_id, partition.error_code
+                                        );
+
+                                        bail!(ErrorKind::KafkaError(partition.error_code))
+                                    }
+                                }
+                            }
+
+                            Ok(())
+                        })
+                }).from_err()
+            })
+            .static_boxed()
+    }
+
+    /// Commit the ongoing transaction after all the batches of it have been completed.
+    pub fn commit_transaction(&self) -> StaticBoxFuture {
+        if let Err(err) = self.check_state("commit transaction", TransactionState::InTransaction) {
+            return err.into();
+        }
+
+        let manager = self.clone();
+
+        self.wait_pending_batches()
+            .and_then(move |_| manager.end_transaction(true))
+            .static_boxed()
+    }
+
+    /// Abort the ongoing transaction after all the batches of it have been completed.
+    pub fn abort_transaction(&self) -> StaticBoxFuture {
+        let transaction = self.state.borrow().transaction;
+
+        if !self.is_transactional() || transaction != TransactionState::InTransaction {
+            return ErrorKind::TransactionError(format!("fail to abort transaction in {:?} state", transaction)).into();
+        }
+
+        let manager = self.clone();
+
+        self.wait_pending_batches()
+            .and_then(move |_| manager.end_transaction(false))
+            .static_boxed()
+    }
+
+    fn wait_pending_batches(&self) -> StaticBoxFuture {
+        let pending_batches = mem::replace(&mut self.state.borrow_mut().pending_batches, Vec::new());
+
+        future::join_all(
+            pending_batches
+                .into_iter()
+                .map(|pending_batch| pending_batch.then(|_| Ok::<(), Error>(()))),
+        ).map(|_| ())
+            .static_boxed()
+    }
+
+    fn end_transaction(&self, committed: bool) -> StaticBoxFuture {
+        let (producer_id_and_epoch, started) = {
+            let mut state = self.state.borrow_mut();
+
+            if committed {
+                if let Some(ref reason) = state.error {
+                    return ErrorKind::TransactionError(format!("transaction can only be aborted, {}", reason)).into();
+                }
+            }
+
+            state.transaction = TransactionState::Completing;
+
+            (state.producer_id_and_epoch, state.started)
+        };
+
+        // The transaction coordinator has not known about the transaction yet,
+        // or it aborts the transaction when the producer id is reinitialized.
+        let end_txn = match producer_id_and_epoch {
+            Some(producer) if started => self.send_to_coordinator(move |client, coordinator, transactional_id| {
+                client.end_txn(coordinator, transactional_id, producer, committed)
+            }),
+            _ => StaticBoxFuture::ok(()),
+        };
+
+        let state = self.state.clone();
+
+        end_txn
+            .then(move |res| {
+                let mut state = state.borrow_mut();
+
+                match res {
+                    Ok(()) => {
+                        debug!("transaction {}", if committed { "committed" } else { "aborted" });
+
+                        state.transaction = TransactionState::Ready;
+                        state.partitions.clear();
+                        state.started = false;
+                        state.error = None;
+
+                        Ok(())
+                    }
+                    Err(err) => {
+                        state.transaction = TransactionState::InTransaction;
+
+                        if state.error.is_none() {
+                            state.error = Some(err.to_string());
+                        }
+
+                        Err(err)
+                    }
+                }
+            })
+            .static_boxed()
+    }
+
+    fn assigned_producer_id(&self) -> Result<ProducerIdAndEpoch> {
+        self.producer_id_and_epoch()
+            .ok_or_else(|| ErrorKind::IllegalArgument("producer id has not been assigned".to_owned()).into())
+    }
+
+    /// Check the ongoing transaction is in the expected state.
+    fn check_state(&self, operation: &str, expected: TransactionState) -> Result<()> {
+        let state = self.state.borrow();
+
+        if !self.is_transactional() {
+            bail!(ErrorKind::TransactionError(format!(
+                "fail to {}, producer is not transactional",
+                operation
+            )))
+        }
+        if state.transaction != expected {
+            bail!(ErrorKind::TransactionError(format!(
+                "fail to {} in {:?} state",
+                operation, state.transaction
+            )))
+        }
+        if let Some(ref reason) = state.error {
+            bail!(ErrorKind::TransactionError(format!(
+                "fail to {}, transaction can only be aborted, {}",
+                operation, reason
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Send the request to the transaction coordinator,
+    /// the coordinator will be discovered again when it has been moved or is not available.
+    fn send_to_coordinator<F, T>(&self, request: F) -> StaticBoxFuture<T>
+    where
+        F: Fn(&KafkaClient<'a>, BrokerRef, Cow<'a, str>) -> StaticBoxFuture<T> + 'static,
+        T: 'static,
+    {
+        let transactional_id = match self.transactional_id {
+            Some(ref transactional_id) => transactional_id.clone(),
+            None => return ErrorKind::TransactionError("producer is not transactional".to_owned()).into(),
+        };
+        let client = self.client.clone();
+        let state = self.state.clone();
+        let request = Rc::new(request);
+
+        Retry::spawn(self.client.retry_strategy(), move || {
+            let cached = state.borrow().coordinator;
+            let coordinator = match cached {
+                Some(coordinator) => StaticBoxFuture::ok(coordinator),
+                None => {
+                    let state = state.clone();
+
+                    client
+                        .transaction_coordinator(transactional_id.clone())
+                        .map(move |broker| {
+                            debug!("transaction coordinator discovered, {:?}", broker);
+
+                            let coordinator = broker.as_ref();
+
+                            state.borrow_mut().coordinator = Some(coordinator);
+
+                            coordinator
+                        })
+                        .static_boxed()
+                }
+            };
+
+            let client = client.clone();
+            let state = state.clone();
+            let request = request.clone();
+            let transactional_id = transactional_id.clone();
+
+            coordinator
+                .and_then(move |coordinator| request(&client, coordinator, transactional_id))
+                .map_err(move |err| {
+                    match err {
+                        Error(ErrorKind::KafkaError(KafkaCode::NotCoordinator), _)
+                        | Error(ErrorKind::KafkaError(KafkaCode::CoordinatorNotAvailable), _)
+                        | Error(ErrorKind::BrokerNotFound(_), _) => {
+                            info!("transaction coordinator is not available, {}", err);
+
+                            state.borrow_mut().coordinator = None;
+                        }
+                        _ => {}
+                    }
+
+                    err
+                })
+        }).from_err()
+            .static_boxed()
+    }
+}
+
+impl<'a> State<'a> {
+    /// Whether the batch was built with the current producer id and epoch.
+    fn is_assigned(&self, header: &RecordBatchHeader) -> bool {
+        self.producer_id_and_epoch.map_or(false, |producer_id_and_epoch| {
+            producer_id_and_epoch.producer_id == header.producer_id
+                && producer_id_and_epoch.producer_epoch == header.producer_epoch
+        })
+    }
+
+    fn reset_producer_id(&mut self) {
+        if let Some(producer_id_and_epoch) = self.producer_id_and_epoch.take() {
+            info!("reset producer id, {:?}", producer_id_and_epoch);
+        }
+
+        self.sequences.clear();
+    }
+}
+
+/// The sequence number wraps around to zero after reaching `i32::MAX`.
+fn increment_sequence(sequence: Sequence, increment: Sequence) -> Sequence {
+    if sequence > i32::MAX - increment {
+        increment - (i32::MAX - sequence) - 1
+    } else {
+        sequence + increment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_core::reactor::Core;
+
+    use client::ClientConfig;
+
+    use super::*;
+
+    fn build_transaction_manager(core: &Core) -> TransactionManager<'static> {
+        let manager = TransactionManager::new(
+            KafkaClient::new(
+                ClientConfig::with_bootstrap_servers(vec!["127.0.0.1:9092".to_owned()]),
+                core.handle(),
+            ),
+            None,
+            Duration::default(),
+        );
+
+        manager.state.borrow_mut().producer_id_and_epoch = Some(ProducerIdAndEpoch {
+            producer_id: 123,
+            producer_epoch: 1,
+        });
+
+        manager
+    }
+
+    #[test]
+    fn test_increment_sequence() {
+        assert_eq!(increment_sequence(0, 5), 5);
+        assert_eq!(increment_sequence(i32::MAX - 5, 5), i32::MAX);
+        assert_eq!(increment_sequence(i32::MAX - 5, 6), 0);
+        assert_eq!(increment_sequence(i32::MAX, 3), 2);
+    }
+
+    #[test]
+    fn test_roll_back_sequence() {
+        let core = Core::new().unwrap();
+        let manager = build_transaction_manager(&core);
+        let tp = topic_partition!("topic", 0);
+
+        let first = manager.next_batch_header(&tp, 5).unwrap();
+        let second = manager.next_batch_header(&tp, 3).unwrap();
+
+        assert_eq!(first.base_sequence, 0);
+        assert_eq!(second.base_sequence, 5);
+
+        manager.fail_batch(&tp, &second, 3);
+
+        assert_eq!(manager.next_batch_header(&tp, 3).unwrap().base_sequence, 5);
+        assert!(manager.producer_id_and_epoch().is_some());
+    }
+
+    #[test]
+    fn test_reset_producer_id() {
+        let core = Core::new().unwrap();
+        let manager = build_transaction_manager(&core);
+        let tp = topic_partition!("topic", 0);
+
+        let first = manager.next_batch_header(&tp, 5).unwrap();
+        let second = manager.next_batch_header(&tp, 3).unwrap();
+
+        // the following batch would be rejected after the gap of sequence numbers
+        manager.fail_batch(&tp, &first, 5);
+
+        assert_eq!(manager.producer_id_and_epoch(), None);
+        assert!(manager.next_batch_header(&tp, 1).is_err());
+
+        let producer_id_and_epoch = ProducerIdAndEpoch {
+            producer_id: 456,
+            producer_epoch: 0,
+        };
+
+        manager.state.borrow_mut().producer_id_and_epoch = Some(producer_id_and_epoch);
+
+        let third = manager.next_batch_header(&tp, 1).unwrap();
+
+        assert_eq!(third.producer_id, 456);
+        assert_eq!(third.base_sequence, 0);
+
+        // the batches with the stale producer id don't reset the new one
+        manager.reset_producer_id(&second);
+        manager.fail_batch(&tp, &second, 3);
+
+        assert_eq!(manager.producer_id_and_epoch(), Some(producer_id_and_epoch));
+        assert_eq!(manager.next_batch_header(&tp, 1).unwrap().base_sequence, 1);
+
+        manager.reset_producer_id(&third);
+
+        assert_eq!(manager.producer_id_and_epoch(), None);
+    }
+
+    #[test]
+    fn test_fail_batch_in_flight() {
+        let core = Core::new().unwrap();
+        let manager = build_transaction_manager(&core);
+        let tp = topic_partition!("topic", 0);
+
+        let first = manager.next_batch_header(&tp, 5).unwrap();
+        let second = manager.next_batch_header(&tp, 3).unwrap();
+        let third = manager.next_batch_header(&tp, 2).unwrap();
+
+        assert_eq!((first.base_sequence, second.base_sequence, third.base_sequence), (0, 5, 8));
+
+        // the middle batch is rejected while the following batch is still in flight
+        manager.fail_batch(&tp, &second, 3);
+
+        assert_eq!(manager.producer_id_and_epoch(), None);
+
+        let producer_id_and_epoch = ProducerIdAndEpoch {
+            producer_id: 456,
+            producer_epoch: 0,
+        };
+
+        manager.state.borrow_mut().producer_id_and_epoch = Some(producer_id_and_epoch);
+
+        let fourth = manager.next_batch_header(&tp, 4).unwrap();
+
+        assert_eq!((fourth.producer_id, fourth.base_sequence), (456, 0));
+
+        // the in-flight batches complete or fail with the stale producer id
+        manager.reset_producer_id(&third);
+        manager.fail_batch(&tp, &third, 2);
+        manager.fail_batch(&tp, &first, 5);
+
+        assert_eq!(manager.producer_id_and_epoch(), Some(producer_id_and_epoch));
+        assert_eq!(manager.next_batch_header(&tp, 1).unwrap().base_sequence, 4);
+    }
+
+    #[test]
+    fn test_reset_producer_id_after_unknown_outcome() {
+        let core = Core::new().unwrap();
+        let manager = build_transaction_manager(&core);
+        let tp = topic_partition!("topic", 0);
+
+        let first = manager.next_batch_header(&tp, 5).unwrap();
+
+        // the batch may have been written, its sequence numbers can't be reused
+        manager.reset_producer_id(&first);
+
+        assert_eq!(manager.producer_id_and_epoch(), None);
+
+        manager.state.borrow_mut().producer_id_and_epoch = Some(ProducerIdAndEpoch {
+            producer_id: 123,
+            producer_epoch: 2,
+        });
+
+        let second = manager.next_batch_header(&tp, 5).unwrap();
+
+        assert_eq!((second.producer_epoch, second.base_sequence), (2, 0));
+    }
+}
@@ -0,0 +1,831 @@
+use std::borrow::Cow;
+
+use bytes::{BufMut, ByteOrder, BytesMut};
+
+use nom::{IResult, be_i16, be_i32, be_i8};
+
+use errors::Result;
+use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, ParseTag,
+               Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE, STR_LEN_SIZE};
+
+const RESOURCE_TYPE_SIZE: usize = 1;
+const PATTERN_TYPE_SIZE: usize = 1;
+const OPERATION_SIZE: usize = 1;
+const PERMISSION_TYPE_SIZE: usize = 1;
+
+/// The type of the resource which the ACL is applied to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    Unknown = 0,
+    /// In a filter, matches any resource type.
+    Any = 1,
+    Topic = 2,
+    Group = 3,
+    Cluster = 4,
+    TransactionalId = 5,
+    DelegationToken = 6,
+}
+
+impl From<i8> for ResourceType {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => ResourceType::Any,
+            2 => ResourceType::Topic,
+            3 => ResourceType::Group,
+            4 => ResourceType::Cluster,
+            5 => ResourceType::TransactionalId,
+            6 => ResourceType::DelegationToken,
+            _ => ResourceType::Unknown,
+        }
+    }
+}
+
+/// How the resource name of the ACL is matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PatternType {
+    Unknown = 0,
+    /// In a filter, matches any pattern type.
+    Any = 1,
+    /// In a filter, matches the literal, wildcard and prefixed patterns which match the resource name.
+    Match = 2,
+    /// The resource name is matched literally, or `*` matches all the resources.
+    Literal = 3,
+    /// The resource name is matched as a prefix (since v1).
+    Prefixed = 4,
+}
+
+impl Default for PatternType {
+    fn default() -> Self {
+        PatternType::Literal
+    }
+}
+
+impl From<i8> for PatternType {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => PatternType::Any,
+            2 => PatternType::Match,
+            3 => PatternType::Literal,
+            4 => PatternType::Prefixed,
+            _ => PatternType::Unknown,
+        }
+    }
+}
+
+/// The operation which the ACL allows or denies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AclOperation {
+    Unknown = 0,
+    /// In a filter, matches any operation.
+    Any = 1,
+    /// Matches all the operations.
+    All = 2,
+    Read = 3,
+    Write = 4,
+    Create = 5,
+    Delete = 6,
+    Alter = 7,
+    Describe = 8,
+    ClusterAction = 9,
+    DescribeConfigs = 10,
+    AlterConfigs = 11,
+    IdempotentWrite = 12,
+}
+
+impl From<i8> for AclOperation {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => AclOperation::Any,
+            2 => AclOperation::All,
+            3 => AclOperation::Read,
+            4 => AclOperation::Write,
+            5 => AclOperation::Create,
+            6 => AclOperation::Delete,
+            7 => AclOperation::Alter,
+            8 => AclOperation::Describe,
+            9 => AclOperation::ClusterAction,
+            10 => AclOperation::DescribeConfigs,
+            11 => AclOperation::AlterConfigs,
+            12 => AclOperation::IdempotentWrite,
+            _ => AclOperation::Unknown,
+        }
+    }
+}
+
+/// Whether the ACL allows or denies the operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AclPermissionType {
+    Unknown = 0,
+    /// In a filter, matches any permission type.
+    Any = 1,
+    Deny = 2,
+    Allow = 3,
+}
+
+impl From<i8> for AclPermissionType {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => AclPermissionType::Any,
+            2 => AclPermissionType::Deny,
+            3 => AclPermissionType::Allow,
+            _ => AclPermissionType::Unknown,
+        }
+    }
+}
+
+/// The resources which the ACL is applied to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourcePattern<'a> {
+    /// The resource type.
+    pub resource_type: ResourceType,
+    /// The resource name, or `*` for all the resources of the type.
+    pub resource_name: Cow<'a, str>,
+    /// How the resource name is matched.
+    pub pattern_type: PatternType,
+}
+
+impl<'a> ResourcePattern<'a> {
+    pub fn new<S>(resource_type: ResourceType, resource_name: S, pattern_type: PatternType) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        ResourcePattern {
+            resource_type,
+            resource_name: resource_name.into(),
+            pattern_type,
+        }
+    }
+
+    /// The topic which has the literal name.
+    pub fn topic<S>(topic_name: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        Self::new(ResourceType::Topic, topic_name, PatternType::Literal)
+    }
+
+    /// The consumer group which has the literal id.
+    pub fn group<S>(group_id: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        Self::new(ResourceType::Group, group_id, PatternType::Literal)
+    }
+
+    /// The Kafka cluster.
+    pub fn cluster() -> Self {
+        Self::new(ResourceType::Cluster, "kafka-cluster", PatternType::Literal)
+    }
+
+    pub fn into_owned(self) -> ResourcePattern<'static> {
+        ResourcePattern {
+            resource_type: self.resource_type,
+            resource_name: Cow::Owned(self.resource_name.into_owned()),
+            pattern_type: self.pattern_type,
+        }
+    }
+}
+
+/// Who is allowed or denied to perform the operation from which host.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AccessControlEntry<'a> {
+    /// The principal, such as `User:alice`.
+    pub principal: Cow<'a, str>,
+    /// The host, or `*` for all the hosts.
+    pub host: Cow<'a, str>,
+    /// The operation.
+    pub operation: AclOperation,
+    /// Whether the operation is allowed or denied.
+    pub permission_type: AclPermissionType,
+}
+
+impl<'a> AccessControlEntry<'a> {
+    pub fn new<P, H>(principal: P, host: H, operation: AclOperation, permission_type: AclPermissionType) -> Self
+    where
+        P: Into<Cow<'a, str>>,
+        H: Into<Cow<'a, str>>,
+    {
+        AccessControlEntry {
+            principal: principal.into(),
+            host: host.into(),
+            operation,
+            permission_type,
+        }
+    }
+
+    /// Allow the principal to perform the operation from all the hosts.
+    pub fn allow<P>(principal: P, operation: AclOperation) -> Self
+    where
+        P: Into<Cow<'a, str>>,
+    {
+        Self::new(principal, "*", operation, AclPermissionType::Allow)
+    }
+
+    /// Deny the principal to perform the operation from all the hosts.
+    pub fn deny<P>(principal: P, operation: AclOperation) -> Self
+    where
+        P: Into<Cow<'a, str>>,
+    {
+        Self::new(principal, "*", operation, AclPermissionType::Deny)
+    }
+
+    pub fn into_owned(self) -> AccessControlEntry<'static> {
+        AccessControlEntry {
+            principal: Cow::Owned(self.principal.into_owned()),
+            host: Cow::Owned(self.host.into_owned()),
+            operation: self.operation,
+            permission_type: self.permission_type,
+        }
+    }
+}
+
+/// The ACL which binds the access control entry to the resource pattern.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AclBinding<'a> {
+    pub pattern: ResourcePattern<'a>,
+    pub entry: AccessControlEntry<'a>,
+}
+
+impl<'a> AclBinding<'a> {
+    pub fn new(pattern: ResourcePattern<'a>, entry: AccessControlEntry<'a>) -> Self {
+        AclBinding { pattern, entry }
+    }
+
+    pub fn into_owned(self) -> AclBinding<'static> {
+        AclBinding {
+            pattern: self.pattern.into_owned(),
+            entry: self.entry.into_owned(),
+        }
+    }
+}
+
+/// The filter which matches the ACL bindings, the `None` fields match any value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AclBindingFilter<'a> {
+    /// The resource type, or `ResourceType::Any`.
+    pub resource_type: ResourceType,
+    /// The resource name.
+    pub resource_name: Option<Cow<'a, str>>,
+    /// The pattern type, or `PatternType::Any` (since v1).
+    pub pattern_type: PatternType,
+    /// The principal.
+    pub principal: Option<Cow<'a, str>>,
+    /// The host.
+    pub host: Option<Cow<'a, str>>,
+    /// The operation, or `AclOperation::Any`.
+    pub operation: AclOperation,
+    /// The permission type, or `AclPermissionType::Any`.
+    pub permission_type: AclPermissionType,
+}
+
+impl<'a> Default for AclBindingFilter<'a> {
+    fn default() -> Self {
+        AclBindingFilter {
+            resource_type: ResourceType::Any,
+            resource_name: None,
+            pattern_type: PatternType::Any,
+            principal: None,
+            host: None,
+            operation: AclOperation::Any,
+            permission_type: AclPermissionType::Any,
+        }
+    }
+}
+
+impl<'a> From<AclBinding<'a>> for AclBindingFilter<'a> {
+    fn from(acl: AclBinding<'a>) -> Self {
+        AclBindingFilter {
+            resource_type: acl.pattern.resource_type,
+            resource_name: Some(acl.pattern.resource_name),
+            pattern_type: acl.pattern.pattern_type,
+            principal: Some(acl.entry.principal),
+            host: Some(acl.entry.host),
+            operation: acl.entry.operation,
+            permission_type: acl.entry.permission_type,
+        }
+    }
+}
+
+impl<'a> AclBindingFilter<'a> {
+    /// The filter which matches all the ACL bindings.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Matches the ACL bindings of the resources.
+    pub fn with_resource<S>(mut self, resource_type: ResourceType, resource_name: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.resource_type = resource_type;
+        self.resource_name = Some(resource_name.into());
+        self
+    }
+
+    /// Matches the ACL bindings of the pattern type.
+    pub fn with_pattern_type(mut self, pattern_type: PatternType) -> Self {
+        self.pattern_type = pattern_type;
+        self
+    }
+
+    /// Matches the ACL bindings of the principal.
+    pub fn with_principal<S>(mut self, principal: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    /// Matches the ACL bindings of the host.
+    pub fn with_host<S>(mut self, host: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Matches the ACL bindings of the operation.
+    pub fn with_operation(mut self, operation: AclOperation) -> Self {
+        self.operation = operation;
+        self
+    }
+
+    /// Matches the ACL bindings of the permission type.
+    pub fn with_permission_type(mut self, permission_type: AclPermissionType) -> Self {
+        self.permission_type = permission_type;
+        self
+    }
+
+    pub fn into_owned(self) -> AclBindingFilter<'static> {
+        AclBindingFilter {
+            resource_type: self.resource_type,
+            resource_name: self.resource_name.map(|s| Cow::Owned(s.into_owned())),
+            pattern_type: self.pattern_type,
+            principal: self.principal.map(|s| Cow::Owned(s.into_owned())),
+            host: self.host.map(|s| Cow::Owned(s.into_owned())),
+            operation: self.operation,
+            permission_type: self.permission_type,
+        }
+    }
+
+    fn size(&self, api_version: ApiVersion) -> usize {
+        RESOURCE_TYPE_SIZE + STR_LEN_SIZE + self.resource_name.as_ref().map_or(0, |s| s.len())
+            + if api_version > 0 { PATTERN_TYPE_SIZE } else { 0 } + STR_LEN_SIZE
+            + self.principal.as_ref().map_or(0, |s| s.len()) + STR_LEN_SIZE
+            + self.host.as_ref().map_or(0, |s| s.len()) + OPERATION_SIZE + PERMISSION_TYPE_SIZE
+    }
+
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut, api_version: ApiVersion) -> Result<()> {
+        dst.put_i8(self.resource_type as i8);
+        dst.put_str::<T, _>(self.resource_name.as_ref())?;
+        if api_version > 0 {
+            dst.put_i8(self.pattern_type as i8);
+        }
+        dst.put_str::<T, _>(self.principal.as_ref())?;
+        dst.put_str::<T, _>(self.host.as_ref())?;
+        dst.put_i8(self.operation as i8);
+        dst.put_i8(self.permission_type as i8);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeAclsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The filter of the ACL bindings to describe.
+    pub filter: AclBindingFilter<'a>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeAclsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+    /// The resources and their ACLs.
+    pub resources: Vec<DescribeAclsResource>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeAclsResource {
+    /// The resource pattern.
+    pub pattern: ResourcePattern<'static>,
+    /// The ACLs of the resource pattern.
+    pub acls: Vec<AccessControlEntry<'static>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateAclsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The ACL bindings to create.
+    pub creations: Vec<AclBinding<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateAclsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results of each creation, in the order of the request.
+    pub creation_responses: Vec<CreateAclsStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateAclsStatus {
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteAclsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The filters of the ACL bindings to delete.
+    pub filters: Vec<AclBindingFilter<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteAclsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results of each filter, in the order of the request.
+    pub filter_responses: Vec<DeleteAclsFilterStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteAclsFilterStatus {
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+    /// The ACL bindings which matched the filter.
+    pub matching_acls: Vec<DeleteAclsMatchingAcl>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteAclsMatchingAcl {
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+    /// The matched ACL binding.
+    pub acl: AclBinding<'static>,
+}
+
+impl<'a> Record for DescribeAclsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.filter.size(api_version)
+    }
+}
+
+impl<'a> Encodable for DescribeAclsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+        self.filter.encode::<T>(dst, self.header.api_version)
+    }
+}
+
+impl<'a> Record for CreateAclsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.creations.iter().fold(ARRAY_LEN_SIZE, |size, acl| {
+            size + RESOURCE_TYPE_SIZE + STR_LEN_SIZE + acl.pattern.resource_name.len()
+                + if api_version > 0 { PATTERN_TYPE_SIZE } else { 0 } + STR_LEN_SIZE + acl.entry.principal.len()
+                + STR_LEN_SIZE + acl.entry.host.len() + OPERATION_SIZE + PERMISSION_TYPE_SIZE
+        })
+    }
+}
+
+impl<'a> Encodable for CreateAclsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        let api_version = self.header.api_version;
+
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.creations, |buf, acl| {
+            buf.put_i8(acl.pattern.resource_type as i8);
+            buf.put_str::<T, _>(Some(acl.pattern.resource_name.as_ref()))?;
+            if api_version > 0 {
+                buf.put_i8(acl.pattern.pattern_type as i8);
+            }
+            buf.put_str::<T, _>(Some(acl.entry.principal.as_ref()))?;
+            buf.put_str::<T, _>(Some(acl.entry.host.as_ref()))?;
+            buf.put_i8(acl.entry.operation as i8);
+            buf.put_i8(acl.entry.permission_type as i8);
+            Ok(())
+        })
+    }
+}
+
+impl<'a> Record for DeleteAclsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.filters.iter().fold(ARRAY_LEN_SIZE, |size, filter| {
+            size + filter.size(api_version)
+        })
+    }
+}
+
+impl<'a> Encodable for DeleteAclsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        let api_version = self.header.api_version;
+
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.filters, |buf, filter| filter.encode::<T>(buf, api_version))
+    }
+}
+
+named_args!(parse_resource_pattern(api_version: ApiVersion)<ResourcePattern<'static>>,
+    do_parse!(
+        resource_type: be_i8
+     >> resource_name: parse_string
+     >> pattern_type: cond!(api_version > 0, be_i8)
+     >> (ResourcePattern {
+            resource_type: resource_type.into(),
+            resource_name: Cow::Owned(resource_name),
+            pattern_type: pattern_type.map_or(PatternType::Literal, PatternType::from),
+        })
+    )
+);
+
+named!(parse_access_control_entry<AccessControlEntry<'static>>,
+    do_parse!(
+        principal: parse_string
+     >> host: parse_string
+     >> operation: be_i8
+     >> permission_type: be_i8
+     >> (AccessControlEntry {
+            principal: Cow::Owned(principal),
+            host: Cow::Owned(host),
+            operation: operation.into(),
+            permission_type: permission_type.into(),
+        })
+    )
+);
+
+impl DescribeAclsResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_describe_acls_response(buf, api_version)
+    }
+}
+
+named_args!(parse_describe_acls_response(api_version: ApiVersion)<DescribeAclsResponse>,
+    parse_tag!(ParseTag::DescribeAclsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> error_code: be_i16
+         >> error_message: parse_opt_string
+         >> resources: length_count!(be_i32, apply!(parse_describe_acls_resource, api_version))
+         >> (DescribeAclsResponse {
+                header,
+                throttle_time,
+                error_code,
+                error_message,
+                resources,
+            })
+        )
+    )
+);
+
+named_args!(parse_describe_acls_resource(api_version: ApiVersion)<DescribeAclsResource>,
+    parse_tag!(ParseTag::DescribeAclsResource,
+        do_parse!(
+            pattern: apply!(parse_resource_pattern, api_version)
+         >> acls: length_count!(be_i32, parse_access_control_entry)
+         >> (DescribeAclsResource {
+                pattern,
+                acls,
+            })
+        )
+    )
+);
+
+impl CreateAclsResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_create_acls_response(buf)
+    }
+}
+
+named!(parse_create_acls_response<CreateAclsResponse>,
+    parse_tag!(ParseTag::CreateAclsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> creation_responses: length_count!(be_i32, parse_create_acls_status)
+         >> (CreateAclsResponse {
+                header,
+                throttle_time,
+                creation_responses,
+            })
+        )
+    )
+);
+
+named!(parse_create_acls_status<CreateAclsStatus>,
+    parse_tag!(ParseTag::CreateAclsStatus,
+        do_parse!(
+            error_code: be_i16
+         >> error_message: parse_opt_string
+         >> (CreateAclsStatus {
+                error_code,
+                error_message,
+            })
+        )
+    )
+);
+
+impl DeleteAclsResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_delete_acls_response(buf, api_version)
+    }
+}
+
+named_args!(parse_delete_acls_response(api_version: ApiVersion)<DeleteAclsResponse>,
+    parse_tag!(ParseTag::DeleteAclsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> filter_responses: length_count!(be_i32, apply!(parse_delete_acls_filter_status, api_version))
+         >> (DeleteAclsResponse {
+                header,
+                throttle_time,
+                filter_responses,
+            })
+        )
+    )
+);
+
+named_args!(parse_delete_acls_filter_status(api_version: ApiVersion)<DeleteAclsFilterStatus>,
+    parse_tag!(ParseTag::DeleteAclsFilterStatus,
+        do_parse!(
+            error_code: be_i16
+         >> error_message: parse_opt_string
+         >> matching_acls: length_count!(be_i32, apply!(parse_delete_acls_matching_acl, api_version))
+         >> (DeleteAclsFilterStatus {
+                error_code,
+                error_message,
+                matching_acls,
+            })
+        )
+    )
+);
+
+named_args!(parse_delete_acls_matching_acl(api_version: ApiVersion)<DeleteAclsMatchingAcl>,
+    parse_tag!(ParseTag::DeleteAclsMatchingAcl,
+        do_parse!(
+            error_code: be_i16
+         >> error_message: parse_opt_string
+         >> pattern: apply!(parse_resource_pattern, api_version)
+         >> entry: parse_access_control_entry
+         >> (DeleteAclsMatchingAcl {
+                error_code,
+                error_message,
+                acl: AclBinding { pattern, entry },
+            })
+        )
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_describe_acls_request() {
+        let req = DescribeAclsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeAcls as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            filter: AclBindingFilter::any()
+                .with_resource(ResourceType::Topic, "topic")
+                .with_operation(AclOperation::Read),
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 29 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */,
+            2 /* resource_type */, 0, 5, b't', b'o', b'p', b'i', b'c' /* resource_name */, 1 /* pattern_type */,
+            255, 255 /* principal */, 255, 255 /* host */, 3 /* operation */, 1 /* permission_type */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_describe_acls_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */, 0,
+            0 /* error_code */, 255, 255 /* error_message */, /* resources */ 0, 0, 0, 1, 2 /* resource_type */,
+            0, 5, b't', b'o', b'p', b'i', b'c' /* resource_name */, /* acls */ 0, 0, 0, 1, 0, 6, b'U', b's', b'e',
+            b'r', b':', b'a' /* principal */, 0, 1, b'*' /* host */, 3 /* operation */, 3 /* permission_type */,
+        ];
+
+        let res = DescribeAclsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            error_code: 0,
+            error_message: None,
+            resources: vec![
+                DescribeAclsResource {
+                    pattern: ResourcePattern::topic("topic"),
+                    acls: vec![AccessControlEntry::allow("User:a", AclOperation::Read)],
+                },
+            ],
+        };
+
+        assert_eq!(parse_describe_acls_response(data.as_slice(), 0), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_encode_create_acls_request() {
+        let req = CreateAclsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreateAcls as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            creations: vec![
+                AclBinding::new(
+                    ResourcePattern::new(ResourceType::Topic, "foo", PatternType::Prefixed),
+                    AccessControlEntry::deny("User:a", AclOperation::Write),
+                ),
+            ],
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 30 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */,
+            /* creations */ 0, 0, 0, 1, 2 /* resource_type */, 0, 3, b'f', b'o', b'o' /* resource_name */,
+            4 /* pattern_type */, 0, 6, b'U', b's', b'e', b'r', b':', b'a' /* principal */, 0, 1, b'*' /* host */,
+            4 /* operation */, 2 /* permission_type */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_delete_acls_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */,
+            /* filter_responses */ 0, 0, 0, 1, 0, 0 /* error_code */, 255, 255 /* error_message */,
+            /* matching_acls */ 0, 0, 0, 1, 0, 0 /* error_code */, 255, 255 /* error_message */,
+            3 /* resource_type */, 0, 1, b'g' /* resource_name */, 3 /* pattern_type */, 0, 6, b'U', b's', b'e',
+            b'r', b':', b'a' /* principal */, 0, 1, b'*' /* host */, 3 /* operation */, 3 /* permission_type */,
+        ];
+
+        let res = DeleteAclsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            filter_responses: vec![
+                DeleteAclsFilterStatus {
+                    error_code: 0,
+                    error_message: None,
+                    matching_acls: vec![
+                        DeleteAclsMatchingAcl {
+                            error_code: 0,
+                            error_message: None,
+                            acl: AclBinding::new(
+                                ResourcePattern::group("g"),
+                                AccessControlEntry::allow("User:a", AclOperation::Read),
+                            ),
+                        },
+                    ],
+                },
+            ],
+        };
+
+        assert_eq!(parse_delete_acls_response(data.as_slice(), 1), IResult::Done(&[][..], res));
+    }
+}
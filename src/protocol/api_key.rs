@@ -1,5 +1,7 @@
 use std::mem;
 
+use protocol::ApiVersion;
+
 /// This is a numeric id for the API being invoked (i.e. is it a metadata request, a produce
 /// request, a fetch request, etc).
 ///
@@ -114,6 +116,83 @@ impl ApiKeys {
             ApiKeys::DeleteGroups => "DeleteGroups",
         }
     }
+
+    /// Gets the first flexible version of the API (KIP-482),
+    /// which uses the compact types and tagged fields, or `None` if the API has no flexible version.
+    pub fn first_flexible_version(&self) -> Option<ApiVersion> {
+        match *self {
+            ApiKeys::Produce => Some(9),
+            ApiKeys::Fetch => Some(12),
+            ApiKeys::ListOffsets => Some(6),
+            ApiKeys::Metadata => Some(9),
+            ApiKeys::LeaderAndIsr => Some(4),
+            ApiKeys::StopReplica => Some(2),
+            ApiKeys::UpdateMetadata => Some(6),
+            ApiKeys::ControlledShutdown => Some(3),
+            ApiKeys::OffsetCommit => Some(8),
+            ApiKeys::OffsetFetch => Some(6),
+            ApiKeys::GroupCoordinator => Some(3),
+            ApiKeys::JoinGroup => Some(6),
+            ApiKeys::Heartbeat => Some(4),
+            ApiKeys::LeaveGroup => Some(4),
+            ApiKeys::SyncGroup => Some(4),
+            ApiKeys::DescribeGroups => Some(5),
+            ApiKeys::ListGroups => Some(3),
+            ApiKeys::SaslHandshake => None,
+            ApiKeys::ApiVersions => Some(3),
+            ApiKeys::CreateTopics => Some(5),
+            ApiKeys::DeleteTopics => Some(4),
+            ApiKeys::DeleteRecords => Some(2),
+            ApiKeys::InitProducerId => Some(2),
+            ApiKeys::OffsetForLeaderEpoch => Some(4),
+            ApiKeys::AddPartitionsToTxn => Some(3),
+            ApiKeys::AddOffsetsToTxn => Some(3),
+            ApiKeys::EndTxn => Some(3),
+            ApiKeys::WriteTxnMarkers => Some(1),
+            ApiKeys::TxnOffsetCommit => Some(3),
+            ApiKeys::DescribeAcls => Some(2),
+            ApiKeys::CreateAcls => Some(2),
+            ApiKeys::DeleteAcls => Some(2),
+            ApiKeys::DescribeConfigs => Some(4),
+            ApiKeys::AlterConfigs => Some(2),
+            ApiKeys::AlterReplicaLogDirs => Some(2),
+            ApiKeys::DescribeLogDirs => Some(2),
+            ApiKeys::SaslAuthenticate => Some(2),
+            ApiKeys::CreatePartitions => Some(2),
+            ApiKeys::CreateDelegationToken => Some(2),
+            ApiKeys::RenewDelegationToken => Some(2),
+            ApiKeys::ExpireDelegationToken => Some(2),
+            ApiKeys::DescribeDelegationToken => Some(2),
+            ApiKeys::DeleteGroups => Some(2),
+        }
+    }
+
+    /// Whether the version of the API is flexible.
+    pub fn is_flexible(&self, api_version: ApiVersion) -> bool {
+        self.first_flexible_version()
+            .map_or(false, |flexible_version| api_version >= flexible_version)
+    }
+
+    /// Gets the version of the request header, v2 adds the tagged fields for the flexible versions.
+    pub fn request_header_version(&self, api_version: ApiVersion) -> ApiVersion {
+        if self.is_flexible(api_version) {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Gets the version of the response header, v1 adds the tagged fields for the flexible versions.
+    ///
+    /// The `ApiVersions` response always uses the v0 header,
+    /// so the client can parse it even if the broker doesn't support the request version.
+    pub fn response_header_version(&self, api_version: ApiVersion) -> ApiVersion {
+        if *self != ApiKeys::ApiVersions && self.is_flexible(api_version) {
+            1
+        } else {
+            0
+        }
+    }
 }
 
 impl From<ApiKey> for ApiKeys {
@@ -121,3 +200,23 @@ impl From<ApiKey> for ApiKeys {
         unsafe { mem::transmute(v) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flexible_versions() {
+        assert!(!ApiKeys::Fetch.is_flexible(11));
+        assert!(ApiKeys::Fetch.is_flexible(12));
+        assert!(!ApiKeys::SaslHandshake.is_flexible(i16::max_value()));
+
+        assert_eq!(ApiKeys::Metadata.request_header_version(8), 1);
+        assert_eq!(ApiKeys::Metadata.request_header_version(9), 2);
+        assert_eq!(ApiKeys::Metadata.response_header_version(8), 0);
+        assert_eq!(ApiKeys::Metadata.response_header_version(9), 1);
+
+        assert_eq!(ApiKeys::ApiVersions.request_header_version(3), 2);
+        assert_eq!(ApiKeys::ApiVersions.response_header_version(3), 0);
+    }
+}
@@ -101,32 +101,132 @@ lazy_static! {
         UsableApiVersion {
             api_key: ApiKeys::Produce,
             min_version: 0,
-            max_version: 1,
+            max_version: 5,
         },
         UsableApiVersion {
             api_key: ApiKeys::Fetch,
             min_version: 0,
-            max_version: 3,
+            max_version: 11,
         },
         UsableApiVersion {
             api_key: ApiKeys::ListOffsets,
             min_version: 0,
+            max_version: 4,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::Metadata,
+            min_version: 0,
             max_version: 1,
         },
         UsableApiVersion {
             api_key: ApiKeys::OffsetCommit,
             min_version: 0,
-            max_version: 2,
+            max_version: 6,
         },
         UsableApiVersion {
             api_key: ApiKeys::OffsetFetch,
             min_version: 0,
+            max_version: 5,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::GroupCoordinator,
+            min_version: 0,
             max_version: 1,
         },
         UsableApiVersion {
             api_key: ApiKeys::JoinGroup,
             min_version: 0,
             max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslHandshake,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateTopics,
+            min_version: 0,
+            max_version: 2,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteTopics,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteRecords,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::InitProducerId,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddPartitionsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AddOffsetsToTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::EndTxn,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::TxnOffsetCommit,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreateAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteAcls,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeConfigs,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::AlterConfigs,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::SaslAuthenticate,
+            min_version: 0,
+            max_version: 1,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::CreatePartitions,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DeleteGroups,
+            min_version: 0,
+            max_version: 0,
+        },
+        UsableApiVersion {
+            api_key: ApiKeys::DescribeLogDirs,
+            min_version: 0,
+            max_version: 2,
         }
     ]);
 }
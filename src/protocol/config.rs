@@ -0,0 +1,561 @@
+use std::borrow::Cow;
+
+use bytes::{BufMut, ByteOrder, BytesMut};
+
+use nom::{IResult, be_i16, be_i32, be_i8};
+
+use errors::Result;
+use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, ConfigEntry, Encodable, ErrorCode,
+               ParseTag, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE, STR_LEN_SIZE};
+
+const RESOURCE_TYPE_SIZE: usize = 1;
+const INCLUDE_SYNONYMS_SIZE: usize = 1;
+const VALIDATE_ONLY_SIZE: usize = 1;
+
+/// The type of the resource which the configuration belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigResourceType {
+    Unknown = 0,
+    Any = 1,
+    Topic = 2,
+    Group = 3,
+    Broker = 4,
+}
+
+impl From<i8> for ConfigResourceType {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => ConfigResourceType::Any,
+            2 => ConfigResourceType::Topic,
+            3 => ConfigResourceType::Group,
+            4 => ConfigResourceType::Broker,
+            _ => ConfigResourceType::Unknown,
+        }
+    }
+}
+
+/// The source of the configuration value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfigSource {
+    Unknown = 0,
+    /// Dynamic topic config that is configured for a specific topic.
+    DynamicTopicConfig = 1,
+    /// Dynamic broker config that is configured for a specific broker.
+    DynamicBrokerConfig = 2,
+    /// Dynamic broker config that is configured as default for all brokers in the cluster.
+    DynamicDefaultBrokerConfig = 3,
+    /// Static broker config provided as broker properties at start up (e.g. server.properties file).
+    StaticBrokerConfig = 4,
+    /// Built-in default configuration for configs that have a default value.
+    DefaultConfig = 5,
+}
+
+impl From<i8> for ConfigSource {
+    fn from(v: i8) -> Self {
+        match v {
+            1 => ConfigSource::DynamicTopicConfig,
+            2 => ConfigSource::DynamicBrokerConfig,
+            3 => ConfigSource::DynamicDefaultBrokerConfig,
+            4 => ConfigSource::StaticBrokerConfig,
+            5 => ConfigSource::DefaultConfig,
+            _ => ConfigSource::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeConfigsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The resources whose configurations we want to describe.
+    pub resources: Vec<DescribeConfigsResource<'a>>,
+    /// Whether to include the synonyms of the configurations (since v1).
+    pub include_synonyms: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeConfigsResource<'a> {
+    /// The resource type.
+    pub resource_type: ConfigResourceType,
+    /// The resource name.
+    pub resource_name: Cow<'a, str>,
+    /// The configuration keys to list, or `None` to list all configuration keys.
+    pub config_names: Option<Vec<Cow<'a, str>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeConfigsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results for each resource.
+    pub resources: Vec<DescribeConfigsResourceStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeConfigsResourceStatus {
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+    /// The resource type.
+    pub resource_type: ConfigResourceType,
+    /// The resource name.
+    pub resource_name: String,
+    /// Each listed configuration.
+    pub config_entries: Vec<DescribeConfigsEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DescribeConfigsEntry {
+    /// The configuration name.
+    pub config_name: String,
+    /// The configuration value.
+    pub config_value: Option<String>,
+    /// True if the configuration is read-only.
+    pub read_only: bool,
+    /// The configuration source.
+    ///
+    /// Before v1, the broker only tells whether the configuration is set to the default value.
+    pub config_source: ConfigSource,
+    /// True if this configuration is sensitive.
+    pub is_sensitive: bool,
+    /// The synonyms for this configuration key (since v1).
+    pub config_synonyms: Vec<ConfigSynonym>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigSynonym {
+    /// The synonym name.
+    pub config_name: String,
+    /// The synonym value.
+    pub config_value: Option<String>,
+    /// The synonym source.
+    pub config_source: ConfigSource,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterConfigsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The updates for each resource.
+    pub resources: Vec<AlterConfigsResource<'a>>,
+    /// Validate the request without actually altering the configurations.
+    pub validate_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterConfigsResource<'a> {
+    /// The resource type.
+    pub resource_type: ConfigResourceType,
+    /// The resource name.
+    pub resource_name: Cow<'a, str>,
+    /// The configurations, which replace all the existing dynamic configurations of the resource.
+    pub config_entries: Vec<ConfigEntry<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterConfigsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results for each resource.
+    pub resources: Vec<AlterConfigsResourceStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterConfigsResourceStatus {
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+    /// The resource type.
+    pub resource_type: ConfigResourceType,
+    /// The resource name.
+    pub resource_name: String,
+}
+
+impl<'a> Record for DescribeConfigsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.resources.iter().fold(ARRAY_LEN_SIZE, |size, resource| {
+            size + RESOURCE_TYPE_SIZE + STR_LEN_SIZE + resource.resource_name.len()
+                + resource.config_names.as_ref().map_or(ARRAY_LEN_SIZE, |config_names| {
+                    config_names.iter().fold(ARRAY_LEN_SIZE, |size, config_name| {
+                        size + STR_LEN_SIZE + config_name.len()
+                    })
+                })
+        }) + if api_version > 0 { INCLUDE_SYNONYMS_SIZE } else { 0 }
+    }
+}
+
+impl<'a> Encodable for DescribeConfigsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.resources, |buf, resource| {
+            buf.put_i8(resource.resource_type as i8);
+            buf.put_str::<T, _>(Some(resource.resource_name.as_ref()))?;
+
+            if let Some(ref config_names) = resource.config_names {
+                buf.put_array::<T, _, _>(config_names, |buf, config_name| {
+                    buf.put_str::<T, _>(Some(config_name.as_ref()))
+                })
+            } else {
+                buf.put_i32::<T>(-1);
+                Ok(())
+            }
+        })?;
+
+        if self.header.api_version > 0 {
+            dst.put_i8(self.include_synonyms as i8);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Record for AlterConfigsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.resources.iter().fold(ARRAY_LEN_SIZE, |size, resource| {
+            size + RESOURCE_TYPE_SIZE + STR_LEN_SIZE + resource.resource_name.len()
+                + resource.config_entries.iter().fold(ARRAY_LEN_SIZE, |size, config| {
+                    size + STR_LEN_SIZE + config.config_name.len() + STR_LEN_SIZE
+                        + config.config_value.as_ref().map_or(0, |s| s.len())
+                })
+        }) + VALIDATE_ONLY_SIZE
+    }
+}
+
+impl<'a> Encodable for AlterConfigsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.resources, |buf, resource| {
+            buf.put_i8(resource.resource_type as i8);
+            buf.put_str::<T, _>(Some(resource.resource_name.as_ref()))?;
+            buf.put_array::<T, _, _>(&resource.config_entries, |buf, config| {
+                buf.put_str::<T, _>(Some(config.config_name.as_ref()))?;
+                buf.put_str::<T, _>(config.config_value.as_ref())
+            })
+        })?;
+        dst.put_i8(self.validate_only as i8);
+
+        Ok(())
+    }
+}
+
+impl DescribeConfigsResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_describe_configs_response(buf, api_version)
+    }
+}
+
+named_args!(parse_describe_configs_response(api_version: ApiVersion)<DescribeConfigsResponse>,
+    parse_tag!(ParseTag::DescribeConfigsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> resources: length_count!(be_i32, apply!(parse_describe_configs_resource_status, api_version))
+         >> (DescribeConfigsResponse {
+                header,
+                throttle_time,
+                resources,
+            })
+        )
+    )
+);
+
+named_args!(parse_describe_configs_resource_status(api_version: ApiVersion)<DescribeConfigsResourceStatus>,
+    parse_tag!(ParseTag::DescribeConfigsResourceStatus,
+        do_parse!(
+            error_code: be_i16
+         >> error_message: parse_opt_string
+         >> resource_type: be_i8
+         >> resource_name: parse_string
+         >> config_entries: length_count!(be_i32, apply!(parse_describe_configs_entry, api_version))
+         >> (DescribeConfigsResourceStatus {
+                error_code,
+                error_message,
+                resource_type: resource_type.into(),
+                resource_name,
+                config_entries,
+            })
+        )
+    )
+);
+
+named_args!(parse_describe_configs_entry(api_version: ApiVersion)<DescribeConfigsEntry>,
+    parse_tag!(ParseTag::DescribeConfigsEntry,
+        do_parse!(
+            config_name: parse_string
+         >> config_value: parse_opt_string
+         >> read_only: be_i8
+         >> is_default: cond!(api_version == 0, be_i8)
+         >> config_source: cond!(api_version > 0, be_i8)
+         >> is_sensitive: be_i8
+         >> config_synonyms: cond!(api_version > 0, length_count!(be_i32, parse_config_synonym))
+         >> (DescribeConfigsEntry {
+                config_name,
+                config_value,
+                read_only: read_only != 0,
+                config_source: match (is_default, config_source) {
+                    (_, Some(config_source)) => config_source.into(),
+                    (Some(is_default), _) if is_default != 0 => ConfigSource::DefaultConfig,
+                    _ => ConfigSource::Unknown,
+                },
+                is_sensitive: is_sensitive != 0,
+                config_synonyms: config_synonyms.unwrap_or_default(),
+            })
+        )
+    )
+);
+
+named!(parse_config_synonym<ConfigSynonym>,
+    parse_tag!(ParseTag::ConfigSynonym,
+        do_parse!(
+            config_name: parse_string
+         >> config_value: parse_opt_string
+         >> config_source: be_i8
+         >> (ConfigSynonym {
+                config_name,
+                config_value,
+                config_source: config_source.into(),
+            })
+        )
+    )
+);
+
+impl AlterConfigsResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_alter_configs_response(buf)
+    }
+}
+
+named!(parse_alter_configs_response<AlterConfigsResponse>,
+    parse_tag!(ParseTag::AlterConfigsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> resources: length_count!(be_i32, parse_alter_configs_resource_status)
+         >> (AlterConfigsResponse {
+                header,
+                throttle_time,
+                resources,
+            })
+        )
+    )
+);
+
+named!(parse_alter_configs_resource_status<AlterConfigsResourceStatus>,
+    parse_tag!(ParseTag::AlterConfigsResourceStatus,
+        do_parse!(
+            error_code: be_i16
+         >> error_message: parse_opt_string
+         >> resource_type: be_i8
+         >> resource_name: parse_string
+         >> (AlterConfigsResourceStatus {
+                error_code,
+                error_message,
+                resource_type: resource_type.into(),
+                resource_name,
+            })
+        )
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_describe_configs_request() {
+        let req = DescribeConfigsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DescribeConfigs as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            resources: vec![
+                DescribeConfigsResource {
+                    resource_type: ConfigResourceType::Topic,
+                    resource_name: "topic".into(),
+                    config_names: Some(vec!["key".into()]),
+                },
+                DescribeConfigsResource {
+                    resource_type: ConfigResourceType::Broker,
+                    resource_name: "1".into(),
+                    config_names: None,
+                },
+            ],
+            include_synonyms: true,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 32 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, /* resources */ 0,
+            0, 0, 2, 2 /* resource_type */, 0, 5, b't', b'o', b'p', b'i', b'c' /* resource_name */, 0, 0, 0, 1, 0,
+            3, b'k', b'e', b'y' /* config_names */, 4 /* resource_type */, 0, 1, b'1' /* resource_name */, 255,
+            255, 255, 255 /* config_names */, 1 /* include_synonyms */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_describe_configs_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* resources */ 0, 0, 0, 1, 0, 0 /* error_code */, 255, 255 /* error_message */, 2 /* resource_type */,
+            0, 5, b't', b'o', b'p', b'i', b'c' /* resource_name */, /* config_entries */ 0, 0, 0, 1, 0, 3, b'k',
+            b'e', b'y' /* config_name */, 0, 5, b'v', b'a', b'l', b'u', b'e' /* config_value */, 0 /* read_only */,
+            1 /* config_source */, 0 /* is_sensitive */, /* config_synonyms */ 0, 0, 0, 1, 0, 3, b'k', b'e',
+            b'y' /* config_name */, 0, 5, b'v', b'a', b'l', b'u', b'e' /* config_value */, 1 /* config_source */,
+        ];
+
+        let res = DescribeConfigsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 1,
+            resources: vec![
+                DescribeConfigsResourceStatus {
+                    error_code: 0,
+                    error_message: None,
+                    resource_type: ConfigResourceType::Topic,
+                    resource_name: "topic".to_owned(),
+                    config_entries: vec![
+                        DescribeConfigsEntry {
+                            config_name: "key".to_owned(),
+                            config_value: Some("value".to_owned()),
+                            read_only: false,
+                            config_source: ConfigSource::DynamicTopicConfig,
+                            is_sensitive: false,
+                            config_synonyms: vec![
+                                ConfigSynonym {
+                                    config_name: "key".to_owned(),
+                                    config_value: Some("value".to_owned()),
+                                    config_source: ConfigSource::DynamicTopicConfig,
+                                },
+                            ],
+                        },
+                    ],
+                },
+            ],
+        };
+
+        assert_eq!(parse_describe_configs_response(data.as_slice(), 1), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_parse_describe_configs_response_v0() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */,
+            /* resources */ 0, 0, 0, 1, 0, 0 /* error_code */, 255, 255 /* error_message */, 4 /* resource_type */,
+            0, 1, b'1' /* resource_name */, /* config_entries */ 0, 0, 0, 1, 0, 3, b'k', b'e',
+            b'y' /* config_name */, 255, 255 /* config_value */, 1 /* read_only */, 1 /* is_default */,
+            1 /* is_sensitive */,
+        ];
+
+        let res = DescribeConfigsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            resources: vec![
+                DescribeConfigsResourceStatus {
+                    error_code: 0,
+                    error_message: None,
+                    resource_type: ConfigResourceType::Broker,
+                    resource_name: "1".to_owned(),
+                    config_entries: vec![
+                        DescribeConfigsEntry {
+                            config_name: "key".to_owned(),
+                            config_value: None,
+                            read_only: true,
+                            config_source: ConfigSource::DefaultConfig,
+                            is_sensitive: true,
+                            config_synonyms: vec![],
+                        },
+                    ],
+                },
+            ],
+        };
+
+        assert_eq!(parse_describe_configs_response(data.as_slice(), 0), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_encode_alter_configs_request() {
+        let req = AlterConfigsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AlterConfigs as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            resources: vec![
+                AlterConfigsResource {
+                    resource_type: ConfigResourceType::Topic,
+                    resource_name: "topic".into(),
+                    config_entries: vec![
+                        ConfigEntry {
+                            config_name: "key".into(),
+                            config_value: Some("value".into()),
+                        },
+                    ],
+                },
+            ],
+            validate_only: false,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 33 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, /* resources */ 0,
+            0, 0, 1, 2 /* resource_type */, 0, 5, b't', b'o', b'p', b'i', b'c' /* resource_name */,
+            /* config_entries */ 0, 0, 0, 1, 0, 3, b'k', b'e', b'y' /* config_name */, 0, 5, b'v', b'a', b'l', b'u',
+            b'e' /* config_value */, 0 /* validate_only */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_alter_configs_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */,
+            /* resources */ 0, 0, 0, 1, 0, 40 /* error_code */, 0, 7, b'i', b'n', b'v', b'a', b'l', b'i',
+            b'd' /* error_message */, 2 /* resource_type */, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* resource_name */,
+        ];
+
+        let res = AlterConfigsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            resources: vec![
+                AlterConfigsResourceStatus {
+                    error_code: 40,
+                    error_message: Some("invalid".to_owned()),
+                    resource_type: ConfigResourceType::Topic,
+                    resource_name: "topic".to_owned(),
+                },
+            ],
+        };
+
+        assert_eq!(parse_alter_configs_response(data.as_slice()), IResult::Done(&[][..], res));
+    }
+}
@@ -2,7 +2,7 @@ use std::i16;
 use std::i32;
 use std::str;
 
-use bytes::{BufMut, ByteOrder, BytesMut};
+use bytes::{BufMut, ByteOrder, Bytes, BytesMut};
 
 use errors::{ErrorKind, Result};
 
@@ -13,6 +13,69 @@ pub const REPLICA_ID_SIZE: usize = 4;
 pub const PARTITION_ID_SIZE: usize = 4;
 pub const TIMESTAMP_SIZE: usize = 8;
 pub const OFFSET_SIZE: usize = 8;
+pub const ISOLATION_LEVEL_SIZE: usize = 1;
+pub const LEADER_EPOCH_SIZE: usize = 4;
+
+/// The size of a zig-zag encoded variable-length int.
+pub fn varint_size(v: i32) -> usize {
+    let mut v = ((v << 1) ^ (v >> 31)) as u32;
+    let mut size = 1;
+    while (v & !0x7F) != 0 {
+        size += 1;
+        v >>= 7;
+    }
+    size
+}
+
+/// The size of a zig-zag encoded variable-length long.
+pub fn varlong_size(v: i64) -> usize {
+    let mut v = ((v << 1) ^ (v >> 63)) as u64;
+    let mut size = 1;
+    while (v & !0x7F) != 0 {
+        size += 1;
+        v >>= 7;
+    }
+    size
+}
+
+/// The size of an unsigned variable-length int.
+pub fn uvarint_size(mut v: u32) -> usize {
+    let mut size = 1;
+    while (v & !0x7F) != 0 {
+        size += 1;
+        v >>= 7;
+    }
+    size
+}
+
+/// The size of a compact nullable string or bytes, which length is encoded as an unsigned varint of `N+1`.
+pub fn compact_bytes_size<D: AsRef<[u8]>>(d: Option<D>) -> usize {
+    d.as_ref().map_or(1, |v| uvarint_size(v.as_ref().len() as u32 + 1) + v.as_ref().len())
+}
+
+/// The size of a compact array with the number of elements, excluding the elements.
+pub fn compact_array_size(len: usize) -> usize {
+    uvarint_size(len as u32 + 1)
+}
+
+/// The size of the tagged fields section.
+pub fn tagged_fields_size(fields: &[TaggedField]) -> usize {
+    fields.iter().fold(uvarint_size(fields.len() as u32), |size, field| {
+        size + uvarint_size(field.tag) + uvarint_size(field.data.len() as u32) + field.data.len()
+    })
+}
+
+/// A tagged field of the flexible versions (KIP-482).
+///
+/// The unknown tagged fields are skipped by the receiver,
+/// so new optional fields can be added without bumping the API version.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaggedField {
+    /// The tag of the field, which is unique in the tagged fields section.
+    pub tag: u32,
+    /// The raw data of the field.
+    pub data: Bytes,
+}
 
 pub trait Encodable {
     fn encode<T: ByteOrder>(&self, buf: &mut BytesMut) -> Result<()>;
@@ -76,6 +139,119 @@ pub trait WriteExt: BufMut + Sized {
 
         Ok(())
     }
+
+    fn put_varint(&mut self, v: i32) {
+        let mut v = ((v << 1) ^ (v >> 31)) as u32;
+        while (v & !0x7F) != 0 {
+            self.put_u8(((v & 0x7f) | 0x80) as u8);
+            v >>= 7;
+        }
+        self.put_u8(v as u8);
+    }
+
+    fn put_varlong(&mut self, v: i64) {
+        let mut v = ((v << 1) ^ (v >> 63)) as u64;
+        while (v & !0x7F) != 0 {
+            self.put_u8(((v & 0x7f) | 0x80) as u8);
+            v >>= 7;
+        }
+        self.put_u8(v as u8);
+    }
+
+    fn put_varbytes<D: AsRef<[u8]>>(&mut self, d: Option<D>) -> Result<()> {
+        match d.as_ref() {
+            Some(v) if v.as_ref().len() > i32::MAX as usize => {
+                bail!(ErrorKind::EncodeError("bytes exceeds the maximum size."))
+            }
+            Some(v) => {
+                self.put_varint(v.as_ref().len() as i32);
+
+                if !v.as_ref().is_empty() {
+                    self.put_slice(v.as_ref());
+                }
+            }
+            _ => {
+                self.put_varint(-1);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn put_uvarint(&mut self, mut v: u32) {
+        while (v & !0x7F) != 0 {
+            self.put_u8(((v & 0x7f) | 0x80) as u8);
+            v >>= 7;
+        }
+        self.put_u8(v as u8);
+    }
+
+    fn put_compact_str<S: AsRef<str>>(&mut self, s: Option<S>) -> Result<()> {
+        match s.as_ref() {
+            Some(v) if v.as_ref().len() > i16::MAX as usize => {
+                bail!(ErrorKind::EncodeError("string exceeds the maximum size."))
+            }
+            Some(v) => self.put_compact_bytes(Some(v.as_ref().as_bytes())),
+            None => self.put_compact_bytes::<&[u8]>(None),
+        }
+    }
+
+    fn put_compact_bytes<D: AsRef<[u8]>>(&mut self, d: Option<D>) -> Result<()> {
+        match d.as_ref() {
+            Some(v) if v.as_ref().len() >= i32::MAX as usize => {
+                bail!(ErrorKind::EncodeError("bytes exceeds the maximum size."))
+            }
+            Some(v) => {
+                self.put_uvarint(v.as_ref().len() as u32 + 1);
+
+                if !v.as_ref().is_empty() {
+                    self.put_slice(v.as_ref());
+                }
+            }
+            None => {
+                self.put_uvarint(0);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn put_compact_array<E, F>(&mut self, items: &[E], mut callback: F) -> Result<()>
+    where
+        F: FnMut(&mut Self, &E) -> Result<()>,
+    {
+        if items.len() >= i32::MAX as usize {
+            bail!(ErrorKind::EncodeError("array exceeds the maximum size."))
+        }
+
+        self.put_uvarint(items.len() as u32 + 1);
+
+        for item in items {
+            callback(self, item)?;
+        }
+
+        Ok(())
+    }
+
+    fn put_tagged_fields(&mut self, fields: &[TaggedField]) -> Result<()> {
+        self.put_uvarint(fields.len() as u32);
+
+        let mut last_tag = None;
+
+        for field in fields {
+            if last_tag.map_or(false, |tag| tag >= field.tag) {
+                bail!(ErrorKind::EncodeError("tagged fields must be sorted by the unique tag."))
+            }
+
+            self.put_uvarint(field.tag);
+            self.put_uvarint(field.data.len() as u32);
+            self.put_slice(&field.data);
+
+            last_tag = Some(field.tag);
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: BufMut> WriteExt for T {}
@@ -161,4 +337,111 @@ mod tests {
 
         assert!(buf.put_bytes::<BigEndian, _>(Some(s)).err().is_some());
     }
+
+    #[test]
+    fn varint() {
+        let mut buf = vec![];
+
+        for &(v, ref encoded) in &[
+            (0, vec![0]),
+            (-1, vec![1]),
+            (1, vec![2]),
+            (63, vec![126]),
+            (-64, vec![127]),
+            (64, vec![128, 1]),
+            (300, vec![216, 4]),
+            (i32::MAX, vec![254, 255, 255, 255, 15]),
+            (i32::MIN, vec![255, 255, 255, 255, 15]),
+        ] {
+            buf.clear();
+            buf.put_varint(v);
+
+            assert_eq!(&buf, encoded);
+            assert_eq!(varint_size(v), encoded.len());
+        }
+    }
+
+    #[test]
+    fn varlong() {
+        let mut buf = vec![];
+
+        buf.put_varlong(-1);
+        assert_eq!(buf.as_slice(), &[1]);
+        assert_eq!(varlong_size(-1), 1);
+
+        buf.clear();
+        buf.put_varlong(1_500_000_000_000);
+        assert_eq!(buf.len(), varlong_size(1_500_000_000_000));
+
+        buf.clear();
+        buf.put_varbytes::<&[u8]>(None).unwrap();
+        assert_eq!(buf.as_slice(), &[1]);
+
+        buf.clear();
+        buf.put_varbytes(Some(&b"test"[..])).unwrap();
+        assert_eq!(buf.as_slice(), &[8, 116, 101, 115, 116]);
+    }
+
+    #[test]
+    fn compact_types() {
+        let mut buf = vec![];
+
+        for &(v, ref encoded) in &[(0, vec![0]), (127, vec![127]), (128, vec![128, 1]), (300, vec![172, 2])] {
+            buf.clear();
+            buf.put_uvarint(v);
+
+            assert_eq!(&buf, encoded);
+            assert_eq!(uvarint_size(v), encoded.len());
+        }
+
+        buf.clear();
+        buf.put_compact_str::<&str>(None).unwrap();
+        assert_eq!(buf.as_slice(), &[0]);
+        assert_eq!(compact_bytes_size::<&[u8]>(None), 1);
+
+        buf.clear();
+        buf.put_compact_str(Some("")).unwrap();
+        assert_eq!(buf.as_slice(), &[1]);
+
+        buf.clear();
+        buf.put_compact_str(Some("test")).unwrap();
+        assert_eq!(buf.as_slice(), &[5, 116, 101, 115, 116]);
+        assert_eq!(compact_bytes_size(Some("test")), buf.len());
+
+        buf.clear();
+        buf.put_compact_array(&[1u8, 2], |buf, &v| {
+            buf.put_u8(v);
+            Ok(())
+        }).unwrap();
+        assert_eq!(buf.as_slice(), &[3, 1, 2]);
+        assert_eq!(compact_array_size(2), 1);
+    }
+
+    #[test]
+    fn tagged_fields() {
+        let mut buf = vec![];
+
+        buf.put_tagged_fields(&[]).unwrap();
+        assert_eq!(buf.as_slice(), &[0]);
+        assert_eq!(tagged_fields_size(&[]), 1);
+
+        let fields = vec![
+            TaggedField {
+                tag: 0,
+                data: Bytes::from(&b"a"[..]),
+            },
+            TaggedField {
+                tag: 200,
+                data: Bytes::from(&b"bc"[..]),
+            },
+        ];
+
+        buf.clear();
+        buf.put_tagged_fields(&fields).unwrap();
+        assert_eq!(buf.as_slice(), &[2, 0, 1, b'a', 200, 1, 2, b'b', b'c']);
+        assert_eq!(tagged_fields_size(&fields), buf.len());
+
+        buf.clear();
+        assert!(buf.put_tagged_fields(&[fields[1].clone(), fields[0].clone()]).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::i32;
 
 use bytes::{BufMut, ByteOrder, BytesMut};
@@ -6,17 +7,34 @@ use bytes::{BufMut, ByteOrder, BytesMut};
 use nom::{IResult, be_i16, be_i32, be_i64};
 
 use errors::Result;
-use protocol::{parse_message_set, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, MessageSet,
-               Offset, ParseTag, PartitionId, Record, ReplicaId, RequestHeader, ResponseHeader, WriteExt,
-               ARRAY_LEN_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE, REPLICA_ID_SIZE, STR_LEN_SIZE};
+use protocol::{parse_message_set, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, IsolationLevel,
+               Message, MessageSet, NodeId, Offset, ParseTag, PartitionId, ProducerId, Record, ReplicaId, RequestHeader,
+               ResponseHeader, WriteExt, ARRAY_LEN_SIZE, ISOLATION_LEVEL_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE,
+               REPLICA_ID_SIZE, STR_LEN_SIZE};
 
 pub const DEFAULT_RESPONSE_MAX_BYTES: i32 = i32::MAX;
 
+/// The log start offset sent by the consumer, which isn't a follower replica.
+pub const INVALID_LOG_START_OFFSET: Offset = -1;
+
+/// The session id used by the full fetch requests, which don't belong to any fetch session.
+pub const INVALID_SESSION_ID: i32 = 0;
+
+/// The epoch of the full fetch request, which creates a new fetch session.
+pub const INITIAL_SESSION_EPOCH: i32 = 0;
+
+/// The epoch of the full fetch request, which closes the fetch session or doesn't use it at all.
+pub const FINAL_SESSION_EPOCH: i32 = -1;
+
 const MAX_WAIT_TIME: usize = 4;
 const MIN_BYTES_SIZE: usize = 4;
 const MAX_BYTES_SIZE: usize = 4;
 const REQUEST_OVERHEAD: usize = REPLICA_ID_SIZE + MAX_WAIT_TIME + MIN_BYTES_SIZE;
 const FETCH_OFFSET_SIZE: usize = OFFSET_SIZE;
+const LOG_START_OFFSET_SIZE: usize = OFFSET_SIZE;
+const SESSION_ID_SIZE: usize = 4;
+const SESSION_EPOCH_SIZE: usize = 4;
+const LEADER_EPOCH_SIZE: usize = 4;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct FetchRequest<'a> {
@@ -37,8 +55,18 @@ pub struct FetchRequest<'a> {
     /// the fetch is larger than this value, the message will still be returned to ensure that
     /// progress can be made.
     pub max_bytes: i32,
+    /// Controls the visibility of the transactional records (since v4).
+    pub isolation_level: IsolationLevel,
+    /// The fetch session id (since v7), or `INVALID_SESSION_ID` for the full fetch request.
+    pub session_id: i32,
+    /// The fetch session epoch (since v7), which is used for ordering requests in a session.
+    pub session_epoch: i32,
     /// Topics to fetch in the order provided.
     pub topics: Vec<FetchTopic<'a>>,
+    /// The partitions to remove from the fetch session (since v7).
+    pub forgotten_topics: Vec<ForgottenTopic<'a>>,
+    /// The rack id of the consumer (since v11), which is used to select the closest replica.
+    pub rack_id: Cow<'a, str>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -49,12 +77,25 @@ pub struct FetchTopic<'a> {
     pub partitions: Vec<FetchPartition>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForgottenTopic<'a> {
+    /// The name of the topic.
+    pub topic_name: Cow<'a, str>,
+    /// The partitions to remove from the fetch session.
+    pub partitions: Vec<PartitionId>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FetchPartition {
     /// The id of the partition the fetch is for.
     pub partition_id: PartitionId,
+    /// The current leader epoch of the partition (since v9), or `NO_PARTITION_LEADER_EPOCH` if unknown.
+    pub current_leader_epoch: i32,
     /// The offset to begin this fetch from.
     pub fetch_offset: Offset,
+    /// The earliest available offset of the follower replica (since v5),
+    /// the consumer should always use `INVALID_LOG_START_OFFSET`.
+    pub log_start_offset: Offset,
     /// The maximum bytes to include in the message set for this partition.
     pub max_bytes: i32,
 }
@@ -62,12 +103,23 @@ pub struct FetchPartition {
 impl<'a> Record for FetchRequest<'a> {
     fn size(&self, api_version: ApiVersion) -> usize {
         self.header.size(api_version) + REQUEST_OVERHEAD + if api_version > 2 { MAX_BYTES_SIZE } else { 0 }
+            + if api_version > 3 { ISOLATION_LEVEL_SIZE } else { 0 }
+            + if api_version > 6 { SESSION_ID_SIZE + SESSION_EPOCH_SIZE } else { 0 }
             + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
                 size + STR_LEN_SIZE + topic.topic_name.len()
                     + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, _| {
-                        size + PARTITION_ID_SIZE + FETCH_OFFSET_SIZE + MAX_BYTES_SIZE
+                        size + PARTITION_ID_SIZE + if api_version > 8 { LEADER_EPOCH_SIZE } else { 0 }
+                            + FETCH_OFFSET_SIZE + if api_version > 4 { LOG_START_OFFSET_SIZE } else { 0 }
+                            + MAX_BYTES_SIZE
                     })
+            }) + if api_version > 6 {
+            self.forgotten_topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+                size + STR_LEN_SIZE + topic.topic_name.len() + ARRAY_LEN_SIZE
+                    + topic.partitions.len() * PARTITION_ID_SIZE
             })
+        } else {
+            0
+        } + if api_version > 10 { STR_LEN_SIZE + self.rack_id.len() } else { 0 }
     }
 }
 
@@ -83,15 +135,40 @@ impl<'a> Encodable for FetchRequest<'a> {
         if api_version > 2 {
             dst.put_i32::<T>(self.max_bytes);
         }
+        if api_version > 3 {
+            dst.put_i8(self.isolation_level as i8);
+        }
+        if api_version > 6 {
+            dst.put_i32::<T>(self.session_id);
+            dst.put_i32::<T>(self.session_epoch);
+        }
         dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
             buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
             buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
                 buf.put_i32::<T>(partition.partition_id);
+                if api_version > 8 {
+                    buf.put_i32::<T>(partition.current_leader_epoch);
+                }
                 buf.put_i64::<T>(partition.fetch_offset);
+                if api_version > 4 {
+                    buf.put_i64::<T>(partition.log_start_offset);
+                }
                 buf.put_i32::<T>(partition.max_bytes);
                 Ok(())
             })
         })?;
+        if api_version > 6 {
+            dst.put_array::<T, _, _>(&self.forgotten_topics, |buf, topic| {
+                buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+                buf.put_array::<T, _, _>(&topic.partitions, |buf, &partition_id| {
+                    buf.put_i32::<T>(partition_id);
+                    Ok(())
+                })
+            })?;
+        }
+        if api_version > 10 {
+            dst.put_str::<T, _>(Some(self.rack_id.as_ref()))?;
+        }
         Ok(())
     }
 }
@@ -102,6 +179,10 @@ pub struct FetchResponse {
     /// Duration in milliseconds for which the request was throttled due to
     /// quota violation.
     pub throttle_time: Option<i32>,
+    /// The top level response error code (since v7).
+    pub error_code: Option<ErrorCode>,
+    /// The fetch session id (since v7), or `INVALID_SESSION_ID` if the broker doesn't create a session.
+    pub session_id: Option<i32>,
     pub topics: Vec<FetchTopicData>,
 }
 
@@ -119,9 +200,89 @@ pub struct FetchPartitionData {
     pub error_code: ErrorCode,
     /// The offset at the end of the log for this partition.
     pub high_watermark: Offset,
+    /// The last stable offset (LSO) of the partition (since v4).
+    ///
+    /// This is the last offset such that the state of all transactional records prior to this offset
+    /// have been decided (committed or aborted).
+    pub last_stable_offset: Option<Offset>,
+    /// The current log start offset of the partition (since v5).
+    pub log_start_offset: Option<Offset>,
+    /// The aborted transactions in the fetched range (since v4),
+    /// or `None` when fetching with `read_uncommitted`.
+    pub aborted_transactions: Option<Vec<AbortedTransaction>>,
+    /// The replica which the consumer should fetch from (since v11), or -1 to fetch from the leader.
+    pub preferred_read_replica: Option<NodeId>,
     pub message_set: MessageSet,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbortedTransaction {
+    /// The producer id associated with the aborted transactions.
+    pub producer_id: ProducerId,
+    /// The first offset in the aborted transaction.
+    pub first_offset: Offset,
+}
+
+impl FetchPartitionData {
+    /// Take the messages visible to the consumer.
+    ///
+    /// The control records of the transaction markers are always skipped,
+    /// and the records of the aborted transactions are skipped when fetching with `read_committed`.
+    pub fn into_messages(self) -> Vec<Message> {
+        let MessageSet { messages, batches } = self.message_set;
+
+        if batches.is_empty() {
+            return messages;
+        }
+
+        let mut aborted_transactions = self.aborted_transactions.unwrap_or_default();
+
+        aborted_transactions.sort_by_key(|txn| txn.first_offset);
+
+        let mut aborted_transactions = aborted_transactions.into_iter().peekable();
+        let mut aborted_producers = HashSet::new();
+        let mut skipped_batches = Vec::new();
+
+        for batch in &batches {
+            while aborted_transactions
+                .peek()
+                .map_or(false, |txn| txn.first_offset <= batch.last_offset())
+            {
+                if let Some(txn) = aborted_transactions.next() {
+                    aborted_producers.insert(txn.producer_id);
+                }
+            }
+
+            if batch.is_control() {
+                // the transaction marker ends the transaction of the producer, committed or aborted
+                aborted_producers.remove(&batch.producer_id);
+            } else if !batch.is_transactional() || !aborted_producers.contains(&batch.producer_id) {
+                continue;
+            }
+
+            skipped_batches.push((batch.base_offset, batch.last_offset()));
+        }
+
+        let mut skipped_batches = skipped_batches.into_iter().peekable();
+
+        messages
+            .into_iter()
+            .filter(|message| {
+                while skipped_batches
+                    .peek()
+                    .map_or(false, |&(_, last_offset)| last_offset < message.offset)
+                {
+                    skipped_batches.next();
+                }
+
+                skipped_batches
+                    .peek()
+                    .map_or(true, |&(base_offset, _)| message.offset < base_offset)
+            })
+            .collect()
+    }
+}
+
 impl FetchResponse {
     pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
         parse_fetch_response(buf, api_version)
@@ -133,10 +294,14 @@ named_args!(parse_fetch_response(api_version: ApiVersion)<FetchResponse>,
         do_parse!(
             header: parse_response_header
          >> throttle_time: cond!(api_version > 0, be_i32)
+         >> error_code: cond!(api_version > 6, be_i16)
+         >> session_id: cond!(api_version > 6, be_i32)
          >> topics: length_count!(be_i32, apply!(parse_fetch_topic_data, api_version))
          >> (FetchResponse {
                 header,
                 throttle_time,
+                error_code,
+                session_id,
                 topics,
             })
         )
@@ -162,17 +327,46 @@ named_args!(parse_fetch_partition_data(api_version: ApiVersion)<FetchPartitionDa
             partition_id: be_i32
          >> error_code: be_i16
          >> high_watermark: be_i64
+         >> last_stable_offset: cond!(api_version > 3, be_i64)
+         >> log_start_offset: cond!(api_version > 4, be_i64)
+         >> aborted_transactions: cond!(api_version > 3, parse_aborted_transactions)
+         >> preferred_read_replica: cond!(api_version > 10, be_i32)
          >> message_set: length_value!(be_i32, apply!(parse_message_set, api_version))
          >> (FetchPartitionData {
                 partition_id,
                 error_code,
                 high_watermark,
+                last_stable_offset,
+                log_start_offset,
+                aborted_transactions: aborted_transactions.and_then(|txns| txns),
+                preferred_read_replica,
                 message_set,
             })
         )
     )
 );
 
+named!(parse_aborted_transactions<Option<Vec<AbortedTransaction>>>,
+    do_parse!(
+        n: be_i32
+     >> txns: cond!(n >= 0, count!(parse_aborted_transaction, n as usize))
+     >> (txns)
+    )
+);
+
+named!(parse_aborted_transaction<AbortedTransaction>,
+    parse_tag!(ParseTag::AbortedTransaction,
+        do_parse!(
+            producer_id: be_i64
+         >> first_offset: be_i64
+         >> (AbortedTransaction {
+                producer_id,
+                first_offset,
+            })
+        )
+    )
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,18 +389,25 @@ mod tests {
             max_wait_time: 3,
             min_bytes: 4,
             max_bytes: 0,
+            isolation_level: IsolationLevel::ReadUncommitted,
+            session_id: INVALID_SESSION_ID,
+            session_epoch: FINAL_SESSION_EPOCH,
             topics: vec![
                 FetchTopic {
                     topic_name: "topic".into(),
                     partitions: vec![
                         FetchPartition {
                             partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
                             max_bytes: 7,
                         },
                     ],
                 },
             ],
+            forgotten_topics: vec![],
+            rack_id: "".into(),
         };
 
         let data = vec![
@@ -242,18 +443,25 @@ mod tests {
             max_wait_time: 3,
             min_bytes: 4,
             max_bytes: 1024,
+            isolation_level: IsolationLevel::ReadUncommitted,
+            session_id: INVALID_SESSION_ID,
+            session_epoch: FINAL_SESSION_EPOCH,
             topics: vec![
                 FetchTopic {
                     topic_name: "topic".into(),
                     partitions: vec![
                         FetchPartition {
                             partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
                             max_bytes: 7,
                         },
                     ],
                 },
             ],
+            forgotten_topics: vec![],
+            rack_id: "".into(),
         };
 
         let data = vec![
@@ -281,6 +489,8 @@ mod tests {
         let response = FetchResponse {
             header: ResponseHeader { correlation_id: 123 },
             throttle_time: None,
+            error_code: None,
+            session_id: None,
             topics: vec![
                 FetchTopicData {
                     topic_name: "topic".to_owned(),
@@ -289,6 +499,10 @@ mod tests {
                             partition_id: 1,
                             error_code: 2,
                             high_watermark: 3,
+                            last_stable_offset: None,
+                            log_start_offset: None,
+                            aborted_transactions: None,
+                            preferred_read_replica: None,
                             message_set: MessageSet {
                                 messages: vec![
                                     Message {
@@ -297,8 +511,10 @@ mod tests {
                                         key: Some(Bytes::from(&b"key"[..])),
                                         value: Some(Bytes::from(&b"value"[..])),
                                         timestamp: None,
+                                        headers: vec![],
                                     },
                                 ],
+                                batches: vec![],
                             },
                         },
                     ],
@@ -331,6 +547,8 @@ mod tests {
         let response = FetchResponse {
             header: ResponseHeader { correlation_id: 123 },
             throttle_time: Some(1),
+            error_code: None,
+            session_id: None,
             topics: vec![
                 FetchTopicData {
                     topic_name: "topic".to_owned(),
@@ -339,6 +557,10 @@ mod tests {
                             partition_id: 1,
                             error_code: 2,
                             high_watermark: 3,
+                            last_stable_offset: None,
+                            log_start_offset: None,
+                            aborted_transactions: None,
+                            preferred_read_replica: None,
                             message_set: MessageSet {
                                 messages: vec![
                                     Message {
@@ -347,8 +569,10 @@ mod tests {
                                         key: Some(Bytes::from(&b"key"[..])),
                                         value: Some(Bytes::from(&b"value"[..])),
                                         timestamp: Some(MessageTimestamp::LogAppendTime(456)),
+                                        headers: vec![],
                                     },
                                 ],
+                                batches: vec![],
                             },
                         },
                     ],
@@ -373,4 +597,490 @@ mod tests {
 
         assert_eq!(res, IResult::Done(&[][..], response));
     }
+
+    #[test]
+    fn encode_fetch_request_v4() {
+        let request = FetchRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::Fetch as ApiKey,
+                api_version: 4,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            max_wait_time: 3,
+            min_bytes: 4,
+            max_bytes: 1024,
+            isolation_level: IsolationLevel::ReadCommitted,
+            session_id: INVALID_SESSION_ID,
+            session_epoch: FINAL_SESSION_EPOCH,
+            topics: vec![
+                FetchTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        FetchPartition {
+                            partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+                            fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
+                            max_bytes: 7,
+                        },
+                    ],
+                },
+            ],
+            forgotten_topics: vec![],
+            rack_id: "".into(),
+        };
+
+        let data = vec![
+            /* FetchRequest
+             * RequestHeader */ 0, 1 /* api_key */, 0, 4 /* api_version */,
+            0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110, 116 /* client_id */, 0, 0, 0,
+            2 /* replica_id */, 0, 0, 0, 3 /* max_wait_time */, 0, 0, 0, 4 /* min_bytes */, 0, 0, 4,
+            0 /* max_bytes */, 1 /* isolation_level */, /* topics: [FetchTopicData] */ 0, 0, 0, 1,
+            /* FetchTopicData */ 0, 5, 116, 111, 112, 105, 99 /* topic_name */,
+            /* partitions: [FetchPartitionData] */ 0, 0, 0, 1, /* FetchPartitionData */ 0, 0, 0,
+            5 /* partition */, 0, 0, 0, 0, 0, 0, 0, 6 /* fetch_offset */, 0, 0, 0, 7 /* max_bytes */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        request.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(request.size(request.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn parse_fetch_response_v4() {
+        let response = FetchResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            error_code: None,
+            session_id: None,
+            topics: vec![
+                FetchTopicData {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        FetchPartitionData {
+                            partition_id: 1,
+                            error_code: 0,
+                            high_watermark: 3,
+                            last_stable_offset: Some(2),
+                            log_start_offset: None,
+                            aborted_transactions: Some(vec![
+                                AbortedTransaction {
+                                    producer_id: 4,
+                                    first_offset: 5,
+                                },
+                            ]),
+                            preferred_read_replica: None,
+                            message_set: MessageSet {
+                                messages: vec![],
+                                batches: vec![],
+                            },
+                        },
+                        FetchPartitionData {
+                            partition_id: 2,
+                            error_code: 0,
+                            high_watermark: 3,
+                            last_stable_offset: Some(3),
+                            log_start_offset: None,
+                            aborted_transactions: None,
+                            preferred_read_replica: None,
+                            message_set: MessageSet {
+                                messages: vec![],
+                                batches: vec![],
+                            },
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [TopicData] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */,
+            /* partitions: [PartitionData] */ 0, 0, 0, 2, 0, 0, 0, 1 /* partition */, 0,
+            0 /* error_code */, 0, 0, 0, 0, 0, 0, 0, 3 /* highwater_mark_offset */, 0, 0, 0, 0, 0, 0, 0,
+            2 /* last_stable_offset */, /* aborted_transactions: [AbortedTransaction] */ 0, 0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0, 4 /* producer_id */, 0, 0, 0, 0, 0, 0, 0, 5 /* first_offset */,
+            /* MessageSet */ 0, 0, 0, 0 /* size */, 0, 0, 0, 2 /* partition */, 0, 0 /* error_code */, 0, 0,
+            0, 0, 0, 0, 0, 3 /* highwater_mark_offset */, 0, 0, 0, 0, 0, 0, 0, 3 /* last_stable_offset */,
+            255, 255, 255, 255 /* aborted_transactions */, /* MessageSet */ 0, 0, 0, 0 /* size */,
+        ];
+
+        let res = parse_fetch_response(&data[..], 4);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn encode_fetch_request_v5() {
+        let request = FetchRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::Fetch as ApiKey,
+                api_version: 5,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            max_wait_time: 3,
+            min_bytes: 4,
+            max_bytes: 1024,
+            isolation_level: IsolationLevel::ReadCommitted,
+            session_id: INVALID_SESSION_ID,
+            session_epoch: FINAL_SESSION_EPOCH,
+            topics: vec![
+                FetchTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        FetchPartition {
+                            partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+                            fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
+                            max_bytes: 7,
+                        },
+                    ],
+                },
+            ],
+            forgotten_topics: vec![],
+            rack_id: "".into(),
+        };
+
+        let data = vec![
+            /* FetchRequest
+             * RequestHeader */ 0, 1 /* api_key */, 0, 5 /* api_version */,
+            0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110, 116 /* client_id */, 0, 0, 0,
+            2 /* replica_id */, 0, 0, 0, 3 /* max_wait_time */, 0, 0, 0, 4 /* min_bytes */, 0, 0, 4,
+            0 /* max_bytes */, 1 /* isolation_level */, /* topics: [FetchTopicData] */ 0, 0, 0, 1,
+            /* FetchTopicData */ 0, 5, 116, 111, 112, 105, 99 /* topic_name */,
+            /* partitions: [FetchPartitionData] */ 0, 0, 0, 1, /* FetchPartitionData */ 0, 0, 0,
+            5 /* partition */, 0, 0, 0, 0, 0, 0, 0, 6 /* fetch_offset */, 255, 255, 255, 255, 255, 255, 255,
+            255 /* log_start_offset */, 0, 0, 0, 7 /* max_bytes */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        request.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(request.size(request.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn parse_fetch_response_v5() {
+        let response = FetchResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            error_code: None,
+            session_id: None,
+            topics: vec![
+                FetchTopicData {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        FetchPartitionData {
+                            partition_id: 1,
+                            error_code: 0,
+                            high_watermark: 3,
+                            last_stable_offset: Some(2),
+                            log_start_offset: Some(1),
+                            aborted_transactions: None,
+                            preferred_read_replica: None,
+                            message_set: MessageSet {
+                                messages: vec![],
+                                batches: vec![],
+                            },
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [TopicData] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */,
+            /* partitions: [PartitionData] */ 0, 0, 0, 1, 0, 0, 0, 1 /* partition */, 0,
+            0 /* error_code */, 0, 0, 0, 0, 0, 0, 0, 3 /* highwater_mark_offset */, 0, 0, 0, 0, 0, 0, 0,
+            2 /* last_stable_offset */, 0, 0, 0, 0, 0, 0, 0, 1 /* log_start_offset */, 255, 255, 255,
+            255 /* aborted_transactions */, /* MessageSet */ 0, 0, 0, 0 /* size */,
+        ];
+
+        let res = parse_fetch_response(&data[..], 5);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn encode_fetch_request_v7() {
+        let request = FetchRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::Fetch as ApiKey,
+                api_version: 7,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            max_wait_time: 3,
+            min_bytes: 4,
+            max_bytes: 1024,
+            isolation_level: IsolationLevel::ReadCommitted,
+            session_id: 9,
+            session_epoch: 2,
+            topics: vec![
+                FetchTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        FetchPartition {
+                            partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+                            fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
+                            max_bytes: 7,
+                        },
+                    ],
+                },
+            ],
+            forgotten_topics: vec![
+                ForgottenTopic {
+                    topic_name: "gone".into(),
+                    partitions: vec![1, 2],
+                },
+            ],
+            rack_id: "".into(),
+        };
+
+        let data = vec![
+            /* FetchRequest
+             * RequestHeader */ 0, 1 /* api_key */, 0, 7 /* api_version */,
+            0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110, 116 /* client_id */, 0, 0, 0,
+            2 /* replica_id */, 0, 0, 0, 3 /* max_wait_time */, 0, 0, 0, 4 /* min_bytes */, 0, 0, 4,
+            0 /* max_bytes */, 1 /* isolation_level */, 0, 0, 0, 9 /* session_id */, 0, 0, 0,
+            2 /* session_epoch */, /* topics: [FetchTopicData] */ 0, 0, 0, 1,
+            /* FetchTopicData */ 0, 5, 116, 111, 112, 105, 99 /* topic_name */,
+            /* partitions: [FetchPartitionData] */ 0, 0, 0, 1, /* FetchPartitionData */ 0, 0, 0,
+            5 /* partition */, 0, 0, 0, 0, 0, 0, 0, 6 /* fetch_offset */, 255, 255, 255, 255, 255, 255, 255,
+            255 /* log_start_offset */, 0, 0, 0, 7 /* max_bytes */,
+            /* forgotten_topics: [ForgottenTopic] */ 0, 0, 0, 1, 0, 4, 103, 111, 110, 101 /* topic_name */,
+            /* partitions: [PartitionId] */ 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        request.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(request.size(request.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn parse_fetch_response_v7() {
+        let response = FetchResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            error_code: Some(0),
+            session_id: Some(9),
+            topics: vec![
+                FetchTopicData {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        FetchPartitionData {
+                            partition_id: 1,
+                            error_code: 0,
+                            high_watermark: 3,
+                            last_stable_offset: Some(2),
+                            log_start_offset: Some(1),
+                            aborted_transactions: None,
+                            preferred_read_replica: None,
+                            message_set: MessageSet {
+                                messages: vec![],
+                                batches: vec![],
+                            },
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */, 0,
+            0 /* error_code */, 0, 0, 0, 9 /* session_id */, /* topics: [TopicData] */ 0, 0, 0, 1, 0, 5,
+            b't', b'o', b'p', b'i', b'c' /* topic_name */, /* partitions: [PartitionData] */ 0, 0, 0, 1, 0,
+            0, 0, 1 /* partition */, 0, 0 /* error_code */, 0, 0, 0, 0, 0, 0, 0,
+            3 /* highwater_mark_offset */, 0, 0, 0, 0, 0, 0, 0, 2 /* last_stable_offset */, 0, 0, 0, 0, 0,
+            0, 0, 1 /* log_start_offset */, 255, 255, 255, 255 /* aborted_transactions */,
+            /* MessageSet */ 0, 0, 0, 0 /* size */,
+        ];
+
+        let res = parse_fetch_response(&data[..], 7);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn encode_fetch_request_v11() {
+        let request = FetchRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::Fetch as ApiKey,
+                api_version: 11,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            max_wait_time: 3,
+            min_bytes: 4,
+            max_bytes: 1024,
+            isolation_level: IsolationLevel::ReadCommitted,
+            session_id: INVALID_SESSION_ID,
+            session_epoch: INITIAL_SESSION_EPOCH,
+            topics: vec![
+                FetchTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        FetchPartition {
+                            partition_id: 5,
+                            current_leader_epoch: 3,
+                            fetch_offset: 6,
+                            log_start_offset: INVALID_LOG_START_OFFSET,
+                            max_bytes: 7,
+                        },
+                    ],
+                },
+            ],
+            forgotten_topics: vec![],
+            rack_id: "az1".into(),
+        };
+
+        let data = vec![
+            /* FetchRequest
+             * RequestHeader */ 0, 1 /* api_key */, 0, 11 /* api_version */,
+            0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110, 116 /* client_id */, 0, 0, 0,
+            2 /* replica_id */, 0, 0, 0, 3 /* max_wait_time */, 0, 0, 0, 4 /* min_bytes */, 0, 0, 4,
+            0 /* max_bytes */, 1 /* isolation_level */, 0, 0, 0, 0 /* session_id */, 0, 0, 0,
+            0 /* session_epoch */, /* topics: [FetchTopicData] */ 0, 0, 0, 1,
+            /* FetchTopicData */ 0, 5, 116, 111, 112, 105, 99 /* topic_name */,
+            /* partitions: [FetchPartitionData] */ 0, 0, 0, 1, /* FetchPartitionData */ 0, 0, 0,
+            5 /* partition */, 0, 0, 0, 3 /* current_leader_epoch */, 0, 0, 0, 0, 0, 0, 0,
+            6 /* fetch_offset */, 255, 255, 255, 255, 255, 255, 255, 255 /* log_start_offset */, 0, 0, 0,
+            7 /* max_bytes */, /* forgotten_topics: [ForgottenTopic] */ 0, 0, 0, 0, 0, 3, 97, 122,
+            49 /* rack_id */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        request.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(request.size(request.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn parse_fetch_response_v11() {
+        let response = FetchResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            error_code: Some(0),
+            session_id: Some(0),
+            topics: vec![
+                FetchTopicData {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        FetchPartitionData {
+                            partition_id: 1,
+                            error_code: 0,
+                            high_watermark: 3,
+                            last_stable_offset: Some(2),
+                            log_start_offset: Some(1),
+                            aborted_transactions: None,
+                            preferred_read_replica: Some(4),
+                            message_set: MessageSet {
+                                messages: vec![],
+                                batches: vec![],
+                            },
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */, 0,
+            0 /* error_code */, 0, 0, 0, 0 /* session_id */, /* topics: [TopicData] */ 0, 0, 0, 1, 0, 5,
+            b't', b'o', b'p', b'i', b'c' /* topic_name */, /* partitions: [PartitionData] */ 0, 0, 0, 1, 0,
+            0, 0, 1 /* partition */, 0, 0 /* error_code */, 0, 0, 0, 0, 0, 0, 0,
+            3 /* highwater_mark_offset */, 0, 0, 0, 0, 0, 0, 0, 2 /* last_stable_offset */, 0, 0, 0, 0, 0,
+            0, 0, 1 /* log_start_offset */, 255, 255, 255, 255 /* aborted_transactions */, 0, 0, 0,
+            4 /* preferred_read_replica */, /* MessageSet */ 0, 0, 0, 0 /* size */,
+        ];
+
+        let res = parse_fetch_response(&data[..], 11);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_skip_aborted_transactions() {
+        let batch = |base_offset: Offset, records: i32, attributes: i16, producer_id: ProducerId| RecordBatchHeader {
+            base_offset,
+            attributes,
+            last_offset_delta: records - 1,
+            producer_id,
+            ..Default::default()
+        };
+        let message = |offset: Offset| Message {
+            offset,
+            compression: Compression::None,
+            key: None,
+            value: Some(Bytes::from(&b"value"[..])),
+            timestamp: None,
+            headers: vec![],
+        };
+        let partition_data = |aborted_transactions| FetchPartitionData {
+            partition_id: 1,
+            error_code: 0,
+            high_watermark: 7,
+            last_stable_offset: Some(7),
+            log_start_offset: None,
+            aborted_transactions,
+            preferred_read_replica: None,
+            message_set: MessageSet {
+                messages: (0..7).map(message).collect(),
+                batches: vec![
+                    batch(0, 2, TRANSACTIONAL_FLAG_MASK, 1),
+                    batch(2, 1, TRANSACTIONAL_FLAG_MASK | CONTROL_FLAG_MASK, 1),
+                    batch(3, 2, TRANSACTIONAL_FLAG_MASK, 2),
+                    batch(5, 1, TRANSACTIONAL_FLAG_MASK | CONTROL_FLAG_MASK, 2),
+                    batch(6, 1, 0, NO_PRODUCER_ID),
+                ],
+            },
+        };
+
+        let offsets = |messages: Vec<Message>| messages.iter().map(|message| message.offset).collect::<Vec<_>>();
+
+        assert_eq!(offsets(partition_data(None).into_messages()), vec![0, 1, 3, 4, 6]);
+        assert_eq!(
+            offsets(
+                partition_data(Some(vec![
+                    AbortedTransaction {
+                        producer_id: 1,
+                        first_offset: 0,
+                    },
+                ])).into_messages()
+            ),
+            vec![3, 4, 6]
+        );
+    }
 }
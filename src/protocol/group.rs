@@ -5,9 +5,11 @@ use bytes::{BufMut, ByteOrder, Bytes, BytesMut};
 use nom::{IResult, be_i16, be_i32};
 
 use errors::Result;
-use protocol::{parse_bytes, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, GenerationId,
-               ParseTag, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE, BYTES_LEN_SIZE, STR_LEN_SIZE};
+use protocol::{parse_bytes, parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode,
+               GenerationId, ParseTag, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE,
+               BYTES_LEN_SIZE, STR_LEN_SIZE};
 
+const COORDINATOR_TYPE_SIZE: usize = 1;
 const SESSION_TIMEOUT_SIZE: usize = 4;
 const REBALANCE_TIMEOUT_SIZE: usize = 4;
 const GROUP_GENERATION_ID_SIZE: usize = 4;
@@ -15,15 +17,38 @@ const GROUP_GENERATION_ID_SIZE: usize = 4;
 #[derive(Clone, Debug, PartialEq)]
 pub struct GroupCoordinatorRequest<'a> {
     pub header: RequestHeader<'a>,
-    /// The unique group id.
+    /// The unique group id, or the transactional id if looking for the transaction coordinator.
     pub group_id: Cow<'a, str>,
+    /// The type of coordinator to find (since v1).
+    pub coordinator_type: CoordinatorType,
+}
+
+/// The type of coordinator to find.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(i8)]
+pub enum CoordinatorType {
+    /// The group coordinator of the consumer group.
+    Group = 0,
+    /// The transaction coordinator of the transactional producer.
+    Transaction = 1,
+}
+
+impl Default for CoordinatorType {
+    fn default() -> Self {
+        CoordinatorType::Group
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct GroupCoordinatorResponse {
     pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: Option<i32>,
     /// Error code.
     pub error_code: ErrorCode,
+    /// The error message (since v1).
+    pub error_message: Option<String>,
     /// The broker id.
     pub coordinator_id: i32,
     /// The hostname of the broker.
@@ -219,17 +244,50 @@ pub struct ListGroupsGroupStatus {
     pub protocol_type: String,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteGroupsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The unique group ids to delete.
+    pub groups: Vec<Cow<'a, str>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteGroupsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The deletion results of the groups.
+    pub groups: Vec<DeleteGroupsGroupStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteGroupsGroupStatus {
+    /// The unique group id.
+    pub group_id: String,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
 impl<'a> Record for GroupCoordinatorRequest<'a> {
     fn size(&self, api_version: ApiVersion) -> usize {
         self.header.size(api_version) + STR_LEN_SIZE + self.group_id.len()
+            + if api_version > 0 { COORDINATOR_TYPE_SIZE } else { 0 }
     }
 }
 
 impl<'a> Encodable for GroupCoordinatorRequest<'a> {
     fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        let api_version = self.header.api_version;
+
         self.header.encode::<T>(dst)?;
 
-        dst.put_str::<T, _>(Some(self.group_id.as_ref()))
+        dst.put_str::<T, _>(Some(self.group_id.as_ref()))?;
+        if api_version > 0 {
+            dst.put_i8(self.coordinator_type as i8);
+        }
+
+        Ok(())
     }
 }
 
@@ -354,26 +412,48 @@ impl<'a> Encodable for ListGroupsRequest<'a> {
     }
 }
 
+impl<'a> Record for DeleteGroupsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version)
+            + self.groups
+                .iter()
+                .fold(ARRAY_LEN_SIZE, |size, group| size + STR_LEN_SIZE + group.len())
+    }
+}
+
+impl<'a> Encodable for DeleteGroupsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.groups, |buf, group| buf.put_str::<T, _>(Some(group.as_ref())))
+    }
+}
+
 impl GroupCoordinatorResponse {
-    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        parse_group_corordinator_response(buf)
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_group_corordinator_response(buf, api_version)
     }
 }
 
-named!(
-    parse_group_corordinator_response<GroupCoordinatorResponse>,
-    parse_tag!(
-        ParseTag::GroupCoordinatorResponse,
+named_args!(parse_group_corordinator_response(api_version: ApiVersion)<GroupCoordinatorResponse>,
+    parse_tag!(ParseTag::GroupCoordinatorResponse,
         do_parse!(
-            header: parse_response_header >> error_code: be_i16 >> coordinator_id: be_i32
-                >> coordinator_host: parse_string >> coordinator_port: be_i32
-                >> (GroupCoordinatorResponse {
-                    header,
-                    error_code,
-                    coordinator_id,
-                    coordinator_host,
-                    coordinator_port,
-                })
+            header: parse_response_header
+         >> throttle_time: cond!(api_version > 0, be_i32)
+         >> error_code: be_i16
+         >> error_message: cond!(api_version > 0, parse_opt_string)
+         >> coordinator_id: be_i32
+         >> coordinator_host: parse_string
+         >> coordinator_port: be_i32
+         >> (GroupCoordinatorResponse {
+                header,
+                throttle_time,
+                error_code,
+                error_message: error_message.and_then(|s| s),
+                coordinator_id,
+                coordinator_host,
+                coordinator_port,
+            })
         )
     )
 );
@@ -555,6 +635,40 @@ named!(
     )
 );
 
+impl DeleteGroupsResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_delete_groups_response(buf)
+    }
+}
+
+named!(parse_delete_groups_response<DeleteGroupsResponse>,
+    parse_tag!(ParseTag::DeleteGroupsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> groups: length_count!(be_i32, parse_delete_groups_group_status)
+         >> (DeleteGroupsResponse {
+                header,
+                throttle_time,
+                groups,
+            })
+        )
+    )
+);
+
+named!(parse_delete_groups_group_status<DeleteGroupsGroupStatus>,
+    parse_tag!(ParseTag::DeleteGroupsGroupStatus,
+        do_parse!(
+            group_id: parse_string
+         >> error_code: be_i16
+         >> (DeleteGroupsGroupStatus {
+                group_id,
+                error_code,
+            })
+        )
+    )
+);
+
 #[cfg(test)]
 mod tests {
     use bytes::BigEndian;
@@ -575,6 +689,7 @@ mod tests {
                 client_id: Some("client".into()),
             },
             group_id: "consumer".into(),
+            coordinator_type: CoordinatorType::Group,
         };
 
         let data = vec![
@@ -603,14 +718,68 @@ mod tests {
 
         let res = GroupCoordinatorResponse {
             header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
             error_code: 1,
+            error_message: None,
             coordinator_id: 2,
             coordinator_host: "localhost".to_owned(),
             coordinator_port: 3,
         };
 
         assert_eq!(
-            parse_group_corordinator_response(data.as_slice()),
+            parse_group_corordinator_response(data.as_slice(), 0),
+            IResult::Done(&[][..], res)
+        );
+    }
+
+    #[test]
+    fn test_encode_group_coordinator_request_v1() {
+        let req = GroupCoordinatorRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::GroupCoordinator as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            group_id: "txn".into(),
+            coordinator_type: CoordinatorType::Transaction,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 10 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x',
+            b'n' /* group_id */, 1 /* coordinator_type */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_group_corordinator_response_v1() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 4 /* throttle_time */, 0,
+            0 /* error_code */, 255, 255 /* error_message */, 0, 0, 0, 2 /* coordinator_id */, 0, 9, b'l', b'o',
+            b'c', b'a', b'l', b'h', b'o', b's', b't' /* coordinator_host */, 0, 0, 0, 3 /* coordinator_port */,
+        ];
+
+        let res = GroupCoordinatorResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(4),
+            error_code: 0,
+            error_message: None,
+            coordinator_id: 2,
+            coordinator_host: "localhost".to_owned(),
+            coordinator_port: 3,
+        };
+
+        assert_eq!(
+            parse_group_corordinator_response(data.as_slice(), 1),
             IResult::Done(&[][..], res)
         );
     }
@@ -1007,4 +1176,59 @@ mod tests {
         assert_eq!(res, IResult::Done(&[][..], response));
     }
 
+    #[test]
+    fn test_encode_delete_groups_request() {
+        let req = DeleteGroupsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteGroups as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            groups: vec!["consumer".into()],
+        };
+
+        let data = vec![
+            /* DeleteGroupsRequest
+             * RequestHeader */ 0, 42 /* api_key */, 0,
+            0 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n',
+            b't' /* client_id */, /* groups[String] */ 0, 0, 0, 1, /* String */ 0, 8, b'c', b'o', b'n',
+            b's', b'u', b'm', b'e', b'r' /* group_id */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_delete_groups_response() {
+        let response = DeleteGroupsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            groups: vec![
+                DeleteGroupsGroupStatus {
+                    group_id: "consumer".to_owned(),
+                    error_code: 68,
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */,
+            /* groups: [DeleteGroupsGroupStatus] */ 0, 0, 0, 1, /* DeleteGroupsGroupStatus */ 0, 8, b'c', b'o',
+            b'n', b's', b'u', b'm', b'e', b'r' /* group_id */, 0, 68 /* error_code */,
+        ];
+
+        let res = parse_delete_groups_response(&data[..]);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
 }
@@ -4,12 +4,14 @@ use std::borrow::Cow;
 use nom::be_i32;
 
 use errors::Result;
-use protocol::{ApiKey, ApiVersion, CorrelationId, Encodable, ParseTag, Record, WriteExt, STR_LEN_SIZE};
+use protocol::{parse_tagged_fields, ApiKey, ApiKeys, ApiVersion, CorrelationId, Encodable, ParseTag, Record, WriteExt,
+               STR_LEN_SIZE};
 
 const API_KEY_SIZE: usize = 2;
 const API_VERSION_SIZE: usize = 2;
 const CORRELATION_ID_SIZE: usize = 4;
 const HEADER_OVERHEAD: usize = API_KEY_SIZE + API_VERSION_SIZE + CORRELATION_ID_SIZE;
+const EMPTY_TAGGED_FIELDS_SIZE: usize = 1;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct RequestHeader<'a> {
@@ -19,9 +21,17 @@ pub struct RequestHeader<'a> {
     pub client_id: Option<Cow<'a, str>>,
 }
 
+impl<'a> RequestHeader<'a> {
+    /// Whether the request uses the v2 header with the tagged fields.
+    pub fn is_flexible(&self) -> bool {
+        ApiKeys::from(self.api_key).request_header_version(self.api_version) > 1
+    }
+}
+
 impl<'a> Record for RequestHeader<'a> {
     fn size(&self, _api_version: ApiVersion) -> usize {
         HEADER_OVERHEAD + STR_LEN_SIZE + self.client_id.as_ref().map_or(0, |s| s.len())
+            + if self.is_flexible() { EMPTY_TAGGED_FIELDS_SIZE } else { 0 }
     }
 }
 
@@ -30,7 +40,12 @@ impl<'a> Encodable for RequestHeader<'a> {
         buf.put_i16::<T>(self.api_key);
         buf.put_i16::<T>(self.api_version);
         buf.put_i32::<T>(self.correlation_id);
-        buf.put_str::<T, _>(self.client_id.as_ref())
+        // the client id is still a nullable string in the v2 header, so it can be parsed by the older brokers.
+        buf.put_str::<T, _>(self.client_id.as_ref())?;
+        if self.is_flexible() {
+            buf.put_tagged_fields(&[])?;
+        }
+        Ok(())
     }
 }
 
@@ -48,11 +63,23 @@ named!(pub parse_response_header<ResponseHeader>,
     ))
 );
 
+// Parse the v1 response header of the flexible versions, the unknown tagged fields are skipped.
+named!(pub parse_flexible_response_header<ResponseHeader>,
+    parse_tag!(ParseTag::ResponseHeader, do_parse!(
+        correlation_id: be_i32
+     >> parse_tagged_fields
+     >> (ResponseHeader {
+            correlation_id,
+        })
+    ))
+);
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
     use bytes::BigEndian;
+    use nom::IResult;
     use protocol::*;
 
     #[test]
@@ -88,4 +115,34 @@ mod tests {
         assert_eq!(remaning, b"");
         assert_eq!(hdr.correlation_id, 123);
     }
+
+    #[test]
+    fn test_flexible_request_header() {
+        let hdr = RequestHeader {
+            api_key: ApiKeys::Metadata as ApiKey,
+            api_version: 9,
+            correlation_id: 123,
+            client_id: Some("test".into()),
+        };
+
+        let mut buf = BytesMut::with_capacity(64);
+
+        hdr.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert!(hdr.is_flexible());
+        assert_eq!(hdr.size(hdr.api_version), buf.len());
+
+        assert_eq!(
+            &buf[..],
+            &[
+                0, 3 /* api_key */, 0, 9 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 4,
+                116, 101, 115, 116 /* client_id */, 0, /* tagged_fields */
+            ]
+        );
+
+        let bytes = &[0, 0, 0, 123, 1, 0, 1, 0xff];
+        let res = parse_flexible_response_header(bytes);
+
+        assert_eq!(res, IResult::Done(&[][..], ResponseHeader { correlation_id: 123 }));
+    }
 }
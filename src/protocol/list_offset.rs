@@ -4,9 +4,10 @@ use std::borrow::Cow;
 use nom::{IResult, be_i16, be_i32, be_i64};
 
 use errors::Result;
-use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, Offset, ParseTag, PartitionId,
-               Record, ReplicaId, RequestHeader, ResponseHeader, Timestamp, WriteExt, ARRAY_LEN_SIZE,
-               PARTITION_ID_SIZE, REPLICA_ID_SIZE, STR_LEN_SIZE, TIMESTAMP_SIZE};
+use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, IsolationLevel, LeaderEpoch,
+               Offset, ParseTag, PartitionId, Record, ReplicaId, RequestHeader, ResponseHeader, Timestamp, WriteExt,
+               ARRAY_LEN_SIZE, ISOLATION_LEVEL_SIZE, LEADER_EPOCH_SIZE, PARTITION_ID_SIZE, REPLICA_ID_SIZE,
+               STR_LEN_SIZE, TIMESTAMP_SIZE};
 
 const MAX_NUMBER_OF_OFFSETS_SIZE: usize = 4;
 
@@ -42,6 +43,8 @@ pub struct ListOffsetRequest<'a> {
     pub header: RequestHeader<'a>,
     /// Broker id of the follower. For normal consumers, use -1.
     pub replica_id: ReplicaId,
+    /// Controls the visibility of the transactional records (since v2).
+    pub isolation_level: IsolationLevel,
     /// Topics to list offsets.
     pub topics: Vec<ListTopicOffset<'a>>,
 }
@@ -58,6 +61,9 @@ pub struct ListTopicOffset<'a> {
 pub struct ListPartitionOffset {
     /// The id of the partition the fetch is for.
     pub partition_id: PartitionId,
+    /// The current leader epoch known by the client (since v4),
+    /// or `NO_PARTITION_LEADER_EPOCH` to skip the fencing check.
+    pub current_leader_epoch: LeaderEpoch,
     /// Used to ask for all messages before a certain time (ms).
     pub timestamp: Timestamp,
     /// Maximum offsets to return.
@@ -66,15 +72,14 @@ pub struct ListPartitionOffset {
 
 impl<'a> Record for ListOffsetRequest<'a> {
     fn size(&self, api_version: ApiVersion) -> usize {
-        self.header.size(api_version) + REPLICA_ID_SIZE + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
-            size + STR_LEN_SIZE + topic.topic_name.len() + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, _| {
-                size + PARTITION_ID_SIZE + TIMESTAMP_SIZE + if api_version == 0 {
-                    MAX_NUMBER_OF_OFFSETS_SIZE
-                } else {
-                    0
-                }
+        self.header.size(api_version) + REPLICA_ID_SIZE + if api_version > 1 { ISOLATION_LEVEL_SIZE } else { 0 }
+            + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+                size + STR_LEN_SIZE + topic.topic_name.len()
+                    + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, _| {
+                        size + PARTITION_ID_SIZE + if api_version > 3 { LEADER_EPOCH_SIZE } else { 0 } + TIMESTAMP_SIZE
+                            + if api_version == 0 { MAX_NUMBER_OF_OFFSETS_SIZE } else { 0 }
+                    })
             })
-        })
     }
 }
 
@@ -85,10 +90,16 @@ impl<'a> Encodable for ListOffsetRequest<'a> {
         self.header.encode::<T>(dst)?;
 
         dst.put_i32::<T>(self.replica_id);
+        if api_version > 1 {
+            dst.put_i8(self.isolation_level as i8);
+        }
         dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
             buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
             buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
                 buf.put_i32::<T>(partition.partition_id);
+                if api_version > 3 {
+                    buf.put_i32::<T>(partition.current_leader_epoch);
+                }
                 buf.put_i64::<T>(partition.timestamp);
                 if api_version == 0 {
                     buf.put_i32::<T>(partition.max_number_of_offsets);
@@ -102,6 +113,9 @@ impl<'a> Encodable for ListOffsetRequest<'a> {
 #[derive(Clone, Debug, PartialEq)]
 pub struct ListOffsetResponse {
     pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation (since v2).
+    pub throttle_time: Option<i32>,
     pub topics: Vec<ListOffsetTopicStatus>,
 }
 
@@ -122,6 +136,8 @@ pub struct ListOffsetPartitionStatus {
     pub offsets: Vec<Offset>,
     /// The timestamp associated with the returned offset
     pub timestamp: Option<Timestamp>,
+    /// The leader epoch of the returned offset (since v4).
+    pub leader_epoch: Option<LeaderEpoch>,
 }
 
 impl ListOffsetResponse {
@@ -134,9 +150,11 @@ named_args!(parse_list_offset_response(api_version: ApiVersion)<ListOffsetRespon
     parse_tag!(ParseTag::ListOffsetResponse,
         do_parse!(
             header: parse_response_header
+         >> throttle_time: cond!(api_version > 1, be_i32)
          >> topics: length_count!(be_i32, apply!(parse_list_offset_topic_status, api_version))
          >> (ListOffsetResponse {
                 header,
+                throttle_time,
                 topics,
             })
         )
@@ -164,10 +182,12 @@ named_args!(parse_list_offset_partition_status(api_version: ApiVersion)<ListOffs
          >> offsets: cond!(api_version == 0, length_count!(be_i32, be_i64))
          >> timestamp: cond!(api_version > 0, be_i64)
          >> offset: cond!(api_version > 0, be_i64)
+         >> leader_epoch: cond!(api_version > 3, be_i32)
          >> (ListOffsetPartitionStatus {
                 partition_id,
                 error_code,
                 timestamp,
+                leader_epoch,
                 offsets: if api_version == 0 { offsets.unwrap_or_default() } else { vec![offset.unwrap_or_default()] },
             })
         )
@@ -193,12 +213,14 @@ mod tests {
                 client_id: Some("client".into()),
             },
             replica_id: 2,
+            isolation_level: IsolationLevel::ReadUncommitted,
             topics: vec![
                 ListTopicOffset {
                     topic_name: "topic".into(),
                     partitions: vec![
                         ListPartitionOffset {
                             partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             timestamp: 6,
                             max_number_of_offsets: 7,
                         },
@@ -237,12 +259,14 @@ mod tests {
                 client_id: Some("client".into()),
             },
             replica_id: 2,
+            isolation_level: IsolationLevel::ReadUncommitted,
             topics: vec![
                 ListTopicOffset {
                     topic_name: "topic".into(),
                     partitions: vec![
                         ListPartitionOffset {
                             partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             timestamp: 6,
                             max_number_of_offsets: 0,
                         },
@@ -274,6 +298,7 @@ mod tests {
     fn test_parse_list_offset_response_v0() {
         let response = ListOffsetResponse {
             header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
             topics: vec![
                 ListOffsetTopicStatus {
                     topic_name: "topic".to_owned(),
@@ -282,6 +307,7 @@ mod tests {
                             partition_id: 1,
                             error_code: 2,
                             timestamp: None,
+                            leader_epoch: None,
                             offsets: vec![3, 4, 5, 6],
                         },
                     ],
@@ -308,6 +334,7 @@ mod tests {
     fn test_parse_list_offset_response_v1() {
         let response = ListOffsetResponse {
             header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
             topics: vec![
                 ListOffsetTopicStatus {
                     topic_name: "topic".to_owned(),
@@ -316,6 +343,7 @@ mod tests {
                             partition_id: 1,
                             error_code: 2,
                             timestamp: Some(3),
+                            leader_epoch: None,
                             offsets: vec![4],
                         },
                     ],
@@ -337,4 +365,167 @@ mod tests {
 
         assert_eq!(res, IResult::Done(&[][..], response));
     }
+
+    #[test]
+    fn test_encode_list_offset_request_v2() {
+        let req = ListOffsetRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::ListOffsets as ApiKey,
+                api_version: 2,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            isolation_level: IsolationLevel::ReadCommitted,
+            topics: vec![
+                ListTopicOffset {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        ListPartitionOffset {
+                            partition_id: 5,
+                            current_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+                            timestamp: 6,
+                            max_number_of_offsets: 0,
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ListOffsetRequest
+             * RequestHeader */ 0, 2 /* api_key */, 0,
+            2 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110,
+            116 /* client_id */, 0, 0, 0, 2 /* replica_id */, 1 /* isolation_level */,
+            /* topics: [ListTopicOffset] */ 0, 0, 0, 1, /* ListTopicOffset */ 0, 5, 116, 111, 112, 105,
+            99 /* topic_name */, /* partitions: [ListPartitionOffset] */ 0, 0, 0, 1,
+            /* ListPartitionOffset */ 0, 0, 0, 5 /* partition */, 0, 0, 0, 0, 0, 0, 0, 6 /* timestamp */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_list_offset_response_v2() {
+        let response = ListOffsetResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            topics: vec![
+                ListOffsetTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        ListOffsetPartitionStatus {
+                            partition_id: 1,
+                            error_code: 2,
+                            timestamp: Some(3),
+                            leader_epoch: None,
+                            offsets: vec![4],
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [ListOffsetTopicStatus] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* topic_name */, /* partitions: [ListOffsetPartitionStatus] */ 0, 0, 0, 1, 0, 0, 0,
+            1 /* partition */, 0, 2 /* error_code */, 0, 0, 0, 0, 0, 0, 0, 3 /* timestamp */, 0, 0, 0,
+            0, 0, 0, 0, 4 /* offset */,
+        ];
+
+        let res = parse_list_offset_response(&data[..], 2);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_encode_list_offset_request_v4() {
+        let req = ListOffsetRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::ListOffsets as ApiKey,
+                api_version: 4,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: 2,
+            isolation_level: IsolationLevel::ReadCommitted,
+            topics: vec![
+                ListTopicOffset {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        ListPartitionOffset {
+                            partition_id: 5,
+                            current_leader_epoch: 7,
+                            timestamp: 6,
+                            max_number_of_offsets: 0,
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ListOffsetRequest
+             * RequestHeader */ 0, 2 /* api_key */, 0,
+            4 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, 99, 108, 105, 101, 110,
+            116 /* client_id */, 0, 0, 0, 2 /* replica_id */, 1 /* isolation_level */,
+            /* topics: [ListTopicOffset] */ 0, 0, 0, 1, /* ListTopicOffset */ 0, 5, 116, 111, 112, 105,
+            99 /* topic_name */, /* partitions: [ListPartitionOffset] */ 0, 0, 0, 1,
+            /* ListPartitionOffset */ 0, 0, 0, 5 /* partition */, 0, 0, 0, 7 /* current_leader_epoch */, 0,
+            0, 0, 0, 0, 0, 0, 6 /* timestamp */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_list_offset_response_v4() {
+        let response = ListOffsetResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            topics: vec![
+                ListOffsetTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        ListOffsetPartitionStatus {
+                            partition_id: 1,
+                            error_code: 2,
+                            timestamp: Some(3),
+                            leader_epoch: Some(5),
+                            offsets: vec![4],
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [ListOffsetTopicStatus] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* topic_name */, /* partitions: [ListOffsetPartitionStatus] */ 0, 0, 0, 1, 0, 0, 0,
+            1 /* partition */, 0, 2 /* error_code */, 0, 0, 0, 0, 0, 0, 0, 3 /* timestamp */, 0, 0, 0,
+            0, 0, 0, 0, 4 /* offset */, 0, 0, 0, 5 /* leader_epoch */,
+        ];
+
+        let res = parse_list_offset_response(&data[..], 4);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
 }
@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use bytes::{BufMut, ByteOrder, Bytes, BytesMut};
 
-use nom::{be_i32, be_i64, be_i8};
+use nom::{IResult, be_i16, be_i32, be_i64, be_i8};
 
 use time;
 
@@ -12,11 +12,20 @@ use crc::crc32;
 
 use compression::Compression;
 use errors::{ErrorKind, Result};
-use protocol::{parse_opt_bytes, ApiVersion, Offset, ParseTag, Record, Timestamp, WriteExt, BYTES_LEN_SIZE,
-               OFFSET_SIZE, TIMESTAMP_SIZE};
+use protocol::{parse_opt_bytes, parse_opt_varbytes, parse_varint, parse_varlong, parse_varstring, varint_size,
+               varlong_size,
+               ApiVersion, Offset, ParseTag, ProducerEpoch, ProducerId, Record, Sequence, Timestamp, WriteExt,
+               BYTES_LEN_SIZE, DEFAULT_TIMESTAMP, OFFSET_SIZE, TIMESTAMP_SIZE};
 
 pub const TIMESTAMP_TYPE_MASK: i8 = 0x08;
 pub const COMPRESSION_CODEC_MASK: i8 = 0x07;
+pub const TRANSACTIONAL_FLAG_MASK: i16 = 0x10;
+pub const CONTROL_FLAG_MASK: i16 = 0x20;
+
+pub const NO_PRODUCER_ID: ProducerId = -1;
+pub const NO_PRODUCER_EPOCH: ProducerEpoch = -1;
+pub const NO_SEQUENCE: Sequence = -1;
+pub const NO_PARTITION_LEADER_EPOCH: i32 = -1;
 
 const MSG_SIZE: usize = 4;
 const CRC_SIZE: usize = 4;
@@ -24,13 +33,49 @@ const MAGIC_SIZE: usize = 1;
 const ATTRIBUTE_SIZE: usize = 1;
 const RECORD_HEADER_SIZE: usize = OFFSET_SIZE + MSG_SIZE + CRC_SIZE + MAGIC_SIZE + ATTRIBUTE_SIZE;
 
+// The magic byte follows the CRC in v0/v1 messages and the partition leader epoch in v2 record batches.
+const MAGIC_OFFSET: usize = OFFSET_SIZE + MSG_SIZE + CRC_SIZE;
+
+const PARTITION_LEADER_EPOCH_SIZE: usize = 4;
+const BATCH_ATTRIBUTES_SIZE: usize = 2;
+const LAST_OFFSET_DELTA_SIZE: usize = 4;
+const PRODUCER_ID_SIZE: usize = 8;
+const PRODUCER_EPOCH_SIZE: usize = 2;
+const BASE_SEQUENCE_SIZE: usize = 4;
+const RECORDS_COUNT_SIZE: usize = 4;
+const RECORD_BATCH_HEADER_SIZE: usize = CRC_SIZE + BATCH_ATTRIBUTES_SIZE + LAST_OFFSET_DELTA_SIZE + TIMESTAMP_SIZE
+    + TIMESTAMP_SIZE + PRODUCER_ID_SIZE + PRODUCER_EPOCH_SIZE + BASE_SEQUENCE_SIZE
+    + RECORDS_COUNT_SIZE;
+const RECORD_BATCH_OVERHEAD: usize =
+    OFFSET_SIZE + MSG_SIZE + PARTITION_LEADER_EPOCH_SIZE + MAGIC_SIZE + RECORD_BATCH_HEADER_SIZE;
+
+// The maximum overhead of a v2 record: length (5), attributes (1), timestamp delta (10), offset delta (5)
+const MAX_RECORD_OVERHEAD: usize = 21;
+
 const COMPRESSION_RATE_ESTIMATION_FACTOR: f32 = 1.05;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(i8)]
 pub enum RecordFormat {
-    V0,
-    V1,
-    V2,
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+}
+
+impl RecordFormat {
+    /// The record format of the message set in the produce request with the given API version.
+    pub fn from_api_version(api_version: ApiVersion) -> Self {
+        match api_version {
+            0 => RecordFormat::V0,
+            1 | 2 => RecordFormat::V1,
+            _ => RecordFormat::V2,
+        }
+    }
+
+    /// The magic byte of the record format.
+    pub fn magic(&self) -> i8 {
+        *self as i8
+    }
 }
 
 /// Message sets
@@ -44,9 +89,14 @@ pub enum RecordFormat {
 /// `MessageSet` => [Offset `MessageSize` Message]
 ///   Offset => int64
 ///   `MessageSize` => int32
+///
+/// Since 0.11.0, a message set may also contain v2 record batches,
+/// the records of those batches are flattened into `messages` with the absolute offsets and timestamps.
 #[derive(Clone, Debug, PartialEq)]
 pub struct MessageSet {
     pub messages: Vec<Message>,
+    /// The headers of the v2 record batches.
+    pub batches: Vec<RecordBatchHeader>,
 }
 
 impl Deref for MessageSet {
@@ -59,7 +109,24 @@ impl Deref for MessageSet {
 
 impl Record for MessageSet {
     fn size(&self, api_version: ApiVersion) -> usize {
-        self.messages.iter().map(|message| message.size(api_version)).sum()
+        if RecordFormat::from_api_version(api_version) == RecordFormat::V2 {
+            let (base_offset, first_timestamp) = self.messages
+                .first()
+                .map_or((0, DEFAULT_TIMESTAMP), |message| (message.offset, message.timestamp_value()));
+
+            RECORD_BATCH_OVERHEAD
+                + self.messages
+                    .iter()
+                    .map(|message| {
+                        message.record_size(
+                            (message.offset - base_offset) as i32,
+                            message.timestamp_value() - first_timestamp,
+                        )
+                    })
+                    .sum::<usize>()
+        } else {
+            self.messages.iter().map(|message| message.size(api_version)).sum()
+        }
     }
 }
 
@@ -81,6 +148,18 @@ impl Record for MessageSet {
 ///   Timestamp => int64
 ///   Key => bytes
 ///   Value => bytes
+///
+/// v2 (supported since 0.11.0)
+/// Record => Length Attributes `TimestampDelta` `OffsetDelta` Key Value [Header]
+///   Length => varint
+///   Attributes => int8
+///   `TimestampDelta` => varlong
+///   `OffsetDelta` => varint
+///   Key => varint bytes
+///   Value => varint bytes
+///   Header => `HeaderKey` `HeaderValue`
+///     `HeaderKey` => varint string
+///     `HeaderValue` => varint bytes
 #[derive(Clone, Debug, PartialEq)]
 pub struct Message {
     pub offset: Offset,
@@ -88,10 +167,42 @@ pub struct Message {
     pub compression: Compression,
     pub key: Option<Bytes>,
     pub value: Option<Bytes>,
+    /// The headers of the record, only supported by the v2 record format.
+    pub headers: Vec<RecordHeader>,
+}
+
+/// A key/value pair attached to the record as the application metadata.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordHeader {
+    /// The key of the header
+    pub key: String,
+    /// The value of the header (or `None` if no value is specified)
+    pub value: Option<Bytes>,
+}
+
+impl RecordHeader {
+    pub fn new<K: Into<String>>(key: K, value: Option<Bytes>) -> Self {
+        RecordHeader {
+            key: key.into(),
+            value,
+        }
+    }
+
+    /// The size of the header in a v2 record.
+    pub fn size(&self) -> usize {
+        varint_size(self.key.len() as i32) + self.key.len()
+            + self.value
+                .as_ref()
+                .map_or(varint_size(-1), |b| varint_size(b.len() as i32) + b.len())
+    }
 }
 
 impl Record for Message {
     fn size(&self, api_version: ApiVersion) -> usize {
+        if RecordFormat::from_api_version(api_version) == RecordFormat::V2 {
+            return self.record_size(0, 0);
+        }
+
         let record_overhead_size = RECORD_HEADER_SIZE + if api_version > 0 { TIMESTAMP_SIZE } else { 0 };
         let key_size = BYTES_LEN_SIZE + self.key.as_ref().map_or(0, |b| b.len());
         let value_size = BYTES_LEN_SIZE + self.value.as_ref().map_or(0, |b| b.len());
@@ -100,6 +211,35 @@ impl Record for Message {
     }
 }
 
+impl Message {
+    fn timestamp_value(&self) -> Timestamp {
+        self.timestamp
+            .as_ref()
+            .map_or(DEFAULT_TIMESTAMP, |timestamp| timestamp.value())
+    }
+
+    /// The size of the record in a v2 record batch, including the length prefix.
+    fn record_size(&self, offset_delta: i32, timestamp_delta: i64) -> usize {
+        let body_size = self.record_body_size(offset_delta, timestamp_delta);
+
+        varint_size(body_size as i32) + body_size
+    }
+
+    fn record_body_size(&self, offset_delta: i32, timestamp_delta: i64) -> usize {
+        let key_size = self.key
+            .as_ref()
+            .map_or(varint_size(-1), |b| varint_size(b.len() as i32) + b.len());
+        let value_size = self.value
+            .as_ref()
+            .map_or(varint_size(-1), |b| varint_size(b.len() as i32) + b.len());
+        let headers_size = varint_size(self.headers.len() as i32)
+            + self.headers.iter().map(|header| header.size()).sum::<usize>();
+
+        ATTRIBUTE_SIZE + varlong_size(timestamp_delta) + varint_size(offset_delta) + key_size + value_size
+            + headers_size
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum MessageTimestamp {
     CreateTime(Timestamp),
@@ -131,6 +271,83 @@ impl fmt::Display for MessageTimestamp {
     }
 }
 
+/// The header of a v2 record batch
+///
+/// `RecordBatch` => `BaseOffset` Length `PartitionLeaderEpoch` Magic Crc Attributes `LastOffsetDelta`
+///                  `FirstTimestamp` `MaxTimestamp` `ProducerId` `ProducerEpoch` `BaseSequence` [Record]
+///   `BaseOffset` => int64
+///   Length => int32
+///   `PartitionLeaderEpoch` => int32
+///   Magic => int8
+///   Crc => uint32 (CRC-32C)
+///   Attributes => int16
+///   `LastOffsetDelta` => int32
+///   `FirstTimestamp` => int64
+///   `MaxTimestamp` => int64
+///   `ProducerId` => int64
+///   `ProducerEpoch` => int16
+///   `BaseSequence` => int32
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordBatchHeader {
+    /// The offset of the first record in the batch.
+    pub base_offset: Offset,
+    /// The epoch of the partition leader which appended the batch.
+    pub partition_leader_epoch: i32,
+    /// The compression codec, timestamp type, transactional and control flags of the batch.
+    pub attributes: i16,
+    /// The offset of the last record relative to the base offset.
+    pub last_offset_delta: i32,
+    /// The timestamp of the first record in the batch.
+    pub first_timestamp: Timestamp,
+    /// The max timestamp of the records in the batch.
+    pub max_timestamp: Timestamp,
+    /// The producer id of the batch, or -1 if the batch was not produced by an idempotent producer.
+    pub producer_id: ProducerId,
+    /// The producer epoch of the batch.
+    pub producer_epoch: ProducerEpoch,
+    /// The sequence number of the first record in the batch.
+    pub base_sequence: Sequence,
+}
+
+impl Default for RecordBatchHeader {
+    fn default() -> Self {
+        RecordBatchHeader {
+            base_offset: 0,
+            partition_leader_epoch: NO_PARTITION_LEADER_EPOCH,
+            attributes: 0,
+            last_offset_delta: 0,
+            first_timestamp: DEFAULT_TIMESTAMP,
+            max_timestamp: DEFAULT_TIMESTAMP,
+            producer_id: NO_PRODUCER_ID,
+            producer_epoch: NO_PRODUCER_EPOCH,
+            base_sequence: NO_SEQUENCE,
+        }
+    }
+}
+
+impl RecordBatchHeader {
+    pub fn compression(&self) -> Compression {
+        Compression::from(self.attributes as i8 & COMPRESSION_CODEC_MASK)
+    }
+
+    pub fn is_log_append_time(&self) -> bool {
+        (self.attributes & i16::from(TIMESTAMP_TYPE_MASK)) != 0
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        (self.attributes & TRANSACTIONAL_FLAG_MASK) != 0
+    }
+
+    pub fn is_control(&self) -> bool {
+        (self.attributes & CONTROL_FLAG_MASK) != 0
+    }
+
+    /// The offset of the last record in the batch.
+    pub fn last_offset(&self) -> Offset {
+        self.base_offset + Offset::from(self.last_offset_delta)
+    }
+}
+
 pub struct MessageSetEncoder {
     api_version: ApiVersion,
     compression: Option<Compression>,
@@ -144,7 +361,15 @@ impl MessageSetEncoder {
         }
     }
 
+    fn record_format(&self) -> RecordFormat {
+        RecordFormat::from_api_version(self.api_version)
+    }
+
     pub fn encode<T: ByteOrder>(&self, message_set: &MessageSet, buf: &mut BytesMut) -> Result<()> {
+        if self.record_format() == RecordFormat::V2 {
+            return self.encode_record_batch::<T>(message_set, buf);
+        }
+
         let mut offset: Offset = 0;
 
         buf.reserve(message_set.size(self.api_version));
@@ -170,7 +395,7 @@ impl MessageSetEncoder {
         let crc_off = buf.len();
         buf.put_i32::<T>(0);
         let data_off = buf.len();
-        buf.put_i8(self.api_version as i8);
+        buf.put_i8(self.record_format().magic());
         buf.put_i8(
             (self.compression.unwrap_or(message.compression) as i8 & COMPRESSION_CODEC_MASK)
                 | if let Some(MessageTimestamp::LogAppendTime(_)) = message.timestamp {
@@ -180,7 +405,7 @@ impl MessageSetEncoder {
                 },
         );
 
-        if self.api_version > 0 {
+        if self.record_format() != RecordFormat::V0 {
             buf.put_i64::<T>(
                 message
                     .timestamp
@@ -201,19 +426,140 @@ impl MessageSetEncoder {
 
         Ok(())
     }
+
+    /// Encode the whole message set as a single v2 record batch.
+    fn encode_record_batch<T: ByteOrder>(&self, message_set: &MessageSet, buf: &mut BytesMut) -> Result<()> {
+        let header = message_set.batches.first().cloned().unwrap_or_default();
+        let compression = self.compression
+            .or_else(|| message_set.messages.first().map(|message| message.compression))
+            .unwrap_or_default();
+        let base_offset = message_set.messages.first().map_or(0, |message| message.offset);
+        let first_timestamp = message_set
+            .messages
+            .first()
+            .map_or(DEFAULT_TIMESTAMP, |message| message.timestamp_value());
+        let max_timestamp = message_set
+            .messages
+            .iter()
+            .map(|message| message.timestamp_value())
+            .max()
+            .unwrap_or(first_timestamp);
+        let last_offset_delta = message_set
+            .messages
+            .last()
+            .map_or(0, |message| message.offset - base_offset);
+        let log_append_time = message_set.messages.first().map_or(false, |message| {
+            if let Some(MessageTimestamp::LogAppendTime(_)) = message.timestamp {
+                true
+            } else {
+                false
+            }
+        });
+
+        let mut records = BytesMut::with_capacity(message_set.size(self.api_version));
+
+        for message in &message_set.messages {
+            let offset_delta = (message.offset - base_offset) as i32;
+            let timestamp_delta = message.timestamp_value() - first_timestamp;
+
+            records.put_varint(message.record_body_size(offset_delta, timestamp_delta) as i32);
+            records.put_i8(0);
+            records.put_varlong(timestamp_delta);
+            records.put_varint(offset_delta);
+            records.put_varbytes(message.key.as_ref())?;
+            records.put_varbytes(message.value.as_ref())?;
+            records.put_varint(message.headers.len() as i32);
+
+            for header in &message.headers {
+                records.put_varbytes(Some(header.key.as_bytes()))?;
+                records.put_varbytes(header.value.as_ref())?;
+            }
+        }
+
+        let compressed;
+        let records = if compression == Compression::None {
+            &records[..]
+        } else {
+            compressed = compression.compress(ApiVersion::from(RecordFormat::V2.magic()), &records)?;
+            &compressed[..]
+        };
+
+        let mut attributes = i16::from(compression as i8 & COMPRESSION_CODEC_MASK)
+            | (header.attributes & (TRANSACTIONAL_FLAG_MASK | CONTROL_FLAG_MASK));
+
+        if log_append_time {
+            attributes |= i16::from(TIMESTAMP_TYPE_MASK);
+        }
+
+        buf.reserve(RECORD_BATCH_OVERHEAD + records.len());
+
+        buf.put_i64::<T>(base_offset);
+        let size_off = buf.len();
+        buf.put_i32::<T>(0);
+        buf.put_i32::<T>(header.partition_leader_epoch);
+        buf.put_i8(RecordFormat::V2.magic());
+        let crc_off = buf.len();
+        buf.put_i32::<T>(0);
+        let data_off = buf.len();
+        buf.put_i16::<T>(attributes);
+        buf.put_i32::<T>(last_offset_delta as i32);
+        buf.put_i64::<T>(first_timestamp);
+        buf.put_i64::<T>(max_timestamp);
+        buf.put_i64::<T>(header.producer_id);
+        buf.put_i16::<T>(header.producer_epoch);
+        buf.put_i32::<T>(header.base_sequence);
+        buf.put_i32::<T>(message_set.messages.len() as i32);
+        buf.put_slice(records);
+
+        let size = buf.len() - size_off - MSG_SIZE;
+        let crc = crc32::checksum_castagnoli(&buf[data_off..]);
+
+        T::write_i32(&mut buf[size_off..], size as i32);
+        T::write_i32(&mut buf[crc_off..], crc as i32);
+
+        Ok(())
+    }
+}
+
+enum MessageSetEntry {
+    Message(Message),
+    RecordBatch(RecordBatchHeader, Vec<Message>),
 }
 
 named_args!(pub parse_message_set(api_version: ApiVersion)<MessageSet>,
     parse_tag!(ParseTag::MessageSet,
         do_parse!(
-            messages: many0!(apply!(parse_message, api_version))
-         >> (MessageSet {
-                messages,
+            entries: many0!(apply!(parse_message_set_entry, api_version))
+         >> ({
+                let mut messages = Vec::new();
+                let mut batches = Vec::new();
+
+                for entry in entries {
+                    match entry {
+                        MessageSetEntry::Message(message) => messages.push(message),
+                        MessageSetEntry::RecordBatch(batch, records) => {
+                            messages.extend(records);
+                            batches.push(batch);
+                        }
+                    }
+                }
+
+                MessageSet {
+                    messages,
+                    batches,
+                }
             })
         )
     )
 );
 
+named_args!(parse_message_set_entry(api_version: ApiVersion)<MessageSetEntry>,
+    switch!(peek!(preceded!(take!(MAGIC_OFFSET), be_i8)),
+        2 => map!(parse_record_batch, |(batch, records)| MessageSetEntry::RecordBatch(batch, records))
+      | _ => map!(apply!(parse_message, api_version), MessageSetEntry::Message)
+    )
+);
+
 named_args!(parse_message(_api_version: ApiVersion)<Message>,
     parse_tag!(ParseTag::Message,
         do_parse!(
@@ -246,11 +592,120 @@ named_args!(parse_message(_api_version: ApiVersion)<Message>,
                 compression: Compression::from(attrs & COMPRESSION_CODEC_MASK),
                 key,
                 value,
+                headers: vec![],
             }})
         )
     )
 );
 
+named!(parse_record_batch<(RecordBatchHeader, Vec<Message>)>,
+    parse_tag!(ParseTag::RecordBatch,
+        do_parse!(
+            base_offset: be_i64
+         >> size: verify!(be_i32, |size: i32| size >= (RECORD_BATCH_OVERHEAD - OFFSET_SIZE - MSG_SIZE) as i32)
+         >> partition_leader_epoch: be_i32
+         >> _magic: be_i8
+         >> data: peek!(take!(size as usize - PARTITION_LEADER_EPOCH_SIZE - MAGIC_SIZE))
+         >> _crc: parse_tag!(ParseTag::RecordBatchCrc,
+            verify!(be_i32, |checksum: i32| {
+                let crc = crc32::checksum_castagnoli(&data[CRC_SIZE..]);
+
+                if crc != checksum as u32 {
+                    trace!("record batch checksum mismatched, expected={}, current={}", crc, checksum as u32);
+                }
+
+                crc == checksum as u32
+            }))
+         >> attributes: be_i16
+         >> last_offset_delta: be_i32
+         >> first_timestamp: be_i64
+         >> max_timestamp: be_i64
+         >> producer_id: be_i64
+         >> producer_epoch: be_i16
+         >> base_sequence: be_i32
+         >> count: be_i32
+         >> records: take!(size as usize + OFFSET_SIZE + MSG_SIZE - RECORD_BATCH_OVERHEAD)
+         >> batch: value!(RecordBatchHeader {
+                base_offset,
+                partition_leader_epoch,
+                attributes,
+                last_offset_delta,
+                first_timestamp,
+                max_timestamp,
+                producer_id,
+                producer_epoch,
+                base_sequence,
+            })
+         >> messages: expr_res!(parse_records(batch, count, records))
+         >> ((batch, messages))
+        )
+    )
+);
+
+/// Parse the records of a v2 record batch, which may be compressed as a whole.
+fn parse_records(batch: RecordBatchHeader, count: i32, records: &[u8]) -> Result<Vec<Message>> {
+    if count < 0 {
+        bail!(ErrorKind::ParseError(format!("invalid records count: {}", count)))
+    }
+
+    let compression = batch.compression();
+    let uncompressed;
+    let records = if compression == Compression::None {
+        records
+    } else {
+        uncompressed = compression.uncompress(ApiVersion::from(RecordFormat::V2.magic()), records)?;
+        &uncompressed[..]
+    };
+
+    match count!(records, apply!(parse_record, batch), count as usize) {
+        IResult::Done(_, messages) => Ok(messages),
+        IResult::Incomplete(_) => bail!(ErrorKind::UnexpectedEOF),
+        IResult::Error(err) => Err(err.into()),
+    }
+}
+
+named_args!(parse_record(batch: RecordBatchHeader)<Message>,
+    parse_tag!(ParseTag::Record,
+        do_parse!(
+            size: verify!(parse_varint, |size: i32| size >= 0)
+         >> message: flat_map!(take!(size as usize), apply!(parse_record_body, batch))
+         >> (message)
+        )
+    )
+);
+
+// Parse the fields of a record, the unknown fields appended by a later version are skipped.
+named_args!(parse_record_body(batch: RecordBatchHeader)<Message>,
+    do_parse!(
+        _attributes: be_i8
+     >> timestamp_delta: parse_varlong
+     >> offset_delta: parse_varint
+     >> key: parse_opt_varbytes
+     >> value: parse_opt_varbytes
+     >> headers: length_count!(parse_varint, parse_record_header)
+     >> (Message {
+            offset: batch.base_offset + Offset::from(offset_delta),
+            timestamp: Some(if batch.is_log_append_time() {
+                MessageTimestamp::LogAppendTime(batch.max_timestamp)
+            } else {
+                MessageTimestamp::CreateTime(batch.first_timestamp + timestamp_delta)
+            }),
+            compression: batch.compression(),
+            key,
+            value,
+            headers,
+        })
+    )
+);
+
+named!(parse_record_header<RecordHeader>,
+    do_parse!(
+        key: parse_varstring
+     >> value: parse_opt_varbytes
+     >> (RecordHeader { key, value })
+    )
+);
+
 /// This class is used to write new log data in memory, i.e.
 #[derive(Debug)]
 pub struct MessageSetBuilder {
@@ -274,7 +729,10 @@ impl MessageSetBuilder {
             base_offset,
             last_offset: None,
             base_timestamp: None,
-            message_set: MessageSet { messages: vec![] },
+            message_set: MessageSet {
+                messages: vec![],
+                batches: vec![],
+            },
         }
     }
 
@@ -286,9 +744,15 @@ impl MessageSetBuilder {
         !self.message_set.is_empty() && self.write_limit <= self.estimated_bytes()
     }
 
-    pub fn has_room_for(&self, timestamp: Timestamp, key: Option<&Bytes>, value: Option<&Bytes>) -> bool {
+    pub fn has_room_for(
+        &self,
+        timestamp: Timestamp,
+        key: Option<&Bytes>,
+        value: Option<&Bytes>,
+        headers: &[RecordHeader],
+    ) -> bool {
         self.message_set.is_empty()
-            || self.write_limit >= self.estimated_bytes() + self.record_size(timestamp, key, value)
+            || self.write_limit >= self.estimated_bytes() + self.record_size(timestamp, key, value, headers)
     }
 
     /// Estimate the written bytes to the underlying byte buffer based on uncompressed written
@@ -300,7 +764,20 @@ impl MessageSetBuilder {
         } * COMPRESSION_RATE_ESTIMATION_FACTOR) as usize
     }
 
-    fn record_size(&self, _timestamp: Timestamp, key: Option<&Bytes>, value: Option<&Bytes>) -> usize {
+    fn record_size(
+        &self,
+        _timestamp: Timestamp,
+        key: Option<&Bytes>,
+        value: Option<&Bytes>,
+        headers: &[RecordHeader],
+    ) -> usize {
+        if RecordFormat::from_api_version(self.api_version) == RecordFormat::V2 {
+            // the deltas are unknown until the batch is built, so assume the worst case
+            return MAX_RECORD_OVERHEAD + 2 * varint_size(-1) + key.map_or(0, |b| b.len())
+                + value.map_or(0, |b| b.len()) + varint_size(headers.len() as i32)
+                + headers.iter().map(|header| header.size()).sum::<usize>();
+        }
+
         let record_overhead_size = RECORD_HEADER_SIZE + if self.api_version > 0 { TIMESTAMP_SIZE } else { 0 };
         let key_size = BYTES_LEN_SIZE + key.map_or(0, |b| b.len());
         let value_size = BYTES_LEN_SIZE + value.map_or(0, |b| b.len());
@@ -322,12 +799,19 @@ impl MessageSetBuilder {
                     compression,
                     key: None,
                     value: Some(Bytes::from(compressed)),
+                    headers: vec![],
                 },
             ],
+            batches: vec![],
         })
     }
 
     pub fn build<T: ByteOrder>(self) -> Result<MessageSet> {
+        if RecordFormat::from_api_version(self.api_version) == RecordFormat::V2 {
+            // the records of v2 record batch will be compressed by the encoder
+            return Ok(self.message_set);
+        }
+
         match self.compression {
             #[cfg(feature = "gzip")]
             Compression::GZIP => self.wrap::<T>(Compression::GZIP),
@@ -343,10 +827,16 @@ impl MessageSetBuilder {
         self.last_offset.map_or(self.base_offset, |off| off + 1)
     }
 
-    pub fn push(&mut self, timestamp: Timestamp, key: Option<Bytes>, value: Option<Bytes>) -> Result<Offset> {
+    pub fn push(
+        &mut self,
+        timestamp: Timestamp,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+        headers: Vec<RecordHeader>,
+    ) -> Result<Offset> {
         let offset = self.next_offset();
 
-        self.push_with_offset(offset, timestamp, key, value)
+        self.push_with_offset(offset, timestamp, key, value, headers)
     }
 
     pub fn push_with_offset(
@@ -355,6 +845,7 @@ impl MessageSetBuilder {
         timestamp: Timestamp,
         key: Option<Bytes>,
         value: Option<Bytes>,
+        headers: Vec<RecordHeader>,
     ) -> Result<Offset> {
         if let Some(last_offset) = self.last_offset {
             if offset <= last_offset {
@@ -369,11 +860,18 @@ impl MessageSetBuilder {
             bail!(ErrorKind::IllegalArgument(format!("negative timestamp: {}", timestamp)))
         }
 
-        if !self.has_room_for(timestamp, key.as_ref(), value.as_ref()) {
+        if !headers.is_empty() && RecordFormat::from_api_version(self.api_version) != RecordFormat::V2 {
+            bail!(ErrorKind::IllegalArgument(format!(
+                "record headers are not supported by API version {}",
+                self.api_version
+            )))
+        }
+
+        if !self.has_room_for(timestamp, key.as_ref(), value.as_ref(), &headers) {
             bail!(ErrorKind::IllegalArgument("message set is full".to_owned()))
         }
 
-        let record_size = self.record_size(timestamp, key.as_ref(), value.as_ref());
+        let record_size = self.record_size(timestamp, key.as_ref(), value.as_ref(), &headers);
         let relative_offset = offset - self.base_offset;
 
         self.message_set.messages.push(Message {
@@ -382,6 +880,7 @@ impl MessageSetBuilder {
             compression: self.compression,
             key,
             value,
+            headers,
         });
 
         self.last_offset = Some(offset);
@@ -398,6 +897,7 @@ impl MessageSetBuilder {
 
 #[cfg(test)]
 mod tests {
+    use bytes::BigEndian;
     use nom::IResult;
 
     use super::*;
@@ -407,7 +907,7 @@ mod tests {
     fn parse_empty_message_set() {
         assert_eq!(
             parse_message_set(&[][..], 0),
-            IResult::Done(&[][..], MessageSet { messages: vec![] })
+            IResult::Done(&[][..], MessageSet { messages: vec![], batches: vec![] })
         );
     }
 
@@ -427,8 +927,10 @@ mod tests {
                     key: Some(Bytes::from(&b"key"[..])),
                     value: Some(Bytes::from(&b"value"[..])),
                     timestamp: None,
+                    headers: vec![],
                 },
             ],
+            batches: vec![],
         };
 
         let res = parse_message_set(&data[..], 0);
@@ -455,8 +957,10 @@ mod tests {
                     key: Some(Bytes::from(&b"key"[..])),
                     value: Some(Bytes::from(&b"value"[..])),
                     timestamp: Some(MessageTimestamp::LogAppendTime(456)),
+                    headers: vec![],
                 },
             ],
+            batches: vec![],
         };
 
         let res = parse_message_set(&data[..], 1);
@@ -465,4 +969,161 @@ mod tests {
 
         assert_eq!(res, IResult::Done(&[][..], message_set));
     }
+
+    #[test]
+    fn encode_record_batch_v2() {
+        let message_set = MessageSet {
+            messages: vec![
+                Message {
+                    offset: 0,
+                    compression: Compression::None,
+                    key: Some(Bytes::from(&b"key"[..])),
+                    value: Some(Bytes::from(&b"value"[..])),
+                    timestamp: Some(MessageTimestamp::CreateTime(456)),
+                    headers: vec![],
+                },
+                Message {
+                    offset: 1,
+                    compression: Compression::None,
+                    key: None,
+                    value: Some(Bytes::from(&b"value"[..])),
+                    timestamp: Some(MessageTimestamp::CreateTime(789)),
+                    headers: vec![
+                        RecordHeader::new("trace-id", Some(Bytes::from(&b"1234"[..]))),
+                        RecordHeader::new("schema-id", None),
+                    ],
+                },
+            ],
+            batches: vec![],
+        };
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        MessageSetEncoder::new(3, None)
+            .encode::<BigEndian>(&message_set, &mut buf)
+            .unwrap();
+
+        assert_eq!(buf.len(), message_set.size(3));
+        assert_eq!(buf[MAGIC_OFFSET], 2);
+
+        let res = parse_message_set(&buf[..], 3);
+
+        display_parse_error::<_>(&buf[..], res.clone());
+
+        assert_eq!(
+            res,
+            IResult::Done(
+                &[][..],
+                MessageSet {
+                    batches: vec![
+                        RecordBatchHeader {
+                            last_offset_delta: 1,
+                            first_timestamp: 456,
+                            max_timestamp: 789,
+                            ..Default::default()
+                        },
+                    ],
+                    ..message_set
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_record_batch_v2() {
+        let data = vec![
+            /* base_offset */ 0, 0, 0, 0, 0, 0, 0, 100, /* size */ 0, 0, 0, 68,
+            /* partition_leader_epoch */ 0, 0, 0, 5, /* magic */ 2, /* crc */ 34, 99, 44, 137,
+            /* attributes */ 0, 0x18, /* last_offset_delta */ 0, 0, 0, 0,
+            /* first_timestamp */ 0, 0, 0, 0, 0, 0, 1, 200, /* max_timestamp */ 0, 0, 0, 0, 0, 0, 3, 21,
+            /* producer_id */ 0, 0, 0, 0, 0, 0, 0, 7, /* producer_epoch */ 0, 1,
+            /* base_sequence */ 0, 0, 0, 3, /* count */ 0, 0, 0, 1,
+            /* records: [Record] */ 36 /* size */, 0 /* attributes */, 0 /* timestamp_delta */,
+            0 /* offset_delta */, 6, b'k', b'e', b'y' /* key */, 10, b'v', b'a', b'l', b'u', b'e' /* value */,
+            2 /* headers */, 2, b'h' /* header key */, 2, b'v' /* header value */,
+        ];
+
+        let res = parse_message_set(&data[..], 3);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        let batch = RecordBatchHeader {
+            base_offset: 100,
+            partition_leader_epoch: 5,
+            attributes: 0x18,
+            last_offset_delta: 0,
+            first_timestamp: 456,
+            max_timestamp: 789,
+            producer_id: 7,
+            producer_epoch: 1,
+            base_sequence: 3,
+        };
+
+        assert!(batch.is_transactional());
+        assert!(batch.is_log_append_time());
+        assert!(!batch.is_control());
+
+        assert_eq!(
+            res,
+            IResult::Done(
+                &[][..],
+                MessageSet {
+                    messages: vec![
+                        Message {
+                            offset: 100,
+                            compression: Compression::None,
+                            key: Some(Bytes::from(&b"key"[..])),
+                            value: Some(Bytes::from(&b"value"[..])),
+                            timestamp: Some(MessageTimestamp::LogAppendTime(789)),
+                            headers: vec![RecordHeader::new("h", Some(Bytes::from(&b"v"[..])))],
+                        },
+                    ],
+                    batches: vec![batch],
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_records_with_record_size() {
+        let batch = RecordBatchHeader {
+            base_offset: 100,
+            partition_leader_epoch: 5,
+            attributes: 0,
+            last_offset_delta: 1,
+            first_timestamp: 456,
+            max_timestamp: 456,
+            producer_id: -1,
+            producer_epoch: -1,
+            base_sequence: -1,
+        };
+        let data = vec![
+            /* records: [Record] */ 18 /* size */, 0 /* attributes */, 0 /* timestamp_delta */,
+            0 /* offset_delta */, 1 /* key */, 2, b'a' /* value */, 0 /* headers */, 0xff, 0xff /* unknown */,
+            14 /* size */, 0 /* attributes */, 0 /* timestamp_delta */, 2 /* offset_delta */, 1 /* key */, 2,
+            b'b' /* value */, 0 /* headers */,
+        ];
+
+        let messages = parse_records(batch, 2, &data).unwrap();
+
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| (message.offset, message.value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (100, Some(Bytes::from(&b"a"[..]))),
+                (101, Some(Bytes::from(&b"b"[..]))),
+            ]
+        );
+        assert_eq!(messages[0].timestamp, Some(MessageTimestamp::CreateTime(456)));
+
+        // the record is shorter than its fields
+        let data = vec![
+            /* records: [Record] */ 6 /* size */, 0 /* attributes */, 0 /* timestamp_delta */,
+            0 /* offset_delta */, 1 /* key */, 2, b'a' /* value */, 0 /* headers */,
+        ];
+
+        assert!(parse_records(batch, 1, &data).is_err());
+    }
 }
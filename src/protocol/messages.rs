@@ -0,0 +1,425 @@
+//! The protocol messages generated from Kafka's JSON message schemas in the `schema` directory.
+//!
+//! Each message is generated into its own module with the nested structures,
+//! the requests implement `Record` and `Encodable`, and the responses are parsed with the API version.
+//! The fields which are not present in all the valid versions are optional in the responses,
+//! and are only encoded for the versions which contain them in the requests.
+
+use bytes::{BufMut, ByteOrder, Bytes, BytesMut};
+
+use nom::{IResult, be_i32, be_i8};
+
+use errors::{ErrorKind, Result};
+use protocol::{compact_array_size, compact_bytes_size, parse_bytes, parse_compact_bytes, parse_compact_opt_bytes,
+               parse_compact_opt_string, parse_compact_string, parse_flexible_response_header, parse_opt_bytes,
+               parse_opt_string, parse_response_header, parse_string, uvarint_size, ApiKey, ApiKeys, ApiVersion,
+               ResponseHeader, TaggedField, WriteExt, ARRAY_LEN_SIZE, BYTES_LEN_SIZE, STR_LEN_SIZE};
+
+include!(concat!(env!("OUT_DIR"), "/messages.rs"));
+
+/// The size of a string, which is compact in the flexible versions.
+fn string_size<S: AsRef<str>>(s: Option<S>, flexible: bool) -> usize {
+    if flexible {
+        compact_bytes_size(s.as_ref().map(|s| s.as_ref().as_bytes()))
+    } else {
+        STR_LEN_SIZE + s.map_or(0, |s| s.as_ref().len())
+    }
+}
+
+/// The size of bytes, which is compact in the flexible versions.
+fn bytes_size<D: AsRef<[u8]>>(d: Option<D>, flexible: bool) -> usize {
+    if flexible {
+        compact_bytes_size(d)
+    } else {
+        BYTES_LEN_SIZE + d.map_or(0, |d| d.as_ref().len())
+    }
+}
+
+/// The size of an array with the number of elements, excluding the elements.
+fn array_size(len: Option<usize>, flexible: bool) -> usize {
+    match len {
+        Some(len) if flexible => compact_array_size(len),
+        None if flexible => uvarint_size(0),
+        _ => ARRAY_LEN_SIZE,
+    }
+}
+
+/// The size of the tagged fields section with the unknown and the present known tagged fields.
+fn tagged_section_size(unknown: &[TaggedField], known: &[Option<(u32, usize)>]) -> usize {
+    let fields = unknown
+        .iter()
+        .map(|field| (field.tag, field.data.len()))
+        .chain(known.iter().flat_map(|field| field.iter().cloned()))
+        .collect::<Vec<_>>();
+
+    fields.iter().fold(uvarint_size(fields.len() as u32), |size, &(tag, len)| {
+        size + uvarint_size(tag) + uvarint_size(len as u32) + len
+    })
+}
+
+fn put_string_field<T: ByteOrder, S: AsRef<str>>(dst: &mut BytesMut, s: Option<S>, flexible: bool) -> Result<()> {
+    if flexible {
+        dst.put_compact_str(s)
+    } else {
+        dst.put_str::<T, _>(s)
+    }
+}
+
+fn put_bytes_field<T: ByteOrder, D: AsRef<[u8]>>(dst: &mut BytesMut, d: Option<D>, flexible: bool) -> Result<()> {
+    if flexible {
+        dst.put_compact_bytes(d)
+    } else {
+        dst.put_bytes::<T, _>(d)
+    }
+}
+
+fn put_array_field<T, E, F>(dst: &mut BytesMut, items: Option<&[E]>, flexible: bool, callback: F) -> Result<()>
+where
+    T: ByteOrder,
+    F: FnMut(&mut BytesMut, &E) -> Result<()>,
+{
+    match items {
+        Some(items) if flexible => dst.put_compact_array(items, callback),
+        Some(items) => dst.put_array::<T, _, _>(items, callback),
+        None if flexible => {
+            dst.put_uvarint(0);
+            Ok(())
+        }
+        None => {
+            dst.put_i32::<T>(-1);
+            Ok(())
+        }
+    }
+}
+
+/// Encode a known tagged field with the encoded size of its value.
+fn encode_tagged_field<F>(tag: u32, size: usize, callback: F) -> Result<TaggedField>
+where
+    F: FnOnce(&mut BytesMut) -> Result<()>,
+{
+    let mut buf = BytesMut::with_capacity(size);
+
+    callback(&mut buf)?;
+
+    Ok(TaggedField {
+        tag,
+        data: buf.freeze(),
+    })
+}
+
+named!(parse_bool<bool>, map!(be_i8, |v| v != 0));
+
+fn parse_string_field(input: &[u8], flexible: bool) -> IResult<&[u8], String> {
+    if flexible {
+        parse_compact_string(input)
+    } else {
+        parse_string(input)
+    }
+}
+
+fn parse_opt_string_field(input: &[u8], flexible: bool) -> IResult<&[u8], Option<String>> {
+    if flexible {
+        parse_compact_opt_string(input)
+    } else {
+        parse_opt_string(input)
+    }
+}
+
+fn parse_bytes_field(input: &[u8], flexible: bool) -> IResult<&[u8], Bytes> {
+    if flexible {
+        parse_compact_bytes(input)
+    } else {
+        parse_bytes(input)
+    }
+}
+
+fn parse_opt_bytes_field(input: &[u8], flexible: bool) -> IResult<&[u8], Option<Bytes>> {
+    if flexible {
+        parse_compact_opt_bytes(input)
+    } else {
+        parse_opt_bytes(input)
+    }
+}
+
+/// Parse an array, the null array is parsed as an empty array.
+fn parse_array_field<'a, O, F>(input: &'a [u8], flexible: bool, f: F) -> IResult<&'a [u8], Vec<O>>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    parse_opt_array_field(input, flexible, f).map(Option::unwrap_or_default)
+}
+
+fn parse_opt_array_field<'a, O, F>(input: &'a [u8], flexible: bool, f: F) -> IResult<&'a [u8], Option<Vec<O>>>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    if flexible {
+        compact_count!(input, call!(f))
+    } else {
+        do_parse!(input,
+            n: be_i32
+         >> items: cond!(n >= 0, count!(call!(f), n as usize))
+         >> (items)
+        )
+    }
+}
+
+/// Parse the response header, which has the tagged fields in the flexible versions.
+fn parse_versioned_response_header(
+    input: &[u8],
+    api_key: ApiKey,
+    api_version: ApiVersion,
+) -> IResult<&[u8], ResponseHeader> {
+    if ApiKeys::from(api_key).response_header_version(api_version) > 0 {
+        parse_flexible_response_header(input)
+    } else {
+        parse_response_header(input)
+    }
+}
+
+/// Parse the value of a known tagged field, returns `None` if it is absent.
+fn parse_tagged_field<'a, O, F>(fields: Option<&'a Vec<TaggedField>>, tag: u32, f: F) -> Result<Option<O>>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    match fields.and_then(|fields| fields.iter().find(|field| field.tag == tag)) {
+        Some(field) => match f(&field.data[..]) {
+            IResult::Done(_, value) => Ok(Some(value)),
+            _ => bail!(ErrorKind::ParseError(format!("invalid tagged field #{}", tag))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// The tagged fields which are not known by the message.
+fn unknown_tagged_fields(fields: Option<Vec<TaggedField>>, known_tags: &[u32]) -> Vec<TaggedField> {
+    fields
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|field| !known_tags.contains(&field.tag))
+        .collect()
+}
+
+/// Flatten the nullable field which is not present in all the versions.
+fn flatten<T>(v: Option<Option<T>>) -> Option<T> {
+    v.and_then(|v| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    fn new_offset_for_leader_epoch_request<'a>(api_version: ApiVersion) -> OffsetForLeaderEpochRequest<'a> {
+        OffsetForLeaderEpochRequest {
+            header: RequestHeader {
+                api_key: OffsetForLeaderEpochRequest::API_KEY,
+                api_version,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            replica_id: -1,
+            topics: vec![
+                offset_for_leader_epoch_request::OffsetForLeaderTopic {
+                    topic: "topic".into(),
+                    partitions: vec![
+                        offset_for_leader_epoch_request::OffsetForLeaderPartition {
+                            partition: 1,
+                            leader_epoch: 5,
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+            unknown_tagged_fields: vec![],
+        }
+    }
+
+    #[test]
+    fn test_encode_offset_for_leader_epoch_request() {
+        let req = new_offset_for_leader_epoch_request(3);
+
+        let data = vec![
+            /* RequestHeader */ 0, 23 /* api_key */, 0, 3 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 255, 255, 255,
+            255 /* replica_id */, /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic */,
+            /* partitions */ 0, 0, 0, 1, 0, 0, 0, 1 /* partition */, 255, 255, 255,
+            255 /* current_leader_epoch */, 0, 0, 0, 5 /* leader_epoch */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_encode_offset_for_leader_epoch_request_v4() {
+        let req = new_offset_for_leader_epoch_request(4);
+
+        let data = vec![
+            /* RequestHeader */ 0, 23 /* api_key */, 0, 4 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */,
+            0 /* tagged_fields */, 255, 255, 255, 255 /* replica_id */, /* topics */ 2, 6, b't', b'o', b'p', b'i',
+            b'c' /* topic */, /* partitions */ 2, 0, 0, 0, 1 /* partition */, 255, 255, 255,
+            255 /* current_leader_epoch */, 0, 0, 0, 5 /* leader_epoch */, 0 /* tagged_fields */,
+            0 /* tagged_fields */, 0 /* tagged_fields */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_encode_describe_log_dirs_request() {
+        let mut req = DescribeLogDirsRequest {
+            header: RequestHeader {
+                api_key: DescribeLogDirsRequest::API_KEY,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            topics: None,
+            unknown_tagged_fields: vec![],
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 35 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 255, 255, 255,
+            255 /* topics */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+        assert_eq!(&buf[..], &data[..]);
+
+        req.header.api_version = 2;
+        req.topics = Some(vec![
+            describe_log_dirs_request::DescribableLogDirTopic {
+                topic: "topic".into(),
+                partition_index: vec![0, 1],
+                unknown_tagged_fields: vec![],
+            },
+        ]);
+        req.unknown_tagged_fields = vec![
+            TaggedField {
+                tag: 7,
+                data: Bytes::from(&b"x"[..]),
+            },
+        ];
+
+        let data = vec![
+            /* RequestHeader */ 0, 35 /* api_key */, 0, 2 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */,
+            0 /* tagged_fields */, /* topics */ 2, 6, b't', b'o', b'p', b'i', b'c' /* topic */, 3, 0, 0, 0, 0, 0,
+            0, 0, 1 /* partition_index */, 0 /* tagged_fields */, 1, 7, 1, b'x' /* tagged_fields */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_offset_for_leader_epoch_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o',
+            b'p', b'i', b'c' /* topic */, /* partitions */ 0, 0, 0, 1, 0, 0 /* error_code */, 0, 0, 0,
+            1 /* partition */, 0, 0, 0, 0, 0, 0, 1, 200 /* end_offset */,
+        ];
+
+        let res = OffsetForLeaderEpochResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time_ms: None,
+            topics: vec![
+                offset_for_leader_epoch_response::OffsetForLeaderTopicResult {
+                    topic: "topic".to_owned(),
+                    partitions: vec![
+                        offset_for_leader_epoch_response::EpochEndOffset {
+                            error_code: 0,
+                            partition: 1,
+                            leader_epoch: None,
+                            end_offset: 456,
+                            unknown_tagged_fields: vec![],
+                        },
+                    ],
+                    unknown_tagged_fields: vec![],
+                },
+            ],
+            unknown_tagged_fields: vec![],
+        };
+
+        assert_eq!(OffsetForLeaderEpochResponse::parse(&data[..], 0), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_parse_describe_log_dirs_response_v2() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0 /* tagged_fields */, 0, 0, 0,
+            0 /* throttle_time_ms */, /* results */ 2, 0, 0 /* error_code */, 5, b'/', b'l', b'o',
+            b'g' /* log_dir */, /* topics */ 2, 6, b't', b'o', b'p', b'i', b'c' /* name */, /* partitions */ 2, 0,
+            0, 0, 1 /* partition_index */, 0, 0, 0, 0, 0, 0, 4, 0 /* partition_size */, 0, 0, 0, 0, 0, 0, 0,
+            0 /* offset_lag */, 1 /* is_future_key */, 0 /* tagged_fields */, 0 /* tagged_fields */,
+            0 /* tagged_fields */, 1, 3, 2, b'o', b'k' /* tagged_fields */,
+        ];
+
+        let res = DescribeLogDirsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time_ms: 0,
+            results: vec![
+                describe_log_dirs_response::DescribeLogDirsResult {
+                    error_code: 0,
+                    log_dir: "/log".to_owned(),
+                    topics: vec![
+                        describe_log_dirs_response::DescribeLogDirsTopic {
+                            name: "topic".to_owned(),
+                            partitions: vec![
+                                describe_log_dirs_response::DescribeLogDirsPartition {
+                                    partition_index: 1,
+                                    partition_size: 1024,
+                                    offset_lag: 0,
+                                    is_future_key: true,
+                                    unknown_tagged_fields: vec![],
+                                },
+                            ],
+                            unknown_tagged_fields: vec![],
+                        },
+                    ],
+                    unknown_tagged_fields: vec![],
+                },
+            ],
+            unknown_tagged_fields: vec![
+                TaggedField {
+                    tag: 3,
+                    data: Bytes::from(&b"ok"[..]),
+                },
+            ],
+        };
+
+        let r = DescribeLogDirsResponse::parse(&data[..], 2);
+
+        display_parse_error::<_>(&data[..], r.clone());
+
+        assert_eq!(r, IResult::Done(&[][..], res));
+    }
+}
@@ -2,15 +2,19 @@ use std::borrow::Cow;
 
 use bytes::{ByteOrder, BytesMut};
 
-use nom::{IResult, be_i16, be_i32};
+use nom::{IResult, be_i16, be_i32, be_i8};
 
 use errors::Result;
-use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, NodeId, ParseTag, PartitionId,
-               Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE, STR_LEN_SIZE};
+use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, NodeId,
+               ParseTag, PartitionId, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE, STR_LEN_SIZE};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MetadataRequest<'a> {
     pub header: RequestHeader<'a>,
+    /// An array of topics to fetch metadata for.
+    ///
+    /// If no topics are specified fetch metadata for all topics in v0,
+    /// but fetch metadata for none of the topics since v1.
     pub topic_names: Vec<Cow<'a, str>>,
 }
 
@@ -38,6 +42,8 @@ impl<'a> Encodable for MetadataRequest<'a> {
 pub struct MetadataResponse {
     pub header: ResponseHeader,
     pub brokers: Vec<BrokerMetadata>,
+    /// The broker id of the controller broker (since v1).
+    pub controller_id: Option<NodeId>,
     pub topics: Vec<TopicMetadata>,
 }
 
@@ -46,12 +52,16 @@ pub struct BrokerMetadata {
     pub node_id: NodeId,
     pub host: String,
     pub port: i32,
+    /// The rack of the broker (since v1).
+    pub rack: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct TopicMetadata {
     pub error_code: ErrorCode,
     pub topic_name: String,
+    /// Indicates if the topic is considered a Kafka internal topic (since v1).
+    pub is_internal: Option<bool>,
     pub partitions: Vec<PartitionMetadata>,
 }
 
@@ -65,43 +75,56 @@ pub struct PartitionMetadata {
 }
 
 impl MetadataResponse {
-    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        parse_metadata_response(buf)
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_metadata_response(buf, api_version)
     }
 }
 
-named!(
-    parse_metadata_response<MetadataResponse>,
-    parse_tag!(
-        ParseTag::MetadataResponse,
+named_args!(parse_metadata_response(api_version: ApiVersion)<MetadataResponse>,
+    parse_tag!(ParseTag::MetadataResponse,
         do_parse!(
-            header: parse_response_header >> brokers: length_count!(be_i32, parse_broker_metadata)
-                >> topics: length_count!(be_i32, parse_topic_metadata) >> (MetadataResponse {
+            header: parse_response_header
+         >> brokers: length_count!(be_i32, apply!(parse_broker_metadata, api_version))
+         >> controller_id: cond!(api_version > 0, be_i32)
+         >> topics: length_count!(be_i32, apply!(parse_topic_metadata, api_version))
+         >> (MetadataResponse {
                 header,
                 brokers,
+                controller_id,
                 topics,
             })
         )
     )
 );
 
-named!(
-    parse_broker_metadata<BrokerMetadata>,
-    parse_tag!(
-        ParseTag::BrokerMetadata,
-        do_parse!(node_id: be_i32 >> host: parse_string >> port: be_i32 >> (BrokerMetadata { node_id, host, port }))
+named_args!(parse_broker_metadata(api_version: ApiVersion)<BrokerMetadata>,
+    parse_tag!(ParseTag::BrokerMetadata,
+        do_parse!(
+            node_id: be_i32
+         >> host: parse_string
+         >> port: be_i32
+         >> rack: cond!(api_version > 0, parse_opt_string)
+         >> (BrokerMetadata {
+                node_id,
+                host,
+                port,
+                rack: rack.and_then(|rack| rack),
+            })
+        )
     )
 );
 
-named!(
-    parse_topic_metadata<TopicMetadata>,
-    parse_tag!(
-        ParseTag::TopicMetadata,
+named_args!(parse_topic_metadata(api_version: ApiVersion)<TopicMetadata>,
+    parse_tag!(ParseTag::TopicMetadata,
         do_parse!(
-            error_code: be_i16 >> topic_name: parse_string
-                >> partitions: length_count!(be_i32, parse_partition_metadata) >> (TopicMetadata {
+            error_code: be_i16
+         >> topic_name: parse_string
+         >> is_internal: cond!(api_version > 0, be_i8)
+         >> partitions: length_count!(be_i32, parse_partition_metadata)
+         >> (TopicMetadata {
                 error_code,
                 topic_name,
+                is_internal: is_internal.map(|is_internal| is_internal != 0),
                 partitions,
             })
         )
@@ -178,10 +201,13 @@ mod tests {
                 node_id: 1,
                 host: "host".to_owned(),
                 port: 80,
+                rack: None,
             }],
+            controller_id: None,
             topics: vec![TopicMetadata {
                 error_code: 2,
                 topic_name: "topic".to_owned(),
+                is_internal: None,
                 partitions: vec![PartitionMetadata {
                     error_code: 3,
                     partition_id: 4,
@@ -217,8 +243,41 @@ mod tests {
     #[test]
     fn test_parse_metadata_response() {
         assert_eq!(
-            parse_metadata_response(TEST_RESPONSE_DATA.as_slice()),
+            parse_metadata_response(TEST_RESPONSE_DATA.as_slice(), 0),
             IResult::Done(&[][..], TEST_RESPONSE.clone())
         );
     }
+
+    #[test]
+    fn test_parse_metadata_response_v1() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, /* brokers */ 0, 0, 0, 1, 0, 0, 0,
+            1 /* node_id */, 0, 4, b'h', b'o', b's', b't' /* host */, 0, 0, 0, 80 /* port */, 0, 4, b'r', b'a',
+            b'c', b'k' /* rack */, 0, 0, 0, 1 /* controller_id */, /* topics */ 0, 0, 0, 1, 0, 0 /* error_code */,
+            0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 1 /* is_internal */, /* partitions */ 0, 0, 0, 0,
+        ];
+
+        let res = MetadataResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            brokers: vec![
+                BrokerMetadata {
+                    node_id: 1,
+                    host: "host".to_owned(),
+                    port: 80,
+                    rack: Some("rack".to_owned()),
+                },
+            ],
+            controller_id: Some(1),
+            topics: vec![
+                TopicMetadata {
+                    error_code: 0,
+                    topic_name: "topic".to_owned(),
+                    is_internal: Some(true),
+                    partitions: vec![],
+                },
+            ],
+        };
+
+        assert_eq!(parse_metadata_response(data.as_slice(), 1), IResult::Done(&[][..], res));
+    }
 }
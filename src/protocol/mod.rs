@@ -13,41 +13,76 @@ mod code;
 mod encode;
 #[macro_use]
 mod parse;
+mod acl;
 mod api_versions;
+mod config;
+mod delete_records;
 mod fetch;
 mod group;
 mod header;
 mod list_offset;
 mod message;
+pub mod messages;
 mod metadata;
 mod offset_commit;
 mod offset_fetch;
 mod produce;
+mod sasl;
 mod schema;
+mod topic;
+mod transaction;
 
+pub use self::acl::{AccessControlEntry, AclBinding, AclBindingFilter, AclOperation, AclPermissionType,
+                    CreateAclsRequest, CreateAclsResponse, CreateAclsStatus, DeleteAclsFilterStatus,
+                    DeleteAclsMatchingAcl, DeleteAclsRequest, DeleteAclsResponse, DescribeAclsRequest,
+                    DescribeAclsResource, DescribeAclsResponse, PatternType, ResourcePattern, ResourceType};
 pub use self::api_key::{ApiKey, ApiKeys};
 pub use self::api_versions::{ApiVersionsRequest, ApiVersionsResponse, UsableApiVersion, UsableApiVersions, SUPPORTED_API_VERSIONS};
 pub use self::code::{ErrorCode, KafkaCode};
-pub use self::encode::{Encodable, WriteExt, ARRAY_LEN_SIZE, BYTES_LEN_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE,
-                       REPLICA_ID_SIZE, STR_LEN_SIZE, TIMESTAMP_SIZE};
-pub use self::fetch::{FetchPartition, FetchRequest, FetchResponse, FetchTopic, FetchTopicData,
-                      DEFAULT_RESPONSE_MAX_BYTES};
-pub use self::group::{DescribeGroupsRequest, DescribeGroupsResponse, GroupCoordinatorRequest,
-                      GroupCoordinatorResponse, HeartbeatRequest, HeartbeatResponse, JoinGroupMember,
-                      JoinGroupProtocol, JoinGroupRequest, JoinGroupResponse, LeaveGroupRequest, LeaveGroupResponse,
-                      ListGroupsRequest, ListGroupsResponse, SyncGroupAssignment, SyncGroupRequest, SyncGroupResponse};
-pub use self::header::{parse_response_header, RequestHeader, ResponseHeader};
+pub use self::config::{AlterConfigsRequest, AlterConfigsResource, AlterConfigsResourceStatus, AlterConfigsResponse,
+                       ConfigResourceType, ConfigSource, ConfigSynonym, DescribeConfigsEntry, DescribeConfigsRequest,
+                       DescribeConfigsResource, DescribeConfigsResourceStatus, DescribeConfigsResponse};
+pub use self::delete_records::{DeleteRecordsPartition, DeleteRecordsPartitionStatus, DeleteRecordsRequest,
+                               DeleteRecordsResponse, DeleteRecordsTopic, DeleteRecordsTopicStatus};
+pub use self::encode::{compact_array_size, compact_bytes_size, tagged_fields_size, uvarint_size, varint_size,
+                       varlong_size, Encodable, TaggedField, WriteExt, ARRAY_LEN_SIZE, BYTES_LEN_SIZE,
+                       ISOLATION_LEVEL_SIZE, LEADER_EPOCH_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE, REPLICA_ID_SIZE,
+                       STR_LEN_SIZE, TIMESTAMP_SIZE};
+pub use self::fetch::{AbortedTransaction, FetchPartition, FetchPartitionData, FetchRequest, FetchResponse, FetchTopic,
+                      FetchTopicData, ForgottenTopic, DEFAULT_RESPONSE_MAX_BYTES, FINAL_SESSION_EPOCH,
+                      INITIAL_SESSION_EPOCH, INVALID_LOG_START_OFFSET, INVALID_SESSION_ID};
+pub use self::group::{CoordinatorType, DeleteGroupsRequest, DeleteGroupsResponse, DescribeGroupsRequest,
+                      DescribeGroupsResponse, GroupCoordinatorRequest, GroupCoordinatorResponse, HeartbeatRequest,
+                      HeartbeatResponse, JoinGroupMember, JoinGroupProtocol, JoinGroupRequest, JoinGroupResponse,
+                      LeaveGroupRequest, LeaveGroupResponse, ListGroupsRequest, ListGroupsResponse, SyncGroupAssignment,
+                      SyncGroupRequest, SyncGroupResponse};
+pub use self::header::{parse_flexible_response_header, parse_response_header, RequestHeader, ResponseHeader};
 pub use self::list_offset::{FetchOffset, ListOffsetRequest, ListOffsetResponse, ListPartitionOffset, ListTopicOffset,
                             EARLIEST_TIMESTAMP, LATEST_TIMESTAMP};
 pub use self::message::{parse_message_set, Message, MessageSet, MessageSetBuilder, MessageSetEncoder,
-                        MessageTimestamp, RecordFormat};
+                        MessageTimestamp, RecordBatchHeader, RecordFormat, RecordHeader, CONTROL_FLAG_MASK,
+                        NO_PARTITION_LEADER_EPOCH, NO_PRODUCER_EPOCH, NO_PRODUCER_ID, NO_SEQUENCE,
+                        TRANSACTIONAL_FLAG_MASK};
 pub use self::metadata::{BrokerMetadata, MetadataRequest, MetadataResponse, PartitionMetadata, TopicMetadata};
 pub use self::offset_commit::{OffsetCommitPartition, OffsetCommitRequest, OffsetCommitResponse, OffsetCommitTopic};
 pub use self::offset_fetch::{OffsetFetchPartition, OffsetFetchRequest, OffsetFetchResponse, OffsetFetchTopic};
-pub use self::parse::{display_parse_error, parse_bytes, parse_opt_bytes, parse_opt_str, parse_opt_string, parse_str,
-                      parse_string, ParseTag, PARSE_TAGS};
-pub use self::produce::{ProducePartitionData, ProduceRequest, ProduceResponse, ProduceTopicData};
-pub use self::schema::{Nullable, Schema, SchemaType, VarInt, VarLong};
+pub use self::parse::{display_parse_error, parse_bytes, parse_compact_bytes, parse_compact_opt_bytes,
+                      parse_compact_opt_string, parse_compact_string, parse_opt_bytes, parse_opt_str, parse_opt_string,
+                      parse_opt_varbytes, parse_str, parse_string, parse_tagged_fields, parse_uvarint, parse_varint,
+                      parse_varlong, parse_varstring, ParseTag, PARSE_TAGS};
+pub use self::produce::{ProducePartitionData, ProduceRequest, ProduceResponse, ProduceTopicData, UNKNOWN_OFFSET};
+pub use self::sasl::{SaslAuthenticateRequest, SaslAuthenticateResponse, SaslHandshakeRequest, SaslHandshakeResponse};
+pub use self::schema::{Compact, CompactNullable, Nullable, Schema, SchemaType, TaggedFields, UVarInt, VarInt, VarLong};
+pub use self::topic::{ConfigEntry, CreatePartitionsRequest, CreatePartitionsResponse, CreatePartitionsTopic,
+                      CreatePartitionsTopicStatus, CreateTopicsRequest, CreateTopicsResponse, CreateTopicsTopic,
+                      CreateTopicsTopicStatus, DeleteTopicsRequest, DeleteTopicsResponse, DeleteTopicsTopicStatus,
+                      ReplicaAssignment};
+pub use self::transaction::{AddOffsetsToTxnRequest, AddOffsetsToTxnResponse, AddPartitionsToTxnPartitionStatus,
+                            AddPartitionsToTxnRequest, AddPartitionsToTxnResponse, AddPartitionsToTxnTopic,
+                            AddPartitionsToTxnTopicStatus, EndTxnRequest, EndTxnResponse, InitProducerIdRequest,
+                            InitProducerIdResponse, TxnOffsetCommitPartition, TxnOffsetCommitPartitionStatus,
+                            TxnOffsetCommitRequest, TxnOffsetCommitResponse, TxnOffsetCommitTopic,
+                            TxnOffsetCommitTopicStatus};
 
 /// Normal client consumers should always specify this as -1 as they have no
 /// node id.
@@ -96,6 +131,18 @@ pub type RequiredAck = i16;
 /// The generation of the group.
 pub type GenerationId = i32;
 
+/// The producer id assigned by the transaction coordinator.
+pub type ProducerId = i64;
+
+/// The epoch of the producer id, which is bumped to fence the zombie producers.
+pub type ProducerEpoch = i16;
+
+/// The sequence number of the record produced by an idempotent producer.
+pub type Sequence = i32;
+
+/// The epoch of the partition leader, which is bumped when the leader changes.
+pub type LeaderEpoch = i32;
+
 /// Possible choices on acknowledgement requirements when producing/sending
 /// messages to Kafka.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -141,6 +188,38 @@ impl FromStr for RequiredAcks {
     }
 }
 
+/// Controls the visibility of the transactional records when fetching or listing offsets.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(i8)]
+pub enum IsolationLevel {
+    /// Makes all records visible, including the records of the ongoing and aborted transactions.
+    ReadUncommitted = 0,
+    /// Makes only the non-transactional records and the records of the committed transactions visible.
+    ///
+    /// The fetching is limited to the last stable offset (LSO),
+    /// which is the offset of the first record of the first ongoing transaction.
+    ReadCommitted = 1,
+}
+
+impl Default for IsolationLevel {
+    fn default() -> Self {
+        IsolationLevel::ReadUncommitted
+    }
+}
+
+impl FromStr for IsolationLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "read_uncommitted" => Ok(IsolationLevel::ReadUncommitted),
+            "read_committed" => Ok(IsolationLevel::ReadCommitted),
+            _ => bail!(ErrorKind::ParseError(format!("unknown isolation level: {}", s))),
+        }
+    }
+}
+
 pub trait Record {
     fn size(&self, api_version: ApiVersion) -> usize;
 }
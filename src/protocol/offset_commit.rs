@@ -4,9 +4,9 @@ use std::borrow::Cow;
 use nom::{IResult, be_i16, be_i32};
 
 use errors::Result;
-use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, Offset, ParseTag, PartitionId,
-               Record, RequestHeader, ResponseHeader, Timestamp, WriteExt, ARRAY_LEN_SIZE, OFFSET_SIZE,
-               PARTITION_ID_SIZE, STR_LEN_SIZE, TIMESTAMP_SIZE};
+use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, LeaderEpoch, Offset, ParseTag,
+               PartitionId, Record, RequestHeader, ResponseHeader, Timestamp, WriteExt, ARRAY_LEN_SIZE,
+               LEADER_EPOCH_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE, STR_LEN_SIZE, TIMESTAMP_SIZE};
 
 pub const DEFAULT_RETENTION_TIME: i64 = -1;
 
@@ -22,7 +22,8 @@ pub struct OffsetCommitRequest<'a> {
     pub group_generation_id: Option<i32>,
     /// The member id assigned by the group coordinator.
     pub member_id: Option<Cow<'a, str>>,
-    /// Time period in ms to retain the offset.
+    /// Time period in ms to retain the offset (v2 to v4),
+    /// the broker's `offsets.retention.minutes` is used since v5.
     pub retention_time: Option<i64>,
     /// Topic to commit.
     pub topics: Vec<OffsetCommitTopic<'a>>,
@@ -42,6 +43,9 @@ pub struct OffsetCommitPartition<'a> {
     pub partition_id: PartitionId,
     /// Message offset to be committed.
     pub offset: Offset,
+    /// The leader epoch of the last consumed record (since v6),
+    /// or `NO_PARTITION_LEADER_EPOCH` if unknown.
+    pub leader_epoch: LeaderEpoch,
     /// Timestamp of the commit
     pub timestamp: Timestamp,
     /// Any associated metadata the client wants to keep.
@@ -51,6 +55,9 @@ pub struct OffsetCommitPartition<'a> {
 #[derive(Clone, Debug, PartialEq)]
 pub struct OffsetCommitResponse {
     pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation (since v3).
+    pub throttle_time: Option<i32>,
     /// Topics to commit offsets.
     pub topics: Vec<OffsetCommitTopicStatus>,
 }
@@ -78,12 +85,13 @@ impl<'a> Record for OffsetCommitRequest<'a> {
                 GROUP_GENERATION_ID_SIZE + STR_LEN_SIZE + self.member_id.as_ref().map_or(0, |s| s.len())
             } else {
                 0
-            } + if api_version > 1 { RETENTION_TIME } else { 0 }
+            } + if api_version > 1 && api_version < 5 { RETENTION_TIME } else { 0 }
             + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
                 size + STR_LEN_SIZE + topic.topic_name.len()
                     + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, partition| {
-                        size + PARTITION_ID_SIZE + OFFSET_SIZE + if api_version == 1 { TIMESTAMP_SIZE } else { 0 }
-                            + STR_LEN_SIZE + partition.metadata.as_ref().map_or(0, |s| s.len())
+                        size + PARTITION_ID_SIZE + OFFSET_SIZE + if api_version > 5 { LEADER_EPOCH_SIZE } else { 0 }
+                            + if api_version == 1 { TIMESTAMP_SIZE } else { 0 } + STR_LEN_SIZE
+                            + partition.metadata.as_ref().map_or(0, |s| s.len())
                     })
             })
     }
@@ -100,7 +108,7 @@ impl<'a> Encodable for OffsetCommitRequest<'a> {
             dst.put_i32::<T>(self.group_generation_id.unwrap_or_default());
             dst.put_str::<T, _>(self.member_id.as_ref())?;
         }
-        if api_version > 1 {
+        if api_version > 1 && api_version < 5 {
             dst.put_i64::<T>(self.retention_time.unwrap_or(DEFAULT_RETENTION_TIME));
         }
         dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
@@ -108,6 +116,9 @@ impl<'a> Encodable for OffsetCommitRequest<'a> {
             buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
                 buf.put_i32::<T>(partition.partition_id);
                 buf.put_i64::<T>(partition.offset);
+                if api_version > 5 {
+                    buf.put_i32::<T>(partition.leader_epoch);
+                }
                 if api_version == 1 {
                     buf.put_i64::<T>(partition.timestamp);
                 }
@@ -118,18 +129,22 @@ impl<'a> Encodable for OffsetCommitRequest<'a> {
 }
 
 impl OffsetCommitResponse {
-    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        parse_offset_commit_response(buf)
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_offset_commit_response(buf, api_version)
     }
 }
 
-named!(
-    parse_offset_commit_response<OffsetCommitResponse>,
-    parse_tag!(
-        ParseTag::OffsetCommitResponse,
+named_args!(parse_offset_commit_response(api_version: ApiVersion)<OffsetCommitResponse>,
+    parse_tag!(ParseTag::OffsetCommitResponse,
         do_parse!(
-            header: parse_response_header >> topics: length_count!(be_i32, parse_offset_commit_topic_status)
-                >> (OffsetCommitResponse { header, topics })
+            header: parse_response_header
+         >> throttle_time: cond!(api_version > 2, be_i32)
+         >> topics: length_count!(be_i32, parse_offset_commit_topic_status)
+         >> (OffsetCommitResponse {
+                header,
+                throttle_time,
+                topics,
+            })
         )
     )
 );
@@ -186,6 +201,7 @@ mod tests {
                         OffsetCommitPartition {
                             partition_id: 5,
                             offset: 6,
+                            leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             timestamp: Default::default(),
                             metadata: Some("metadata".into()),
                         },
@@ -234,6 +250,7 @@ mod tests {
                         OffsetCommitPartition {
                             partition_id: 5,
                             offset: 6,
+                            leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             timestamp: 7,
                             metadata: Some("metadata".into()),
                         },
@@ -284,6 +301,7 @@ mod tests {
                         OffsetCommitPartition {
                             partition_id: 5,
                             offset: 6,
+                            leader_epoch: NO_PARTITION_LEADER_EPOCH,
                             timestamp: Default::default(),
                             metadata: Some("metadata".into()),
                         },
@@ -318,6 +336,7 @@ mod tests {
     fn test_parse_offset_commit_response() {
         let response = OffsetCommitResponse {
             header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
             topics: vec![
                 OffsetCommitTopicStatus {
                     topic_name: "topic".to_owned(),
@@ -338,7 +357,89 @@ mod tests {
             1 /* partition */, 0, 2 /* error_code */,
         ];
 
-        let res = parse_offset_commit_response(&data[..]);
+        let res = parse_offset_commit_response(&data[..], 0);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_encode_offset_commit_request_v6() {
+        let req = OffsetCommitRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::OffsetCommit as ApiKey,
+                api_version: 6,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            group_id: Some("consumer".into()),
+            group_generation_id: Some(456),
+            member_id: Some("member".into()),
+            retention_time: Some(789),
+            topics: vec![
+                OffsetCommitTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        OffsetCommitPartition {
+                            partition_id: 5,
+                            offset: 6,
+                            leader_epoch: 7,
+                            timestamp: Default::default(),
+                            metadata: Some("metadata".into()),
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* OffsetCommitRequest
+             * RequestHeader */ 0, 8 /* api_key */, 0,
+            6 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n',
+            b't' /* client_id */, 0, 8, b'c', b'o', b'n', b's', b'u', b'm', b'e', b'r' /* group_id */, 0, 0,
+            1, 200 /* group_generation_id */, 0, 6, b'm', b'e', b'm', b'b', b'e', b'r' /* member_id */,
+            /* topics: [OffsetCommitTopic] */ 0, 0, 0, 1, /* OffsetCommitTopic */ 0, 5, b't', b'o', b'p',
+            b'i', b'c' /* topic_name */, /* partitions: [OffsetCommitPartition] */ 0, 0, 0, 1,
+            /* OffsetCommitPartition */ 0, 0, 0, 5 /* partition */, 0, 0, 0, 0, 0, 0, 0, 6 /* offset */,
+            0, 0, 0, 7 /* leader_epoch */, 0, 8, b'm', b'e', b't', b'a', b'd', b'a', b't', b'a' /* metadata */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_offset_commit_response_v3() {
+        let response = OffsetCommitResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            topics: vec![
+                OffsetCommitTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        OffsetCommitPartitionStatus {
+                            partition_id: 1,
+                            error_code: 2,
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [OffsetCommitTopicStatus] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* topic_name */, /* partitions: [OffsetCommitPartitionStatus] */ 0, 0, 0, 1, 0, 0, 0,
+            1 /* partition */, 0, 2 /* error_code */,
+        ];
+
+        let res = parse_offset_commit_response(&data[..], 3);
 
         display_parse_error::<_>(&data[..], res.clone());
 
@@ -4,8 +4,8 @@ use std::borrow::Cow;
 use nom::{IResult, be_i16, be_i32, be_i64};
 
 use errors::Result;
-use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, Offset,
-               ParseTag, PartitionId, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE,
+use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, LeaderEpoch,
+               Offset, ParseTag, PartitionId, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE,
                PARTITION_ID_SIZE, STR_LEN_SIZE};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -13,8 +13,8 @@ pub struct OffsetFetchRequest<'a> {
     pub header: RequestHeader<'a>,
     /// The group id.
     pub group_id: Cow<'a, str>,
-    /// Topic to fetch.
-    pub topics: Vec<OffsetFetchTopic<'a>>,
+    /// Topic to fetch, or `None` to fetch all the committed offsets of the group (since v2).
+    pub topics: Option<Vec<OffsetFetchTopic<'a>>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -34,8 +34,13 @@ pub struct OffsetFetchPartition {
 #[derive(Clone, Debug, PartialEq)]
 pub struct OffsetFetchResponse {
     pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation (since v3).
+    pub throttle_time: Option<i32>,
     /// Topics to fetch offsets.
     pub topics: Vec<OffsetFetchTopicStatus>,
+    /// The top-level error code, such as the group coordinator isn't available (since v2).
+    pub error_code: Option<ErrorCode>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -52,6 +57,8 @@ pub struct OffsetFetchPartitionStatus {
     pub partition_id: PartitionId,
     /// Last committed message offset.
     pub offset: Offset,
+    /// The leader epoch of the last consumed record (since v5).
+    pub leader_epoch: Option<LeaderEpoch>,
     /// Any associated metadata the client wants to keep.
     pub metadata: Option<String>,
     /// Error code.
@@ -61,12 +68,14 @@ pub struct OffsetFetchPartitionStatus {
 impl<'a> Record for OffsetFetchRequest<'a> {
     fn size(&self, api_version: ApiVersion) -> usize {
         self.header.size(api_version) + STR_LEN_SIZE + self.group_id.len()
-            + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
-                size + STR_LEN_SIZE + topic.topic_name.len()
-                    + topic
-                        .partitions
-                        .iter()
-                        .fold(ARRAY_LEN_SIZE, |size, _| size + PARTITION_ID_SIZE)
+            + self.topics.as_ref().map_or(ARRAY_LEN_SIZE, |topics| {
+                topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+                    size + STR_LEN_SIZE + topic.topic_name.len()
+                        + topic
+                            .partitions
+                            .iter()
+                            .fold(ARRAY_LEN_SIZE, |size, _| size + PARTITION_ID_SIZE)
+                })
             })
     }
 }
@@ -76,56 +85,73 @@ impl<'a> Encodable for OffsetFetchRequest<'a> {
         self.header.encode::<T>(dst)?;
 
         dst.put_str::<T, _>(Some(self.group_id.as_ref()))?;
-        dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
-            buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
-            buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
-                buf.put_i32::<T>(partition.partition_id);
-                Ok(())
+
+        if let Some(ref topics) = self.topics {
+            dst.put_array::<T, _, _>(topics, |buf, topic| {
+                buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+                buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
+                    buf.put_i32::<T>(partition.partition_id);
+                    Ok(())
+                })
             })
-        })
+        } else {
+            dst.put_i32::<T>(-1);
+            Ok(())
+        }
     }
 }
 
 impl OffsetFetchResponse {
-    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
-        parse_offset_fetch_response(buf)
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_offset_fetch_response(buf, api_version)
     }
 }
 
-named!(
-    parse_offset_fetch_response<OffsetFetchResponse>,
-    parse_tag!(
-        ParseTag::OffsetFetchResponse,
+named_args!(parse_offset_fetch_response(api_version: ApiVersion)<OffsetFetchResponse>,
+    parse_tag!(ParseTag::OffsetFetchResponse,
         do_parse!(
-            header: parse_response_header >> topics: length_count!(be_i32, parse_offset_fetch_topic_status)
-                >> (OffsetFetchResponse { header, topics })
+            header: parse_response_header
+         >> throttle_time: cond!(api_version > 2, be_i32)
+         >> topics: length_count!(be_i32, apply!(parse_offset_fetch_topic_status, api_version))
+         >> error_code: cond!(api_version > 1, be_i16)
+         >> (OffsetFetchResponse {
+                header,
+                throttle_time,
+                topics,
+                error_code,
+            })
         )
     )
 );
 
-named!(
-    parse_offset_fetch_topic_status<OffsetFetchTopicStatus>,
-    parse_tag!(
-        ParseTag::OffsetFetchTopicStatus,
+named_args!(parse_offset_fetch_topic_status(api_version: ApiVersion)<OffsetFetchTopicStatus>,
+    parse_tag!(ParseTag::OffsetFetchTopicStatus,
         do_parse!(
-            topic_name: parse_string >> partitions: length_count!(be_i32, parse_offset_fetch_partition_status)
-                >> (OffsetFetchTopicStatus { topic_name, partitions })
+            topic_name: parse_string
+         >> partitions: length_count!(be_i32, apply!(parse_offset_fetch_partition_status, api_version))
+         >> (OffsetFetchTopicStatus {
+                topic_name,
+                partitions,
+            })
         )
     )
 );
 
-named!(
-    parse_offset_fetch_partition_status<OffsetFetchPartitionStatus>,
-    parse_tag!(
-        ParseTag::OffsetFetchPartitionStatus,
+named_args!(parse_offset_fetch_partition_status(api_version: ApiVersion)<OffsetFetchPartitionStatus>,
+    parse_tag!(ParseTag::OffsetFetchPartitionStatus,
         do_parse!(
-            partition_id: be_i32 >> offset: be_i64 >> metadata: parse_opt_string >> error_code: be_i16
-                >> (OffsetFetchPartitionStatus {
-                    partition_id,
-                    offset,
-                    metadata,
-                    error_code,
-                })
+            partition_id: be_i32
+         >> offset: be_i64
+         >> leader_epoch: cond!(api_version > 4, be_i32)
+         >> metadata: parse_opt_string
+         >> error_code: be_i16
+         >> (OffsetFetchPartitionStatus {
+                partition_id,
+                offset,
+                leader_epoch,
+                metadata,
+                error_code,
+            })
         )
     )
 );
@@ -148,12 +174,12 @@ mod tests {
                 client_id: Some("client".into()),
             },
             group_id: "consumer".into(),
-            topics: vec![
+            topics: Some(vec![
                 OffsetFetchTopic {
                     topic_name: "topic".into(),
                     partitions: vec![OffsetFetchPartition { partition_id: 1 }],
                 },
-            ],
+            ]),
         };
 
         let data = vec![
@@ -175,10 +201,41 @@ mod tests {
         assert_eq!(&buf[..], &data[..]);
     }
 
+    #[test]
+    fn test_encode_offset_fetch_request_all_topics() {
+        let req = OffsetFetchRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::OffsetFetch as ApiKey,
+                api_version: 2,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            group_id: "consumer".into(),
+            topics: None,
+        };
+
+        let data = vec![
+            /* OffsetFetchRequest
+             * RequestHeader */ 0, 9 /* api_key */, 0,
+            2 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n',
+            b't' /* client_id */, 0, 8, b'c', b'o', b'n', b's', b'u', b'm', b'e', b'r' /* group_id */,
+            /* topics: null */ 255, 255, 255, 255,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
     #[test]
     fn test_parse_offset_fetch_response() {
         let response = OffsetFetchResponse {
             header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
             topics: vec![
                 OffsetFetchTopicStatus {
                     topic_name: "topic".to_owned(),
@@ -186,12 +243,14 @@ mod tests {
                         OffsetFetchPartitionStatus {
                             partition_id: 1,
                             offset: 2,
+                            leader_epoch: None,
                             metadata: Some("metadata".to_owned()),
                             error_code: 3,
                         },
                     ],
                 },
             ],
+            error_code: None,
         };
 
         let data = vec![
@@ -202,7 +261,44 @@ mod tests {
             b't', b'a' /* metadata */, 0, 3 /* error_code */,
         ];
 
-        let res = parse_offset_fetch_response(&data[..]);
+        let res = parse_offset_fetch_response(&data[..], 0);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_parse_offset_fetch_response_v5() {
+        let response = OffsetFetchResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            topics: vec![
+                OffsetFetchTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        OffsetFetchPartitionStatus {
+                            partition_id: 1,
+                            offset: 2,
+                            leader_epoch: Some(4),
+                            metadata: None,
+                            error_code: 0,
+                        },
+                    ],
+                },
+            ],
+            error_code: Some(3),
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics: [OffsetFetchTopicStatus] */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* topic_name */, /* partitions: [OffsetFetchPartitionStatus] */ 0, 0, 0, 1, 0, 0, 0,
+            1 /* partition */, 0, 0, 0, 0, 0, 0, 0, 2 /* offset */, 0, 0, 0, 4 /* leader_epoch */, 255,
+            255 /* metadata */, 0, 0 /* error_code */, 0, 3 /* error_code */,
+        ];
+
+        let res = parse_offset_fetch_response(&data[..], 5);
 
         display_parse_error::<_>(&data[..], res.clone());
 
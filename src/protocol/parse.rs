@@ -4,7 +4,9 @@ use std::str;
 
 use bytes::Bytes;
 
-use nom::{self, prepare_errors, print_offsets, IResult, be_i16, be_i32, error_to_u32};
+use nom::{self, prepare_errors, print_offsets, IResult, Needed, be_i16, be_i32, error_to_u32};
+
+use protocol::TaggedField;
 
 macro_rules! parse_tag (
     ($i:expr, $tag:expr, $submac:ident!( $($args:tt)* )) => (
@@ -25,6 +27,21 @@ macro_rules! parse_tag (
     );
 );
 
+/// Parse a compact array, which length is encoded as an unsigned varint of `N+1`,
+/// returns `None` for the null array.
+macro_rules! compact_count (
+    ($i:expr, $submac:ident!( $($args:tt)* )) => (
+        do_parse!($i,
+            n: call!($crate::protocol::parse_uvarint)
+         >> items: cond!(n > 0, count!($submac!($($args)*), (n - 1) as usize))
+         >> (items)
+        )
+    );
+    ($i:expr, $f:expr) => (
+        compact_count!($i, call!($f))
+    );
+);
+
 #[repr(i32)]
 pub enum ParseTag {
     ResponseHeader = 8000,
@@ -32,10 +49,15 @@ pub enum ParseTag {
 
     String = 8002,
     Bytes = 8003,
+    VarInt = 8004,
+    TaggedFields = 8005,
 
     MessageSet = 9001,
     Message = 9002,
     MessageCrc = 9003,
+    RecordBatch = 9004,
+    RecordBatchCrc = 9005,
+    Record = 9006,
 
     ProduceResponse = 10000,
     ProduceTopicStatus = 10001,
@@ -44,6 +66,7 @@ pub enum ParseTag {
     FetchResponse = 10100,
     FetchTopicData = 10101,
     FetchPartitionData = 10102,
+    AbortedTransaction = 10103,
 
     ListOffsetResponse = 10200,
     ListOffsetTopicStatus = 10201,
@@ -82,6 +105,58 @@ pub enum ParseTag {
 
     ApiVersionsResponse = 11800,
     ApiVersion = 11801,
+
+    CreateTopicsResponse = 11900,
+    CreateTopicsTopicStatus = 11901,
+
+    DeleteTopicsResponse = 12000,
+    DeleteTopicsTopicStatus = 12001,
+
+    DeleteRecordsResponse = 12100,
+    DeleteRecordsTopicStatus = 12101,
+    DeleteRecordsPartitionStatus = 12102,
+
+    SaslHandshakeResponse = 11700,
+
+    InitProducerIdResponse = 12200,
+
+    AddPartitionsToTxnResponse = 12400,
+    AddPartitionsToTxnTopicStatus = 12401,
+    AddPartitionsToTxnPartitionStatus = 12402,
+
+    AddOffsetsToTxnResponse = 12500,
+
+    EndTxnResponse = 12600,
+
+    TxnOffsetCommitResponse = 12800,
+    TxnOffsetCommitTopicStatus = 12801,
+    TxnOffsetCommitPartitionStatus = 12802,
+
+    DescribeAclsResponse = 12900,
+    DescribeAclsResource = 12901,
+
+    CreateAclsResponse = 13000,
+    CreateAclsStatus = 13001,
+
+    DeleteAclsResponse = 13100,
+    DeleteAclsFilterStatus = 13101,
+    DeleteAclsMatchingAcl = 13102,
+
+    DescribeConfigsResponse = 13200,
+    DescribeConfigsResourceStatus = 13201,
+    DescribeConfigsEntry = 13202,
+    ConfigSynonym = 13203,
+
+    AlterConfigsResponse = 13300,
+    AlterConfigsResourceStatus = 13301,
+
+    SaslAuthenticateResponse = 13600,
+
+    CreatePartitionsResponse = 13700,
+    CreatePartitionsTopicStatus = 13701,
+
+    DeleteGroupsResponse = 14200,
+    DeleteGroupsGroupStatus = 14201,
 }
 
 lazy_static! {
@@ -91,10 +166,15 @@ lazy_static! {
 
         h.insert(ParseTag::String as u32, "String");
         h.insert(ParseTag::Bytes as u32, "Bytes");
+        h.insert(ParseTag::VarInt as u32, "VarInt");
+        h.insert(ParseTag::TaggedFields as u32, "TaggedFields");
 
         h.insert(ParseTag::MessageSet as u32, "MessageSet");
         h.insert(ParseTag::Message as u32, "Message");
         h.insert(ParseTag::MessageCrc as u32, "MessageCrc");
+        h.insert(ParseTag::RecordBatch as u32, "RecordBatch");
+        h.insert(ParseTag::RecordBatchCrc as u32, "RecordBatchCrc");
+        h.insert(ParseTag::Record as u32, "Record");
 
         h.insert(ParseTag::ProduceResponse as u32, "ProduceResponse");
         h.insert(ParseTag::ProduceTopicStatus as u32, "ProduceTopicStatus");
@@ -103,6 +183,7 @@ lazy_static! {
         h.insert(ParseTag::FetchResponse as u32, "FetchResponse");
         h.insert(ParseTag::FetchTopicData as u32, "FetchTopicData");
         h.insert(ParseTag::FetchPartitionData as u32, "FetchPartitionData");
+        h.insert(ParseTag::AbortedTransaction as u32, "AbortedTransaction");
 
         h.insert(ParseTag::ListOffsetResponse as u32, "OffsetResponse");
         h.insert(ParseTag::ListOffsetTopicStatus as u32, "ListOffsetTopicStatus");
@@ -150,6 +231,76 @@ lazy_static! {
 
         h.insert(ParseTag::ApiVersionsResponse as u32, "ApiVersionsResponse");
         h.insert(ParseTag::ApiVersion as u32, "ApiVersion");
+
+        h.insert(ParseTag::CreateTopicsResponse as u32, "CreateTopicsResponse");
+        h.insert(ParseTag::CreateTopicsTopicStatus as u32, "CreateTopicsTopicStatus");
+
+        h.insert(ParseTag::DeleteTopicsResponse as u32, "DeleteTopicsResponse");
+        h.insert(ParseTag::DeleteTopicsTopicStatus as u32, "DeleteTopicsTopicStatus");
+
+        h.insert(ParseTag::DeleteRecordsResponse as u32, "DeleteRecordsResponse");
+        h.insert(ParseTag::DeleteRecordsTopicStatus as u32, "DeleteRecordsTopicStatus");
+        h.insert(
+            ParseTag::DeleteRecordsPartitionStatus as u32,
+            "DeleteRecordsPartitionStatus",
+        );
+
+        h.insert(ParseTag::InitProducerIdResponse as u32, "InitProducerIdResponse");
+
+        h.insert(ParseTag::AddPartitionsToTxnResponse as u32, "AddPartitionsToTxnResponse");
+        h.insert(
+            ParseTag::AddPartitionsToTxnTopicStatus as u32,
+            "AddPartitionsToTxnTopicStatus",
+        );
+        h.insert(
+            ParseTag::AddPartitionsToTxnPartitionStatus as u32,
+            "AddPartitionsToTxnPartitionStatus",
+        );
+
+        h.insert(ParseTag::AddOffsetsToTxnResponse as u32, "AddOffsetsToTxnResponse");
+
+        h.insert(ParseTag::EndTxnResponse as u32, "EndTxnResponse");
+
+        h.insert(ParseTag::SaslHandshakeResponse as u32, "SaslHandshakeResponse");
+
+        h.insert(ParseTag::TxnOffsetCommitResponse as u32, "TxnOffsetCommitResponse");
+        h.insert(
+            ParseTag::TxnOffsetCommitTopicStatus as u32,
+            "TxnOffsetCommitTopicStatus",
+        );
+        h.insert(
+            ParseTag::TxnOffsetCommitPartitionStatus as u32,
+            "TxnOffsetCommitPartitionStatus",
+        );
+
+        h.insert(ParseTag::DescribeAclsResponse as u32, "DescribeAclsResponse");
+        h.insert(ParseTag::DescribeAclsResource as u32, "DescribeAclsResource");
+
+        h.insert(ParseTag::CreateAclsResponse as u32, "CreateAclsResponse");
+        h.insert(ParseTag::CreateAclsStatus as u32, "CreateAclsStatus");
+
+        h.insert(ParseTag::DeleteAclsResponse as u32, "DeleteAclsResponse");
+        h.insert(ParseTag::DeleteAclsFilterStatus as u32, "DeleteAclsFilterStatus");
+        h.insert(ParseTag::DeleteAclsMatchingAcl as u32, "DeleteAclsMatchingAcl");
+
+        h.insert(ParseTag::DescribeConfigsResponse as u32, "DescribeConfigsResponse");
+        h.insert(
+            ParseTag::DescribeConfigsResourceStatus as u32,
+            "DescribeConfigsResourceStatus",
+        );
+        h.insert(ParseTag::DescribeConfigsEntry as u32, "DescribeConfigsEntry");
+        h.insert(ParseTag::ConfigSynonym as u32, "ConfigSynonym");
+
+        h.insert(ParseTag::AlterConfigsResponse as u32, "AlterConfigsResponse");
+        h.insert(ParseTag::AlterConfigsResourceStatus as u32, "AlterConfigsResourceStatus");
+
+        h.insert(ParseTag::SaslAuthenticateResponse as u32, "SaslAuthenticateResponse");
+
+        h.insert(ParseTag::CreatePartitionsResponse as u32, "CreatePartitionsResponse");
+        h.insert(ParseTag::CreatePartitionsTopicStatus as u32, "CreatePartitionsTopicStatus");
+
+        h.insert(ParseTag::DeleteGroupsResponse as u32, "DeleteGroupsResponse");
+        h.insert(ParseTag::DeleteGroupsGroupStatus as u32, "DeleteGroupsGroupStatus");
         h
     };
 }
@@ -260,6 +411,16 @@ named!(pub parse_string<String>,
     )
 );
 
+named!(pub parse_varstring<String>,
+    parse_tag!(ParseTag::String,
+        do_parse!(
+            len: parse_varint
+         >> s: cond_reduce!(len >= 0, map!(map_res!(take!(len), str::from_utf8), ToOwned::to_owned))
+         >> (s)
+        )
+    )
+);
+
 named!(pub parse_bytes<Bytes>,
     parse_tag!(ParseTag::Bytes,
         do_parse!(
@@ -280,6 +441,117 @@ named!(pub parse_opt_bytes<Option<Bytes>>,
     )
 );
 
+/// Parse an unsigned variable-length value with at most `max_bytes` bytes.
+fn parse_unsigned(input: &[u8], max_bytes: usize) -> IResult<&[u8], u64> {
+    let mut value = 0u64;
+
+    for (i, &b) in input.iter().enumerate() {
+        if i >= max_bytes {
+            break;
+        }
+
+        value |= u64::from(b & 0x7f) << (i * 7);
+
+        if (b & 0x80) == 0 {
+            return IResult::Done(&input[i + 1..], value);
+        }
+    }
+
+    if input.len() < max_bytes {
+        IResult::Incomplete(Needed::Unknown)
+    } else {
+        IResult::Error(error_position!(nom::ErrorKind::Custom(ParseTag::VarInt as u32), input))
+    }
+}
+
+/// Parse a zig-zag encoded variable-length value with at most `max_bytes` bytes.
+fn parse_zigzag(input: &[u8], max_bytes: usize) -> IResult<&[u8], i64> {
+    parse_unsigned(input, max_bytes).map(|value| (value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+pub fn parse_uvarint(input: &[u8]) -> IResult<&[u8], u32> {
+    parse_unsigned(input, 5).map(|v| v as u32)
+}
+
+pub fn parse_varint(input: &[u8]) -> IResult<&[u8], i32> {
+    parse_zigzag(input, 5).map(|v| v as i32)
+}
+
+pub fn parse_varlong(input: &[u8]) -> IResult<&[u8], i64> {
+    parse_zigzag(input, 10)
+}
+
+named!(pub parse_opt_varbytes<Option<Bytes>>,
+    parse_tag!(ParseTag::Bytes,
+        do_parse!(
+            len: parse_varint
+         >> s: cond!(len >= 0, map!(take!(len), Bytes::from))
+         >> (s)
+        )
+    )
+);
+
+named!(pub parse_compact_string<String>,
+    parse_tag!(ParseTag::String,
+        do_parse!(
+            len: parse_uvarint
+         >> s: cond_reduce!(len > 0, map!(map_res!(take!(len - 1), str::from_utf8), ToOwned::to_owned))
+         >> (s)
+        )
+    )
+);
+
+named!(pub parse_compact_opt_string<Option<String>>,
+    parse_tag!(ParseTag::String,
+        do_parse!(
+            len: parse_uvarint
+         >> s: cond!(len > 0, map!(map_res!(take!(len - 1), str::from_utf8), ToOwned::to_owned))
+         >> (s)
+        )
+    )
+);
+
+named!(pub parse_compact_bytes<Bytes>,
+    parse_tag!(ParseTag::Bytes,
+        do_parse!(
+            len: parse_uvarint
+         >> s: cond_reduce!(len > 0, map!(take!(len - 1), Bytes::from))
+         >> (s)
+        )
+    )
+);
+
+named!(pub parse_compact_opt_bytes<Option<Bytes>>,
+    parse_tag!(ParseTag::Bytes,
+        do_parse!(
+            len: parse_uvarint
+         >> s: cond!(len > 0, map!(take!(len - 1), Bytes::from))
+         >> (s)
+        )
+    )
+);
+
+named!(pub parse_tagged_fields<Vec<TaggedField>>,
+    parse_tag!(ParseTag::TaggedFields,
+        do_parse!(
+            n: parse_uvarint
+         >> fields: count!(parse_tagged_field, n as usize)
+         >> (fields)
+        )
+    )
+);
+
+named!(parse_tagged_field<TaggedField>,
+    do_parse!(
+        tag: parse_uvarint
+     >> data: length_bytes!(parse_uvarint)
+     >> (TaggedField {
+            tag,
+            data: Bytes::from(data),
+        })
+    )
+);
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -372,4 +644,104 @@ mod tests {
             IResult::Done(&b""[..], Some(Bytes::from(&b"test"[..])))
         );
     }
+
+    #[test]
+    fn test_parse_varint() {
+        assert_eq!(parse_varint(b""), IResult::Incomplete(Needed::Unknown));
+        assert_eq!(parse_varint(b"\x80"), IResult::Incomplete(Needed::Unknown));
+        assert_eq!(parse_varint(b"\0"), IResult::Done(&b""[..], 0));
+        assert_eq!(parse_varint(b"\x01"), IResult::Done(&b""[..], -1));
+        assert_eq!(parse_varint(b"\xd8\x04"), IResult::Done(&b""[..], 300));
+        assert_eq!(parse_varint(b"\xfe\xff\xff\xff\x0f"), IResult::Done(&b""[..], i32::max_value()));
+        assert_eq!(
+            parse_varint(b"\xff\xff\xff\xff\xff\x01"),
+            IResult::Error(Err::Position(
+                ErrorKind::Custom(ParseTag::VarInt as u32),
+                &b"\xff\xff\xff\xff\xff\x01"[..]
+            ))
+        );
+        assert_eq!(parse_varlong(b"\x01"), IResult::Done(&b""[..], -1));
+    }
+
+    #[test]
+    fn test_parse_opt_varbytes() {
+        assert_eq!(parse_opt_varbytes(b"\x01"), IResult::Done(&b""[..], None));
+        assert_eq!(parse_opt_varbytes(b"\0"), IResult::Done(&b""[..], Some(Bytes::new())));
+        assert_eq!(
+            parse_opt_varbytes(b"\x08test"),
+            IResult::Done(&b""[..], Some(Bytes::from(&b"test"[..])))
+        );
+    }
+
+    #[test]
+    fn test_parse_varstring() {
+        assert_eq!(parse_varstring(b"\0"), IResult::Done(&b""[..], "".to_owned()));
+        assert_eq!(parse_varstring(b"\x08test"), IResult::Done(&b""[..], "test".to_owned()));
+        assert!(parse_varstring(b"\x01").is_err());
+    }
+
+    #[test]
+    fn test_parse_uvarint() {
+        assert_eq!(parse_uvarint(b""), IResult::Incomplete(Needed::Unknown));
+        assert_eq!(parse_uvarint(b"\0"), IResult::Done(&b""[..], 0));
+        assert_eq!(parse_uvarint(b"\x01"), IResult::Done(&b""[..], 1));
+        assert_eq!(parse_uvarint(b"\xac\x02"), IResult::Done(&b""[..], 300));
+        assert_eq!(parse_uvarint(b"\xff\xff\xff\xff\x0f"), IResult::Done(&b""[..], u32::max_value()));
+    }
+
+    #[test]
+    fn test_parse_compact_string() {
+        assert!(parse_compact_string(b"\0").is_err());
+        assert_eq!(parse_compact_string(b"\x01"), IResult::Done(&b""[..], "".to_owned()));
+        assert_eq!(parse_compact_string(b"\x05test"), IResult::Done(&b""[..], "test".to_owned()));
+        assert_eq!(parse_compact_opt_string(b"\0"), IResult::Done(&b""[..], None));
+        assert_eq!(
+            parse_compact_opt_string(b"\x05test"),
+            IResult::Done(&b""[..], Some("test".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_parse_compact_bytes() {
+        assert!(parse_compact_bytes(b"\0").is_err());
+        assert_eq!(parse_compact_bytes(b"\x01"), IResult::Done(&b""[..], Bytes::new()));
+        assert_eq!(parse_compact_opt_bytes(b"\0"), IResult::Done(&b""[..], None));
+        assert_eq!(
+            parse_compact_opt_bytes(b"\x05test"),
+            IResult::Done(&b""[..], Some(Bytes::from(&b"test"[..])))
+        );
+    }
+
+    named!(parse_compact_strings<Option<Vec<String>>>, compact_count!(parse_compact_string));
+
+    #[test]
+    fn test_parse_compact_array() {
+        assert_eq!(parse_compact_strings(b"\0"), IResult::Done(&b""[..], None));
+        assert_eq!(parse_compact_strings(b"\x01"), IResult::Done(&b""[..], Some(vec![])));
+        assert_eq!(
+            parse_compact_strings(b"\x03\x02a\x02b"),
+            IResult::Done(&b""[..], Some(vec!["a".to_owned(), "b".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn test_parse_tagged_fields() {
+        assert_eq!(parse_tagged_fields(b"\0"), IResult::Done(&b""[..], vec![]));
+        assert_eq!(
+            parse_tagged_fields(b"\x02\0\x01a\xc8\x01\x02bc"),
+            IResult::Done(
+                &b""[..],
+                vec![
+                    TaggedField {
+                        tag: 0,
+                        data: Bytes::from(&b"a"[..]),
+                    },
+                    TaggedField {
+                        tag: 200,
+                        data: Bytes::from(&b"bc"[..]),
+                    },
+                ]
+            )
+        );
+    }
 }
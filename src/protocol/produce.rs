@@ -10,12 +10,18 @@ use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, Error
                Offset, ParseTag, PartitionId, Record, RequestHeader, RequiredAck, ResponseHeader, Timestamp, WriteExt,
                ARRAY_LEN_SIZE, BYTES_LEN_SIZE, PARTITION_ID_SIZE, STR_LEN_SIZE};
 
+/// The offset of the records produced with `acks=0`, since the broker doesn't send the response.
+pub const UNKNOWN_OFFSET: Offset = -1;
+
 const REQUIRED_ACKS_SIZE: usize = 2;
 const ACK_TIMEOUT_SIZE: usize = 4;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ProduceRequest<'a> {
     pub header: RequestHeader<'a>,
+    /// The transactional id of the producer (since v3),
+    /// or `None` if the producer is not transactional.
+    pub transactional_id: Option<Cow<'a, str>>,
     /// This field indicates how many acknowledgements the servers should
     /// receive before responding to the request.
     pub required_acks: RequiredAck,
@@ -44,7 +50,12 @@ pub struct ProducePartitionData<'a> {
 
 impl<'a> Record for ProduceRequest<'a> {
     fn size(&self, api_version: ApiVersion) -> usize {
-        self.header.size(api_version) + REQUIRED_ACKS_SIZE + ACK_TIMEOUT_SIZE
+        self.header.size(api_version)
+            + if api_version > 2 {
+                STR_LEN_SIZE + self.transactional_id.as_ref().map_or(0, |s| s.len())
+            } else {
+                0
+            } + REQUIRED_ACKS_SIZE + ACK_TIMEOUT_SIZE
             + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
                 size + STR_LEN_SIZE + topic.topic_name.len()
                     + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, partition| {
@@ -60,6 +71,9 @@ impl<'a> Encodable for ProduceRequest<'a> {
 
         self.header.encode::<T>(dst)?;
 
+        if self.header.api_version > 2 {
+            dst.put_str::<T, _>(self.transactional_id.as_ref())?;
+        }
         dst.put_i16::<T>(self.required_acks);
         dst.put_i32::<T>(self.ack_timeout);
         dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
@@ -111,6 +125,8 @@ pub struct ProducePartitionStatus {
     /// Unit is milliseconds since beginning of the epoch (midnight Jan 1, 1970
     /// (UTC)).
     pub timestamp: Option<Timestamp>,
+    /// The start offset of the log at the time this produce response was created (since v5).
+    pub log_start_offset: Option<Offset>,
 }
 
 impl ProduceResponse {
@@ -154,11 +170,13 @@ named_args!(parse_produce_partition_status(api_version: ApiVersion)<ProduceParti
          >> error_code: be_i16
          >> offset: be_i64
          >> timestamp: cond!(api_version > 1, be_i64)
+         >> log_start_offset: cond!(api_version > 4, be_i64)
          >> (ProducePartitionStatus {
                 partition_id,
                 error_code,
                 offset,
                 timestamp,
+                log_start_offset,
             })
         )
     )
@@ -229,6 +247,7 @@ mod tests {
                                                   error_code: 2,
                                                   offset: 3,
                                                   timestamp: Some(4),
+                                                  log_start_offset: None,
                                               }],
                          }],
             throttle_time: Some(5),
@@ -244,6 +263,7 @@ mod tests {
                 correlation_id: 123,
                 client_id: Some("client".into()),
             },
+            transactional_id: None,
             required_acks: RequiredAcks::All as RequiredAck,
             ack_timeout: 123,
             topics: vec![
@@ -260,8 +280,10 @@ mod tests {
                                         key: Some(Bytes::from(&b"key"[..])),
                                         value: Some(Bytes::from(&b"value"[..])),
                                         timestamp: Some(MessageTimestamp::CreateTime(456)),
+                                        headers: vec![],
                                     },
                                 ],
+                                batches: vec![],
                             }),
                         },
                     ],
@@ -278,6 +300,59 @@ mod tests {
         assert_eq!(&buf[..], &TEST_REQUEST_DATA[..]);
     }
 
+    #[test]
+    fn test_encode_produce_request_v3() {
+        let req = ProduceRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::Produce as ApiVersion,
+                api_version: 3,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: Some("txn".into()),
+            required_acks: RequiredAcks::All as RequiredAck,
+            ack_timeout: 123,
+            topics: vec![
+                ProduceTopicData {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        ProducePartitionData {
+                            partition_id: 1,
+                            message_set: Cow::Owned(MessageSet {
+                                messages: vec![
+                                    Message {
+                                        offset: 0,
+                                        compression: Compression::None,
+                                        key: Some(Bytes::from(&b"key"[..])),
+                                        value: Some(Bytes::from(&b"value"[..])),
+                                        timestamp: Some(MessageTimestamp::CreateTime(456)),
+                                        headers: vec![],
+                                    },
+                                ],
+                                batches: vec![],
+                            }),
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(
+            &buf[..25],
+            &[
+                0, 0 /* api_key */, 0, 3 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l',
+                b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x', b'n' /* transactional_id */, 255,
+                255 /* required_acks */, 0, 0, /* ack_timeout */
+            ][..]
+        );
+    }
+
     #[test]
     fn test_parse_produce_response() {
         assert_eq!(
@@ -285,4 +360,18 @@ mod tests {
             IResult::Done(&[][..], TEST_RESPONSE.clone())
         );
     }
+
+    #[test]
+    fn test_parse_produce_response_v5() {
+        let mut data = TEST_RESPONSE_DATA[..41].to_vec();
+
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 6 /* log_start_offset */]);
+        data.extend_from_slice(&TEST_RESPONSE_DATA[41..]);
+
+        let mut response = TEST_RESPONSE.clone();
+
+        response.topics[0].partitions[0].log_start_offset = Some(6);
+
+        assert_eq!(parse_produce_response(&data[..], 5), IResult::Done(&[][..], response));
+    }
 }
@@ -0,0 +1,252 @@
+use std::borrow::Cow;
+
+use bytes::{ByteOrder, Bytes, BytesMut};
+
+use nom::{IResult, be_i16, be_i32, be_i64};
+
+use errors::Result;
+use protocol::{parse_bytes, parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode,
+               ParseTag, Record, RequestHeader, ResponseHeader, WriteExt, BYTES_LEN_SIZE, STR_LEN_SIZE};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaslHandshakeRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// SASL Mechanism chosen by the client.
+    pub mechanism: Cow<'a, str>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaslHandshakeResponse {
+    pub header: ResponseHeader,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// Array of mechanisms enabled in the server.
+    pub enabled_mechanisms: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaslAuthenticateRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// SASL authentication bytes from client as defined by the SASL mechanism.
+    pub auth_bytes: Bytes,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaslAuthenticateResponse {
+    pub header: ResponseHeader,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// Error message.
+    pub error_message: Option<String>,
+    /// SASL authentication bytes from server as defined by the SASL mechanism.
+    pub auth_bytes: Bytes,
+    /// The number of milliseconds the session is valid, or 0 if the session never expires (since v1).
+    pub session_lifetime: Option<i64>,
+}
+
+impl<'a> Record for SaslHandshakeRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.mechanism.len()
+    }
+}
+
+impl<'a> Encodable for SaslHandshakeRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(Some(self.mechanism.as_ref()))
+    }
+}
+
+impl<'a> Record for SaslAuthenticateRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + BYTES_LEN_SIZE + self.auth_bytes.len()
+    }
+}
+
+impl<'a> Encodable for SaslAuthenticateRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_bytes::<T, _>(Some(self.auth_bytes.as_ref()))
+    }
+}
+
+impl SaslHandshakeResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_sasl_handshake_response(buf)
+    }
+}
+
+named!(parse_sasl_handshake_response<SaslHandshakeResponse>,
+    parse_tag!(ParseTag::SaslHandshakeResponse,
+        do_parse!(
+            header: parse_response_header
+         >> error_code: be_i16
+         >> enabled_mechanisms: length_count!(be_i32, parse_string)
+         >> (SaslHandshakeResponse {
+                header,
+                error_code,
+                enabled_mechanisms,
+            })
+        )
+    )
+);
+
+impl SaslAuthenticateResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_sasl_authenticate_response(buf, api_version)
+    }
+}
+
+named_args!(parse_sasl_authenticate_response(api_version: ApiVersion)<SaslAuthenticateResponse>,
+    parse_tag!(ParseTag::SaslAuthenticateResponse,
+        do_parse!(
+            header: parse_response_header
+         >> error_code: be_i16
+         >> error_message: parse_opt_string
+         >> auth_bytes: parse_bytes
+         >> session_lifetime: cond!(api_version > 0, be_i64)
+         >> (SaslAuthenticateResponse {
+                header,
+                error_code,
+                error_message,
+                auth_bytes,
+                session_lifetime,
+            })
+        )
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_sasl_handshake_request() {
+        let req = SaslHandshakeRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::SaslHandshake as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            mechanism: "PLAIN".into(),
+        };
+
+        let data = vec![
+            /* SaslHandshakeRequest
+             * RequestHeader */ 0, 17 /* api_key */, 0,
+            1 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n',
+            b't' /* client_id */, 0, 5, b'P', b'L', b'A', b'I', b'N' /* mechanism */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_sasl_handshake_response() {
+        let response = SaslHandshakeResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            error_code: 33,
+            enabled_mechanisms: vec!["PLAIN".to_owned()],
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 33 /* error_code */,
+            /* enabled_mechanisms: [String] */ 0, 0, 0, 1, 0, 5, b'P', b'L', b'A', b'I', b'N',
+        ];
+
+        let res = parse_sasl_handshake_response(&data[..]);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_encode_sasl_authenticate_request() {
+        let req = SaslAuthenticateRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::SaslAuthenticate as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            auth_bytes: Bytes::from(&b"\0user\0pass"[..]),
+        };
+
+        let data = vec![
+            /* SaslAuthenticateRequest
+             * RequestHeader */ 0, 36 /* api_key */, 0,
+            0 /* api_version */, 0, 0, 0, 123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n',
+            b't' /* client_id */, 0, 0, 0, 10, 0, b'u', b's', b'e', b'r', 0, b'p', b'a', b's',
+            b's' /* auth_bytes */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_sasl_authenticate_response() {
+        let response = SaslAuthenticateResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            error_code: 58,
+            error_message: Some("failed".to_owned()),
+            auth_bytes: Bytes::new(),
+            session_lifetime: None,
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 58 /* error_code */, 0, 6, b'f',
+            b'a', b'i', b'l', b'e', b'd' /* error_message */, 0, 0, 0, 0 /* auth_bytes */,
+        ];
+
+        let res = parse_sasl_authenticate_response(&data[..], 0);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+
+    #[test]
+    fn test_parse_sasl_authenticate_response_v1() {
+        let response = SaslAuthenticateResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            error_code: 0,
+            error_message: None,
+            auth_bytes: Bytes::from(&b"token"[..]),
+            session_lifetime: Some(3_600_000),
+        };
+
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0 /* error_code */, 255,
+            255 /* error_message */, 0, 0, 0, 5, b't', b'o', b'k', b'e', b'n' /* auth_bytes */, 0, 0, 0, 0, 0,
+            0x36, 0xee, 0x80 /* session_lifetime */,
+        ];
+
+        let res = parse_sasl_authenticate_response(&data[..], 1);
+
+        display_parse_error::<_>(&data[..], res.clone());
+
+        assert_eq!(res, IResult::Done(&[][..], response));
+    }
+}
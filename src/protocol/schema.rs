@@ -9,11 +9,13 @@ use std::result::Result as StdResult;
 use std::str;
 
 use serde::de::{self, Deserialize, Visitor};
-use serde::ser::{self, Serialize, SerializeSeq};
+use serde::ser::{self, Serialize, SerializeSeq, SerializeTuple};
 
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
+use bytes::Bytes;
 
 use errors::{Error, ErrorKind, Result};
+use protocol::TaggedField;
 
 pub struct Schema {}
 
@@ -49,6 +51,13 @@ pub enum SchemaType {
     NULLABLE_BYTES,
     VARINT,
     VARLONG,
+    UNSIGNED_VARINT,
+    COMPACT_STRING,
+    COMPACT_NULLABLE_STRING,
+    COMPACT_BYTES,
+    COMPACT_NULLABLE_BYTES,
+    COMPACT_ARRAY,
+    TAGGED_FIELDS,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
@@ -60,6 +69,24 @@ pub struct VarLong(i64);
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Nullable<T>(Option<T>);
 
+/// An unsigned variable-length int, which is used by the flexible versions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UVarInt(u32);
+
+/// A compact string, bytes or array of the flexible versions,
+/// which length is encoded as an unsigned varint of `N+1`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Compact<T>(T);
+
+/// A compact nullable string, bytes or array of the flexible versions,
+/// which is encoded as the length `0` if it is null.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactNullable<T>(Option<T>);
+
+/// The tagged fields section at the end of the structures of the flexible versions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaggedFields(Vec<TaggedField>);
+
 impl<T> Nullable<T> {
     pub fn into_raw(self) -> Option<T> {
         self.0
@@ -70,6 +97,52 @@ impl<T> Nullable<T> {
     }
 }
 
+impl<T> Compact<T> {
+    pub fn into_raw(self) -> T {
+        self.0
+    }
+}
+
+impl<T> CompactNullable<T> {
+    pub fn into_raw(self) -> Option<T> {
+        self.0
+    }
+
+    pub fn null() -> Self {
+        CompactNullable(None)
+    }
+}
+
+impl TaggedFields {
+    pub fn into_raw(self) -> Vec<TaggedField> {
+        self.0
+    }
+}
+
+impl From<u32> for UVarInt {
+    fn from(v: u32) -> Self {
+        UVarInt(v)
+    }
+}
+
+impl<T> From<T> for Compact<T> {
+    fn from(v: T) -> Self {
+        Compact(v)
+    }
+}
+
+impl<T> From<Option<T>> for CompactNullable<T> {
+    fn from(v: Option<T>) -> Self {
+        CompactNullable(v)
+    }
+}
+
+impl From<Vec<TaggedField>> for TaggedFields {
+    fn from(v: Vec<TaggedField>) -> Self {
+        TaggedFields(v)
+    }
+}
+
 impl From<i32> for VarInt {
     fn from(v: i32) -> Self {
         VarInt(v)
@@ -130,6 +203,38 @@ impl<T> DerefMut for Nullable<T> {
     }
 }
 
+impl Deref for UVarInt {
+    type Target = u32;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Deref for Compact<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Deref for CompactNullable<T> {
+    type Target = Option<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Deref for TaggedFields {
+    type Target = Vec<TaggedField>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 impl Serialize for VarInt {
     fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
     where
@@ -362,6 +467,308 @@ impl<'de> de::Visitor<'de> for NullableVisitor<Vec<u8>> {
     }
 }
 
+fn uvarint_bytes(buf: &mut Vec<u8>, mut v: u32) {
+    while (v & !0x7F) != 0 {
+        buf.push(((v & 0x7f) | 0x80) as u8);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn next_uvarint<'de, A>(seq: &mut A) -> StdResult<u32, A::Error>
+where
+    A: de::SeqAccess<'de>,
+{
+    let mut value = 0;
+    let mut i = 0;
+
+    loop {
+        let b = seq.next_element::<u8>()?
+            .ok_or_else(|| de::Error::custom("unexpected end of unsigned varint"))?;
+
+        value |= u32::from(b & 0x7f) << i;
+
+        if (b & 0x80) == 0 {
+            return Ok(value);
+        }
+
+        i += 7;
+
+        if i > 28 {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(u64::from(b)),
+                &"variable-length bytes of unsigned int",
+            ));
+        }
+    }
+}
+
+fn next_bytes<'de, A>(seq: &mut A, len: usize) -> StdResult<Vec<u8>, A::Error>
+where
+    A: de::SeqAccess<'de>,
+{
+    let mut buf = Vec::with_capacity(len);
+
+    for i in 0..len {
+        buf.push(seq.next_element::<u8>()?
+            .ok_or_else(|| de::Error::invalid_length(i, &"compact bytes"))?);
+    }
+
+    Ok(buf)
+}
+
+impl Serialize for UVarInt {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut buf = Vec::with_capacity(5);
+        uvarint_bytes(&mut buf, self.0);
+        serializer.serialize_bytes(&buf)
+    }
+}
+
+impl<'de> Deserialize<'de> for UVarInt {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct("UVarInt", UVarIntVisitor)
+    }
+}
+
+struct UVarIntVisitor;
+
+impl<'de> Visitor<'de> for UVarIntVisitor {
+    type Value = UVarInt;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("variable-length bytes of unsigned int")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        next_uvarint(&mut seq).map(UVarInt)
+    }
+}
+
+/// Serialize a compact string or bytes, or `null` if it is `None`.
+fn serialize_compact_bytes<S>(serializer: S, v: Option<&[u8]>) -> StdResult<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+{
+    let mut buf = Vec::with_capacity(v.map_or(0, |v| v.len()) + 5);
+
+    match v {
+        Some(v) => {
+            uvarint_bytes(&mut buf, v.len() as u32 + 1);
+            buf.extend_from_slice(v);
+        }
+        None => uvarint_bytes(&mut buf, 0),
+    }
+
+    serializer.serialize_bytes(&buf)
+}
+
+/// Serialize a compact array, or `null` if it is `None`.
+fn serialize_compact_array<S, T>(serializer: S, v: Option<&[T]>) -> StdResult<S::Ok, S::Error>
+where
+    S: ser::Serializer,
+    T: Serialize,
+{
+    let len = v.map_or(0, |v| v.len() + 1);
+    let mut tuple = serializer.serialize_tuple(len + 1)?;
+
+    tuple.serialize_element(&UVarInt(len as u32))?;
+
+    for item in v.unwrap_or_default() {
+        tuple.serialize_element(item)?;
+    }
+
+    tuple.end()
+}
+
+impl Serialize for Compact<String> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serialize_compact_bytes(serializer, Some(self.0.as_bytes()))
+    }
+}
+
+impl Serialize for CompactNullable<String> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serialize_compact_bytes(serializer, self.0.as_ref().map(|s| s.as_bytes()))
+    }
+}
+
+impl<T: Serialize> Serialize for Compact<Vec<T>> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serialize_compact_array(serializer, Some(self.0.as_slice()))
+    }
+}
+
+impl<T: Serialize> Serialize for CompactNullable<Vec<T>> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serialize_compact_array(serializer, self.0.as_ref().map(|v| v.as_slice()))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Compact<T>
+where
+    CompactVisitor<T>: Visitor<'de, Value = Option<T>>,
+{
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_newtype_struct("Compact", CompactVisitor::new())?
+            .map(Compact)
+            .ok_or_else(|| de::Error::custom("unexpected null of compact type"))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CompactNullable<T>
+where
+    CompactVisitor<T>: Visitor<'de, Value = Option<T>>,
+{
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_newtype_struct("CompactNullable", CompactVisitor::new())
+            .map(CompactNullable)
+    }
+}
+
+/// The visitor of the compact types, which returns `None` if the value is null.
+pub struct CompactVisitor<T> {
+    phantom: PhantomData<T>,
+}
+
+impl<T> CompactVisitor<T> {
+    pub fn new() -> Self {
+        CompactVisitor { phantom: PhantomData }
+    }
+}
+
+impl<'de> Visitor<'de> for CompactVisitor<String> {
+    type Value = Option<String>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("compact string with unsigned varint length prefix")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        match next_uvarint(&mut seq)? {
+            0 => Ok(None),
+            n => String::from_utf8(next_bytes(&mut seq, n as usize - 1)?)
+                .map(Some)
+                .map_err(de::Error::custom),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for CompactVisitor<Vec<T>> {
+    type Value = Option<Vec<T>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("compact array with unsigned varint length prefix")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        match next_uvarint(&mut seq)? {
+            0 => Ok(None),
+            n => {
+                let mut items = Vec::with_capacity(n as usize - 1);
+
+                for i in 0..n as usize - 1 {
+                    items.push(seq.next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?);
+                }
+
+                Ok(Some(items))
+            }
+        }
+    }
+}
+
+impl Serialize for TaggedFields {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let mut buf = Vec::with_capacity(16);
+
+        uvarint_bytes(&mut buf, self.0.len() as u32);
+
+        for field in &self.0 {
+            uvarint_bytes(&mut buf, field.tag);
+            uvarint_bytes(&mut buf, field.data.len() as u32);
+            buf.extend_from_slice(&field.data);
+        }
+
+        serializer.serialize_bytes(&buf)
+    }
+}
+
+impl<'de> Deserialize<'de> for TaggedFields {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct("TaggedFields", TaggedFieldsVisitor)
+    }
+}
+
+struct TaggedFieldsVisitor;
+
+impl<'de> Visitor<'de> for TaggedFieldsVisitor {
+    type Value = TaggedFields;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("tagged fields with unsigned varint tag and length")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let n = next_uvarint(&mut seq)?;
+        let mut fields = Vec::with_capacity(n as usize);
+
+        for _ in 0..n {
+            let tag = next_uvarint(&mut seq)?;
+            let len = next_uvarint(&mut seq)?;
+            let data = Bytes::from(next_bytes(&mut seq, len as usize)?);
+
+            fields.push(TaggedField { tag, data });
+        }
+
+        Ok(TaggedFields(fields))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct SchemaSerializer<O> {
     buf: Vec<u8>,
@@ -391,7 +798,7 @@ impl<'a, O: ByteOrder> ser::Serializer for &'a mut SchemaSerializer<O> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
-    type SerializeTuple = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Self;
     type SerializeTupleStruct = ser::Impossible<Self::Ok, Self::Error>;
     type SerializeTupleVariant = ser::Impossible<Self::Ok, Self::Error>;
     type SerializeMap = ser::Impossible<Self::Ok, Self::Error>;
@@ -571,7 +978,7 @@ impl<'a, O: ByteOrder> ser::Serializer for &'a mut SchemaSerializer<O> {
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
         trace!("serialize tuple with {} elements", len);
 
-        bail!(ErrorKind::SchemaError("unsupported tuple".to_owned()))
+        Ok(self)
     }
     fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
         trace!("serialize tuple struct `{}` with {} elements", name, len);
@@ -627,6 +1034,21 @@ impl<'a, O: ByteOrder> ser::SerializeSeq for &'a mut SchemaSerializer<O> {
     }
 }
 
+impl<'a, O: ByteOrder> ser::SerializeTuple for &'a mut SchemaSerializer<O> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
 impl<'a, O: ByteOrder> ser::SerializeStruct for &'a mut SchemaSerializer<O> {
     type Ok = ();
     type Error = Error;
@@ -1135,4 +1557,52 @@ mod tests {
 
         assert_eq!(schema, *TEST_SCHEMA);
     }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestFlexibleSchema {
+        uvarint: UVarInt,
+        compact_string: Compact<String>,
+        compact_nullable_string: CompactNullable<String>,
+        compact_null_string: CompactNullable<String>,
+        compact_bytes: Compact<Vec<u8>>,
+        compact_array: Compact<Vec<SubSchema>>,
+        compact_null_array: CompactNullable<Vec<i32>>,
+        tagged_fields: TaggedFields,
+    }
+
+    lazy_static! {
+        static ref TEST_FLEXIBLE_SCHEMA: TestFlexibleSchema = TestFlexibleSchema {
+            uvarint: 300.into(),
+            compact_string: "str".to_owned().into(),
+            compact_nullable_string: Some("str".to_owned()).into(),
+            compact_null_string: CompactNullable::null(),
+            compact_bytes: b"bytes".to_vec().into(),
+            compact_array: vec![SubSchema { name: "name".to_owned() }].into(),
+            compact_null_array: CompactNullable::null(),
+            tagged_fields: vec![TaggedField { tag: 1, data: Bytes::from(&b"ab"[..]) }].into(),
+        };
+
+        static ref TEST_FLEXIBLE_DATA: Vec<u8> = vec![
+            172, 2,                                     // uvarint
+            4, b's', b't', b'r',                        // compact_string
+            4, b's', b't', b'r',                        // compact_nullable_string
+            0,                                          // compact_null_string
+            6, b'b', b'y', b't', b'e', b's',            // compact_bytes
+            2, 0, 4, 110, 97, 109, 101,                 // compact_array
+            0,                                          // compact_null_array
+            1, 1, 2, b'a', b'b',                        // tagged_fields
+        ];
+    }
+
+    #[test]
+    fn test_flexible_schema_serializer() {
+        assert_eq!(Schema::serialize(&*TEST_FLEXIBLE_SCHEMA).unwrap(), *TEST_FLEXIBLE_DATA);
+    }
+
+    #[test]
+    fn test_flexible_schema_deserializer() {
+        let schema: TestFlexibleSchema = Schema::deserialize(Cursor::new(TEST_FLEXIBLE_DATA.clone())).unwrap();
+
+        assert_eq!(schema, *TEST_FLEXIBLE_SCHEMA);
+    }
 }
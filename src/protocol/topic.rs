@@ -0,0 +1,552 @@
+use std::borrow::Cow;
+
+use bytes::{BufMut, ByteOrder, BytesMut};
+
+use nom::{IResult, be_i16, be_i32};
+
+use errors::Result;
+use protocol::{parse_opt_string, parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, NodeId,
+               ParseTag, PartitionId, Record, RequestHeader, ResponseHeader, WriteExt, ARRAY_LEN_SIZE,
+               PARTITION_ID_SIZE, REPLICA_ID_SIZE, STR_LEN_SIZE};
+
+const NUM_PARTITIONS_SIZE: usize = 4;
+const REPLICATION_FACTOR_SIZE: usize = 2;
+const TIMEOUT_SIZE: usize = 4;
+const VALIDATE_ONLY_SIZE: usize = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTopicsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The topics to create.
+    pub topics: Vec<CreateTopicsTopic<'a>>,
+    /// The time in ms to wait for the topics to be completely created on the controller node.
+    pub timeout: i32,
+    /// Validate the request without actually creating the topics (since v1).
+    pub validate_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTopicsTopic<'a> {
+    /// The name of the topic.
+    pub topic_name: Cow<'a, str>,
+    /// Number of partitions to be created, -1 indicates unset.
+    pub num_partitions: i32,
+    /// Replication factor for the topic, -1 indicates unset.
+    pub replication_factor: i16,
+    /// Replica assignment among the brokers.
+    pub replica_assignment: Vec<ReplicaAssignment>,
+    /// Topic level configuration.
+    pub configs: Vec<ConfigEntry<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicaAssignment {
+    /// The partition id.
+    pub partition_id: PartitionId,
+    /// The set of all nodes that should host this partition.
+    pub replicas: Vec<NodeId>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigEntry<'a> {
+    /// The configuration name.
+    pub config_name: Cow<'a, str>,
+    /// The configuration value.
+    pub config_value: Option<Cow<'a, str>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTopicsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: Option<i32>,
+    /// The results of the topics.
+    pub topics: Vec<CreateTopicsTopicStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTopicsTopicStatus {
+    /// The name of the topic.
+    pub topic_name: String,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message (since v1).
+    pub error_message: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteTopicsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The topics to delete.
+    pub topic_names: Vec<Cow<'a, str>>,
+    /// The time in ms to wait for the topics to be completely deleted on the controller node.
+    pub timeout: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteTopicsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: Option<i32>,
+    /// The results of the topics.
+    pub topics: Vec<DeleteTopicsTopicStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteTopicsTopicStatus {
+    /// The name of the topic.
+    pub topic_name: String,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatePartitionsRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The topics to add partitions to.
+    pub topics: Vec<CreatePartitionsTopic<'a>>,
+    /// The time in ms to wait for the partitions to be created on the controller node.
+    pub timeout: i32,
+    /// Validate the request without actually creating the partitions.
+    pub validate_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatePartitionsTopic<'a> {
+    /// The name of the topic.
+    pub topic_name: Cow<'a, str>,
+    /// The new partition count.
+    pub count: i32,
+    /// The assigned brokers of each new partition, or `None` to let the controller assign them.
+    pub assignment: Option<Vec<Vec<NodeId>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatePartitionsResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results of the topics.
+    pub topics: Vec<CreatePartitionsTopicStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatePartitionsTopicStatus {
+    /// The name of the topic.
+    pub topic_name: String,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The error message.
+    pub error_message: Option<String>,
+}
+
+impl<'a> Record for CreateTopicsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+            size + STR_LEN_SIZE + topic.topic_name.len() + NUM_PARTITIONS_SIZE + REPLICATION_FACTOR_SIZE
+                + topic.replica_assignment.iter().fold(ARRAY_LEN_SIZE, |size, assignment| {
+                    size + PARTITION_ID_SIZE + ARRAY_LEN_SIZE + REPLICA_ID_SIZE * assignment.replicas.len()
+                })
+                + topic.configs.iter().fold(ARRAY_LEN_SIZE, |size, config| {
+                    size + STR_LEN_SIZE + config.config_name.len() + STR_LEN_SIZE
+                        + config.config_value.as_ref().map_or(0, |s| s.len())
+                })
+        }) + TIMEOUT_SIZE + if api_version > 0 { VALIDATE_ONLY_SIZE } else { 0 }
+    }
+}
+
+impl<'a> Encodable for CreateTopicsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
+            buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+            buf.put_i32::<T>(topic.num_partitions);
+            buf.put_i16::<T>(topic.replication_factor);
+            buf.put_array::<T, _, _>(&topic.replica_assignment, |buf, assignment| {
+                buf.put_i32::<T>(assignment.partition_id);
+                buf.put_array::<T, _, _>(&assignment.replicas, |buf, replica| {
+                    buf.put_i32::<T>(*replica);
+                    Ok(())
+                })
+            })?;
+            buf.put_array::<T, _, _>(&topic.configs, |buf, config| {
+                buf.put_str::<T, _>(Some(config.config_name.as_ref()))?;
+                buf.put_str::<T, _>(config.config_value.as_ref())
+            })
+        })?;
+        dst.put_i32::<T>(self.timeout);
+
+        if self.header.api_version > 0 {
+            dst.put_i8(self.validate_only as i8);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Record for DeleteTopicsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.topic_names.iter().fold(ARRAY_LEN_SIZE, |size, topic_name| {
+            size + STR_LEN_SIZE + topic_name.len()
+        }) + TIMEOUT_SIZE
+    }
+}
+
+impl<'a> Encodable for DeleteTopicsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.topic_names, |buf, topic_name| {
+            buf.put_str::<T, _>(Some(topic_name.as_ref()))
+        })?;
+        dst.put_i32::<T>(self.timeout);
+
+        Ok(())
+    }
+}
+
+impl<'a> Record for CreatePartitionsRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+            size + STR_LEN_SIZE + topic.topic_name.len() + NUM_PARTITIONS_SIZE
+                + topic.assignment.as_ref().map_or(ARRAY_LEN_SIZE, |assignment| {
+                    assignment.iter().fold(ARRAY_LEN_SIZE, |size, replicas| {
+                        size + ARRAY_LEN_SIZE + REPLICA_ID_SIZE * replicas.len()
+                    })
+                })
+        }) + TIMEOUT_SIZE + VALIDATE_ONLY_SIZE
+    }
+}
+
+impl<'a> Encodable for CreatePartitionsRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
+            buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+            buf.put_i32::<T>(topic.count);
+
+            if let Some(ref assignment) = topic.assignment {
+                buf.put_array::<T, _, _>(assignment, |buf, replicas| {
+                    buf.put_array::<T, _, _>(replicas, |buf, replica| {
+                        buf.put_i32::<T>(*replica);
+                        Ok(())
+                    })
+                })
+            } else {
+                buf.put_i32::<T>(-1);
+                Ok(())
+            }
+        })?;
+        dst.put_i32::<T>(self.timeout);
+        dst.put_i8(self.validate_only as i8);
+
+        Ok(())
+    }
+}
+
+impl CreateTopicsResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_create_topics_response(buf, api_version)
+    }
+}
+
+named_args!(parse_create_topics_response(api_version: ApiVersion)<CreateTopicsResponse>,
+    parse_tag!(ParseTag::CreateTopicsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: cond!(api_version > 1, be_i32)
+         >> topics: length_count!(be_i32, apply!(parse_create_topics_topic_status, api_version))
+         >> (CreateTopicsResponse {
+                header,
+                throttle_time,
+                topics,
+            })
+        )
+    )
+);
+
+named_args!(parse_create_topics_topic_status(api_version: ApiVersion)<CreateTopicsTopicStatus>,
+    parse_tag!(ParseTag::CreateTopicsTopicStatus,
+        do_parse!(
+            topic_name: parse_string
+         >> error_code: be_i16
+         >> error_message: cond!(api_version > 0, parse_opt_string)
+         >> (CreateTopicsTopicStatus {
+                topic_name,
+                error_code,
+                error_message: error_message.and_then(|message| message),
+            })
+        )
+    )
+);
+
+impl DeleteTopicsResponse {
+    pub fn parse(buf: &[u8], api_version: ApiVersion) -> IResult<&[u8], Self> {
+        parse_delete_topics_response(buf, api_version)
+    }
+}
+
+named_args!(parse_delete_topics_response(api_version: ApiVersion)<DeleteTopicsResponse>,
+    parse_tag!(ParseTag::DeleteTopicsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: cond!(api_version > 0, be_i32)
+         >> topics: length_count!(be_i32, parse_delete_topics_topic_status)
+         >> (DeleteTopicsResponse {
+                header,
+                throttle_time,
+                topics,
+            })
+        )
+    )
+);
+
+named!(
+    parse_delete_topics_topic_status<DeleteTopicsTopicStatus>,
+    parse_tag!(
+        ParseTag::DeleteTopicsTopicStatus,
+        do_parse!(
+            topic_name: parse_string >> error_code: be_i16 >> (DeleteTopicsTopicStatus { topic_name, error_code })
+        )
+    )
+);
+
+impl CreatePartitionsResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_create_partitions_response(buf)
+    }
+}
+
+named!(parse_create_partitions_response<CreatePartitionsResponse>,
+    parse_tag!(ParseTag::CreatePartitionsResponse,
+        do_parse!(
+            header: parse_response_header
+         >> throttle_time: be_i32
+         >> topics: length_count!(be_i32, parse_create_partitions_topic_status)
+         >> (CreatePartitionsResponse {
+                header,
+                throttle_time,
+                topics,
+            })
+        )
+    )
+);
+
+named!(parse_create_partitions_topic_status<CreatePartitionsTopicStatus>,
+    parse_tag!(ParseTag::CreatePartitionsTopicStatus,
+        do_parse!(
+            topic_name: parse_string
+         >> error_code: be_i16
+         >> error_message: parse_opt_string
+         >> (CreatePartitionsTopicStatus {
+                topic_name,
+                error_code,
+                error_message,
+            })
+        )
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_create_topics_request() {
+        let req = CreateTopicsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreateTopics as ApiKey,
+                api_version: 1,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            topics: vec![
+                CreateTopicsTopic {
+                    topic_name: "topic".into(),
+                    num_partitions: -1,
+                    replication_factor: -1,
+                    replica_assignment: vec![
+                        ReplicaAssignment {
+                            partition_id: 0,
+                            replicas: vec![1, 2],
+                        },
+                    ],
+                    configs: vec![
+                        ConfigEntry {
+                            config_name: "key".into(),
+                            config_value: Some("value".into()),
+                        },
+                    ],
+                },
+            ],
+            timeout: 1000,
+            validate_only: true,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 19 /* api_key */, 0, 1 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, /* topics */ 0, 0,
+            0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 255, 255, 255, 255 /* num_partitions */, 255,
+            255 /* replication_factor */, /* replica_assignment */ 0, 0, 0, 1, 0, 0, 0, 0 /* partition_id */, 0, 0,
+            0, 2, 0, 0, 0, 1, 0, 0, 0, 2 /* replicas */, /* configs */ 0, 0, 0, 1, 0, 3, b'k', b'e',
+            b'y' /* config_name */, 0, 5, b'v', b'a', b'l', b'u', b'e' /* config_value */, 0, 0, 3,
+            232 /* timeout */, 1 /* validate_only */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_create_topics_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 0,
+            36 /* error_code */, 0, 6, b'e', b'x', b'i', b's', b't', b's' /* error_message */,
+        ];
+
+        let res = CreateTopicsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: Some(1),
+            topics: vec![
+                CreateTopicsTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    error_code: 36,
+                    error_message: Some("exists".to_owned()),
+                },
+            ],
+        };
+
+        assert_eq!(parse_create_topics_response(data.as_slice(), 2), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_encode_delete_topics_request() {
+        let req = DeleteTopicsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::DeleteTopics as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            topic_names: vec!["topic".into()],
+            timeout: 1000,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 20 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, /* topic_names */ 0,
+            0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c', 0, 0, 3, 232 /* timeout */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_delete_topics_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o',
+            b'p', b'i', b'c' /* topic_name */, 0, 3 /* error_code */,
+        ];
+
+        let res = DeleteTopicsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: None,
+            topics: vec![
+                DeleteTopicsTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    error_code: 3,
+                },
+            ],
+        };
+
+        assert_eq!(parse_delete_topics_response(data.as_slice(), 0), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_encode_create_partitions_request() {
+        let req = CreatePartitionsRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::CreatePartitions as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            topics: vec![
+                CreatePartitionsTopic {
+                    topic_name: "foo".into(),
+                    count: 2,
+                    assignment: Some(vec![vec![1, 2]]),
+                },
+                CreatePartitionsTopic {
+                    topic_name: "bar".into(),
+                    count: 3,
+                    assignment: None,
+                },
+            ],
+            timeout: 1000,
+            validate_only: false,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 37 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, /* topics */ 0, 0,
+            0, 2, 0, 3, b'f', b'o', b'o' /* topic_name */, 0, 0, 0, 2 /* count */, /* assignment */ 0, 0, 0, 1, 0,
+            0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2 /* replicas */, 0, 3, b'b', b'a', b'r' /* topic_name */, 0, 0, 0,
+            3 /* count */, 255, 255, 255, 255 /* assignment */, 0, 0, 3, 232 /* timeout */, 0 /* validate_only */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_create_partitions_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 0 /* throttle_time */,
+            /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 0,
+            37 /* error_code */, 255, 255 /* error_message */,
+        ];
+
+        let res = CreatePartitionsResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 0,
+            topics: vec![
+                CreatePartitionsTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    error_code: 37,
+                    error_message: None,
+                },
+            ],
+        };
+
+        assert_eq!(parse_create_partitions_response(data.as_slice()), IResult::Done(&[][..], res));
+    }
+}
@@ -0,0 +1,714 @@
+use std::borrow::Cow;
+
+use bytes::{BufMut, ByteOrder, BytesMut};
+
+use nom::{IResult, be_i16, be_i32, be_i64};
+
+use errors::Result;
+use protocol::{parse_response_header, parse_string, ApiVersion, Encodable, ErrorCode, Offset, ParseTag,
+               PartitionId, ProducerEpoch, ProducerId, Record, RequestHeader, ResponseHeader, WriteExt,
+               ARRAY_LEN_SIZE, OFFSET_SIZE, PARTITION_ID_SIZE, STR_LEN_SIZE};
+
+const TRANSACTION_TIMEOUT_SIZE: usize = 4;
+const PRODUCER_ID_SIZE: usize = 8;
+const PRODUCER_EPOCH_SIZE: usize = 2;
+const TRANSACTION_RESULT_SIZE: usize = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitProducerIdRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The transactional id whose producer id we want to retrieve or generate,
+    /// or `None` if the producer is only idempotent.
+    pub transactional_id: Option<Cow<'a, str>>,
+    /// The time in ms to wait for before aborting idle transactions sent by this producer.
+    pub transaction_timeout: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitProducerIdResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// Error code.
+    pub error_code: ErrorCode,
+    /// The producer id assigned by the broker.
+    pub producer_id: ProducerId,
+    /// The epoch of the producer id.
+    pub producer_epoch: ProducerEpoch,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddPartitionsToTxnRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The transactional id corresponding to the transaction.
+    pub transactional_id: Cow<'a, str>,
+    /// Current producer id in use by the transactional id.
+    pub producer_id: ProducerId,
+    /// Current epoch associated with the producer id.
+    pub producer_epoch: ProducerEpoch,
+    /// The partitions to add to the transaction.
+    pub topics: Vec<AddPartitionsToTxnTopic<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddPartitionsToTxnTopic<'a> {
+    /// The name of the topic.
+    pub topic_name: Cow<'a, str>,
+    /// The partitions of the topic to add to the transaction.
+    pub partitions: Vec<PartitionId>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddPartitionsToTxnResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results of the topics.
+    pub topics: Vec<AddPartitionsToTxnTopicStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddPartitionsToTxnTopicStatus {
+    /// The name of the topic.
+    pub topic_name: String,
+    /// The results of the partitions.
+    pub partitions: Vec<AddPartitionsToTxnPartitionStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddPartitionsToTxnPartitionStatus {
+    /// The partition id.
+    pub partition_id: PartitionId,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddOffsetsToTxnRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The transactional id corresponding to the transaction.
+    pub transactional_id: Cow<'a, str>,
+    /// Current producer id in use by the transactional id.
+    pub producer_id: ProducerId,
+    /// Current epoch associated with the producer id.
+    pub producer_epoch: ProducerEpoch,
+    /// Consumer group id whose offsets should be included in the transaction.
+    pub group_id: Cow<'a, str>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddOffsetsToTxnResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndTxnRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The transactional id corresponding to the transaction.
+    pub transactional_id: Cow<'a, str>,
+    /// Current producer id in use by the transactional id.
+    pub producer_id: ProducerId,
+    /// Current epoch associated with the producer id.
+    pub producer_epoch: ProducerEpoch,
+    /// The result of the transaction, `true` to commit or `false` to abort.
+    pub transaction_result: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndTxnResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitRequest<'a> {
+    pub header: RequestHeader<'a>,
+    /// The transactional id corresponding to the transaction.
+    pub transactional_id: Cow<'a, str>,
+    /// The consumer group id.
+    pub group_id: Cow<'a, str>,
+    /// Current producer id in use by the transactional id.
+    pub producer_id: ProducerId,
+    /// Current epoch associated with the producer id.
+    pub producer_epoch: ProducerEpoch,
+    /// Topics to commit offsets.
+    pub topics: Vec<TxnOffsetCommitTopic<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitTopic<'a> {
+    /// The name of the topic.
+    pub topic_name: Cow<'a, str>,
+    /// Partitions to commit offsets.
+    pub partitions: Vec<TxnOffsetCommitPartition<'a>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitPartition<'a> {
+    /// The id of the partition the commit is for.
+    pub partition_id: PartitionId,
+    /// Message offset to be committed.
+    pub offset: Offset,
+    /// Any associated metadata the client wants to keep.
+    pub metadata: Option<Cow<'a, str>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitResponse {
+    pub header: ResponseHeader,
+    /// Duration in milliseconds for which the request was throttled due to
+    /// quota violation. (Zero if the request did not violate any quota).
+    pub throttle_time: i32,
+    /// The results of the topics.
+    pub topics: Vec<TxnOffsetCommitTopicStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitTopicStatus {
+    /// The name of the topic.
+    pub topic_name: String,
+    /// The results of the partitions.
+    pub partitions: Vec<TxnOffsetCommitPartitionStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxnOffsetCommitPartitionStatus {
+    /// The id of the partition the commit is for.
+    pub partition_id: PartitionId,
+    /// Error code.
+    pub error_code: ErrorCode,
+}
+
+impl<'a> Record for InitProducerIdRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.transactional_id.as_ref().map_or(0, |s| s.len())
+            + TRANSACTION_TIMEOUT_SIZE
+    }
+}
+
+impl<'a> Encodable for InitProducerIdRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(self.transactional_id.as_ref())?;
+        dst.put_i32::<T>(self.transaction_timeout);
+
+        Ok(())
+    }
+}
+
+impl<'a> Record for AddPartitionsToTxnRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.transactional_id.len() + PRODUCER_ID_SIZE
+            + PRODUCER_EPOCH_SIZE
+            + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+                size + STR_LEN_SIZE + topic.topic_name.len() + ARRAY_LEN_SIZE
+                    + PARTITION_ID_SIZE * topic.partitions.len()
+            })
+    }
+}
+
+impl<'a> Encodable for AddPartitionsToTxnRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(Some(self.transactional_id.as_ref()))?;
+        dst.put_i64::<T>(self.producer_id);
+        dst.put_i16::<T>(self.producer_epoch);
+        dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
+            buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+            buf.put_array::<T, _, _>(&topic.partitions, |buf, partition_id| {
+                buf.put_i32::<T>(*partition_id);
+                Ok(())
+            })
+        })
+    }
+}
+
+impl<'a> Record for AddOffsetsToTxnRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.transactional_id.len() + PRODUCER_ID_SIZE
+            + PRODUCER_EPOCH_SIZE + STR_LEN_SIZE + self.group_id.len()
+    }
+}
+
+impl<'a> Encodable for AddOffsetsToTxnRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(Some(self.transactional_id.as_ref()))?;
+        dst.put_i64::<T>(self.producer_id);
+        dst.put_i16::<T>(self.producer_epoch);
+        dst.put_str::<T, _>(Some(self.group_id.as_ref()))
+    }
+}
+
+impl<'a> Record for EndTxnRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.transactional_id.len() + PRODUCER_ID_SIZE
+            + PRODUCER_EPOCH_SIZE + TRANSACTION_RESULT_SIZE
+    }
+}
+
+impl<'a> Encodable for EndTxnRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(Some(self.transactional_id.as_ref()))?;
+        dst.put_i64::<T>(self.producer_id);
+        dst.put_i16::<T>(self.producer_epoch);
+        dst.put_i8(self.transaction_result as i8);
+
+        Ok(())
+    }
+}
+
+impl<'a> Record for TxnOffsetCommitRequest<'a> {
+    fn size(&self, api_version: ApiVersion) -> usize {
+        self.header.size(api_version) + STR_LEN_SIZE + self.transactional_id.len() + STR_LEN_SIZE
+            + self.group_id.len() + PRODUCER_ID_SIZE + PRODUCER_EPOCH_SIZE
+            + self.topics.iter().fold(ARRAY_LEN_SIZE, |size, topic| {
+                size + STR_LEN_SIZE + topic.topic_name.len()
+                    + topic.partitions.iter().fold(ARRAY_LEN_SIZE, |size, partition| {
+                        size + PARTITION_ID_SIZE + OFFSET_SIZE + STR_LEN_SIZE
+                            + partition.metadata.as_ref().map_or(0, |s| s.len())
+                    })
+            })
+    }
+}
+
+impl<'a> Encodable for TxnOffsetCommitRequest<'a> {
+    fn encode<T: ByteOrder>(&self, dst: &mut BytesMut) -> Result<()> {
+        self.header.encode::<T>(dst)?;
+
+        dst.put_str::<T, _>(Some(self.transactional_id.as_ref()))?;
+        dst.put_str::<T, _>(Some(self.group_id.as_ref()))?;
+        dst.put_i64::<T>(self.producer_id);
+        dst.put_i16::<T>(self.producer_epoch);
+        dst.put_array::<T, _, _>(&self.topics, |buf, topic| {
+            buf.put_str::<T, _>(Some(topic.topic_name.as_ref()))?;
+            buf.put_array::<T, _, _>(&topic.partitions, |buf, partition| {
+                buf.put_i32::<T>(partition.partition_id);
+                buf.put_i64::<T>(partition.offset);
+                buf.put_str::<T, _>(partition.metadata.as_ref())
+            })
+        })
+    }
+}
+
+impl InitProducerIdResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_init_producer_id_response(buf)
+    }
+}
+
+named!(
+    parse_init_producer_id_response<InitProducerIdResponse>,
+    parse_tag!(
+        ParseTag::InitProducerIdResponse,
+        do_parse!(
+            header: parse_response_header >> throttle_time: be_i32 >> error_code: be_i16 >> producer_id: be_i64
+                >> producer_epoch: be_i16 >> (InitProducerIdResponse {
+                header,
+                throttle_time,
+                error_code,
+                producer_id,
+                producer_epoch,
+            })
+        )
+    )
+);
+
+impl AddPartitionsToTxnResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_add_partitions_to_txn_response(buf)
+    }
+}
+
+named!(
+    parse_add_partitions_to_txn_response<AddPartitionsToTxnResponse>,
+    parse_tag!(
+        ParseTag::AddPartitionsToTxnResponse,
+        do_parse!(
+            header: parse_response_header >> throttle_time: be_i32
+                >> topics: length_count!(be_i32, parse_add_partitions_to_txn_topic_status)
+                >> (AddPartitionsToTxnResponse {
+                    header,
+                    throttle_time,
+                    topics,
+                })
+        )
+    )
+);
+
+named!(
+    parse_add_partitions_to_txn_topic_status<AddPartitionsToTxnTopicStatus>,
+    parse_tag!(
+        ParseTag::AddPartitionsToTxnTopicStatus,
+        do_parse!(
+            topic_name: parse_string >> partitions: length_count!(be_i32, parse_add_partitions_to_txn_partition_status)
+                >> (AddPartitionsToTxnTopicStatus { topic_name, partitions })
+        )
+    )
+);
+
+named!(
+    parse_add_partitions_to_txn_partition_status<AddPartitionsToTxnPartitionStatus>,
+    parse_tag!(
+        ParseTag::AddPartitionsToTxnPartitionStatus,
+        do_parse!(
+            partition_id: be_i32 >> error_code: be_i16 >> (AddPartitionsToTxnPartitionStatus {
+                partition_id,
+                error_code,
+            })
+        )
+    )
+);
+
+impl AddOffsetsToTxnResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_add_offsets_to_txn_response(buf)
+    }
+}
+
+named!(
+    parse_add_offsets_to_txn_response<AddOffsetsToTxnResponse>,
+    parse_tag!(
+        ParseTag::AddOffsetsToTxnResponse,
+        do_parse!(
+            header: parse_response_header >> throttle_time: be_i32 >> error_code: be_i16
+                >> (AddOffsetsToTxnResponse {
+                    header,
+                    throttle_time,
+                    error_code,
+                })
+        )
+    )
+);
+
+impl EndTxnResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_end_txn_response(buf)
+    }
+}
+
+named!(
+    parse_end_txn_response<EndTxnResponse>,
+    parse_tag!(
+        ParseTag::EndTxnResponse,
+        do_parse!(
+            header: parse_response_header >> throttle_time: be_i32 >> error_code: be_i16 >> (EndTxnResponse {
+                header,
+                throttle_time,
+                error_code,
+            })
+        )
+    )
+);
+
+impl TxnOffsetCommitResponse {
+    pub fn parse(buf: &[u8]) -> IResult<&[u8], Self> {
+        parse_txn_offset_commit_response(buf)
+    }
+}
+
+named!(
+    parse_txn_offset_commit_response<TxnOffsetCommitResponse>,
+    parse_tag!(
+        ParseTag::TxnOffsetCommitResponse,
+        do_parse!(
+            header: parse_response_header >> throttle_time: be_i32
+                >> topics: length_count!(be_i32, parse_txn_offset_commit_topic_status)
+                >> (TxnOffsetCommitResponse {
+                    header,
+                    throttle_time,
+                    topics,
+                })
+        )
+    )
+);
+
+named!(
+    parse_txn_offset_commit_topic_status<TxnOffsetCommitTopicStatus>,
+    parse_tag!(
+        ParseTag::TxnOffsetCommitTopicStatus,
+        do_parse!(
+            topic_name: parse_string >> partitions: length_count!(be_i32, parse_txn_offset_commit_partition_status)
+                >> (TxnOffsetCommitTopicStatus { topic_name, partitions })
+        )
+    )
+);
+
+named!(
+    parse_txn_offset_commit_partition_status<TxnOffsetCommitPartitionStatus>,
+    parse_tag!(
+        ParseTag::TxnOffsetCommitPartitionStatus,
+        do_parse!(
+            partition_id: be_i32 >> error_code: be_i16 >> (TxnOffsetCommitPartitionStatus {
+                partition_id,
+                error_code,
+            })
+        )
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use bytes::BigEndian;
+
+    use nom::IResult;
+
+    use protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_init_producer_id_request() {
+        let req = InitProducerIdRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::InitProducerId as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: None,
+            transaction_timeout: 60_000,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 22 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 255,
+            255 /* transactional_id */, 0, 0, 234, 96 /* transaction_timeout */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_init_producer_id_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */, 0,
+            0 /* error_code */, 0, 0, 0, 0, 0, 0, 0, 2 /* producer_id */, 0, 3 /* producer_epoch */,
+        ];
+
+        let res = InitProducerIdResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 1,
+            error_code: 0,
+            producer_id: 2,
+            producer_epoch: 3,
+        };
+
+        assert_eq!(
+            parse_init_producer_id_response(data.as_slice()),
+            IResult::Done(&[][..], res)
+        );
+    }
+
+    #[test]
+    fn test_encode_add_partitions_to_txn_request() {
+        let req = AddPartitionsToTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AddPartitionsToTxn as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: "txn".into(),
+            producer_id: 2,
+            producer_epoch: 3,
+            topics: vec![
+                AddPartitionsToTxnTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![4, 5],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 24 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x',
+            b'n' /* transactional_id */, 0, 0, 0, 0, 0, 0, 0, 2 /* producer_id */, 0, 3 /* producer_epoch */,
+            /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 0, 0, 0, 2, 0, 0, 0, 4, 0,
+            0, 0, 5 /* partitions */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_add_partitions_to_txn_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */,
+            /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i', b'c' /* topic_name */, 0, 0, 0, 1, 0, 0, 0,
+            4 /* partition_id */, 0, 51 /* error_code */,
+        ];
+
+        let res = AddPartitionsToTxnResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 1,
+            topics: vec![
+                AddPartitionsToTxnTopicStatus {
+                    topic_name: "topic".to_owned(),
+                    partitions: vec![
+                        AddPartitionsToTxnPartitionStatus {
+                            partition_id: 4,
+                            error_code: 51,
+                        },
+                    ],
+                },
+            ],
+        };
+
+        assert_eq!(
+            parse_add_partitions_to_txn_response(data.as_slice()),
+            IResult::Done(&[][..], res)
+        );
+    }
+
+    #[test]
+    fn test_encode_add_offsets_to_txn_request() {
+        let req = AddOffsetsToTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::AddOffsetsToTxn as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: "txn".into(),
+            producer_id: 2,
+            producer_epoch: 3,
+            group_id: "group".into(),
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 25 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x',
+            b'n' /* transactional_id */, 0, 0, 0, 0, 0, 0, 0, 2 /* producer_id */, 0, 3 /* producer_epoch */, 0,
+            5, b'g', b'r', b'o', b'u', b'p' /* group_id */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_encode_end_txn_request() {
+        let req = EndTxnRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::EndTxn as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: "txn".into(),
+            producer_id: 2,
+            producer_epoch: 3,
+            transaction_result: true,
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 26 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x',
+            b'n' /* transactional_id */, 0, 0, 0, 0, 0, 0, 0, 2 /* producer_id */, 0, 3 /* producer_epoch */,
+            1 /* transaction_result */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+
+    #[test]
+    fn test_parse_end_txn_response() {
+        let data = vec![
+            /* ResponseHeader */ 0, 0, 0, 123 /* correlation_id */, 0, 0, 0, 1 /* throttle_time */, 0,
+            48 /* error_code */,
+        ];
+
+        let res = EndTxnResponse {
+            header: ResponseHeader { correlation_id: 123 },
+            throttle_time: 1,
+            error_code: 48,
+        };
+
+        assert_eq!(parse_end_txn_response(data.as_slice()), IResult::Done(&[][..], res));
+    }
+
+    #[test]
+    fn test_encode_txn_offset_commit_request() {
+        let req = TxnOffsetCommitRequest {
+            header: RequestHeader {
+                api_key: ApiKeys::TxnOffsetCommit as ApiKey,
+                api_version: 0,
+                correlation_id: 123,
+                client_id: Some("client".into()),
+            },
+            transactional_id: "txn".into(),
+            group_id: "group".into(),
+            producer_id: 2,
+            producer_epoch: 3,
+            topics: vec![
+                TxnOffsetCommitTopic {
+                    topic_name: "topic".into(),
+                    partitions: vec![
+                        TxnOffsetCommitPartition {
+                            partition_id: 4,
+                            offset: 5,
+                            metadata: None,
+                        },
+                    ],
+                },
+            ],
+        };
+
+        let data = vec![
+            /* RequestHeader */ 0, 28 /* api_key */, 0, 0 /* api_version */, 0, 0, 0,
+            123 /* correlation_id */, 0, 6, b'c', b'l', b'i', b'e', b'n', b't' /* client_id */, 0, 3, b't', b'x',
+            b'n' /* transactional_id */, 0, 5, b'g', b'r', b'o', b'u', b'p' /* group_id */, 0, 0, 0, 0, 0, 0, 0,
+            2 /* producer_id */, 0, 3 /* producer_epoch */, /* topics */ 0, 0, 0, 1, 0, 5, b't', b'o', b'p', b'i',
+            b'c' /* topic_name */, 0, 0, 0, 1, 0, 0, 0, 4 /* partition_id */, 0, 0, 0, 0, 0, 0, 0, 5 /* offset */,
+            255, 255 /* metadata */,
+        ];
+
+        let mut buf = BytesMut::with_capacity(128);
+
+        req.encode::<BigEndian>(&mut buf).unwrap();
+
+        assert_eq!(req.size(req.header.api_version), buf.len());
+
+        assert_eq!(&buf[..], &data[..]);
+    }
+}
@@ -17,7 +17,7 @@ mod common;
 mod tests {
     use futures::Future;
 
-    use tokio_kafka::{Client, Cluster, FetchOffset, KafkaCode, ListedOffset};
+    use tokio_kafka::{Client, Cluster, FetchOffset, IsolationLevel, KafkaCode, ListedOffset};
 
     use common;
 
@@ -54,7 +54,7 @@ mod tests {
                         .collect::<Vec<_>>()
                 };
 
-                client.list_offsets(partitions).map(|responses| {
+                client.list_offsets(IsolationLevel::default(), partitions).map(|responses| {
                     assert!(responses.contains_key("foo"));
                     assert!(responses.contains_key("bar"));
 